@@ -103,6 +103,14 @@ pub const SIGNATURE_VALUE_LENGTH: usize = 6;
 pub const SIGNATURE_LENGTH: usize =
     SIGNATURE_LINK_ID_LENGTH + SIGNATURE_TIMESTAMP_LENGTH + SIGNATURE_VALUE_LENGTH;
 
+/// Maximum size of a MAVLink frame in bytes (signed `MAVLink 2` frame with the largest payload).
+///
+/// # Links
+///
+///  * [`FrameParser`](crate::protocol::FrameParser)
+pub const FRAME_MAX_SIZE: usize =
+    HEADER_MAX_SIZE + PAYLOAD_MAX_SIZE + CHECKSUM_SIZE + SIGNATURE_LENGTH;
+
 /// Timestamp offset in seconds from MAVLink to Unix epoch
 ///
 /// Number of seconds between MAVLink epoch (1st January 2015 GMT) and Unix epoch (1st January 1970 GMT)
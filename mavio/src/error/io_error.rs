@@ -116,6 +116,39 @@ impl IoError {
         &self.kind
     }
 
+    /// Returns `true` if reader has reached end of file.
+    ///
+    /// Matches [`IoErrorKind::UnexpectedEof`] and [`std::io::ErrorKind::UnexpectedEof`].
+    ///
+    /// Since [`embedded_io::ErrorKind`] has no such kind, errors of embedded readers are matched
+    /// only when adapters translate [`embedded_io::ReadExactError::UnexpectedEof`] into
+    /// [`IoErrorKind::UnexpectedEof`].
+    pub fn is_unexpected_eof(&self) -> bool {
+        match self.kind {
+            IoErrorKind::UnexpectedEof => true,
+            #[cfg(feature = "std")]
+            IoErrorKind::Std(kind) => kind == std::io::ErrorKind::UnexpectedEof,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if operation would block or has timed out.
+    ///
+    /// Such errors are returned by non-blocking readers or readers with timeouts when no data is
+    /// available yet, and the operation can be retried later.
+    pub fn is_would_block(&self) -> bool {
+        match self.kind {
+            #[cfg(feature = "std")]
+            IoErrorKind::Std(kind) => matches!(
+                kind,
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+            ),
+            #[cfg(any(feature = "embedded-io", feature = "embedded-io-async"))]
+            IoErrorKind::Embedded(kind) => kind == embedded_io::ErrorKind::TimedOut,
+            _ => false,
+        }
+    }
+
     /// Returns optional error.
     #[inline]
     #[cfg(feature = "std")]
@@ -223,10 +256,7 @@ impl<'de> serde::Deserialize<'de> for IoError {
         #[cfg(feature = "std")]
         return Ok(IoError {
             kind: IoErrorKind::Std(std::io::ErrorKind::Other),
-            error: Some(Arc::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Foreign I/O error",
-            ))),
+            error: Some(Arc::new(std::io::Error::other("Foreign I/O error"))),
         });
         #[cfg(not(feature = "std"))]
         return Ok(IoError {
@@ -29,6 +29,10 @@ pub use mavspec::rust::spec::SpecError;
 #[doc(inline)]
 pub use crate::microservices::mission::MissionError;
 
+#[cfg(all(feature = "msrv", feature = "dlct-minimal"))]
+#[doc(inline)]
+pub use crate::services::ServiceError;
+
 /// Common result type returned by `mavio` functions and methods.
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -78,6 +82,11 @@ pub enum Error {
     #[cfg_attr(feature = "std", error("mission error: {0:?}"))]
     #[cfg(all(feature = "msrv-utils-mission", feature = "unstable"))]
     Mission(MissionError),
+
+    /// MAVLink microservice errors.
+    #[cfg_attr(feature = "std", error("service error: {0:?}"))]
+    #[cfg(all(feature = "msrv", feature = "dlct-minimal"))]
+    Service(ServiceError),
 }
 
 /// Errors related to MAVLink frame validation.
@@ -245,3 +254,12 @@ impl From<MissionError> for Error {
         Error::Mission(value)
     }
 }
+
+#[cfg(all(feature = "msrv", feature = "dlct-minimal"))]
+impl From<ServiceError> for Error {
+    /// Converts [`ServiceError`] into [`Error::Service`] variant of [`Error`].
+    #[inline(always)]
+    fn from(value: ServiceError) -> Self {
+        Error::Service(value)
+    }
+}
@@ -0,0 +1,262 @@
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::error::{IoError, IoErrorKind};
+use crate::io::ErrorPolicy;
+use crate::protocol::{Dialect, Frame, FrameParser, MaybeVersioned, Versionless};
+
+use crate::prelude::*;
+
+/// Validates frame against a dialect.
+type Validator<V> = fn(&Frame<V>) -> Result<()>;
+
+/// MAVLink codec for [tokio-util](https://docs.rs/tokio-util/) that implements [`Decoder`] and
+/// [`Encoder`] for [`Frame`].
+///
+/// Allows to use MAVLink with [`Framed`](tokio_util::codec::Framed),
+/// [`FramedRead`](tokio_util::codec::FramedRead), and
+/// [`FramedWrite`](tokio_util::codec::FramedWrite).
+///
+/// By default, codec does not validate checksums. Use [`MavlinkCodec::with_dialect`] to validate
+/// incoming frames against a dialect. Invalid frames are handled according to [`ErrorPolicy`].
+/// Since [`Framed`](tokio_util::codec::Framed) streams are terminated after the first error, the
+/// default policy is [`ErrorPolicy::Count`].
+///
+/// [`Versioned`](crate::protocol::Versioned) codecs accept only frames of a specific MAVLink
+/// protocol version.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(not(feature = "dlct-minimal"))]
+/// # fn main() {}
+/// # #[cfg(feature = "dlct-minimal")]
+/// # fn main() {
+/// use bytes::BytesMut;
+/// use tokio_util::codec::{Decoder, Encoder};
+///
+/// use mavio::dialects::minimal::messages::Heartbeat;
+/// use mavio::dialects::Minimal;
+/// use mavio::io::MavlinkCodec;
+/// use mavio::prelude::*;
+///
+/// let frame = Endpoint::v2(MavLinkId::new(1, 1))
+///     .next_frame(&Heartbeat::default())
+///     .unwrap();
+///
+/// let mut codec = MavlinkCodec::<V2>::new().with_dialect::<Minimal>();
+/// let mut buf = BytesMut::new();
+/// codec.encode(&frame, &mut buf).unwrap();
+///
+/// let decoded = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(decoded.message_id(), frame.message_id());
+///
+/// // The same codec can be used with `Framed`, for example:
+/// // let framed = tokio_util::codec::Framed::new(tcp_stream, codec);
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct MavlinkCodec<V: MaybeVersioned = Versionless> {
+    parser: FrameParser<V>,
+    validator: Option<Validator<V>>,
+    policy: ErrorPolicy,
+    invalid_frames: u64,
+}
+
+impl<V: MaybeVersioned> MavlinkCodec<V> {
+    /// Creates a codec that does not validate frames.
+    pub fn new() -> Self {
+        Self {
+            parser: FrameParser::new(),
+            validator: None,
+            policy: ErrorPolicy::default(),
+            invalid_frames: 0,
+        }
+    }
+
+    /// Validates checksums of incoming frames against a dialect `D`.
+    ///
+    /// Frames with messages that are not part of the dialect are considered invalid.
+    pub fn with_dialect<D: Dialect>(self) -> Self {
+        Self {
+            validator: Some(Frame::<V>::validate_checksum::<D>),
+            ..self
+        }
+    }
+
+    /// Sets policy for invalid frames.
+    pub fn with_policy(self, policy: ErrorPolicy) -> Self {
+        Self { policy, ..self }
+    }
+
+    /// Policy for invalid frames.
+    #[inline(always)]
+    pub fn policy(&self) -> ErrorPolicy {
+        self.policy
+    }
+
+    /// Number of invalid frames handled with [`ErrorPolicy::Count`].
+    #[inline(always)]
+    pub fn invalid_frames(&self) -> u64 {
+        self.invalid_frames
+    }
+
+    fn validate(&mut self, frame: &Frame<V>) -> Result<bool> {
+        let validator = match self.validator {
+            Some(validator) => validator,
+            None => return Ok(true),
+        };
+
+        match (validator(frame), self.policy) {
+            (Ok(_), _) => Ok(true),
+            (Err(err), ErrorPolicy::Surface) => Err(err),
+            (Err(_), ErrorPolicy::Skip) => Ok(false),
+            (Err(_), ErrorPolicy::Count) => {
+                self.invalid_frames = self.invalid_frames.wrapping_add(1);
+                Ok(false)
+            }
+        }
+    }
+}
+
+impl<V: MaybeVersioned> Default for MavlinkCodec<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: MaybeVersioned> Decoder for MavlinkCodec<V> {
+    type Item = Frame<V>;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Frame<V>>> {
+        while !src.is_empty() {
+            let (consumed, frame) = self.parser.push(src);
+            src.advance(consumed);
+
+            if let Some(frame) = frame {
+                if self.validate(&frame)? {
+                    return Ok(Some(frame));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Decodes remaining frames once the underlying stream reached end of file.
+    ///
+    /// Returns [`IoErrorKind::UnexpectedEof`] if stream ended in the middle of a frame.
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Frame<V>>> {
+        if let Some(frame) = self.decode(src)? {
+            return Ok(Some(frame));
+        }
+        if self.parser.is_empty() {
+            return Ok(None);
+        }
+
+        self.parser.reset();
+        Err(IoError::from(IoErrorKind::UnexpectedEof).into())
+    }
+}
+
+impl<V: MaybeVersioned> Encoder<&Frame<V>> for MavlinkCodec<V> {
+    type Error = Error;
+
+    fn encode(&mut self, frame: &Frame<V>, dst: &mut BytesMut) -> Result<()> {
+        V::expect(frame.version())?;
+
+        let start = dst.len();
+        dst.resize(start + frame.size(), 0);
+        frame.to_bytes(&mut dst[start..]);
+        Ok(())
+    }
+}
+
+impl<V: MaybeVersioned> Encoder<Frame<V>> for MavlinkCodec<V> {
+    type Error = Error;
+
+    fn encode(&mut self, frame: Frame<V>, dst: &mut BytesMut) -> Result<()> {
+        self.encode(&frame, dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::V2;
+    use crate::utils::test_utils::{raw_frame, SENDER};
+
+    #[test]
+    fn encode_decode_in_chunks() {
+        let mut codec = MavlinkCodec::<V2>::new();
+        let mut encoded = BytesMut::new();
+        codec
+            .encode(raw_frame::<V2>(SENDER, 0, 0, 50), &mut encoded)
+            .unwrap();
+        codec
+            .encode(&raw_frame::<V2>(SENDER, 0, 1, 50), &mut encoded)
+            .unwrap();
+
+        let mut src = BytesMut::new();
+        let mut decoded = Vec::new();
+        for chunk in encoded.chunks(7) {
+            src.extend_from_slice(chunk);
+            while let Some(frame) = codec.decode(&mut src).unwrap() {
+                decoded.push(frame.message_id());
+            }
+        }
+        assert_eq!(decoded, [0, 1]);
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn partial_frame_at_eof() {
+        let mut codec = MavlinkCodec::<V2>::new();
+        let mut encoded = BytesMut::new();
+        codec
+            .encode(raw_frame::<V2>(SENDER, 0, 0, 50), &mut encoded)
+            .unwrap();
+        codec
+            .encode(raw_frame::<V2>(SENDER, 0, 1, 50), &mut encoded)
+            .unwrap();
+        encoded.truncate(encoded.len() - 3);
+
+        assert_eq!(
+            codec
+                .decode_eof(&mut encoded)
+                .unwrap()
+                .unwrap()
+                .message_id(),
+            0
+        );
+        match codec.decode_eof(&mut encoded) {
+            Err(Error::Io(err)) => assert!(err.is_unexpected_eof()),
+            result => panic!("unexpected result: {result:?}"),
+        }
+        assert!(codec.decode_eof(&mut encoded).unwrap().is_none());
+    }
+
+    #[cfg(feature = "dlct-minimal")]
+    #[test]
+    fn validates_against_dialect() {
+        let mut codec = MavlinkCodec::<V2>::new().with_dialect::<crate::dialects::Minimal>();
+        let mut src = BytesMut::new();
+        codec
+            .encode(raw_frame::<V2>(SENDER, 0, 0, 0), &mut src)
+            .unwrap();
+        codec
+            .encode(raw_frame::<V2>(SENDER, 0, 0, 50), &mut src)
+            .unwrap();
+
+        assert!(codec.decode(&mut src).unwrap().is_some());
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        assert_eq!(codec.invalid_frames(), 1);
+
+        let mut codec = codec.with_policy(ErrorPolicy::Surface);
+        let mut src = BytesMut::new();
+        codec
+            .encode(raw_frame::<V2>(SENDER, 0, 0, 0), &mut src)
+            .unwrap();
+        assert!(codec.decode(&mut src).is_err());
+    }
+}
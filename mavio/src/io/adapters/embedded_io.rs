@@ -36,6 +36,13 @@ impl<R: embedded_io::Read> Read<IoError> for EmbeddedIoReader<R> {
             ReadExactError::Other(err) => IoError::from_embedded_io_error(err),
         })
     }
+
+    #[inline(always)]
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, IoError> {
+        self.reader
+            .read(buf)
+            .map_err(IoError::from_embedded_io_error)
+    }
 }
 
 /// Adapter for [`embedded_io::Write`] that produces [`Write`].
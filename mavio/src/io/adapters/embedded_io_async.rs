@@ -36,6 +36,14 @@ impl<R: embedded_io_async::Read> AsyncRead<IoError> for EmbeddedIoAsyncReader<R>
             ReadExactError::Other(err) => IoError::from_embedded_io_error(err),
         })
     }
+
+    #[inline(always)]
+    async fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> Result<usize, IoError> {
+        self.reader
+            .read(buf)
+            .await
+            .map_err(IoError::from_embedded_io_error)
+    }
 }
 
 /// Adapter for [`embedded_io_async::Write`] that produces [`AsyncWrite`].
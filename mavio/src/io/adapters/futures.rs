@@ -1,6 +1,13 @@
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
 use futures::io::{AsyncReadExt, AsyncWriteExt};
+use futures::{Sink, Stream};
+
+use crate::io::{AsyncRead, AsyncReceiver, AsyncSender, AsyncWrite};
+use crate::protocol::{Frame, MaybeVersioned};
 
-use crate::io::{AsyncRead, AsyncWrite};
+use crate::error::Error;
 
 /// Adapter for [`futures::io::AsyncRead`] that produces [`AsyncRead`].
 pub struct FuturesReader<R: futures::io::AsyncRead> {
@@ -27,12 +34,27 @@ impl<R: futures::io::AsyncRead> From<R> for FuturesReader<R> {
     }
 }
 
+impl<R: futures::io::AsyncRead + Unpin> FuturesReader<R> {
+    pub(crate) fn poll_read(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.reader).poll_read(cx, buf)
+    }
+}
+
 impl<R: futures::io::AsyncRead + Unpin> AsyncRead<std::io::Error> for FuturesReader<R> {
     #[inline(always)]
     async fn read_exact<'a>(&'a mut self, buf: &'a mut [u8]) -> Result<(), std::io::Error> {
         self.reader.read_exact(buf).await?;
         Ok(())
     }
+
+    #[inline(always)]
+    async fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> Result<usize, std::io::Error> {
+        self.reader.read(buf).await
+    }
 }
 
 /// Adapter for [`futures::io::AsyncWrite`] that produces [`AsyncWrite`].
@@ -54,6 +76,24 @@ impl<W: futures::io::AsyncWrite> FuturesWriter<W> {
     }
 }
 
+impl<W: futures::io::AsyncWrite + Unpin> FuturesWriter<W> {
+    pub(crate) fn poll_write(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.writer).poll_write(cx, buf)
+    }
+
+    pub(crate) fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.writer).poll_flush(cx)
+    }
+
+    pub(crate) fn poll_close(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.writer).poll_close(cx)
+    }
+}
+
 impl<W: futures::io::AsyncWrite + Unpin> AsyncWrite<std::io::Error> for FuturesWriter<W> {
     #[inline(always)]
     async fn write_all<'a>(&'a mut self, buf: &'a [u8]) -> Result<(), std::io::Error> {
@@ -61,7 +101,151 @@ impl<W: futures::io::AsyncWrite + Unpin> AsyncWrite<std::io::Error> for FuturesW
     }
 
     #[inline]
-    async fn flush<'a>(&'a mut self) -> Result<(), std::io::Error> {
+    async fn flush(&mut self) -> Result<(), std::io::Error> {
         self.writer.flush().await
     }
 }
+
+impl<R: futures::io::AsyncRead + Unpin, V: MaybeVersioned> Stream
+    for AsyncReceiver<std::io::Error, FuturesReader<R>, V>
+{
+    type Item = crate::error::Result<Frame<V>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_next_frame(cx, FuturesReader::poll_read)
+    }
+}
+
+impl<W: futures::io::AsyncWrite + Unpin, V: MaybeVersioned> Sink<Frame<V>>
+    for AsyncSender<std::io::Error, FuturesWriter<W>, V>
+{
+    type Error = Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<crate::error::Result<()>> {
+        self.get_mut()
+            .poll_write_outgoing(cx, FuturesWriter::poll_write)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Frame<V>) -> crate::error::Result<()> {
+        self.get_mut().start_send_frame(&item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<crate::error::Result<()>> {
+        self.get_mut()
+            .poll_after_outgoing(cx, FuturesWriter::poll_write, FuturesWriter::poll_flush)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<crate::error::Result<()>> {
+        self.get_mut()
+            .poll_after_outgoing(cx, FuturesWriter::poll_write, FuturesWriter::poll_close)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+    use futures::{FutureExt, SinkExt, StreamExt};
+
+    use crate::protocol::V2;
+    use crate::utils::test_utils::{frame, SENDER};
+
+    /// Returns at most 5 bytes per read and every other read is pending.
+    struct ChunkedReader<'a> {
+        bytes: &'a [u8],
+        ready: bool,
+    }
+
+    impl futures::io::AsyncRead for ChunkedReader<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<std::io::Result<usize>> {
+            self.ready = !self.ready;
+            if !self.ready {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            let n = buf.len().min(self.bytes.len()).min(5);
+            buf[..n].copy_from_slice(&self.bytes[..n]);
+            self.bytes = &self.bytes[n..];
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    #[test]
+    fn recv_is_cancellation_safe() {
+        let mut bytes = [0u8; 64];
+        let len = frame::<V2>(SENDER, 7).to_bytes(&mut bytes);
+        let reader = ChunkedReader {
+            bytes: &bytes[..len],
+            ready: false,
+        };
+        let mut receiver = AsyncReceiver::versioned(FuturesReader::new(reader), V2);
+
+        // Drop receiving futures in the middle of a frame
+        for _ in 0..3 {
+            assert!(receiver.recv().now_or_never().is_none());
+        }
+
+        let frame = block_on(receiver.recv()).unwrap();
+        assert_eq!(frame.sequence(), 7);
+        assert!(frame.validate_checksum_with_crc_extra(50).is_ok());
+    }
+
+    #[test]
+    fn forward_frames() {
+        block_on(async {
+            let mut sent = Vec::new();
+            let mut sender = AsyncSender::versioned(FuturesWriter::new(Cursor::new(&mut sent)), V2);
+            for sequence in 0..3 {
+                sender.feed(frame::<V2>(SENDER, sequence)).await.unwrap();
+            }
+            SinkExt::flush(&mut sender).await.unwrap();
+
+            let mut forwarded = Vec::new();
+            let receiver = AsyncReceiver::versioned(FuturesReader::new(Cursor::new(&sent)), V2);
+            let sender =
+                AsyncSender::versioned(FuturesWriter::new(Cursor::new(&mut forwarded)), V2);
+            receiver.forward(sender).await.unwrap();
+            assert_eq!(forwarded, sent);
+
+            let sequences: Vec<_> =
+                AsyncReceiver::versioned(FuturesReader::new(Cursor::new(&forwarded)), V2)
+                    .map(|frame| frame.unwrap().sequence())
+                    .collect()
+                    .await;
+            assert_eq!(sequences, [0, 1, 2]);
+        });
+    }
+
+    #[cfg(feature = "dlct-minimal")]
+    #[test]
+    fn connection_sends_sequenced_messages() {
+        use crate::dialects::minimal::messages::Heartbeat;
+        use crate::io::AsyncConnection;
+        use crate::protocol::{Endpoint, MavLinkId};
+
+        block_on(async {
+            let mut outgoing = Vec::new();
+            let conn = AsyncConnection::new(
+                FuturesReader::new(Cursor::new(Vec::new())),
+                FuturesWriter::new(Cursor::new(&mut outgoing)),
+                Endpoint::v2(MavLinkId::new(1, 1)),
+            );
+            let (_, mut sender) = conn.split();
+            for _ in 0..2 {
+                sender.send_message(&Heartbeat::default()).await.unwrap();
+            }
+
+            let sequences: Vec<_> =
+                AsyncReceiver::versioned(FuturesReader::new(Cursor::new(&outgoing)), V2)
+                    .map(|frame| frame.unwrap().sequence())
+                    .collect()
+                    .await;
+            assert_eq!(sequences, [0, 1]);
+        });
+    }
+}
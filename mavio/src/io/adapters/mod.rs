@@ -1,5 +1,7 @@
 //! I/O adapters.
 
+#[cfg(feature = "tokio-util")]
+mod codec;
 #[cfg(feature = "embedded-io")]
 mod embedded_io;
 #[cfg(feature = "embedded-io-async")]
@@ -11,6 +13,8 @@ mod std_io;
 #[cfg(feature = "tokio")]
 mod tokio;
 
+#[cfg(feature = "tokio-util")]
+pub use codec::MavlinkCodec;
 #[cfg(feature = "embedded-io")]
 pub use embedded_io::{EmbeddedIoReader, EmbeddedIoWriter};
 #[cfg(feature = "embedded-io-async")]
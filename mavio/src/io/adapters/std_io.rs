@@ -71,6 +71,15 @@ impl<R: std::io::Read> Read<std::io::Error> for StdIoReader<R> {
     fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), std::io::Error> {
         self.reader.read_exact(buf)
     }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
+        loop {
+            match self.reader.read(buf) {
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                result => return result,
+            }
+        }
+    }
 }
 
 /// Adapter for [`std::io::Write`] that produces [`Write`].
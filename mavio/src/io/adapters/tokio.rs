@@ -1,6 +1,15 @@
+#[cfg(feature = "futures")]
+use core::pin::Pin;
+#[cfg(feature = "futures")]
+use core::task::{Context, Poll};
+
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 use crate::io::{AsyncRead, AsyncWrite};
+#[cfg(feature = "futures")]
+use crate::io::{AsyncReceiver, AsyncSender};
+#[cfg(feature = "futures")]
+use crate::protocol::{Frame, MaybeVersioned};
 
 /// Adapter for [`tokio::io::AsyncRead`] that produces [`AsyncRead`].
 pub struct TokioReader<R: tokio::io::AsyncRead> {
@@ -27,12 +36,31 @@ impl<R: tokio::io::AsyncRead> From<R> for TokioReader<R> {
     }
 }
 
+#[cfg(feature = "futures")]
+impl<R: tokio::io::AsyncRead + Unpin> TokioReader<R> {
+    pub(crate) fn poll_read(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let mut buf = tokio::io::ReadBuf::new(buf);
+        Pin::new(&mut self.reader)
+            .poll_read(cx, &mut buf)
+            .map_ok(|_| buf.filled().len())
+    }
+}
+
 impl<R: tokio::io::AsyncRead + Unpin> AsyncRead<std::io::Error> for TokioReader<R> {
     #[inline(always)]
     async fn read_exact<'a>(&'a mut self, buf: &'a mut [u8]) -> Result<(), std::io::Error> {
         self.reader.read_exact(buf).await?;
         Ok(())
     }
+
+    #[inline(always)]
+    async fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> Result<usize, std::io::Error> {
+        self.reader.read(buf).await
+    }
 }
 
 /// Adapter for [`tokio::io::AsyncWrite`] that produces [`AsyncWrite`].
@@ -54,6 +82,25 @@ impl<W: tokio::io::AsyncWrite> TokioWriter<W> {
     }
 }
 
+#[cfg(feature = "futures")]
+impl<W: tokio::io::AsyncWrite + Unpin> TokioWriter<W> {
+    pub(crate) fn poll_write(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.writer).poll_write(cx, buf)
+    }
+
+    pub(crate) fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.writer).poll_flush(cx)
+    }
+
+    pub(crate) fn poll_shutdown(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.writer).poll_shutdown(cx)
+    }
+}
+
 impl<W: tokio::io::AsyncWrite + Unpin> AsyncWrite<std::io::Error> for TokioWriter<W> {
     #[inline(always)]
     async fn write_all<'a>(&'a mut self, buf: &'a [u8]) -> Result<(), std::io::Error> {
@@ -61,7 +108,44 @@ impl<W: tokio::io::AsyncWrite + Unpin> AsyncWrite<std::io::Error> for TokioWrite
     }
 
     #[inline]
-    async fn flush<'a>(&'a mut self) -> Result<(), std::io::Error> {
+    async fn flush(&mut self) -> Result<(), std::io::Error> {
         self.writer.flush().await
     }
 }
+
+#[cfg(feature = "futures")]
+impl<R: tokio::io::AsyncRead + Unpin, V: MaybeVersioned> futures::Stream
+    for AsyncReceiver<std::io::Error, TokioReader<R>, V>
+{
+    type Item = crate::error::Result<Frame<V>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_next_frame(cx, TokioReader::poll_read)
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<W: tokio::io::AsyncWrite + Unpin, V: MaybeVersioned> futures::Sink<Frame<V>>
+    for AsyncSender<std::io::Error, TokioWriter<W>, V>
+{
+    type Error = crate::error::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<crate::error::Result<()>> {
+        self.get_mut()
+            .poll_write_outgoing(cx, TokioWriter::poll_write)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Frame<V>) -> crate::error::Result<()> {
+        self.get_mut().start_send_frame(&item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<crate::error::Result<()>> {
+        self.get_mut()
+            .poll_after_outgoing(cx, TokioWriter::poll_write, TokioWriter::poll_flush)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<crate::error::Result<()>> {
+        self.get_mut()
+            .poll_after_outgoing(cx, TokioWriter::poll_write, TokioWriter::poll_shutdown)
+    }
+}
@@ -0,0 +1,198 @@
+//! # Asynchronous MAVLink duplex connection
+
+use crate::io::{AsyncRead, AsyncReceiver, AsyncSender, AsyncWrite};
+use crate::protocol::{Endpoint, Frame, MavLinkId, MaybeVersioned, Message, Versioned};
+
+use crate::prelude::*;
+
+/// Asynchronous duplex MAVLink connection.
+///
+/// Owns [`AsyncReceiver`], [`AsyncSender`], and an [`Endpoint`] that is used to sequence outgoing
+/// messages. Connection can be [split](AsyncConnection::split) into independently owned halves,
+/// that can be moved to different tasks.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # fn main() {}
+/// # #[cfg(all(feature = "dlct-minimal", feature = "tokio"))]
+/// # async fn run() -> mavio::error::Result<()> {
+/// use mavio::dialects::minimal::messages::Heartbeat;
+/// use mavio::io::AsyncConnection;
+/// use mavio::prelude::*;
+///
+/// // Any `AsyncRead` / `AsyncWrite` pair will do, e.g. halves of a TCP stream
+/// let (stream, _remote) = tokio::io::duplex(1024);
+/// let (reader, writer) = tokio::io::split(stream);
+/// let mut conn = AsyncConnection::new(
+///     TokioReader::new(reader),
+///     TokioWriter::new(writer),
+///     Endpoint::v2(MavLinkId::new(1, 1)),
+/// );
+///
+/// conn.send_message(&Heartbeat::default()).await?;
+/// let frame = conn.recv().await?;
+///
+/// // Halves can be moved to different tasks
+/// let (mut receiver, mut sender) = conn.split();
+/// sender.send_message(&Heartbeat::default()).await?;
+/// let frame = receiver.recv().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AsyncConnection<E: Into<Error>, R: AsyncRead<E>, W: AsyncWrite<E>, V: MaybeVersioned> {
+    receiver: AsyncReceiver<E, R, V>,
+    sender: AsyncSendHalf<E, W, V>,
+}
+
+/// Sending half of an [`AsyncConnection`].
+///
+/// Owns [`AsyncSender`] and an [`Endpoint`] that is used to sequence outgoing messages. Created by
+/// [`AsyncConnection::split`].
+#[derive(Debug)]
+pub struct AsyncSendHalf<E: Into<Error>, W: AsyncWrite<E>, V: MaybeVersioned> {
+    sender: AsyncSender<E, W, V>,
+    endpoint: Endpoint<V>,
+}
+
+impl<E: Into<Error>, R: AsyncRead<E>, W: AsyncWrite<E>, V: MaybeVersioned>
+    AsyncConnection<E, R, W, V>
+{
+    /// Creates a connection from reader, writer, and endpoint.
+    pub fn new(reader: R, writer: W, endpoint: Endpoint<V>) -> Self {
+        Self::from_parts(
+            AsyncReceiver::new(reader),
+            AsyncSender::new(writer),
+            endpoint,
+        )
+    }
+
+    /// Creates a connection from [`AsyncReceiver`], [`AsyncSender`], and [`Endpoint`].
+    pub fn from_parts(
+        receiver: AsyncReceiver<E, R, V>,
+        sender: AsyncSender<E, W, V>,
+        endpoint: Endpoint<V>,
+    ) -> Self {
+        Self {
+            receiver,
+            sender: AsyncSendHalf { sender, endpoint },
+        }
+    }
+
+    /// Endpoint used to sequence outgoing messages.
+    #[inline(always)]
+    pub fn endpoint(&self) -> &Endpoint<V> {
+        self.sender.endpoint()
+    }
+
+    /// MAVLink `ID` of the connection endpoint.
+    #[inline(always)]
+    pub fn id(&self) -> MavLinkId {
+        self.sender.endpoint.id()
+    }
+
+    /// Underlying [`AsyncReceiver`].
+    #[inline(always)]
+    pub fn receiver(&mut self) -> &mut AsyncReceiver<E, R, V> {
+        &mut self.receiver
+    }
+
+    /// Underlying [`AsyncSender`].
+    #[inline(always)]
+    pub fn sender(&mut self) -> &mut AsyncSender<E, W, V> {
+        &mut self.sender.sender
+    }
+
+    /// Receives MAVLink [`Frame`].
+    ///
+    /// See [`AsyncReceiver::recv`].
+    #[inline]
+    pub async fn recv(&mut self) -> Result<Frame<V>> {
+        self.receiver.recv().await
+    }
+
+    /// Sends MAVLink [`Frame`] as is.
+    ///
+    /// Frame sequence is not changed. Use [`AsyncConnection::send_message`] to send messages
+    /// sequenced by the connection endpoint.
+    ///
+    /// See [`AsyncSender::send`].
+    #[inline]
+    pub async fn send(&mut self, frame: &Frame<V>) -> Result<usize> {
+        self.sender.send(frame).await
+    }
+
+    /// Flushes all buffers.
+    #[inline]
+    pub async fn flush(&mut self) -> Result<()> {
+        self.sender.flush().await
+    }
+
+    /// Splits connection into [`AsyncReceiver`] and [`AsyncSendHalf`].
+    ///
+    /// Halves can be joined back by [`AsyncSendHalf::reunite`].
+    pub fn split(self) -> (AsyncReceiver<E, R, V>, AsyncSendHalf<E, W, V>) {
+        (self.receiver, self.sender)
+    }
+}
+
+impl<E: Into<Error>, R: AsyncRead<E>, W: AsyncWrite<E>, V: Versioned> AsyncConnection<E, R, W, V> {
+    /// Wraps message into a frame with the next sequence number of the connection endpoint and
+    /// sends it.
+    ///
+    /// Returns the number of bytes sent.
+    #[inline]
+    pub async fn send_message(&mut self, message: &dyn Message) -> Result<usize> {
+        self.sender.send_message(message).await
+    }
+}
+
+impl<E: Into<Error>, W: AsyncWrite<E>, V: MaybeVersioned> AsyncSendHalf<E, W, V> {
+    /// Endpoint used to sequence outgoing messages.
+    #[inline(always)]
+    pub fn endpoint(&self) -> &Endpoint<V> {
+        &self.endpoint
+    }
+
+    /// Underlying [`AsyncSender`].
+    #[inline(always)]
+    pub fn sender(&mut self) -> &mut AsyncSender<E, W, V> {
+        &mut self.sender
+    }
+
+    /// Sends MAVLink [`Frame`] as is.
+    ///
+    /// See [`AsyncConnection::send`].
+    #[inline]
+    pub async fn send(&mut self, frame: &Frame<V>) -> Result<usize> {
+        self.sender.send(frame).await
+    }
+
+    /// Flushes all buffers.
+    #[inline]
+    pub async fn flush(&mut self) -> Result<()> {
+        self.sender.flush().await
+    }
+
+    /// Joins [`AsyncReceiver`] and sending half back into an [`AsyncConnection`].
+    pub fn reunite<R: AsyncRead<E>>(
+        self,
+        receiver: AsyncReceiver<E, R, V>,
+    ) -> AsyncConnection<E, R, W, V> {
+        AsyncConnection {
+            receiver,
+            sender: self,
+        }
+    }
+}
+
+impl<E: Into<Error>, W: AsyncWrite<E>, V: Versioned> AsyncSendHalf<E, W, V> {
+    /// Wraps message into a frame with the next sequence number and sends it.
+    ///
+    /// See [`AsyncConnection::send_message`].
+    pub async fn send_message(&mut self, message: &dyn Message) -> Result<usize> {
+        let frame = self.endpoint.next_frame(message)?;
+        self.sender.send(&frame).await
+    }
+}
@@ -0,0 +1,106 @@
+//! # Asynchronous MAVLink message receiver
+
+use core::marker::PhantomData;
+
+use crate::io::{AsyncRead, AsyncReceiver, MessagePolicy, MessageStats, Received};
+use crate::protocol::{Dialect, Frame, MaybeVersioned};
+
+use crate::prelude::*;
+
+/// Receives MAVLink messages decoded into a dialect asynchronously.
+///
+/// Wraps [`AsyncReceiver`], validates checksums of incoming frames and decodes them into a
+/// dialect `D`. Invalid frames are handled according to [`MessagePolicy`]. Frames with messages
+/// that are not part of the dialect are available through [`AsyncMessageReceiver::recv_any`].
+///
+/// This is an asynchronous counterpart of [`MessageReceiver`](crate::io::MessageReceiver).
+#[derive(Clone, Debug)]
+pub struct AsyncMessageReceiver<E: Into<Error>, R: AsyncRead<E>, V: MaybeVersioned, D: Dialect> {
+    receiver: AsyncReceiver<E, R, V>,
+    policy: MessagePolicy,
+    stats: MessageStats,
+    _dialect: PhantomData<D>,
+}
+
+impl<E: Into<Error>, R: AsyncRead<E>, V: MaybeVersioned, D: Dialect>
+    AsyncMessageReceiver<E, R, V, D>
+{
+    /// Creates a message receiver from [`AsyncReceiver`] with the default [`MessagePolicy`].
+    pub fn new(receiver: AsyncReceiver<E, R, V>) -> Self {
+        Self {
+            receiver,
+            policy: MessagePolicy::default(),
+            stats: MessageStats::default(),
+            _dialect: PhantomData,
+        }
+    }
+
+    /// Sets error handling policy.
+    pub fn with_policy(self, policy: MessagePolicy) -> Self {
+        Self { policy, ..self }
+    }
+
+    /// Error handling policy.
+    #[inline(always)]
+    pub fn policy(&self) -> &MessagePolicy {
+        &self.policy
+    }
+
+    /// Collected statistics.
+    #[inline(always)]
+    pub fn stats(&self) -> &MessageStats {
+        &self.stats
+    }
+
+    /// Resets collected statistics.
+    pub fn reset_stats(&mut self) {
+        self.stats = MessageStats::default();
+    }
+
+    /// Underlying [`AsyncReceiver`].
+    #[inline(always)]
+    pub fn receiver(&mut self) -> &mut AsyncReceiver<E, R, V> {
+        &mut self.receiver
+    }
+
+    /// Consumes message receiver and returns the underlying [`AsyncReceiver`].
+    pub fn into_inner(self) -> AsyncReceiver<E, R, V> {
+        self.receiver
+    }
+
+    /// Receives a frame and a message decoded into dialect `D`.
+    ///
+    /// Waits until a valid frame with a message from dialect `D` is received or until an error
+    /// that should be surfaced according to [`MessagePolicy`] occurs. I/O errors are always
+    /// returned.
+    pub async fn recv(&mut self) -> Result<(Frame<V>, D)> {
+        loop {
+            let frame = self.receiver.recv().await?;
+            if let Some(Received::Message(frame, message)) =
+                self.policy.process(&mut self.stats, frame, false)?
+            {
+                return Ok((frame, message));
+            }
+        }
+    }
+
+    /// Receives a frame with either a decoded message or a message that is not part of dialect `D`.
+    ///
+    /// Frames with unknown messages are returned as [`Received::Unknown`] regardless of
+    /// [`MessagePolicy::unknown`]. Other errors are handled according to [`MessagePolicy`].
+    pub async fn recv_any(&mut self) -> Result<Received<V, D>> {
+        loop {
+            let frame = self.receiver.recv().await?;
+            if let Some(received) = self.policy.process(&mut self.stats, frame, true)? {
+                return Ok(received);
+            }
+        }
+    }
+}
+
+impl<E: Into<Error>, R: AsyncRead<E>, V: MaybeVersioned> AsyncReceiver<E, R, V> {
+    /// Converts receiver into an [`AsyncMessageReceiver`] that decodes messages into dialect `D`.
+    pub fn with_dialect<D: Dialect>(self) -> AsyncMessageReceiver<E, R, V, D> {
+        AsyncMessageReceiver::new(self)
+    }
+}
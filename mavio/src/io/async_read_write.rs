@@ -29,6 +29,33 @@ pub trait AsyncRead<Err: Into<Error>> {
     ///
     /// Returns generic error in case of I/O failure.
     fn read_exact<'a>(&'a mut self, buf: &'a mut [u8]) -> impl Future<Output = Result<(), Err>>;
+
+    /// Reads asynchronously some bytes into the buffer and returns the number of bytes read.
+    ///
+    /// Mimics the corresponding method from [`std::io::Read`]. Returning `0` for a non-empty buffer
+    /// means that reader has reached end of file.
+    ///
+    /// This method is used by [`AsyncReceiver`] to keep partially received frames between calls.
+    /// Implementations should be cancellation-safe: if the returned future is dropped before
+    /// completion, no data should be consumed from the reader. All bundled adapters meet this
+    /// requirement.
+    ///
+    /// The default implementation fills the entire buffer with [`AsyncRead::read_exact`]. This is
+    /// safe for [`AsyncReceiver`] since it never requests more bytes than required to make progress
+    /// with the current frame. However, the default implementation is cancellation-safe only if
+    /// [`AsyncRead::read_exact`] is, so readers that can return partial data should override it.
+    ///
+    /// # Errors
+    ///
+    /// Returns generic error in case of I/O failure.
+    ///
+    /// [`AsyncReceiver`]: crate::io::AsyncReceiver
+    fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> impl Future<Output = Result<usize, Err>> {
+        async move {
+            self.read_exact(buf).await?;
+            Ok(buf.len())
+        }
+    }
 }
 
 /// Generic asynchronous write trait similar to [`std::io::Write`].
@@ -2,17 +2,28 @@
 
 use core::marker::PhantomData;
 
+#[cfg(feature = "futures")]
+use core::task::{Context, Poll};
+
+use crate::error::{IoError, IoErrorKind};
 use crate::io::AsyncRead;
-use crate::protocol::{Frame, MaybeVersioned, Versioned, Versionless};
+use crate::protocol::{Frame, FrameParser, MaybeVersioned, Versioned, Versionless};
 
 use crate::prelude::*;
 
 /// Receives MAVLink frames asynchronously.
 ///
 /// Receives MAVLink frames from an instance of [`AsyncRead`].
+///
+/// When `futures` feature is enabled, receivers based on [`FuturesReader`](crate::io::FuturesReader)
+/// and [`TokioReader`](crate::io::TokioReader) implement
+/// [`Stream`](https://docs.rs/futures/latest/futures/stream/trait.Stream.html) of frames. The stream
+/// ends once the underlying reader reaches end of file. Partially received frames are kept between
+/// polls, so stream can be used with combinators like `select` or `timeout`.
 #[derive(Clone, Debug)]
 pub struct AsyncReceiver<E: Into<Error>, R: AsyncRead<E>, V: MaybeVersioned> {
     reader: R,
+    parser: FrameParser<V>,
     _error_marker: PhantomData<E>,
     _marker_version: PhantomData<V>,
 }
@@ -22,6 +33,7 @@ impl<E: Into<Error>, R: AsyncRead<E>> AsyncReceiver<E, R, Versionless> {
     pub fn new<V: MaybeVersioned>(reader: R) -> AsyncReceiver<E, R, V> {
         AsyncReceiver {
             reader,
+            parser: FrameParser::new(),
             _error_marker: PhantomData,
             _marker_version: PhantomData,
         }
@@ -66,9 +78,67 @@ impl<E: Into<Error>, R: AsyncRead<E>, V: MaybeVersioned> AsyncReceiver<E, R, V>
     /// [`Versioned`] receiver accepts only frames of a specific MAVLink protocol version.
     ///
     /// [`Versionless`] receiver accepts both `MAVLink 1` and `MAVLink 2` frames.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancellation-safe as long as [`AsyncRead::read`] of the underlying reader is
+    /// cancellation-safe, which is true for all bundled adapters. Partially received frames are
+    /// kept inside the receiver, so [`AsyncReceiver::recv`] can be used as a branch of `select!`.
+    /// Receiving will resume from where it stopped on the next call.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IoErrorKind::UnexpectedEof`] if reader reached end of file.
     pub async fn recv(&mut self) -> Result<Frame<V>> {
-        Frame::<V>::recv_async(&mut self.reader)
-            .await
-            .map_err(E::into)
+        loop {
+            let n = self
+                .reader
+                .read(self.parser.buffer_mut())
+                .await
+                .map_err(E::into)?;
+
+            if n == 0 {
+                self.parser.reset();
+                return Err(IoError::from(IoErrorKind::UnexpectedEof).into());
+            }
+
+            if let Some(frame) = self.parser.advance(n) {
+                return Ok(frame);
+            }
+        }
+    }
+
+    /// Polls the next frame reading data into the internal [`FrameParser`].
+    ///
+    /// Returns [`None`], when reader reached the end of file between frames.
+    #[cfg(feature = "futures")]
+    pub(crate) fn poll_next_frame<F>(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut poll_read: F,
+    ) -> Poll<Option<Result<Frame<V>>>>
+    where
+        F: FnMut(&mut R, &mut Context<'_>, &mut [u8]) -> Poll<std::io::Result<usize>>,
+    {
+        loop {
+            match poll_read(&mut self.reader, cx, self.parser.buffer_mut()) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(err)) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(err.into()))),
+                Poll::Ready(Ok(0)) if self.parser.is_empty() => return Poll::Ready(None),
+                Poll::Ready(Ok(0)) => {
+                    self.parser.reset();
+                    let err = IoError::from(IoErrorKind::UnexpectedEof);
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+                Poll::Ready(Ok(n)) => {
+                    if let Some(frame) = self.parser.advance(n) {
+                        return Poll::Ready(Some(Ok(frame)));
+                    }
+                }
+            }
+        }
     }
 }
+
+impl<E: Into<Error>, R: AsyncRead<E> + Unpin, V: MaybeVersioned> Unpin for AsyncReceiver<E, R, V> {}
@@ -2,6 +2,11 @@
 
 use core::marker::PhantomData;
 
+#[cfg(feature = "futures")]
+use core::task::{Context, Poll};
+
+#[cfg(feature = "futures")]
+use crate::consts::FRAME_MAX_SIZE;
 use crate::io::AsyncWrite;
 use crate::protocol::{Frame, MaybeVersioned, Versioned, Versionless};
 
@@ -9,10 +14,17 @@ use crate::prelude::*;
 
 /// Sends MAVLink frames asynchronously.
 ///
-/// Sends MAVLink frames to an instance of [`AsyncWrite`].  
+/// Sends MAVLink frames to an instance of [`AsyncWrite`].
+///
+/// When `futures` feature is enabled, senders based on [`FuturesWriter`](crate::io::FuturesWriter)
+/// and [`TokioWriter`](crate::io::TokioWriter) implement
+/// [`Sink`](https://docs.rs/futures/latest/futures/sink/trait.Sink.html) of frames. This allows to
+/// pipe a stream of frames from [`AsyncReceiver`](crate::io::AsyncReceiver) with `forward`.
 #[derive(Clone, Debug)]
 pub struct AsyncSender<E: Into<Error>, W: AsyncWrite<E>, V: MaybeVersioned> {
     writer: W,
+    #[cfg(feature = "futures")]
+    outgoing: Outgoing,
     _error_marker: PhantomData<E>,
     _marker_version: PhantomData<V>,
 }
@@ -22,6 +34,8 @@ impl<E: Into<Error>, W: AsyncWrite<E>> AsyncSender<E, W, Versionless> {
     pub fn new<V: MaybeVersioned>(writer: W) -> AsyncSender<E, W, V> {
         AsyncSender {
             writer,
+            #[cfg(feature = "futures")]
+            outgoing: Outgoing::default(),
             _error_marker: PhantomData,
             _marker_version: PhantomData,
         }
@@ -65,6 +79,8 @@ impl<E: Into<Error>, W: AsyncWrite<E>, V: MaybeVersioned> AsyncSender<E, W, V> {
     #[inline(always)]
     pub async fn send(&mut self, frame: &Frame<V>) -> Result<usize> {
         V::expect(frame.version())?;
+        #[cfg(feature = "futures")]
+        self.write_outgoing().await?;
         frame.send_async(&mut self.writer).await.map_err(E::into)
     }
 
@@ -73,6 +89,105 @@ impl<E: Into<Error>, W: AsyncWrite<E>, V: MaybeVersioned> AsyncSender<E, W, V> {
     /// Certain writers require flush to be called on tear down in order to write all contents.
     #[inline]
     pub async fn flush(&mut self) -> Result<()> {
+        #[cfg(feature = "futures")]
+        self.write_outgoing().await?;
         self.writer.flush().await.map_err(E::into)
     }
+
+    /// Writes frame left in the outgoing buffer by the `Sink` implementation.
+    #[cfg(feature = "futures")]
+    async fn write_outgoing(&mut self) -> Result<()> {
+        if !self.outgoing.is_empty() {
+            let bytes = &self.outgoing.bytes[self.outgoing.pos..self.outgoing.len];
+            self.writer.write_all(bytes).await.map_err(E::into)?;
+            self.outgoing = Outgoing::default();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<E: Into<Error>, W: AsyncWrite<E>, V: MaybeVersioned> AsyncSender<E, W, V> {
+    /// Serializes frame into the outgoing buffer.
+    ///
+    /// Must be called only after [`AsyncSender::poll_write_outgoing`] returned [`Poll::Ready`].
+    pub(crate) fn start_send_frame(&mut self, frame: &Frame<V>) -> Result<()> {
+        V::expect(frame.version())?;
+        debug_assert!(self.outgoing.is_empty(), "previous frame was not sent");
+        self.outgoing.len = frame.to_bytes(&mut self.outgoing.bytes);
+        self.outgoing.pos = 0;
+        Ok(())
+    }
+
+    /// Writes the outgoing buffer.
+    pub(crate) fn poll_write_outgoing<F>(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut poll_write: F,
+    ) -> Poll<Result<()>>
+    where
+        F: FnMut(&mut W, &mut Context<'_>, &[u8]) -> Poll<std::io::Result<usize>>,
+    {
+        while !self.outgoing.is_empty() {
+            let bytes = &self.outgoing.bytes[self.outgoing.pos..self.outgoing.len];
+            match poll_write(&mut self.writer, cx, bytes) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(err)) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err.into())),
+                Poll::Ready(Ok(0)) => {
+                    let err = std::io::Error::from(std::io::ErrorKind::WriteZero);
+                    return Poll::Ready(Err(err.into()));
+                }
+                Poll::Ready(Ok(n)) => self.outgoing.pos += n,
+            }
+        }
+        self.outgoing = Outgoing::default();
+        Poll::Ready(Ok(()))
+    }
+
+    /// Writes the outgoing buffer and then polls the underlying writer with `poll`.
+    pub(crate) fn poll_after_outgoing<F, P>(
+        &mut self,
+        cx: &mut Context<'_>,
+        poll_write: F,
+        mut poll: P,
+    ) -> Poll<Result<()>>
+    where
+        F: FnMut(&mut W, &mut Context<'_>, &[u8]) -> Poll<std::io::Result<usize>>,
+        P: FnMut(&mut W, &mut Context<'_>) -> Poll<std::io::Result<()>>,
+    {
+        match self.poll_write_outgoing(cx, poll_write) {
+            Poll::Ready(Ok(())) => poll(&mut self.writer, cx).map_err(Error::from),
+            other => other,
+        }
+    }
+}
+
+impl<E: Into<Error>, W: AsyncWrite<E> + Unpin, V: MaybeVersioned> Unpin for AsyncSender<E, W, V> {}
+
+/// Serialized frame which is not yet written.
+#[cfg(feature = "futures")]
+#[derive(Clone, Debug)]
+struct Outgoing {
+    bytes: [u8; FRAME_MAX_SIZE],
+    pos: usize,
+    len: usize,
+}
+
+#[cfg(feature = "futures")]
+impl Outgoing {
+    fn is_empty(&self) -> bool {
+        self.pos == self.len
+    }
+}
+
+#[cfg(feature = "futures")]
+impl Default for Outgoing {
+    fn default() -> Self {
+        Self {
+            bytes: [0u8; FRAME_MAX_SIZE],
+            pos: 0,
+            len: 0,
+        }
+    }
 }
@@ -0,0 +1,190 @@
+//! # MAVLink duplex connection
+
+use crate::io::{Read, Receiver, Sender, Write};
+use crate::protocol::{Endpoint, Frame, MavLinkId, MaybeVersioned, Message, Versioned};
+
+use crate::prelude::*;
+
+/// Duplex MAVLink connection.
+///
+/// Owns [`Receiver`], [`Sender`], and an [`Endpoint`] that is used to sequence outgoing messages.
+/// Connection can be [split](Connection::split) into independently owned halves, that can be moved
+/// to different threads.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # #[cfg(not(all(feature = "dlct-minimal", feature = "std")))]
+/// # fn main() {}
+/// # #[cfg(all(feature = "dlct-minimal", feature = "std"))]
+/// # fn main() -> mavio::error::Result<()> {
+/// use std::net::TcpStream;
+///
+/// use mavio::dialects::minimal::messages::Heartbeat;
+/// use mavio::io::Connection;
+/// use mavio::prelude::*;
+///
+/// let stream = TcpStream::connect("0.0.0.0:5600")?;
+/// let mut conn = Connection::new(
+///     StdIoReader::new(stream.try_clone()?),
+///     StdIoWriter::new(stream),
+///     Endpoint::v2(MavLinkId::new(1, 1)),
+/// );
+///
+/// conn.send_message(&Heartbeat::default())?;
+/// let frame = conn.recv()?;
+///
+/// // Move halves to different threads
+/// let (mut receiver, mut sender) = conn.split();
+/// std::thread::spawn(move || receiver.recv());
+/// sender.send_message(&Heartbeat::default())?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Connection<E: Into<Error>, R: Read<E>, W: Write<E>, V: MaybeVersioned> {
+    receiver: Receiver<E, R, V>,
+    sender: SendHalf<E, W, V>,
+}
+
+/// Sending half of a [`Connection`].
+///
+/// Owns [`Sender`] and an [`Endpoint`] that is used to sequence outgoing messages. Created by
+/// [`Connection::split`].
+#[derive(Debug)]
+pub struct SendHalf<E: Into<Error>, W: Write<E>, V: MaybeVersioned> {
+    sender: Sender<E, W, V>,
+    endpoint: Endpoint<V>,
+}
+
+impl<E: Into<Error>, R: Read<E>, W: Write<E>, V: MaybeVersioned> Connection<E, R, W, V> {
+    /// Creates a connection from reader, writer, and endpoint.
+    pub fn new(reader: R, writer: W, endpoint: Endpoint<V>) -> Self {
+        Self::from_parts(Receiver::new(reader), Sender::new(writer), endpoint)
+    }
+
+    /// Creates a connection from [`Receiver`], [`Sender`], and [`Endpoint`].
+    pub fn from_parts(
+        receiver: Receiver<E, R, V>,
+        sender: Sender<E, W, V>,
+        endpoint: Endpoint<V>,
+    ) -> Self {
+        Self {
+            receiver,
+            sender: SendHalf { sender, endpoint },
+        }
+    }
+
+    /// Endpoint used to sequence outgoing messages.
+    #[inline(always)]
+    pub fn endpoint(&self) -> &Endpoint<V> {
+        self.sender.endpoint()
+    }
+
+    /// MAVLink `ID` of the connection endpoint.
+    #[inline(always)]
+    pub fn id(&self) -> MavLinkId {
+        self.sender.endpoint.id()
+    }
+
+    /// Underlying [`Receiver`].
+    #[inline(always)]
+    pub fn receiver(&mut self) -> &mut Receiver<E, R, V> {
+        &mut self.receiver
+    }
+
+    /// Underlying [`Sender`].
+    #[inline(always)]
+    pub fn sender(&mut self) -> &mut Sender<E, W, V> {
+        &mut self.sender.sender
+    }
+
+    /// Receives MAVLink [`Frame`].
+    ///
+    /// See [`Receiver::recv`].
+    #[inline]
+    pub fn recv(&mut self) -> Result<Frame<V>> {
+        self.receiver.recv()
+    }
+
+    /// Sends MAVLink [`Frame`] as is.
+    ///
+    /// Frame sequence is not changed. Use [`Connection::send_message`] to send messages sequenced
+    /// by the connection endpoint.
+    ///
+    /// See [`Sender::send`].
+    #[inline]
+    pub fn send(&mut self, frame: &Frame<V>) -> Result<usize> {
+        self.sender.send(frame)
+    }
+
+    /// Flushes all buffers.
+    #[inline]
+    pub fn flush(&mut self) -> Result<()> {
+        self.sender.flush()
+    }
+
+    /// Splits connection into [`Receiver`] and [`SendHalf`].
+    ///
+    /// Halves can be joined back by [`SendHalf::reunite`].
+    pub fn split(self) -> (Receiver<E, R, V>, SendHalf<E, W, V>) {
+        (self.receiver, self.sender)
+    }
+}
+
+impl<E: Into<Error>, R: Read<E>, W: Write<E>, V: Versioned> Connection<E, R, W, V> {
+    /// Wraps message into a frame with the next sequence number of the connection endpoint and
+    /// sends it.
+    ///
+    /// Returns the number of bytes sent.
+    #[inline]
+    pub fn send_message(&mut self, message: &dyn Message) -> Result<usize> {
+        self.sender.send_message(message)
+    }
+}
+
+impl<E: Into<Error>, W: Write<E>, V: MaybeVersioned> SendHalf<E, W, V> {
+    /// Endpoint used to sequence outgoing messages.
+    #[inline(always)]
+    pub fn endpoint(&self) -> &Endpoint<V> {
+        &self.endpoint
+    }
+
+    /// Underlying [`Sender`].
+    #[inline(always)]
+    pub fn sender(&mut self) -> &mut Sender<E, W, V> {
+        &mut self.sender
+    }
+
+    /// Sends MAVLink [`Frame`] as is.
+    ///
+    /// See [`Connection::send`].
+    #[inline]
+    pub fn send(&mut self, frame: &Frame<V>) -> Result<usize> {
+        self.sender.send(frame)
+    }
+
+    /// Flushes all buffers.
+    #[inline]
+    pub fn flush(&mut self) -> Result<()> {
+        self.sender.flush()
+    }
+
+    /// Joins [`Receiver`] and sending half back into a [`Connection`].
+    pub fn reunite<R: Read<E>>(self, receiver: Receiver<E, R, V>) -> Connection<E, R, W, V> {
+        Connection {
+            receiver,
+            sender: self,
+        }
+    }
+}
+
+impl<E: Into<Error>, W: Write<E>, V: Versioned> SendHalf<E, W, V> {
+    /// Wraps message into a frame with the next sequence number and sends it.
+    ///
+    /// See [`Connection::send_message`].
+    pub fn send_message(&mut self, message: &dyn Message) -> Result<usize> {
+        let frame = self.endpoint.next_frame(message)?;
+        self.sender.send(&frame)
+    }
+}
@@ -0,0 +1,558 @@
+//! # MAVLink message dispatcher
+
+use crate::io::{AsyncMessageReceiver, AsyncRead, MessageReceiver, Read};
+use crate::protocol::{ComponentId, Dialect, Frame, MaybeVersioned, MessageId, SystemId};
+
+use crate::mavspec::rust::spec::MessageSpecStatic;
+use crate::prelude::*;
+
+/// Default number of subscription slots in [`Subscriptions`].
+pub const DEFAULT_SUBSCRIPTIONS_CAPACITY: usize = 16;
+
+/// Selects frames delivered to a subscriber.
+///
+/// By default, filter matches all frames. Filter conditions can be combined.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(not(feature = "dlct-minimal"))]
+/// # fn main() {}
+/// # #[cfg(feature = "dlct-minimal")]
+/// # fn main() {
+/// use mavio::dialects::minimal::messages::Heartbeat;
+/// use mavio::io::Filter;
+///
+/// // Heartbeats from autopilot of system #1
+/// let filter = Filter::message::<Heartbeat>().system_id(1).component_id(1);
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Filter {
+    message_id: Option<MessageId>,
+    system_id: Option<SystemId>,
+    component_id: Option<ComponentId>,
+}
+
+impl Filter {
+    /// Creates a filter that matches all frames.
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    /// Creates a filter that matches messages of type `M`.
+    pub fn message<M: MessageSpecStatic>() -> Self {
+        Self::message_id(M::message_id())
+    }
+
+    /// Creates a filter that matches messages with the specified `ID`.
+    pub fn message_id(message_id: MessageId) -> Self {
+        Self {
+            message_id: Some(message_id),
+            ..Self::default()
+        }
+    }
+
+    /// Restricts filter to frames sent by a specific system.
+    pub fn system_id(self, system_id: SystemId) -> Self {
+        Self {
+            system_id: Some(system_id),
+            ..self
+        }
+    }
+
+    /// Restricts filter to frames sent by a specific component.
+    pub fn component_id(self, component_id: ComponentId) -> Self {
+        Self {
+            component_id: Some(component_id),
+            ..self
+        }
+    }
+
+    /// Checks whether a frame matches the filter.
+    pub fn matches<V: MaybeVersioned>(&self, frame: &Frame<V>) -> bool {
+        self.message_id.is_none_or(|id| id == frame.message_id())
+            && self.system_id.is_none_or(|id| id == frame.system_id())
+            && self
+                .component_id
+                .is_none_or(|id| id == frame.component_id())
+    }
+}
+
+/// <sup>`std`</sup>
+/// Receiving end of a bounded channel created by [`Subscriptions::subscribe_channel`].
+#[cfg(feature = "std")]
+pub type MessageChannel<V, D> = std::sync::mpsc::Receiver<(Frame<V>, D)>;
+
+/// <sup>`tokio-rt`</sup>
+/// Receiving end of a bounded channel created by [`Subscriptions::subscribe_tokio`].
+#[cfg(feature = "tokio-rt")]
+pub type TokioMessageChannel<V, D> = tokio::sync::mpsc::Receiver<(Frame<V>, D)>;
+
+/// Identifier of a subscription in [`Subscriptions`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SubscriptionId {
+    slot: usize,
+    generation: u32,
+}
+
+/// Delivery statistics of a single subscription.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SubscriptionStats {
+    /// Number of messages delivered to subscriber.
+    pub delivered: u64,
+    /// Number of messages dropped since subscriber's queue was full.
+    pub dropped: u64,
+}
+
+enum Subscriber<'a, V: MaybeVersioned, D: Dialect> {
+    Callback(&'a mut dyn FnMut(&Frame<V>, &D)),
+    #[cfg(feature = "std")]
+    Channel(std::sync::mpsc::SyncSender<(Frame<V>, D)>),
+    #[cfg(feature = "tokio-rt")]
+    Tokio(tokio::sync::mpsc::Sender<(Frame<V>, D)>),
+}
+
+enum Delivery {
+    Delivered,
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    Dropped,
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    Disconnected,
+}
+
+impl<V: MaybeVersioned, D: Dialect + Clone> Subscriber<'_, V, D> {
+    fn deliver(&mut self, frame: &Frame<V>, message: &D) -> Delivery {
+        match self {
+            Subscriber::Callback(callback) => {
+                callback(frame, message);
+                Delivery::Delivered
+            }
+            #[cfg(feature = "std")]
+            Subscriber::Channel(sender) => {
+                use std::sync::mpsc::TrySendError;
+                match sender.try_send((frame.clone(), message.clone())) {
+                    Ok(_) => Delivery::Delivered,
+                    Err(TrySendError::Full(_)) => Delivery::Dropped,
+                    Err(TrySendError::Disconnected(_)) => Delivery::Disconnected,
+                }
+            }
+            #[cfg(feature = "tokio-rt")]
+            Subscriber::Tokio(sender) => {
+                use tokio::sync::mpsc::error::TrySendError;
+                match sender.try_send((frame.clone(), message.clone())) {
+                    Ok(_) => Delivery::Delivered,
+                    Err(TrySendError::Full(_)) => Delivery::Dropped,
+                    Err(TrySendError::Closed(_)) => Delivery::Disconnected,
+                }
+            }
+        }
+    }
+}
+
+struct Subscription<'a, V: MaybeVersioned, D: Dialect> {
+    generation: u32,
+    filter: Filter,
+    subscriber: Subscriber<'a, V, D>,
+    stats: SubscriptionStats,
+}
+
+/// Fixed-capacity table of message subscriptions.
+///
+/// This is a sans-I/O core of [`Dispatcher`] and [`AsyncDispatcher`] which can be used directly
+/// with frames and messages obtained elsewhere.
+///
+/// Subscribers receive decoded messages through callbacks or, with `std` and `tokio-rt` features,
+/// through bounded channels. Channels never block the dispatcher: when subscriber's queue is full, the
+/// message is dropped and counted in [`SubscriptionStats::dropped`]. Subscriptions with
+/// disconnected channels are removed automatically.
+///
+/// The number of subscriptions is limited by `N`.
+pub struct Subscriptions<
+    'a,
+    V: MaybeVersioned,
+    D: Dialect,
+    const N: usize = DEFAULT_SUBSCRIPTIONS_CAPACITY,
+> {
+    slots: [Option<Subscription<'a, V, D>>; N],
+    generation: u32,
+}
+
+impl<'a, V: MaybeVersioned, D: Dialect + Clone, const N: usize> Subscriptions<'a, V, D, N> {
+    /// Creates an empty subscription table.
+    pub fn new() -> Self {
+        Self {
+            slots: core::array::from_fn(|_| None),
+            generation: 0,
+        }
+    }
+
+    /// Subscribes a callback to messages matching `filter`.
+    ///
+    /// Returns [`None`] if there are no free subscription slots.
+    pub fn subscribe(
+        &mut self,
+        filter: Filter,
+        callback: &'a mut dyn FnMut(&Frame<V>, &D),
+    ) -> Option<SubscriptionId> {
+        self.insert(filter, Subscriber::Callback(callback))
+    }
+
+    /// <sup>`std`</sup>
+    /// Subscribes to messages matching `filter` through a bounded channel of a given `capacity`.
+    ///
+    /// Zero `capacity` is treated as `1` since a rendezvous channel would drop every message.
+    ///
+    /// Returns [`None`] if there are no free subscription slots.
+    ///
+    /// Available only when `std` feature is enabled.
+    #[cfg(feature = "std")]
+    pub fn subscribe_channel(
+        &mut self,
+        filter: Filter,
+        capacity: usize,
+    ) -> Option<(SubscriptionId, MessageChannel<V, D>)> {
+        let (sender, receiver) = std::sync::mpsc::sync_channel(capacity.max(1));
+        let id = self.insert(filter, Subscriber::Channel(sender))?;
+        Some((id, receiver))
+    }
+
+    /// <sup>`tokio-rt`</sup>
+    /// Subscribes to messages matching `filter` through a bounded Tokio channel of a given
+    /// `capacity`.
+    ///
+    /// Zero `capacity` is treated as `1` since Tokio channels require a positive capacity.
+    ///
+    /// Returns [`None`] if there are no free subscription slots.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    #[cfg(feature = "tokio-rt")]
+    pub fn subscribe_tokio(
+        &mut self,
+        filter: Filter,
+        capacity: usize,
+    ) -> Option<(SubscriptionId, TokioMessageChannel<V, D>)> {
+        let (sender, receiver) = tokio::sync::mpsc::channel(capacity.max(1));
+        let id = self.insert(filter, Subscriber::Tokio(sender))?;
+        Some((id, receiver))
+    }
+
+    /// Removes subscription.
+    ///
+    /// Returns statistics of a removed subscription or [`None`] if subscription does not exist.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> Option<SubscriptionStats> {
+        self.get(id)?;
+        self.slots[id.slot].take().map(|s| s.stats)
+    }
+
+    /// Delivery statistics of a subscription.
+    pub fn stats(&self, id: SubscriptionId) -> Option<SubscriptionStats> {
+        self.get(id).map(|s| s.stats)
+    }
+
+    /// Number of active subscriptions.
+    pub fn len(&self) -> usize {
+        self.slots.iter().filter(|s| s.is_some()).count()
+    }
+
+    /// Returns `true` if there are no active subscriptions.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Delivers a message to all matching subscribers.
+    ///
+    /// Returns the number of subscribers that received the message.
+    pub fn dispatch(&mut self, frame: &Frame<V>, message: &D) -> usize {
+        let mut delivered = 0;
+        for slot in self.slots.iter_mut() {
+            let Some(subscription) = slot else {
+                continue;
+            };
+            if !subscription.filter.matches(frame) {
+                continue;
+            }
+            match subscription.subscriber.deliver(frame, message) {
+                Delivery::Delivered => {
+                    subscription.stats.delivered = subscription.stats.delivered.wrapping_add(1);
+                    delivered += 1;
+                }
+                Delivery::Dropped => {
+                    subscription.stats.dropped = subscription.stats.dropped.wrapping_add(1);
+                }
+                Delivery::Disconnected => *slot = None,
+            }
+        }
+        delivered
+    }
+
+    fn insert(
+        &mut self,
+        filter: Filter,
+        subscriber: Subscriber<'a, V, D>,
+    ) -> Option<SubscriptionId> {
+        let slot = self.slots.iter().position(Option::is_none)?;
+        self.generation = self.generation.wrapping_add(1);
+        self.slots[slot] = Some(Subscription {
+            generation: self.generation,
+            filter,
+            subscriber,
+            stats: SubscriptionStats::default(),
+        });
+        Some(SubscriptionId {
+            slot,
+            generation: self.generation,
+        })
+    }
+
+    fn get(&self, id: SubscriptionId) -> Option<&Subscription<'a, V, D>> {
+        self.slots
+            .get(id.slot)?
+            .as_ref()
+            .filter(|s| s.generation == id.generation)
+    }
+}
+
+impl<V: MaybeVersioned, D: Dialect + Clone, const N: usize> Default for Subscriptions<'_, V, D, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Dispatches messages received by [`MessageReceiver`] to subscribers.
+///
+/// See [`Subscriptions`] for details on subscription management.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # #[cfg(not(all(feature = "dlct-minimal", feature = "std")))]
+/// # fn main() {}
+/// # #[cfg(all(feature = "dlct-minimal", feature = "std"))]
+/// # fn main() {
+/// use std::net::TcpStream;
+///
+/// use mavio::dialects::minimal::messages::Heartbeat;
+/// use mavio::dialects::Minimal;
+/// use mavio::io::{Dispatcher, Filter, StdIoReader};
+/// use mavio::prelude::*;
+///
+/// let reader = StdIoReader::new(TcpStream::connect("0.0.0.0:5600").unwrap());
+/// let mut dispatcher = Dispatcher::<_, _, _, _>::new(
+///     Receiver::versionless(reader).with_dialect::<Minimal>(),
+/// );
+///
+/// let mut on_heartbeat = |frame: &Frame<Versionless>, _: &Minimal| {
+///     println!("heartbeat from {}", frame.system_id());
+/// };
+/// dispatcher
+///     .subscriptions()
+///     .subscribe(Filter::message::<Heartbeat>(), &mut on_heartbeat)
+///     .unwrap();
+///
+/// let (_, channel) = dispatcher
+///     .subscriptions()
+///     .subscribe_channel(Filter::any().system_id(1), 32)
+///     .unwrap();
+/// std::thread::spawn(move || {
+///     for (frame, message) in channel {
+///         println!("{message:?} from {}", frame.component_id());
+///     }
+/// });
+///
+/// dispatcher.run().unwrap();
+/// # }
+/// ```
+pub struct Dispatcher<
+    'a,
+    E: Into<Error>,
+    R: Read<E>,
+    V: MaybeVersioned,
+    D: Dialect,
+    const N: usize = DEFAULT_SUBSCRIPTIONS_CAPACITY,
+> {
+    receiver: MessageReceiver<E, R, V, D>,
+    subscriptions: Subscriptions<'a, V, D, N>,
+}
+
+impl<'a, E: Into<Error>, R: Read<E>, V: MaybeVersioned, D: Dialect + Clone, const N: usize>
+    Dispatcher<'a, E, R, V, D, N>
+{
+    /// Creates a dispatcher that owns a [`MessageReceiver`].
+    pub fn new(receiver: MessageReceiver<E, R, V, D>) -> Self {
+        Self {
+            receiver,
+            subscriptions: Subscriptions::new(),
+        }
+    }
+
+    /// Subscriptions table.
+    #[inline(always)]
+    pub fn subscriptions(&mut self) -> &mut Subscriptions<'a, V, D, N> {
+        &mut self.subscriptions
+    }
+
+    /// Underlying [`MessageReceiver`].
+    #[inline(always)]
+    pub fn receiver(&mut self) -> &mut MessageReceiver<E, R, V, D> {
+        &mut self.receiver
+    }
+
+    /// Receives a single message and dispatches it to subscribers.
+    ///
+    /// Returns the number of subscribers that received the message.
+    pub fn dispatch_next(&mut self) -> Result<usize> {
+        let (frame, message) = self.receiver.recv()?;
+        Ok(self.subscriptions.dispatch(&frame, &message))
+    }
+
+    /// Receives and dispatches messages until an error occurs.
+    pub fn run(&mut self) -> Result<()> {
+        loop {
+            self.dispatch_next()?;
+        }
+    }
+}
+
+/// Dispatches messages received by [`AsyncMessageReceiver`] to subscribers.
+///
+/// Asynchronous counterpart of [`Dispatcher`]. See [`Subscriptions`] for details on subscription
+/// management.
+pub struct AsyncDispatcher<
+    'a,
+    E: Into<Error>,
+    R: AsyncRead<E>,
+    V: MaybeVersioned,
+    D: Dialect,
+    const N: usize = DEFAULT_SUBSCRIPTIONS_CAPACITY,
+> {
+    receiver: AsyncMessageReceiver<E, R, V, D>,
+    subscriptions: Subscriptions<'a, V, D, N>,
+}
+
+impl<
+        'a,
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        V: MaybeVersioned,
+        D: Dialect + Clone,
+        const N: usize,
+    > AsyncDispatcher<'a, E, R, V, D, N>
+{
+    /// Creates a dispatcher that owns an [`AsyncMessageReceiver`].
+    pub fn new(receiver: AsyncMessageReceiver<E, R, V, D>) -> Self {
+        Self {
+            receiver,
+            subscriptions: Subscriptions::new(),
+        }
+    }
+
+    /// Subscriptions table.
+    #[inline(always)]
+    pub fn subscriptions(&mut self) -> &mut Subscriptions<'a, V, D, N> {
+        &mut self.subscriptions
+    }
+
+    /// Underlying [`AsyncMessageReceiver`].
+    #[inline(always)]
+    pub fn receiver(&mut self) -> &mut AsyncMessageReceiver<E, R, V, D> {
+        &mut self.receiver
+    }
+
+    /// Receives a single message and dispatches it to subscribers.
+    ///
+    /// Returns the number of subscribers that received the message.
+    pub async fn dispatch_next(&mut self) -> Result<usize> {
+        let (frame, message) = self.receiver.recv().await?;
+        Ok(self.subscriptions.dispatch(&frame, &message))
+    }
+
+    /// Receives and dispatches messages until an error occurs.
+    pub async fn run(&mut self) -> Result<()> {
+        loop {
+            self.dispatch_next().await?;
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "dlct-minimal")]
+mod tests {
+    use super::*;
+    use crate::dialects::minimal::messages::Heartbeat;
+    use crate::dialects::Minimal;
+    use crate::protocol::{Endpoint, MavLinkId, V2};
+
+    fn heartbeat(system_id: SystemId) -> (Frame<V2>, Minimal) {
+        let endpoint = Endpoint::v2(MavLinkId::new(system_id, 1));
+        let frame = endpoint.next_frame(&Heartbeat::default()).unwrap();
+        let message = frame.decode::<Minimal>().unwrap();
+        (frame, message)
+    }
+
+    #[test]
+    fn callbacks_are_filtered() {
+        let mut count = 0;
+        let mut callback = |_: &Frame<V2>, _: &Minimal| count += 1;
+
+        let mut subscriptions = Subscriptions::<V2, Minimal, 2>::new();
+        let id = subscriptions
+            .subscribe(Filter::message::<Heartbeat>().system_id(1), &mut callback)
+            .unwrap();
+
+        let (frame, message) = heartbeat(1);
+        assert_eq!(subscriptions.dispatch(&frame, &message), 1);
+        let (frame, message) = heartbeat(2);
+        assert_eq!(subscriptions.dispatch(&frame, &message), 0);
+
+        assert_eq!(subscriptions.unsubscribe(id).unwrap().delivered, 1);
+        assert!(subscriptions.unsubscribe(id).is_none());
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn slow_channels_drop_messages() {
+        let mut subscriptions = Subscriptions::<V2, Minimal, 2>::new();
+        let (id, channel) = subscriptions.subscribe_channel(Filter::any(), 1).unwrap();
+
+        let (frame, message) = heartbeat(1);
+        subscriptions.dispatch(&frame, &message);
+        subscriptions.dispatch(&frame, &message);
+
+        let stats = subscriptions.stats(id).unwrap();
+        assert_eq!(stats.delivered, 1);
+        assert_eq!(stats.dropped, 1);
+        assert!(channel.try_recv().is_ok());
+
+        drop(channel);
+        subscriptions.dispatch(&frame, &message);
+        assert!(subscriptions.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn zero_capacity_channels_deliver_messages() {
+        let mut subscriptions = Subscriptions::<V2, Minimal, 2>::new();
+        let (id, channel) = subscriptions.subscribe_channel(Filter::any(), 0).unwrap();
+
+        let (frame, message) = heartbeat(1);
+        subscriptions.dispatch(&frame, &message);
+
+        assert_eq!(subscriptions.stats(id).unwrap().delivered, 1);
+        assert!(channel.try_recv().is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "tokio-rt")]
+    fn zero_capacity_tokio_channels_deliver_messages() {
+        let mut subscriptions = Subscriptions::<V2, Minimal, 2>::new();
+        let (id, mut channel) = subscriptions.subscribe_tokio(Filter::any(), 0).unwrap();
+
+        let (frame, message) = heartbeat(1);
+        subscriptions.dispatch(&frame, &message);
+
+        assert_eq!(subscriptions.stats(id).unwrap().delivered, 1);
+        assert!(channel.try_recv().is_ok());
+    }
+}
@@ -0,0 +1,207 @@
+//! # Iterators over received MAVLink frames
+
+use core::marker::PhantomData;
+
+use crate::io::{Read, Receiver};
+use crate::protocol::{Dialect, Frame, MaybeVersioned, SystemId};
+
+use crate::prelude::*;
+
+/// Iterator over frames received by a borrowed [`Receiver`].
+///
+/// Created by [`Receiver::iter`]. Yields results of [`Receiver::recv`] and ends once the reader
+/// reaches end of file. A truncated frame at the end of the stream is discarded. After the end of
+/// the stream or after any other I/O error, the iterator is exhausted.
+///
+/// Errors that do not interrupt the stream such as [`FrameError`] are yielded without stopping the
+/// iteration.
+pub struct Frames<'a, E: Into<Error>, R: Read<E>, V: MaybeVersioned> {
+    receiver: &'a mut Receiver<E, R, V>,
+    done: bool,
+}
+
+/// Iterator over frames received by an owned [`Receiver`].
+///
+/// Created by [`Receiver::into_iter`](IntoIterator::into_iter). Behaves the same as [`Frames`].
+pub struct IntoFrames<E: Into<Error>, R: Read<E>, V: MaybeVersioned> {
+    receiver: Receiver<E, R, V>,
+    done: bool,
+}
+
+impl<E: Into<Error>, R: Read<E>, V: MaybeVersioned> Receiver<E, R, V> {
+    /// Returns an iterator over received frames.
+    ///
+    /// See [`Frames`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(not(all(feature = "dlct-minimal", feature = "extras", feature = "std")))]
+    /// # fn main() {}
+    /// # #[cfg(all(feature = "dlct-minimal", feature = "extras", feature = "std"))]
+    /// # fn main() {
+    /// use mavio::dialects::minimal::messages::Heartbeat;
+    /// use mavio::dialects::Minimal;
+    /// use mavio::io::FrameIteratorExt;
+    /// use mavio::utils::{SliceReader, SliceWriter};
+    /// use mavio::prelude::*;
+    ///
+    /// # let mut buf = [0u8; 64];
+    /// # let endpoint = Endpoint::v2(MavLinkId::new(1, 1));
+    /// # Sender::new(SliceWriter::new(&mut buf))
+    /// #     .send(&endpoint.next_frame(&Heartbeat::default()).unwrap())
+    /// #     .unwrap();
+    /// let mut receiver = Receiver::versionless(SliceReader::new(&buf));
+    ///
+    /// for result in receiver.iter().frames_from(1).messages::<Minimal>() {
+    ///     let (frame, message) = result.unwrap();
+    ///     println!("{}: {message:?}", frame.sequence());
+    /// }
+    /// # }
+    /// ```
+    pub fn iter(&mut self) -> Frames<'_, E, R, V> {
+        Frames {
+            receiver: self,
+            done: false,
+        }
+    }
+}
+
+impl<'a, E: Into<Error>, R: Read<E>, V: MaybeVersioned> IntoIterator for &'a mut Receiver<E, R, V> {
+    type Item = Result<Frame<V>>;
+    type IntoIter = Frames<'a, E, R, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<E: Into<Error>, R: Read<E>, V: MaybeVersioned> IntoIterator for Receiver<E, R, V> {
+    type Item = Result<Frame<V>>;
+    type IntoIter = IntoFrames<E, R, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoFrames {
+            receiver: self,
+            done: false,
+        }
+    }
+}
+
+impl<E: Into<Error>, R: Read<E>, V: MaybeVersioned> Iterator for Frames<'_, E, R, V> {
+    type Item = Result<Frame<V>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        next_frame(self.receiver, &mut self.done)
+    }
+}
+
+impl<E: Into<Error>, R: Read<E>, V: MaybeVersioned> Iterator for IntoFrames<E, R, V> {
+    type Item = Result<Frame<V>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        next_frame(&mut self.receiver, &mut self.done)
+    }
+}
+
+impl<E: Into<Error>, R: Read<E>, V: MaybeVersioned> core::iter::FusedIterator
+    for Frames<'_, E, R, V>
+{
+}
+
+impl<E: Into<Error>, R: Read<E>, V: MaybeVersioned> core::iter::FusedIterator
+    for IntoFrames<E, R, V>
+{
+}
+
+fn next_frame<E: Into<Error>, R: Read<E>, V: MaybeVersioned>(
+    receiver: &mut Receiver<E, R, V>,
+    done: &mut bool,
+) -> Option<Result<Frame<V>>> {
+    if *done {
+        return None;
+    }
+
+    match receiver.recv() {
+        Ok(frame) => Some(Ok(frame)),
+        Err(Error::Io(err)) if err.is_unexpected_eof() => {
+            *done = true;
+            None
+        }
+        Err(err @ Error::Io(_)) => {
+            *done = true;
+            Some(Err(err))
+        }
+        Err(err) => Some(Err(err)),
+    }
+}
+
+/// Adapters for iterators over received frames.
+///
+/// Implemented for all iterators that yield `Result<Frame<V>>` such as [`Frames`] and
+/// [`IntoFrames`]. Errors are passed through by all adapters.
+pub trait FrameIteratorExt<V: MaybeVersioned>: Iterator<Item = Result<Frame<V>>> + Sized {
+    /// Yields only frames sent by a system with the specified `system_id`.
+    fn frames_from(self, system_id: SystemId) -> FramesFrom<Self> {
+        FramesFrom {
+            iter: self,
+            system_id,
+        }
+    }
+
+    /// Decodes frames into messages of dialect `D`.
+    ///
+    /// Yields frames along with decoded messages. Frames with messages that are not part of the
+    /// dialect, or with invalid checksum, are yielded as errors (see [`Frame::decode`]).
+    fn messages<D: Dialect>(self) -> Messages<Self, D> {
+        Messages {
+            iter: self,
+            _dialect: PhantomData,
+        }
+    }
+}
+
+impl<V: MaybeVersioned, I: Iterator<Item = Result<Frame<V>>>> FrameIteratorExt<V> for I {}
+
+/// Iterator adapter that yields frames from a specific system.
+///
+/// Created by [`FrameIteratorExt::frames_from`].
+pub struct FramesFrom<I> {
+    iter: I,
+    system_id: SystemId,
+}
+
+impl<V: MaybeVersioned, I: Iterator<Item = Result<Frame<V>>>> Iterator for FramesFrom<I> {
+    type Item = Result<Frame<V>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let system_id = self.system_id;
+        self.iter.find(|result| {
+            result
+                .as_ref()
+                .map_or(true, |frame| frame.system_id() == system_id)
+        })
+    }
+}
+
+/// Iterator adapter that decodes frames into messages of dialect `D`.
+///
+/// Created by [`FrameIteratorExt::messages`].
+pub struct Messages<I, D: Dialect> {
+    iter: I,
+    _dialect: PhantomData<D>,
+}
+
+impl<V: MaybeVersioned, I: Iterator<Item = Result<Frame<V>>>, D: Dialect> Iterator
+    for Messages<I, D>
+{
+    type Item = Result<(Frame<V>, D)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = match self.iter.next()? {
+            Ok(frame) => frame,
+            Err(err) => return Some(Err(err)),
+        };
+        Some(frame.decode::<D>().map(|message| (frame, message)))
+    }
+}
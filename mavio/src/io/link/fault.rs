@@ -0,0 +1,674 @@
+//! Fault injection.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{mpsc, Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use crate::consts::FRAME_MAX_SIZE;
+use crate::io::{AsyncRead, AsyncWrite, Read, Write};
+
+use crate::prelude::*;
+
+/// Default maximum length of garbage bursts.
+pub const DEFAULT_MAX_GARBAGE_LEN: usize = 16;
+
+/// Configuration of injected faults.
+///
+/// Faults are applied to chunks of data: each write to [`FaultyWriter`] or each read from the
+/// underlying reader of [`FaultyReader`] is a chunk. Since [`Sender`](crate::io::Sender) writes
+/// each frame at once, chunks of [`FaultyWriter`] correspond to frames.
+///
+/// Probabilities are in the range from `0.0` (never) to `1.0` (always). The same seed produces
+/// the same sequence of faults for the same data.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FaultConfig {
+    /// Seed of the random number generator.
+    pub seed: u64,
+    /// Probability of dropping each byte.
+    pub drop_byte: f64,
+    /// Probability of flipping a random bit in each byte.
+    pub bit_flip: f64,
+    /// Probability of sending a chunk twice.
+    pub duplicate: f64,
+    /// Probability of holding a chunk back and sending it after the next one.
+    pub reorder: f64,
+    /// Probability of injecting a burst of random bytes before a chunk.
+    pub garbage: f64,
+    /// Maximum length of a garbage burst.
+    pub max_garbage_len: usize,
+    /// Delay before each chunk is passed through.
+    pub latency: Duration,
+}
+
+impl FaultConfig {
+    /// Creates configuration with the specified seed and no faults.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            drop_byte: 0.0,
+            bit_flip: 0.0,
+            duplicate: 0.0,
+            reorder: 0.0,
+            garbage: 0.0,
+            max_garbage_len: DEFAULT_MAX_GARBAGE_LEN,
+            latency: Duration::ZERO,
+        }
+    }
+}
+
+impl Default for FaultConfig {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// Statistics of injected faults.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FaultStats {
+    /// Number of dropped bytes.
+    pub dropped_bytes: u64,
+    /// Number of flipped bits.
+    pub flipped_bits: u64,
+    /// Number of duplicated chunks.
+    pub duplicated_chunks: u64,
+    /// Number of reordered chunks.
+    pub reordered_chunks: u64,
+    /// Number of injected garbage bytes.
+    pub garbage_bytes: u64,
+}
+
+/// SplitMix64 pseudo-random number generator.
+#[derive(Clone, Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        if probability <= 0.0 {
+            return false;
+        }
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n.max(1) as u64) as usize
+    }
+}
+
+/// Fault injection state of a single reader or writer.
+#[derive(Clone, Debug)]
+struct Faults {
+    config: FaultConfig,
+    rng: Rng,
+    stats: FaultStats,
+    held: Option<Vec<u8>>,
+    timer: Option<Timer>,
+}
+
+impl Faults {
+    fn new(config: FaultConfig) -> Self {
+        Self {
+            config,
+            rng: Rng(config.seed),
+            stats: FaultStats::default(),
+            held: None,
+            timer: None,
+        }
+    }
+
+    /// Asynchronous delay of a chunk, the timer thread is spawned on first use.
+    fn delay(&mut self) -> Delay {
+        let latency = self.config.latency;
+        self.timer.get_or_insert_with(Timer::spawn).delay(latency)
+    }
+
+    /// Applies faults to a chunk and appends the bytes that should be passed through to `out`.
+    fn apply(&mut self, chunk: &[u8], out: &mut Vec<u8>) {
+        let mut data = Vec::with_capacity(chunk.len());
+
+        if self.rng.chance(self.config.garbage) {
+            let len = 1 + self.rng.below(self.config.max_garbage_len);
+            data.extend((0..len).map(|_| self.rng.next_u64() as u8));
+            self.stats.garbage_bytes += len as u64;
+        }
+
+        for &byte in chunk {
+            if self.rng.chance(self.config.drop_byte) {
+                self.stats.dropped_bytes += 1;
+                continue;
+            }
+            if self.rng.chance(self.config.bit_flip) {
+                self.stats.flipped_bits += 1;
+                data.push(byte ^ (1 << self.rng.below(8)));
+            } else {
+                data.push(byte);
+            }
+        }
+
+        if self.rng.chance(self.config.duplicate) {
+            self.stats.duplicated_chunks += 1;
+            data.extend_from_within(..);
+        }
+
+        match self.held.take() {
+            Some(held) => {
+                out.extend_from_slice(&data);
+                out.extend_from_slice(&held);
+            }
+            None if self.rng.chance(self.config.reorder) => {
+                self.stats.reordered_chunks += 1;
+                self.held = Some(data);
+            }
+            None => out.extend_from_slice(&data),
+        }
+    }
+
+    /// Appends a chunk held back for reordering to `out`.
+    fn release(&mut self, out: &mut Vec<u8>) {
+        if let Some(held) = self.held.take() {
+            out.extend_from_slice(&held);
+        }
+    }
+}
+
+/// Reader that injects faults into the data received from the underlying reader.
+///
+/// Implements [`Read`] and [`AsyncRead`] if the underlying reader does. See [`FaultConfig`] for
+/// the available faults.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(not(feature = "dlct-minimal"))]
+/// # fn main() {}
+/// # #[cfg(feature = "dlct-minimal")]
+/// # fn main() {
+/// use mavio::io::link::{pipe, FaultConfig, FaultyReader};
+/// use mavio::prelude::*;
+///
+/// let (reader, writer) = pipe();
+/// let config = FaultConfig {
+///     bit_flip: 0.01,
+///     garbage: 0.1,
+///     ..FaultConfig::new(42)
+/// };
+/// let mut receiver = Receiver::versioned(FaultyReader::new(reader, config), V2);
+/// # drop(writer);
+/// # drop(receiver);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FaultyReader<R> {
+    reader: R,
+    faults: Faults,
+    buf: VecDeque<u8>,
+    eof: bool,
+    // Chunk received by asynchronous read is kept until its delay is completed, so cancelled
+    // reads never lose data
+    pending: Option<(Vec<u8>, Option<Delay>)>,
+}
+
+impl<R> FaultyReader<R> {
+    /// Wraps reader into a fault injector.
+    pub fn new(reader: R, config: FaultConfig) -> Self {
+        Self {
+            reader,
+            faults: Faults::new(config),
+            buf: VecDeque::new(),
+            eof: false,
+            pending: None,
+        }
+    }
+
+    /// Configuration of injected faults.
+    #[inline(always)]
+    pub fn config(&self) -> &FaultConfig {
+        &self.faults.config
+    }
+
+    /// Statistics of injected faults.
+    #[inline(always)]
+    pub fn stats(&self) -> FaultStats {
+        self.faults.stats
+    }
+
+    /// Extracts the underlying reader.
+    ///
+    /// Buffered bytes are lost.
+    pub fn extract(self) -> R {
+        self.reader
+    }
+
+    /// Applies faults to a chunk received from the underlying reader.
+    ///
+    /// Empty chunk means end of file.
+    fn push_chunk(&mut self, chunk: &[u8]) {
+        let mut out = Vec::new();
+        if chunk.is_empty() {
+            self.eof = true;
+            self.faults.release(&mut out);
+        } else {
+            self.faults.apply(chunk, &mut out);
+        }
+        self.buf.extend(out);
+    }
+
+    fn read_buffered(&mut self, buf: &mut [u8]) -> usize {
+        let n = buf.len().min(self.buf.len());
+        for (dst, src) in buf.iter_mut().zip(self.buf.drain(..n)) {
+            *dst = src;
+        }
+        n
+    }
+}
+
+impl<E: Into<Error>, R: Read<E>> Read<E> for FaultyReader<R> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> core::result::Result<(), E> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match Read::read(self, &mut buf[filled..])? {
+                // Underlying reader is exhausted, let it report end of file in its own error type
+                0 => return self.reader.read_exact(&mut buf[filled..]),
+                n => filled += n,
+            }
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> core::result::Result<usize, E> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        while self.buf.is_empty() && !self.eof {
+            let mut chunk = [0u8; FRAME_MAX_SIZE];
+            let n = self.reader.read(&mut chunk)?;
+            if n > 0 && !self.faults.config.latency.is_zero() {
+                std::thread::sleep(self.faults.config.latency);
+            }
+            self.push_chunk(&chunk[..n]);
+        }
+
+        Ok(self.read_buffered(buf))
+    }
+}
+
+impl<E: Into<Error>, R: AsyncRead<E>> AsyncRead<E> for FaultyReader<R> {
+    async fn read_exact<'a>(&'a mut self, buf: &'a mut [u8]) -> core::result::Result<(), E> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match AsyncRead::read(self, &mut buf[filled..]).await? {
+                // Underlying reader is exhausted, let it report end of file in its own error type
+                0 => return self.reader.read_exact(&mut buf[filled..]).await,
+                n => filled += n,
+            }
+        }
+        Ok(())
+    }
+
+    async fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> core::result::Result<usize, E> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        while self.buf.is_empty() && !self.eof {
+            if self.pending.is_none() {
+                let mut chunk = [0u8; FRAME_MAX_SIZE];
+                let n = self.reader.read(&mut chunk).await?;
+                let delay =
+                    (n > 0 && !self.faults.config.latency.is_zero()).then(|| self.faults.delay());
+                self.pending = Some((chunk[..n].to_vec(), delay));
+            }
+            if let Some((_, Some(delay))) = &mut self.pending {
+                delay.await;
+            }
+            if let Some((chunk, _)) = self.pending.take() {
+                self.push_chunk(&chunk);
+            }
+        }
+
+        Ok(self.read_buffered(buf))
+    }
+}
+
+/// Writer that injects faults into the data sent to the underlying writer.
+///
+/// Implements [`Write`] and [`AsyncWrite`] if the underlying writer does. See [`FaultConfig`] for
+/// the available faults. Chunks held back for reordering are written on flush.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(not(feature = "dlct-minimal"))]
+/// # fn main() {}
+/// # #[cfg(feature = "dlct-minimal")]
+/// # fn main() {
+/// use mavio::dialects::minimal::messages::Heartbeat;
+/// use mavio::io::link::{pipe, FaultConfig, FaultyWriter};
+/// use mavio::prelude::*;
+///
+/// let (reader, writer) = pipe();
+/// let config = FaultConfig {
+///     duplicate: 1.0,
+///     ..FaultConfig::new(42)
+/// };
+/// let mut sender = Sender::versioned(FaultyWriter::new(writer, config), V2);
+/// let mut receiver = Receiver::versioned(reader, V2);
+///
+/// let frame = Endpoint::v2(MavLinkId::new(1, 1))
+///     .next_frame(&Heartbeat::default())
+///     .unwrap();
+/// sender.send(&frame).unwrap();
+///
+/// // Frame is received twice
+/// assert_eq!(receiver.recv().unwrap().sequence(), frame.sequence());
+/// assert_eq!(receiver.recv().unwrap().sequence(), frame.sequence());
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FaultyWriter<W> {
+    writer: W,
+    faults: Faults,
+}
+
+impl<W> FaultyWriter<W> {
+    /// Wraps writer into a fault injector.
+    pub fn new(writer: W, config: FaultConfig) -> Self {
+        Self {
+            writer,
+            faults: Faults::new(config),
+        }
+    }
+
+    /// Configuration of injected faults.
+    #[inline(always)]
+    pub fn config(&self) -> &FaultConfig {
+        &self.faults.config
+    }
+
+    /// Statistics of injected faults.
+    #[inline(always)]
+    pub fn stats(&self) -> FaultStats {
+        self.faults.stats
+    }
+
+    /// Extracts the underlying writer.
+    ///
+    /// Chunks held back for reordering are lost.
+    pub fn extract(self) -> W {
+        self.writer
+    }
+}
+
+impl<E: Into<Error>, W: Write<E>> Write<E> for FaultyWriter<W> {
+    fn write_all(&mut self, buf: &[u8]) -> core::result::Result<(), E> {
+        if !self.faults.config.latency.is_zero() {
+            std::thread::sleep(self.faults.config.latency);
+        }
+
+        let mut out = Vec::new();
+        self.faults.apply(buf, &mut out);
+        self.writer.write_all(&out)
+    }
+
+    fn flush(&mut self) -> core::result::Result<(), E> {
+        let mut out = Vec::new();
+        self.faults.release(&mut out);
+        self.writer.write_all(&out)?;
+        self.writer.flush()
+    }
+}
+
+impl<E: Into<Error>, W: AsyncWrite<E>> AsyncWrite<E> for FaultyWriter<W> {
+    async fn write_all<'a>(&'a mut self, buf: &'a [u8]) -> core::result::Result<(), E> {
+        if !self.faults.config.latency.is_zero() {
+            self.faults.delay().await;
+        }
+
+        let mut out = Vec::new();
+        self.faults.apply(buf, &mut out);
+        self.writer.write_all(&out).await
+    }
+
+    async fn flush(&mut self) -> core::result::Result<(), E> {
+        let mut out = Vec::new();
+        self.faults.release(&mut out);
+        self.writer.write_all(&out).await?;
+        self.writer.flush().await
+    }
+}
+
+/// Timer thread that completes delays of a single reader or writer.
+///
+/// Delays of a link have the same duration and are requested in order, so the thread completes
+/// them one by one. The thread exits once the timer is dropped.
+#[derive(Clone, Debug)]
+struct Timer {
+    requests: mpsc::Sender<(Instant, Arc<Mutex<DelayState>>)>,
+}
+
+impl Timer {
+    fn spawn() -> Self {
+        let (requests, queue) = mpsc::channel::<(Instant, Arc<Mutex<DelayState>>)>();
+        std::thread::spawn(move || {
+            for (deadline, state) in queue {
+                std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+                let mut state = state.lock().unwrap();
+                state.done = true;
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            }
+        });
+        Self { requests }
+    }
+
+    fn delay(&self, duration: Duration) -> Delay {
+        let state = Arc::new(Mutex::new(DelayState::default()));
+        // Timer thread lives as long as the sender, so the request is never lost
+        let _ = self
+            .requests
+            .send((Instant::now() + duration, state.clone()));
+        Delay { state }
+    }
+}
+
+/// Runtime-agnostic delay that is completed by a [`Timer`].
+#[derive(Debug)]
+struct Delay {
+    state: Arc<Mutex<DelayState>>,
+}
+
+#[derive(Debug, Default)]
+struct DelayState {
+    done: bool,
+    waker: Option<Waker>,
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.done {
+            return Poll::Ready(());
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::link::pipe;
+    use crate::io::{Receiver, Sender};
+    use crate::protocol::V2;
+    use crate::utils::test_utils::{frame, SENDER};
+
+    fn transmit(config: FaultConfig, num_frames: u8) -> (Vec<u8>, FaultStats) {
+        let (mut reader, writer) = pipe();
+        let mut writer = FaultyWriter::new(writer, config);
+        let mut bytes = [0u8; FRAME_MAX_SIZE];
+        for sequence in 0..num_frames {
+            let len = frame::<V2>(SENDER, sequence).to_bytes(&mut bytes);
+            Write::write_all(&mut writer, &bytes[..len]).unwrap();
+        }
+        Write::flush(&mut writer).unwrap();
+        let stats = writer.stats();
+        drop(writer);
+
+        let mut bytes = vec![0u8; reader.available()];
+        Read::read_exact(&mut reader, &mut bytes).unwrap();
+        (bytes, stats)
+    }
+
+    #[test]
+    fn faults_are_reproducible() {
+        let config = FaultConfig {
+            drop_byte: 0.01,
+            bit_flip: 0.01,
+            duplicate: 0.1,
+            reorder: 0.1,
+            garbage: 0.1,
+            ..FaultConfig::new(7)
+        };
+
+        let (bytes, stats) = transmit(config, 100);
+        assert_eq!(transmit(config, 100), (bytes.clone(), stats));
+        assert_ne!(transmit(FaultConfig { seed: 8, ..config }, 100).0, bytes);
+
+        assert!(stats.dropped_bytes > 0);
+        assert!(stats.flipped_bits > 0);
+        assert!(stats.duplicated_chunks > 0);
+        assert!(stats.reordered_chunks > 0);
+        assert!(stats.garbage_bytes > 0);
+
+        let (bytes, stats) = transmit(FaultConfig::new(7), 100);
+        assert_eq!(stats, FaultStats::default());
+        assert_eq!(bytes.len(), 100 * frame::<V2>(SENDER, 0).size());
+    }
+
+    #[test]
+    fn reordered_frames() {
+        let config = FaultConfig {
+            reorder: 1.0,
+            ..FaultConfig::new(1)
+        };
+        let (reader, writer) = pipe();
+        let mut sender = Sender::versioned(FaultyWriter::new(writer, config), V2);
+        let mut receiver = Receiver::versioned(reader, V2);
+
+        for sequence in 0..4 {
+            sender.send(&frame::<V2>(SENDER, sequence)).unwrap();
+        }
+        sender.flush().unwrap();
+
+        let received: Vec<u8> = (0..4)
+            .map(|_| receiver.recv().unwrap().sequence())
+            .collect();
+        assert_eq!(received, [1, 0, 3, 2]);
+    }
+
+    #[cfg(feature = "dlct-minimal")]
+    #[test]
+    fn receiver_resynchronises() {
+        use crate::dialects::Minimal;
+
+        let config = FaultConfig {
+            bit_flip: 0.005,
+            drop_byte: 0.005,
+            garbage: 0.2,
+            ..FaultConfig::new(3)
+        };
+        let (reader, mut writer) = pipe();
+        let mut receiver = Receiver::versioned(FaultyReader::new(reader, config), V2);
+
+        let mut bytes = [0u8; FRAME_MAX_SIZE];
+        for sequence in 0..=200 {
+            let len = frame::<V2>(SENDER, sequence).to_bytes(&mut bytes);
+            Write::write_all(&mut writer, &bytes[..len]).unwrap();
+        }
+        drop(writer);
+
+        let (mut valid, mut invalid, mut last) = (0, 0, 0);
+        for result in &mut receiver {
+            match result {
+                Ok(frame) if frame.validate_checksum::<Minimal>().is_ok() => {
+                    valid += 1;
+                    last = frame.sequence();
+                }
+                _ => invalid += 1,
+            }
+        }
+
+        assert!(invalid > 0, "corrupted frames should be rejected");
+        assert!(valid > 100, "receiver should recover after faults: {valid}");
+        assert!(
+            last > 190,
+            "receiver should reach the end of the stream: {last}"
+        );
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn async_latency() {
+        use crate::io::{AsyncReceiver, AsyncSender};
+
+        let config = FaultConfig {
+            latency: Duration::from_millis(20),
+            ..FaultConfig::new(0)
+        };
+        let (reader, writer) = pipe();
+        let mut sender = AsyncSender::versioned(FaultyWriter::new(writer, config), V2);
+        let mut receiver = AsyncReceiver::versioned(reader, V2);
+
+        let started = std::time::Instant::now();
+        futures::executor::block_on(async {
+            // Delays are completed by the same timer thread one after another
+            for sequence in 0..3 {
+                sender.send(&frame::<V2>(SENDER, sequence)).await.unwrap();
+                assert_eq!(receiver.recv().await.unwrap().sequence(), sequence);
+            }
+        });
+        assert!(started.elapsed() >= config.latency * 3);
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn cancelled_async_reads_keep_sequence() {
+        use crate::io::AsyncReceiver;
+
+        let config = FaultConfig {
+            latency: Duration::from_millis(10),
+            ..FaultConfig::new(0)
+        };
+        let (reader, writer) = pipe();
+        let mut sender = Sender::versioned(writer, V2);
+        let mut receiver = AsyncReceiver::versioned(FaultyReader::new(reader, config), V2);
+
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        for sequence in 0..3 {
+            sender.send(&frame::<V2>(SENDER, sequence)).unwrap();
+
+            // Cancel receiving while the chunk is delayed
+            assert!(Box::pin(receiver.recv())
+                .as_mut()
+                .poll(&mut cx)
+                .is_pending());
+
+            let frame = futures::executor::block_on(receiver.recv()).unwrap();
+            assert_eq!(frame.sequence(), sequence);
+        }
+    }
+}
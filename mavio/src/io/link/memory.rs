@@ -0,0 +1,533 @@
+//! In-memory links.
+
+use std::collections::VecDeque;
+use std::future::poll_fn;
+use std::io::ErrorKind;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Poll, Waker};
+
+use crate::consts::FRAME_MAX_SIZE;
+use crate::io::{AsyncRead, AsyncWrite, Read, Write};
+use crate::protocol::FrameParser;
+
+/// Creates an in-memory unidirectional pipe.
+///
+/// Bytes written to [`PipeWriter`] become available to [`PipeReader`]. The pipe is unbounded, so
+/// writes never block. Once the writer is dropped, the reader reaches end of file after consuming
+/// the remaining bytes.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(not(feature = "dlct-minimal"))]
+/// # fn main() {}
+/// # #[cfg(feature = "dlct-minimal")]
+/// # fn main() {
+/// use mavio::dialects::minimal::messages::Heartbeat;
+/// use mavio::io::link::pipe;
+/// use mavio::prelude::*;
+///
+/// let (reader, writer) = pipe();
+/// let mut receiver = Receiver::versioned(reader, V2);
+/// let mut sender = Sender::versioned(writer, V2);
+///
+/// let frame = Endpoint::v2(MavLinkId::new(1, 1))
+///     .next_frame(&Heartbeat::default())
+///     .unwrap();
+/// sender.send(&frame).unwrap();
+///
+/// assert_eq!(receiver.recv().unwrap().message_id(), frame.message_id());
+/// # }
+/// ```
+pub fn pipe() -> (PipeReader, PipeWriter) {
+    let shared = Arc::new(Shared::default());
+    (
+        PipeReader {
+            shared: shared.clone(),
+            nonblocking: false,
+        },
+        PipeWriter { shared },
+    )
+}
+
+/// Creates a pair of connected in-memory duplex links.
+///
+/// Bytes written by one side are read by the other. See [`pipe`] for details.
+pub fn duplex() -> ((PipeReader, PipeWriter), (PipeReader, PipeWriter)) {
+    let (left_reader, right_writer) = pipe();
+    let (right_reader, left_writer) = pipe();
+    ((left_reader, left_writer), (right_reader, right_writer))
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<State>,
+    readable: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    buf: VecDeque<u8>,
+    writer_closed: bool,
+    reader_closed: bool,
+    waker: Option<Waker>,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    fn notify(&self, mut state: MutexGuard<'_, State>) {
+        let waker = state.waker.take();
+        drop(state);
+        self.readable.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Reading half of an in-memory [`pipe`].
+///
+/// Implements [`Read`] and [`AsyncRead`]. By default, reads block until data is available, use
+/// [`PipeReader::set_nonblocking`] to fail with [`ErrorKind::WouldBlock`] instead.
+#[derive(Debug)]
+pub struct PipeReader {
+    shared: Arc<Shared>,
+    nonblocking: bool,
+}
+
+impl PipeReader {
+    /// Sets non-blocking mode for synchronous reads.
+    ///
+    /// In non-blocking mode, reads from an empty pipe fail with [`ErrorKind::WouldBlock`], which
+    /// allows to use [`Receiver::try_recv`](crate::io::Receiver::try_recv).
+    pub fn set_nonblocking(&mut self, nonblocking: bool) {
+        self.nonblocking = nonblocking;
+    }
+
+    /// Number of bytes available for reading.
+    pub fn available(&self) -> usize {
+        self.shared.lock().buf.len()
+    }
+
+    fn read_available(state: &mut State, buf: &mut [u8]) -> usize {
+        let n = buf.len().min(state.buf.len());
+        for (dst, src) in buf.iter_mut().zip(state.buf.drain(..n)) {
+            *dst = src;
+        }
+        n
+    }
+}
+
+impl Read<std::io::Error> for PipeReader {
+    fn read_exact(&mut self, buf: &mut [u8]) -> std::io::Result<()> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match Read::read(self, &mut buf[filled..])? {
+                0 => return Err(ErrorKind::UnexpectedEof.into()),
+                n => filled += n,
+            }
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let mut state = self.shared.lock();
+        loop {
+            if !state.buf.is_empty() {
+                return Ok(Self::read_available(&mut state, buf));
+            }
+            if state.writer_closed {
+                return Ok(0);
+            }
+            if self.nonblocking {
+                return Err(ErrorKind::WouldBlock.into());
+            }
+            state = self.shared.readable.wait(state).unwrap();
+        }
+    }
+}
+
+impl AsyncRead<std::io::Error> for PipeReader {
+    async fn read_exact<'a>(&'a mut self, buf: &'a mut [u8]) -> std::io::Result<()> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match AsyncRead::read(self, &mut buf[filled..]).await? {
+                0 => return Err(ErrorKind::UnexpectedEof.into()),
+                n => filled += n,
+            }
+        }
+        Ok(())
+    }
+
+    async fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        poll_fn(|cx| {
+            let mut state = self.shared.lock();
+            if !state.buf.is_empty() {
+                return Poll::Ready(Ok(Self::read_available(&mut state, buf)));
+            }
+            if state.writer_closed {
+                return Poll::Ready(Ok(0));
+            }
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+impl Drop for PipeReader {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.reader_closed = true;
+        state.buf.clear();
+    }
+}
+
+/// Writing half of an in-memory [`pipe`].
+///
+/// Implements [`Write`] and [`AsyncWrite`]. Writes fail with [`ErrorKind::BrokenPipe`] once the
+/// reader is dropped.
+#[derive(Debug)]
+pub struct PipeWriter {
+    shared: Arc<Shared>,
+}
+
+impl PipeWriter {
+    fn write_bytes(&self, buf: &[u8]) -> std::io::Result<()> {
+        let mut state = self.shared.lock();
+        if state.reader_closed {
+            return Err(ErrorKind::BrokenPipe.into());
+        }
+        if buf.is_empty() {
+            return Ok(());
+        }
+        state.buf.extend(buf);
+        self.shared.notify(state);
+        Ok(())
+    }
+}
+
+impl Write<std::io::Error> for PipeWriter {
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.write_bytes(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsyncWrite<std::io::Error> for PipeWriter {
+    async fn write_all<'a>(&'a mut self, buf: &'a [u8]) -> std::io::Result<()> {
+        self.write_bytes(buf)
+    }
+
+    async fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for PipeWriter {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.writer_closed = true;
+        self.shared.notify(state);
+    }
+}
+
+/// Virtual broadcast bus that connects multiple simulated nodes.
+///
+/// Each node is [connected](VirtualBus::connect) by a [`PipeReader`] / [`BusWriter`] pair. Frames
+/// written by a node are delivered to all other connected nodes, but not to the sender itself.
+///
+/// The bus is frame-aware: bytes written by a node are parsed into frames, and each frame is
+/// delivered as a whole. This means that frames of different nodes are never interleaved, even if
+/// nodes write them in chunks. Bytes that do not belong to a valid frame are discarded.
+///
+/// Nodes with dropped readers are disconnected from the bus.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(not(feature = "dlct-minimal"))]
+/// # fn main() {}
+/// # #[cfg(feature = "dlct-minimal")]
+/// # fn main() {
+/// use mavio::dialects::minimal::messages::Heartbeat;
+/// use mavio::io::link::VirtualBus;
+/// use mavio::prelude::*;
+///
+/// let bus = VirtualBus::new();
+/// let (_, writer) = bus.connect();
+/// let (reader_1, _) = bus.connect();
+/// let (reader_2, _) = bus.connect();
+///
+/// let frame = Endpoint::v2(MavLinkId::new(1, 1))
+///     .next_frame(&Heartbeat::default())
+///     .unwrap();
+/// Sender::new(writer).send(&frame).unwrap();
+///
+/// for reader in [reader_1, reader_2] {
+///     let mut receiver = Receiver::versionless(reader);
+///     assert_eq!(receiver.recv().unwrap().system_id(), 1);
+/// }
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct VirtualBus {
+    state: Arc<Mutex<BusState>>,
+}
+
+#[derive(Debug, Default)]
+struct BusState {
+    nodes: Vec<Node>,
+    // Identifiers are never reused, so writers of disconnected nodes can't impersonate new ones
+    next_id: usize,
+}
+
+#[derive(Debug)]
+struct Node {
+    id: usize,
+    writer: PipeWriter,
+}
+
+impl VirtualBus {
+    /// Creates an empty bus.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connects a new node and returns its reader and writer.
+    pub fn connect(&self) -> (PipeReader, BusWriter) {
+        let (reader, writer) = pipe();
+
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.nodes.push(Node { id, writer });
+
+        (
+            reader,
+            BusWriter {
+                bus: self.clone(),
+                id,
+                parser: FrameParser::new(),
+            },
+        )
+    }
+
+    /// Number of connected nodes.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().nodes.len()
+    }
+
+    /// Returns `true` if there are no connected nodes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn broadcast(&self, sender_id: usize, bytes: &[u8]) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .nodes
+            .retain(|node| node.id == sender_id || node.writer.write_bytes(bytes).is_ok());
+    }
+}
+
+/// Writer of a [`VirtualBus`] node.
+///
+/// Implements [`Write`] and [`AsyncWrite`].
+#[derive(Debug)]
+pub struct BusWriter {
+    bus: VirtualBus,
+    id: usize,
+    parser: FrameParser,
+}
+
+impl BusWriter {
+    fn write_bytes(&mut self, mut buf: &[u8]) {
+        while !buf.is_empty() {
+            let (consumed, frame) = self.parser.push(buf);
+            buf = &buf[consumed..];
+
+            if let Some(frame) = frame {
+                let mut bytes = [0u8; FRAME_MAX_SIZE];
+                let len = frame.to_bytes(&mut bytes);
+                self.bus.broadcast(self.id, &bytes[..len]);
+            }
+        }
+    }
+}
+
+impl Write<std::io::Error> for BusWriter {
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.write_bytes(buf);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsyncWrite<std::io::Error> for BusWriter {
+    async fn write_all<'a>(&'a mut self, buf: &'a [u8]) -> std::io::Result<()> {
+        self.write_bytes(buf);
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{Receiver, Sender};
+    use crate::protocol::{MavLinkId, V2};
+    use crate::utils::test_utils::frame;
+
+    #[test]
+    fn pipe_round_trip_and_eof() {
+        let ((left_reader, left_writer), (right_reader, right_writer)) = duplex();
+        let mut left_receiver = Receiver::versioned(left_reader, V2);
+        let mut right_receiver = Receiver::versioned(right_reader, V2);
+
+        let mut left_sender = Sender::versioned(left_writer, V2);
+        left_sender
+            .send(&frame::<V2>(MavLinkId::new(1, 1), 1))
+            .unwrap();
+        assert_eq!(right_receiver.recv().unwrap().sequence(), 1);
+
+        let handle = std::thread::spawn(move || left_receiver.recv().unwrap().sequence());
+        Sender::versioned(right_writer, V2)
+            .send(&frame::<V2>(MavLinkId::new(2, 1), 2))
+            .unwrap();
+        assert_eq!(handle.join().unwrap(), 2);
+
+        drop(left_sender);
+        assert!(matches!(
+            right_receiver.recv(),
+            Err(crate::error::Error::Io(err)) if err.is_unexpected_eof()
+        ));
+    }
+
+    #[test]
+    fn nonblocking_pipe() {
+        let (mut reader, mut writer) = pipe();
+        reader.set_nonblocking(true);
+        let mut receiver = Receiver::versioned(reader, V2);
+        assert!(receiver.try_recv().unwrap().is_none());
+
+        let mut bytes = [0u8; FRAME_MAX_SIZE];
+        let len = frame::<V2>(MavLinkId::new(1, 1), 3).to_bytes(&mut bytes);
+        Write::write_all(&mut writer, &bytes[..5]).unwrap();
+        assert!(receiver.try_recv().unwrap().is_none());
+        Write::write_all(&mut writer, &bytes[5..len]).unwrap();
+        assert_eq!(receiver.try_recv().unwrap().unwrap().sequence(), 3);
+
+        drop(receiver);
+        assert!(Write::write_all(&mut writer, &bytes[..len]).is_err());
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn async_pipe() {
+        use crate::io::{AsyncReceiver, AsyncSender};
+
+        let (reader, writer) = pipe();
+        let handle = std::thread::spawn(move || {
+            futures::executor::block_on(async {
+                let mut receiver = AsyncReceiver::versioned(reader, V2);
+                receiver.recv().await.unwrap().sequence()
+            })
+        });
+
+        futures::executor::block_on(async {
+            let mut sender = AsyncSender::versioned(writer, V2);
+            sender
+                .send(&frame::<V2>(MavLinkId::new(1, 1), 4))
+                .await
+                .unwrap();
+        });
+        assert_eq!(handle.join().unwrap(), 4);
+    }
+
+    #[test]
+    fn bus_broadcasts_whole_frames() {
+        let bus = VirtualBus::new();
+        let (mut reader_0, writer_0) = bus.connect();
+        let (reader_1, mut writer_1) = bus.connect();
+        let (reader_2, writer_2) = bus.connect();
+        assert_eq!(bus.len(), 3);
+
+        // Node 1 writes garbage and a frame in small chunks
+        let mut bytes = [0u8; FRAME_MAX_SIZE];
+        let len = frame::<V2>(MavLinkId::new(2, 1), 1).to_bytes(&mut bytes);
+        Write::write_all(&mut writer_1, &[0, 1, 2]).unwrap();
+        Write::write_all(&mut writer_1, &bytes[..3]).unwrap();
+        // Node 0 writes a whole frame in between
+        Sender::versioned(writer_0, V2)
+            .send(&frame::<V2>(MavLinkId::new(1, 1), 0))
+            .unwrap();
+        for chunk in bytes[3..len].chunks(3) {
+            Write::write_all(&mut writer_1, chunk).unwrap();
+        }
+
+        let mut receiver_2 = Receiver::versioned(reader_2, V2);
+        assert_eq!(receiver_2.recv().unwrap().system_id(), 1);
+        assert_eq!(receiver_2.recv().unwrap().system_id(), 2);
+
+        let mut receiver_1 = Receiver::versioned(reader_1, V2);
+        assert_eq!(receiver_1.recv().unwrap().system_id(), 1);
+
+        // Sender does not receive its own frames
+        reader_0.set_nonblocking(true);
+        let mut receiver_0 = Receiver::versioned(reader_0, V2);
+        assert_eq!(receiver_0.try_recv().unwrap().unwrap().system_id(), 2);
+        assert!(receiver_0.try_recv().unwrap().is_none());
+
+        // Nodes with dropped readers are disconnected
+        drop(receiver_0);
+        Sender::versioned(writer_2, V2)
+            .send(&frame::<V2>(MavLinkId::new(3, 1), 0))
+            .unwrap();
+        assert_eq!(bus.len(), 2);
+        assert_eq!(receiver_1.recv().unwrap().system_id(), 3);
+    }
+
+    #[test]
+    fn bus_never_reuses_node_ids() {
+        let bus = VirtualBus::new();
+        let (_reader_0, writer_0) = bus.connect();
+        let (reader_1, writer_1) = bus.connect();
+
+        // Disconnect the last node
+        drop(reader_1);
+        let mut sender_0 = Sender::versioned(writer_0, V2);
+        sender_0
+            .send(&frame::<V2>(MavLinkId::new(1, 1), 0))
+            .unwrap();
+        assert_eq!(bus.len(), 1);
+
+        // Writer of a disconnected node still reaches the new node
+        let (reader_2, _writer_2) = bus.connect();
+        Sender::versioned(writer_1, V2)
+            .send(&frame::<V2>(MavLinkId::new(2, 1), 0))
+            .unwrap();
+        let mut receiver_2 = Receiver::versioned(reader_2, V2);
+        assert_eq!(receiver_2.recv().unwrap().system_id(), 2);
+    }
+}
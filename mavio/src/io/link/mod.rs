@@ -0,0 +1,401 @@
+//! # Link factory
+//!
+//! Creates [`Receiver`] / [`Sender`] pairs from connection strings that are used by
+//! [MAVProxy](https://ardupilot.org/mavproxy/), [mavlink-router](https://github.com/mavlink-router/mavlink-router),
+//! and [pymavlink](https://github.com/ArduPilot/pymavlink):
+//!
+//! | Connection string           | Link                                                        |
+//! |-----------------------------|-------------------------------------------------------------|
+//! | `udpin:<host>:<port>`       | listens for UDP datagrams and replies to the latest peer    |
+//! | `udp:<host>:<port>`         | same as `udpin`                                             |
+//! | `udpout:<host>:<port>`      | sends UDP datagrams to a remote address                     |
+//! | `tcpin:<host>:<port>`       | accepts a single TCP connection                             |
+//! | `tcpout:<host>:<port>`      | connects to a TCP server                                    |
+//! | `tcp:<host>:<port>`         | same as `tcpout`                                            |
+//! | `serial:<path>[:<baud>]`    | serial port, default baud rate is [`DEFAULT_BAUD_RATE`]     |
+//!
+//! Serial links are available on Unix platforms with `serial` feature enabled. Serial ports are
+//! configured as 8N1 without flow control, use [`SerialPort`] directly for other settings.
+//!
+//! Use [`LinkAddr::open`] to create a synchronous [`Receiver`] / [`Sender`] pair and
+//! [`LinkAddr::open_async`] (requires `tokio-net` feature) for [`AsyncReceiver`] / [`AsyncSender`].
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use mavio::io::link::LinkAddr;
+//! use mavio::protocol::V2;
+//!
+//! let link: LinkAddr = "udpin:0.0.0.0:14550".parse().unwrap();
+//! let (mut receiver, mut sender) = link.open::<V2>().unwrap();
+//!
+//! let frame = receiver.recv().unwrap();
+//! sender.send(&frame).unwrap();
+//! ```
+//!
+//! # In-memory links
+//!
+//! For tests and simulations, [`pipe`] and [`duplex`] create in-memory links, while [`VirtualBus`]
+//! connects multiple simulated nodes that broadcast frames to each other. In-memory links implement
+//! both synchronous and asynchronous I/O traits.
+//!
+//! [`FaultyReader`] and [`FaultyWriter`] wrap any reader or writer and inject reproducible faults
+//! such as dropped bytes, bit flips, duplicated or reordered frames, latency, and garbage bursts.
+//!
+//! [`AsyncReceiver`]: crate::io::AsyncReceiver
+//! [`AsyncSender`]: crate::io::AsyncSender
+
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+
+use crate::io::{Receiver, Sender, StdIoReader, StdIoWriter};
+use crate::protocol::MaybeVersioned;
+
+use crate::prelude::*;
+
+mod fault;
+pub use fault::{FaultConfig, FaultStats, FaultyReader, FaultyWriter, DEFAULT_MAX_GARBAGE_LEN};
+mod memory;
+pub use memory::{duplex, pipe, BusWriter, PipeReader, PipeWriter, VirtualBus};
+mod udp;
+pub use udp::{UdpReader, UdpWriter};
+
+#[cfg(all(feature = "serial", unix))]
+mod serial;
+#[cfg(all(feature = "serial", unix, feature = "tokio-net"))]
+pub use serial::AsyncSerialPort;
+#[cfg(all(feature = "serial", unix))]
+pub use serial::{FlowControl, SerialConfig, SerialPort};
+
+#[cfg(feature = "tokio-net")]
+mod tokio;
+#[cfg(feature = "tokio-net")]
+pub use tokio::{AsyncLinkReader, AsyncLinkReceiver, AsyncLinkSender, AsyncLinkWriter};
+
+/// Default baud rate for serial links.
+pub const DEFAULT_BAUD_RATE: u32 = 57600;
+
+/// [`Receiver`] created by [`LinkAddr::open`].
+pub type LinkReceiver<V> = Receiver<std::io::Error, StdIoReader<LinkReader>, V>;
+/// [`Sender`] created by [`LinkAddr::open`].
+pub type LinkSender<V> = Sender<std::io::Error, StdIoWriter<LinkWriter>, V>;
+
+/// Link described by a connection string.
+///
+/// See [module](self) documentation for supported formats.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LinkAddr {
+    /// Listen for UDP datagrams on a local address.
+    UdpIn(String),
+    /// Send UDP datagrams to a remote address.
+    UdpOut(String),
+    /// Accept a single TCP connection on a local address.
+    ///
+    /// The listener is closed once the first client is connected, so further clients are refused.
+    /// Open the link again to accept another connection.
+    TcpIn(String),
+    /// Connect to a remote TCP server.
+    TcpOut(String),
+    /// Serial port.
+    Serial {
+        /// Path to a serial device.
+        path: String,
+        /// Baud rate.
+        baud_rate: u32,
+    },
+}
+
+impl LinkAddr {
+    /// Opens link and returns synchronous [`Receiver`] / [`Sender`] pair.
+    ///
+    /// Blocks until the link is ready. For `tcpin` links this means that a client is connected,
+    /// the link serves only this client.
+    pub fn open<V: MaybeVersioned>(&self) -> Result<(LinkReceiver<V>, LinkSender<V>)> {
+        let (reader, writer) = match self {
+            LinkAddr::UdpIn(addr) => {
+                let (reader, writer) = udp::bind(addr)?;
+                (LinkReader::Udp(reader), LinkWriter::Udp(writer))
+            }
+            LinkAddr::UdpOut(addr) => {
+                let (reader, writer) = udp::connect(addr)?;
+                (LinkReader::Udp(reader), LinkWriter::Udp(writer))
+            }
+            LinkAddr::TcpIn(addr) => {
+                let (stream, _) = TcpListener::bind(addr)?.accept()?;
+                (
+                    LinkReader::Tcp(stream.try_clone()?),
+                    LinkWriter::Tcp(stream),
+                )
+            }
+            LinkAddr::TcpOut(addr) => {
+                let stream = TcpStream::connect(addr)?;
+                (
+                    LinkReader::Tcp(stream.try_clone()?),
+                    LinkWriter::Tcp(stream),
+                )
+            }
+            #[cfg(all(feature = "serial", unix))]
+            LinkAddr::Serial { path, baud_rate } => {
+                let port = SerialPort::open(path, &SerialConfig::new(*baud_rate))?;
+                (
+                    LinkReader::Serial(port.try_clone()?),
+                    LinkWriter::Serial(port),
+                )
+            }
+            #[cfg(not(all(feature = "serial", unix)))]
+            LinkAddr::Serial { .. } => return Err(serial_unsupported().into()),
+        };
+
+        Ok((
+            Receiver::new(StdIoReader::new(reader)),
+            Sender::new(StdIoWriter::new(writer)),
+        ))
+    }
+}
+
+impl FromStr for LinkAddr {
+    type Err = Error;
+
+    /// Parses connection string.
+    ///
+    /// Returns [`Error::Io`] with [`ErrorKind::InvalidInput`] if connection string is invalid.
+    fn from_str(s: &str) -> Result<Self> {
+        let (scheme, rest) = s
+            .split_once(':')
+            .ok_or_else(|| invalid_input("connection string should start with a link type"))?;
+
+        let link = match scheme {
+            "udpin" | "udp" => LinkAddr::UdpIn(parse_socket_addr(rest)?),
+            "udpout" => LinkAddr::UdpOut(parse_socket_addr(rest)?),
+            "tcpin" => LinkAddr::TcpIn(parse_socket_addr(rest)?),
+            "tcpout" | "tcp" => LinkAddr::TcpOut(parse_socket_addr(rest)?),
+            "serial" => parse_serial(rest)?,
+            _ => return Err(invalid_input("unknown link type")),
+        };
+
+        Ok(link)
+    }
+}
+
+impl Display for LinkAddr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            LinkAddr::UdpIn(addr) => write!(f, "udpin:{addr}"),
+            LinkAddr::UdpOut(addr) => write!(f, "udpout:{addr}"),
+            LinkAddr::TcpIn(addr) => write!(f, "tcpin:{addr}"),
+            LinkAddr::TcpOut(addr) => write!(f, "tcpout:{addr}"),
+            LinkAddr::Serial { path, baud_rate } => write!(f, "serial:{path}:{baud_rate}"),
+        }
+    }
+}
+
+/// Reader of a link opened by [`LinkAddr::open`].
+#[derive(Debug)]
+pub enum LinkReader {
+    /// TCP stream.
+    Tcp(TcpStream),
+    /// UDP socket.
+    Udp(UdpReader),
+    /// Serial port.
+    #[cfg(all(feature = "serial", unix))]
+    Serial(SerialPort),
+}
+
+impl Read for LinkReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            LinkReader::Tcp(stream) => stream.read(buf),
+            LinkReader::Udp(reader) => reader.read(buf),
+            #[cfg(all(feature = "serial", unix))]
+            LinkReader::Serial(port) => port.read(buf),
+        }
+    }
+}
+
+/// Writer of a link opened by [`LinkAddr::open`].
+#[derive(Debug)]
+pub enum LinkWriter {
+    /// TCP stream.
+    Tcp(TcpStream),
+    /// UDP socket.
+    Udp(UdpWriter),
+    /// Serial port.
+    #[cfg(all(feature = "serial", unix))]
+    Serial(SerialPort),
+}
+
+impl Write for LinkWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            LinkWriter::Tcp(stream) => stream.write(buf),
+            LinkWriter::Udp(writer) => writer.write(buf),
+            #[cfg(all(feature = "serial", unix))]
+            LinkWriter::Serial(port) => port.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            LinkWriter::Tcp(stream) => stream.flush(),
+            LinkWriter::Udp(writer) => writer.flush(),
+            #[cfg(all(feature = "serial", unix))]
+            LinkWriter::Serial(port) => port.flush(),
+        }
+    }
+}
+
+fn parse_socket_addr(addr: &str) -> Result<String> {
+    let (host, port) = addr
+        .rsplit_once(':')
+        .ok_or_else(|| invalid_input("address should be in `<host>:<port>` format"))?;
+    if host.is_empty() || port.parse::<u16>().is_err() {
+        return Err(invalid_input("address should be in `<host>:<port>` format"));
+    }
+    Ok(addr.to_string())
+}
+
+fn parse_serial(rest: &str) -> Result<LinkAddr> {
+    let (path, baud_rate) = match rest.rsplit_once(':') {
+        Some((path, baud_rate)) if baud_rate.chars().all(|c| c.is_ascii_digit()) => {
+            let baud_rate = baud_rate
+                .parse()
+                .map_err(|_| invalid_input("invalid baud rate"))?;
+            (path, baud_rate)
+        }
+        _ => (rest, DEFAULT_BAUD_RATE),
+    };
+    if path.is_empty() {
+        return Err(invalid_input("serial device path is empty"));
+    }
+    Ok(LinkAddr::Serial {
+        path: path.to_string(),
+        baud_rate,
+    })
+}
+
+pub(crate) fn resolve(addr: &str) -> Result<SocketAddr> {
+    addr.to_socket_addrs()?
+        .next()
+        .ok_or_else(|| invalid_input("address can't be resolved"))
+}
+
+fn invalid_input(msg: &'static str) -> Error {
+    std::io::Error::new(ErrorKind::InvalidInput, msg).into()
+}
+
+#[cfg(not(all(feature = "serial", unix)))]
+pub(crate) fn serial_unsupported() -> std::io::Error {
+    std::io::Error::new(
+        ErrorKind::Unsupported,
+        "serial links require `serial` feature on Unix platforms",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_connection_strings() {
+        let cases = [
+            (
+                "udpin:0.0.0.0:14550",
+                LinkAddr::UdpIn("0.0.0.0:14550".into()),
+            ),
+            ("udp:[::]:14550", LinkAddr::UdpIn("[::]:14550".into())),
+            (
+                "udpout:localhost:14550",
+                LinkAddr::UdpOut("localhost:14550".into()),
+            ),
+            ("tcpin:0.0.0.0:5760", LinkAddr::TcpIn("0.0.0.0:5760".into())),
+            (
+                "tcp:127.0.0.1:5760",
+                LinkAddr::TcpOut("127.0.0.1:5760".into()),
+            ),
+            (
+                "serial:/dev/ttyUSB0:115200",
+                LinkAddr::Serial {
+                    path: "/dev/ttyUSB0".into(),
+                    baud_rate: 115200,
+                },
+            ),
+            (
+                "serial:/dev/ttyUSB0",
+                LinkAddr::Serial {
+                    path: "/dev/ttyUSB0".into(),
+                    baud_rate: DEFAULT_BAUD_RATE,
+                },
+            ),
+        ];
+        for (s, expected) in cases {
+            assert_eq!(s.parse::<LinkAddr>().unwrap(), expected, "{s}");
+        }
+
+        for s in [
+            "",
+            "udpin",
+            "udpin:14550",
+            "tcp:host:port",
+            "ftp:host:21",
+            "serial:",
+        ] {
+            assert!(s.parse::<LinkAddr>().is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn udp_round_trip() {
+        use crate::protocol::V2;
+        use crate::utils::test_utils::{frame, SENDER};
+
+        let (reader, writer) = udp::bind("127.0.0.1:0").unwrap();
+        let server_addr = reader.local_addr().unwrap();
+        let mut server_receiver =
+            Receiver::versioned(StdIoReader::new(LinkReader::Udp(reader)), V2);
+        let mut server_sender = Sender::versioned(StdIoWriter::new(LinkWriter::Udp(writer)), V2);
+
+        // Server doesn't know its peer yet
+        assert!(server_sender.send(&frame(SENDER, 0)).is_err());
+
+        let (mut client_receiver, mut client_sender) = LinkAddr::UdpOut(server_addr.to_string())
+            .open::<V2>()
+            .unwrap();
+
+        client_sender.send(&frame(SENDER, 1)).unwrap();
+        assert_eq!(server_receiver.recv().unwrap().sequence(), 1);
+
+        server_sender.send(&frame(SENDER, 2)).unwrap();
+        assert_eq!(client_receiver.recv().unwrap().sequence(), 2);
+    }
+
+    #[cfg(not(all(feature = "serial", unix)))]
+    #[test]
+    fn serial_is_unsupported() {
+        let link: LinkAddr = "serial:/dev/null".parse().unwrap();
+        assert!(link.open::<crate::protocol::V2>().is_err());
+    }
+
+    #[cfg(all(feature = "serial", target_os = "linux"))]
+    #[test]
+    fn serial_link() {
+        use crate::protocol::V2;
+        use crate::utils::test_utils::{frame, SENDER};
+
+        let (master, path) = serial::tests::open_pty();
+        let link: LinkAddr = format!("serial:{path}:115200").parse().unwrap();
+        let (mut receiver, mut sender) = link.open::<V2>().unwrap();
+
+        let frame = frame::<V2>(SENDER, 7);
+
+        let mut master_sender =
+            Sender::versioned(StdIoWriter::new(master.try_clone().unwrap()), V2);
+        master_sender.send(&frame).unwrap();
+        assert_eq!(receiver.recv().unwrap().sequence(), 7);
+
+        sender.send(&frame).unwrap();
+        let mut master_receiver = Receiver::versioned(StdIoReader::new(master), V2);
+        assert_eq!(master_receiver.recv().unwrap().sequence(), 7);
+    }
+}
@@ -0,0 +1,476 @@
+//! Serial links.
+
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read, Write};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+use crate::io::link::DEFAULT_BAUD_RATE;
+
+/// Flow control of a serial port.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FlowControl {
+    /// No flow control.
+    #[default]
+    None,
+    /// Hardware flow control using RTS/CTS lines.
+    Hardware,
+    /// Software flow control using XON/XOFF characters.
+    Software,
+}
+
+/// Configuration of a serial port.
+///
+/// Serial ports are always configured in raw mode with 8 data bits, no parity, and one stop bit
+/// (8N1).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SerialConfig {
+    /// Baud rate.
+    pub baud_rate: u32,
+    /// Flow control.
+    pub flow_control: FlowControl,
+}
+
+impl SerialConfig {
+    /// Creates configuration with the specified baud rate and no flow control.
+    pub fn new(baud_rate: u32) -> Self {
+        Self {
+            baud_rate,
+            flow_control: FlowControl::None,
+        }
+    }
+
+    /// Sets flow control.
+    pub fn flow_control(self, flow_control: FlowControl) -> Self {
+        Self {
+            flow_control,
+            ..self
+        }
+    }
+}
+
+impl Default for SerialConfig {
+    /// Configuration with [`DEFAULT_BAUD_RATE`] and no flow control.
+    fn default() -> Self {
+        Self::new(DEFAULT_BAUD_RATE)
+    }
+}
+
+/// Serial port configured via termios.
+///
+/// Implements [`Read`] and [`Write`], use [`SerialPort::try_clone`] to obtain independent handles
+/// for [`StdIoReader`](crate::io::StdIoReader) and [`StdIoWriter`](crate::io::StdIoWriter). Reads
+/// block until at least one byte is available.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # #[cfg(not(feature = "dlct-minimal"))]
+/// # fn main() {}
+/// # #[cfg(feature = "dlct-minimal")]
+/// # fn main() -> mavio::error::Result<()> {
+/// use mavio::io::link::{FlowControl, SerialConfig, SerialPort};
+/// use mavio::prelude::*;
+///
+/// let config = SerialConfig::new(115200).flow_control(FlowControl::Hardware);
+/// let port = SerialPort::open("/dev/ttyUSB0", &config)?;
+///
+/// let mut receiver = Receiver::versionless(StdIoReader::new(port.try_clone()?));
+/// let mut sender = Sender::versionless(StdIoWriter::new(port));
+///
+/// let frame = receiver.recv()?;
+/// sender.send(&frame)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SerialPort {
+    file: File,
+}
+
+impl SerialPort {
+    /// Opens serial device and configures it according to `config`.
+    ///
+    /// Returns [`ErrorKind::InvalidInput`] if baud rate is not supported by the platform.
+    pub fn open<P: AsRef<Path>>(path: P, config: &SerialConfig) -> std::io::Result<Self> {
+        let file = open_nonblocking(path.as_ref(), config)?;
+        set_nonblocking(file.as_raw_fd(), false)?;
+        Ok(Self { file })
+    }
+
+    /// Creates a new independently owned handle to the same serial port.
+    pub fn try_clone(&self) -> std::io::Result<Self> {
+        Ok(Self {
+            file: self.file.try_clone()?,
+        })
+    }
+}
+
+impl Read for SerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Read for &SerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        (&self.file).read(buf)
+    }
+}
+
+impl Write for SerialPort {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        drain(self.file.as_raw_fd())
+    }
+}
+
+impl Write for &SerialPort {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        (&self.file).write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        drain(self.file.as_raw_fd())
+    }
+}
+
+impl AsFd for SerialPort {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.file.as_fd()
+    }
+}
+
+impl AsRawFd for SerialPort {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+/// Opens serial device in non-blocking mode and configures it.
+fn open_nonblocking(path: &Path, config: &SerialConfig) -> std::io::Result<File> {
+    // Validate baud rate before touching the device
+    let speed = speed(config.baud_rate)?;
+
+    // `O_NONBLOCK` prevents blocking on devices that wait for carrier detect
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK)
+        .open(path)?;
+    configure(file.as_raw_fd(), speed, config.flow_control)?;
+    Ok(file)
+}
+
+fn configure(fd: RawFd, speed: libc::speed_t, flow_control: FlowControl) -> std::io::Result<()> {
+    // SAFETY: `termios` is a plain C struct that is fully initialized by `tcgetattr`.
+    let mut termios: libc::termios = unsafe { core::mem::zeroed() };
+    // SAFETY: `fd` is an open file descriptor and `termios` is a valid pointer.
+    check(unsafe { libc::tcgetattr(fd, &mut termios) })?;
+
+    // SAFETY: `termios` is a valid pointer.
+    unsafe { libc::cfmakeraw(&mut termios) };
+
+    // 8N1, ignore modem control lines, enable receiver
+    termios.c_cflag &= !(libc::CSIZE | libc::PARENB | libc::CSTOPB | libc::CRTSCTS);
+    termios.c_cflag |= libc::CS8 | libc::CLOCAL | libc::CREAD;
+    termios.c_iflag &= !(libc::IXON | libc::IXOFF | libc::IXANY);
+    match flow_control {
+        FlowControl::None => {}
+        FlowControl::Hardware => termios.c_cflag |= libc::CRTSCTS,
+        FlowControl::Software => termios.c_iflag |= libc::IXON | libc::IXOFF,
+    }
+
+    // Block until at least one byte is available
+    termios.c_cc[libc::VMIN] = 1;
+    termios.c_cc[libc::VTIME] = 0;
+
+    // SAFETY: `termios` is a valid pointer.
+    check(unsafe { libc::cfsetispeed(&mut termios, speed) })?;
+    // SAFETY: `termios` is a valid pointer.
+    check(unsafe { libc::cfsetospeed(&mut termios, speed) })?;
+    // SAFETY: `fd` is an open file descriptor and `termios` is a valid pointer.
+    check(unsafe { libc::tcsetattr(fd, libc::TCSANOW, &termios) })?;
+    // SAFETY: `fd` is an open file descriptor.
+    check(unsafe { libc::tcflush(fd, libc::TCIOFLUSH) })?;
+
+    Ok(())
+}
+
+fn set_nonblocking(fd: RawFd, nonblocking: bool) -> std::io::Result<()> {
+    // SAFETY: `fd` is an open file descriptor.
+    let flags = check(unsafe { libc::fcntl(fd, libc::F_GETFL) })?;
+    let flags = if nonblocking {
+        flags | libc::O_NONBLOCK
+    } else {
+        flags & !libc::O_NONBLOCK
+    };
+    // SAFETY: `fd` is an open file descriptor.
+    check(unsafe { libc::fcntl(fd, libc::F_SETFL, flags) })?;
+    Ok(())
+}
+
+fn drain(fd: RawFd) -> std::io::Result<()> {
+    // SAFETY: `fd` is an open file descriptor.
+    check(unsafe { libc::tcdrain(fd) })?;
+    Ok(())
+}
+
+fn check(ret: libc::c_int) -> std::io::Result<libc::c_int> {
+    if ret < 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
+
+/// Converts baud rate into termios speed constant.
+fn speed(baud_rate: u32) -> std::io::Result<libc::speed_t> {
+    let speed = match baud_rate {
+        1200 => libc::B1200,
+        2400 => libc::B2400,
+        4800 => libc::B4800,
+        9600 => libc::B9600,
+        19200 => libc::B19200,
+        38400 => libc::B38400,
+        57600 => libc::B57600,
+        115200 => libc::B115200,
+        230400 => libc::B230400,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        460800 => libc::B460800,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        500000 => libc::B500000,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        921600 => libc::B921600,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        1000000 => libc::B1000000,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        1500000 => libc::B1500000,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        2000000 => libc::B2000000,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        3000000 => libc::B3000000,
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        4000000 => libc::B4000000,
+        _ => {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                "unsupported baud rate",
+            ))
+        }
+    };
+    Ok(speed)
+}
+
+#[cfg(feature = "tokio-net")]
+pub use async_serial::AsyncSerialPort;
+
+#[cfg(feature = "tokio-net")]
+mod async_serial {
+    use std::fs::File;
+    use std::io::{Read, Write};
+    use std::os::fd::AsRawFd;
+    use std::path::Path;
+    use std::pin::Pin;
+    use std::task::{ready, Context, Poll};
+
+    use tokio::io::unix::AsyncFd;
+    use tokio::io::ReadBuf;
+
+    use super::{drain, open_nonblocking, SerialConfig};
+
+    /// Asynchronous serial port based on Tokio.
+    ///
+    /// Implements Tokio [`AsyncRead`](tokio::io::AsyncRead) and
+    /// [`AsyncWrite`](tokio::io::AsyncWrite), so it can be used with
+    /// [`TokioReader`](crate::io::TokioReader) and [`TokioWriter`](crate::io::TokioWriter) after
+    /// [`tokio::io::split`]. Requires Tokio runtime with I/O driver enabled.
+    ///
+    /// Written data is passed to the kernel immediately, so flushing and shutting down never wait
+    /// for transmission. Use [`AsyncSerialPort::drain`] to wait until data is physically sent.
+    #[derive(Debug)]
+    pub struct AsyncSerialPort {
+        fd: AsyncFd<File>,
+    }
+
+    impl AsyncSerialPort {
+        /// Opens serial device and configures it according to `config`.
+        ///
+        /// See [`SerialPort::open`](super::SerialPort::open).
+        pub fn open<P: AsRef<Path>>(path: P, config: &SerialConfig) -> std::io::Result<Self> {
+            let file = open_nonblocking(path.as_ref(), config)?;
+            Ok(Self {
+                fd: AsyncFd::new(file)?,
+            })
+        }
+
+        /// Waits until all written data is transmitted.
+        ///
+        /// This call blocks the current thread. Avoid calling it from asynchronous tasks or wrap it
+        /// into [`block_in_place`](https://docs.rs/tokio/latest/tokio/task/fn.block_in_place.html).
+        pub fn drain(&self) -> std::io::Result<()> {
+            drain(self.fd.get_ref().as_raw_fd())
+        }
+    }
+
+    impl tokio::io::AsyncRead for AsyncSerialPort {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            loop {
+                let mut guard = ready!(self.fd.poll_read_ready(cx))?;
+                let unfilled = buf.initialize_unfilled();
+                match guard.try_io(|fd| fd.get_ref().read(unfilled)) {
+                    Ok(Ok(n)) => {
+                        buf.advance(n);
+                        return Poll::Ready(Ok(()));
+                    }
+                    Ok(Err(err)) => return Poll::Ready(Err(err)),
+                    Err(_would_block) => continue,
+                }
+            }
+        }
+    }
+
+    impl tokio::io::AsyncWrite for AsyncSerialPort {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            loop {
+                let mut guard = ready!(self.fd.poll_write_ready(cx))?;
+                match guard.try_io(|fd| fd.get_ref().write(buf)) {
+                    Ok(result) => return Poll::Ready(result),
+                    Err(_would_block) => continue,
+                }
+            }
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+pub(crate) mod tests {
+    use std::ffi::CStr;
+    use std::os::fd::FromRawFd;
+
+    use super::*;
+
+    /// Opens pseudo-terminal and returns its master side along with the path to the slave device.
+    pub(crate) fn open_pty() -> (File, String) {
+        // SAFETY: arguments are valid flags.
+        let master = unsafe { libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY) };
+        check(master).unwrap();
+        // SAFETY: `master` is an open file descriptor that is owned by the returned file.
+        let master_file = unsafe { File::from_raw_fd(master) };
+        // SAFETY: `master` is an open pseudo-terminal master.
+        check(unsafe { libc::grantpt(master) }).unwrap();
+        // SAFETY: `master` is an open pseudo-terminal master.
+        check(unsafe { libc::unlockpt(master) }).unwrap();
+
+        let mut name = [0 as libc::c_char; 128];
+        // SAFETY: `name` is a valid buffer of the specified length.
+        let ret = unsafe { libc::ptsname_r(master, name.as_mut_ptr(), name.len()) };
+        assert_eq!(ret, 0);
+        // SAFETY: `ptsname_r` writes a null-terminated string.
+        let path = unsafe { CStr::from_ptr(name.as_ptr()) };
+
+        (master_file, path.to_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn unsupported_baud_rate() {
+        let (_master, path) = open_pty();
+        let err = SerialPort::open(&path, &SerialConfig::new(12345)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn software_flow_control() {
+        let (_master, path) = open_pty();
+        let config = SerialConfig::new(57600).flow_control(FlowControl::Software);
+        let port = SerialPort::open(&path, &config).unwrap();
+
+        // SAFETY: `termios` is fully initialized by `tcgetattr`.
+        let mut termios: libc::termios = unsafe { core::mem::zeroed() };
+        // SAFETY: `port` is an open file descriptor and `termios` is a valid pointer.
+        check(unsafe { libc::tcgetattr(port.as_raw_fd(), &mut termios) }).unwrap();
+        assert_ne!(termios.c_iflag & libc::IXON, 0);
+        assert_ne!(termios.c_iflag & libc::IXOFF, 0);
+    }
+
+    #[test]
+    fn pty_round_trip() {
+        use crate::io::{Receiver, Sender, StdIoReader, StdIoWriter};
+        use crate::protocol::V2;
+        use crate::utils::test_utils::{frame, SENDER};
+
+        let (master, path) = open_pty();
+        let port = SerialPort::open(&path, &SerialConfig::new(115200)).unwrap();
+
+        // Raw mode is applied to the slave side
+        // SAFETY: `termios` is fully initialized by `tcgetattr`.
+        let mut termios: libc::termios = unsafe { core::mem::zeroed() };
+        // SAFETY: `port` is an open file descriptor and `termios` is a valid pointer.
+        check(unsafe { libc::tcgetattr(port.as_raw_fd(), &mut termios) }).unwrap();
+        assert_eq!(termios.c_cflag & libc::CSIZE, libc::CS8);
+        assert_eq!(termios.c_cflag & (libc::PARENB | libc::CSTOPB), 0);
+        assert_eq!(termios.c_lflag & (libc::ICANON | libc::ECHO), 0);
+
+        let mut port_receiver =
+            Receiver::versioned(StdIoReader::new(port.try_clone().unwrap()), V2);
+        let mut port_sender = Sender::versioned(StdIoWriter::new(port), V2);
+        let mut master_receiver =
+            Receiver::versioned(StdIoReader::new(master.try_clone().unwrap()), V2);
+        let mut master_sender = Sender::versioned(StdIoWriter::new(master), V2);
+
+        master_sender.send(&frame(SENDER, 1)).unwrap();
+        assert_eq!(port_receiver.recv().unwrap().sequence(), 1);
+
+        port_sender.send(&frame(SENDER, 2)).unwrap();
+        assert_eq!(master_receiver.recv().unwrap().sequence(), 2);
+    }
+
+    #[cfg(all(feature = "tokio-net", feature = "tokio-rt"))]
+    #[test]
+    fn async_pty_round_trip() {
+        use crate::io::{
+            AsyncReceiver, AsyncSender, Receiver, Sender, StdIoReader, StdIoWriter, TokioReader,
+            TokioWriter,
+        };
+        use crate::protocol::V2;
+        use crate::utils::test_utils::{block_on, frame, SENDER};
+
+        let (master, path) = open_pty();
+        let mut master_receiver =
+            Receiver::versioned(StdIoReader::new(master.try_clone().unwrap()), V2);
+        let mut master_sender = Sender::versioned(StdIoWriter::new(master), V2);
+
+        block_on(async {
+            let port = AsyncSerialPort::open(&path, &SerialConfig::new(115200)).unwrap();
+            let (reader, writer) = tokio::io::split(port);
+            let mut port_receiver = AsyncReceiver::versioned(TokioReader::new(reader), V2);
+            let mut port_sender = AsyncSender::versioned(TokioWriter::new(writer), V2);
+
+            master_sender.send(&frame(SENDER, 1)).unwrap();
+            assert_eq!(port_receiver.recv().await.unwrap().sequence(), 1);
+
+            port_sender.send(&frame(SENDER, 2)).await.unwrap();
+            assert_eq!(master_receiver.recv().unwrap().sequence(), 2);
+        });
+    }
+}
@@ -0,0 +1,278 @@
+//! Asynchronous links based on Tokio.
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+
+#[cfg(not(all(feature = "serial", unix)))]
+use crate::io::link::serial_unsupported;
+use crate::io::link::{resolve, udp, LinkAddr};
+#[cfg(all(feature = "serial", unix))]
+use crate::io::link::{AsyncSerialPort, SerialConfig};
+use crate::io::{AsyncRead, AsyncReceiver, AsyncSender, AsyncWrite};
+use crate::protocol::MaybeVersioned;
+
+use crate::prelude::*;
+
+/// [`AsyncReceiver`] created by [`LinkAddr::open_async`].
+pub type AsyncLinkReceiver<V> = AsyncReceiver<std::io::Error, AsyncLinkReader, V>;
+/// [`AsyncSender`] created by [`LinkAddr::open_async`].
+pub type AsyncLinkSender<V> = AsyncSender<std::io::Error, AsyncLinkWriter, V>;
+
+/// Maximum size of UDP datagram payload.
+const DATAGRAM_MAX_SIZE: usize = 65536;
+
+/// Remote address shared between reading and writing halves of a UDP link.
+type Peer = Arc<Mutex<Option<SocketAddr>>>;
+
+/// Reader of a link opened by [`LinkAddr::open_async`].
+#[derive(Debug)]
+pub struct AsyncLinkReader {
+    inner: ReaderInner,
+}
+
+#[derive(Debug)]
+enum ReaderInner {
+    Tcp(OwnedReadHalf),
+    Udp {
+        socket: Arc<UdpSocket>,
+        peer: Peer,
+        track_peer: bool,
+        buf: Box<[u8]>,
+        pos: usize,
+        len: usize,
+    },
+    #[cfg(all(feature = "serial", unix))]
+    Serial(tokio::io::ReadHalf<AsyncSerialPort>),
+}
+
+/// Writer of a link opened by [`LinkAddr::open_async`].
+#[derive(Debug)]
+pub struct AsyncLinkWriter {
+    inner: WriterInner,
+}
+
+#[derive(Debug)]
+enum WriterInner {
+    Tcp(OwnedWriteHalf),
+    Udp {
+        socket: Arc<UdpSocket>,
+        peer: Peer,
+    },
+    #[cfg(all(feature = "serial", unix))]
+    Serial(tokio::io::WriteHalf<AsyncSerialPort>),
+}
+
+impl LinkAddr {
+    /// Opens link and returns asynchronous [`AsyncReceiver`] / [`AsyncSender`] pair.
+    ///
+    /// Waits until the link is ready. For `tcpin` links this means that a client is connected,
+    /// the link serves only this client.
+    pub async fn open_async<V: MaybeVersioned>(
+        &self,
+    ) -> Result<(AsyncLinkReceiver<V>, AsyncLinkSender<V>)> {
+        let (reader, writer) = match self {
+            LinkAddr::UdpIn(addr) => {
+                let socket = UdpSocket::bind(resolve(addr)?).await?;
+                split_udp(socket, None)
+            }
+            LinkAddr::UdpOut(addr) => {
+                let remote = resolve(addr)?;
+                let socket = UdpSocket::bind(udp::unspecified(&remote)).await?;
+                split_udp(socket, Some(remote))
+            }
+            LinkAddr::TcpIn(addr) => {
+                let (stream, _) = TcpListener::bind(addr.as_str()).await?.accept().await?;
+                split_tcp(stream)
+            }
+            LinkAddr::TcpOut(addr) => split_tcp(TcpStream::connect(addr.as_str()).await?),
+            #[cfg(all(feature = "serial", unix))]
+            LinkAddr::Serial { path, baud_rate } => {
+                let (reader, writer) =
+                    tokio::io::split(AsyncSerialPort::open(path, &SerialConfig::new(*baud_rate))?);
+                (
+                    AsyncLinkReader {
+                        inner: ReaderInner::Serial(reader),
+                    },
+                    AsyncLinkWriter {
+                        inner: WriterInner::Serial(writer),
+                    },
+                )
+            }
+            #[cfg(not(all(feature = "serial", unix)))]
+            LinkAddr::Serial { .. } => return Err(serial_unsupported().into()),
+        };
+
+        Ok((AsyncReceiver::new(reader), AsyncSender::new(writer)))
+    }
+}
+
+impl AsyncRead<std::io::Error> for AsyncLinkReader {
+    async fn read_exact<'a>(&'a mut self, buf: &'a mut [u8]) -> std::io::Result<()> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.read(&mut buf[filled..]).await? {
+                0 => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+                n => filled += n,
+            }
+        }
+        Ok(())
+    }
+
+    async fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> std::io::Result<usize> {
+        match &mut self.inner {
+            ReaderInner::Tcp(reader) => reader.read(buf).await,
+            #[cfg(all(feature = "serial", unix))]
+            ReaderInner::Serial(reader) => reader.read(buf).await,
+            ReaderInner::Udp {
+                socket,
+                peer,
+                track_peer,
+                buf: datagram,
+                pos,
+                len,
+            } => {
+                if buf.is_empty() {
+                    return Ok(0);
+                }
+                while *pos == *len {
+                    let (n, addr) = socket.recv_from(datagram).await?;
+                    if *track_peer {
+                        *peer.lock().unwrap() = Some(addr);
+                    }
+                    *pos = 0;
+                    *len = n;
+                }
+
+                let n = buf.len().min(*len - *pos);
+                buf[..n].copy_from_slice(&datagram[*pos..*pos + n]);
+                *pos += n;
+                Ok(n)
+            }
+        }
+    }
+}
+
+impl AsyncWrite<std::io::Error> for AsyncLinkWriter {
+    async fn write_all<'a>(&'a mut self, buf: &'a [u8]) -> std::io::Result<()> {
+        match &mut self.inner {
+            WriterInner::Tcp(writer) => writer.write_all(buf).await,
+            #[cfg(all(feature = "serial", unix))]
+            WriterInner::Serial(writer) => writer.write_all(buf).await,
+            WriterInner::Udp { socket, peer } => {
+                let peer = *peer.lock().unwrap();
+                match peer {
+                    Some(addr) => socket.send_to(buf, addr).await.map(|_| ()),
+                    None => Err(std::io::Error::new(
+                        std::io::ErrorKind::NotConnected,
+                        "UDP peer is unknown",
+                    )),
+                }
+            }
+        }
+    }
+
+    async fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.inner {
+            WriterInner::Tcp(writer) => writer.flush().await,
+            #[cfg(all(feature = "serial", unix))]
+            WriterInner::Serial(writer) => writer.flush().await,
+            WriterInner::Udp { .. } => Ok(()),
+        }
+    }
+}
+
+fn split_tcp(stream: TcpStream) -> (AsyncLinkReader, AsyncLinkWriter) {
+    let (reader, writer) = stream.into_split();
+    (
+        AsyncLinkReader {
+            inner: ReaderInner::Tcp(reader),
+        },
+        AsyncLinkWriter {
+            inner: WriterInner::Tcp(writer),
+        },
+    )
+}
+
+fn split_udp(socket: UdpSocket, remote: Option<SocketAddr>) -> (AsyncLinkReader, AsyncLinkWriter) {
+    let socket = Arc::new(socket);
+    let peer = Arc::new(Mutex::new(remote));
+    (
+        AsyncLinkReader {
+            inner: ReaderInner::Udp {
+                socket: socket.clone(),
+                peer: peer.clone(),
+                track_peer: remote.is_none(),
+                buf: vec![0u8; DATAGRAM_MAX_SIZE].into_boxed_slice(),
+                pos: 0,
+                len: 0,
+            },
+        },
+        AsyncLinkWriter {
+            inner: WriterInner::Udp { socket, peer },
+        },
+    )
+}
+
+#[cfg(all(test, feature = "tokio-rt"))]
+mod tests {
+    use super::*;
+    use crate::protocol::V2;
+    use crate::utils::test_utils::{block_on, frame, SENDER};
+
+    #[test]
+    fn udp_round_trip() {
+        block_on(async {
+            let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let server_addr = socket.local_addr().unwrap();
+            let (reader, writer) = split_udp(socket, None);
+            let mut server_receiver = AsyncReceiver::versioned(reader, V2);
+            let mut server_sender = AsyncSender::versioned(writer, V2);
+
+            // Server doesn't know its peer yet
+            assert!(server_sender.send(&frame(SENDER, 0)).await.is_err());
+
+            let (mut client_receiver, mut client_sender) =
+                LinkAddr::UdpOut(server_addr.to_string())
+                    .open_async::<V2>()
+                    .await
+                    .unwrap();
+
+            client_sender.send(&frame(SENDER, 1)).await.unwrap();
+            assert_eq!(server_receiver.recv().await.unwrap().sequence(), 1);
+
+            server_sender.send(&frame(SENDER, 2)).await.unwrap();
+            assert_eq!(client_receiver.recv().await.unwrap().sequence(), 2);
+        });
+    }
+
+    #[test]
+    fn tcp_round_trip() {
+        block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let server_addr = listener.local_addr().unwrap();
+
+            let client = tokio::spawn(async move {
+                LinkAddr::TcpOut(server_addr.to_string())
+                    .open_async::<V2>()
+                    .await
+            });
+            let (stream, _) = listener.accept().await.unwrap();
+            let (mut client_receiver, mut client_sender) = client.await.unwrap().unwrap();
+            let (reader, writer) = split_tcp(stream);
+            let mut server_receiver = AsyncReceiver::versioned(reader, V2);
+            let mut server_sender = AsyncSender::versioned(writer, V2);
+
+            client_sender.send(&frame(SENDER, 1)).await.unwrap();
+            client_sender.send(&frame(SENDER, 2)).await.unwrap();
+            assert_eq!(server_receiver.recv().await.unwrap().sequence(), 1);
+            assert_eq!(server_receiver.recv().await.unwrap().sequence(), 2);
+
+            server_sender.send(&frame(SENDER, 3)).await.unwrap();
+            assert_eq!(client_receiver.recv().await.unwrap().sequence(), 3);
+        });
+    }
+}
@@ -0,0 +1,124 @@
+//! UDP links.
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+
+use crate::io::link::resolve;
+
+use crate::prelude::*;
+
+/// Maximum size of UDP datagram payload.
+const DATAGRAM_MAX_SIZE: usize = 65536;
+
+/// Remote address shared between [`UdpReader`] and [`UdpWriter`].
+type Peer = Arc<Mutex<Option<SocketAddr>>>;
+
+/// Reading half of a UDP link.
+///
+/// Implements [`Read`] over datagrams. Datagrams are buffered, so they can be read in chunks of
+/// arbitrary size. For `udpin` links, the sender of the latest datagram becomes a peer of the
+/// corresponding [`UdpWriter`].
+#[derive(Debug)]
+pub struct UdpReader {
+    socket: UdpSocket,
+    peer: Peer,
+    track_peer: bool,
+    buf: Box<[u8]>,
+    pos: usize,
+    len: usize,
+}
+
+/// Writing half of a UDP link.
+///
+/// Each write is sent as a single datagram to the link peer. Fails with [`ErrorKind::NotConnected`]
+/// if peer is not known yet, i.e. `udpin` link hasn't received any datagrams.
+#[derive(Debug)]
+pub struct UdpWriter {
+    socket: UdpSocket,
+    peer: Peer,
+}
+
+impl UdpReader {
+    /// Local address of the socket.
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+}
+
+impl Read for UdpReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        while self.pos == self.len {
+            let (len, addr) = self.socket.recv_from(&mut self.buf)?;
+            if self.track_peer {
+                *self.peer.lock().unwrap() = Some(addr);
+            }
+            self.pos = 0;
+            self.len = len;
+        }
+
+        let n = buf.len().min(self.len - self.pos);
+        buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl Write for UdpWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let peer = *self.peer.lock().unwrap();
+        match peer {
+            Some(addr) => self.socket.send_to(buf, addr),
+            None => Err(std::io::Error::new(
+                ErrorKind::NotConnected,
+                "UDP peer is unknown",
+            )),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Binds UDP socket to a local address (`udpin`).
+pub(super) fn bind(addr: &str) -> Result<(UdpReader, UdpWriter)> {
+    let socket = UdpSocket::bind(resolve(addr)?)?;
+    Ok(split(socket, None)?)
+}
+
+/// Creates UDP socket that sends datagrams to a remote address (`udpout`).
+pub(super) fn connect(addr: &str) -> Result<(UdpReader, UdpWriter)> {
+    let remote = resolve(addr)?;
+    let socket = UdpSocket::bind(unspecified(&remote))?;
+    Ok(split(socket, Some(remote))?)
+}
+
+/// Unspecified local address of the same family as `remote`.
+pub(super) fn unspecified(remote: &SocketAddr) -> SocketAddr {
+    match remote {
+        SocketAddr::V4(_) => SocketAddr::from(([0, 0, 0, 0], 0)),
+        SocketAddr::V6(_) => SocketAddr::from(([0u16; 8], 0)),
+    }
+}
+
+fn split(socket: UdpSocket, remote: Option<SocketAddr>) -> std::io::Result<(UdpReader, UdpWriter)> {
+    let peer = Arc::new(Mutex::new(remote));
+    let writer = UdpWriter {
+        socket: socket.try_clone()?,
+        peer: peer.clone(),
+    };
+    let reader = UdpReader {
+        socket,
+        peer,
+        track_peer: remote.is_none(),
+        buf: vec![0u8; DATAGRAM_MAX_SIZE].into_boxed_slice(),
+        pos: 0,
+        len: 0,
+    };
+    Ok((reader, writer))
+}
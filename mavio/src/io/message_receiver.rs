@@ -0,0 +1,264 @@
+//! # MAVLink message receiver
+
+use core::marker::PhantomData;
+
+use crate::io::{Read, Receiver};
+use crate::protocol::{Dialect, Frame, MaybeVersioned};
+
+use crate::prelude::*;
+
+/// Defines how [`MessageReceiver`] / [`AsyncMessageReceiver`](crate::io::AsyncMessageReceiver)
+/// handle a particular class of invalid frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorPolicy {
+    /// Silently skip frame and proceed to the next one.
+    Skip,
+    /// Return an error to the caller.
+    Surface,
+    /// Skip frame and increment the corresponding counter in [`MessageStats`].
+    #[default]
+    Count,
+}
+
+/// Error handling policy for [`MessageReceiver`] /
+/// [`AsyncMessageReceiver`](crate::io::AsyncMessageReceiver).
+///
+/// By default, all invalid frames are skipped and counted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MessagePolicy {
+    /// Frames with invalid checksum ([`FrameError::Checksum`]).
+    pub checksum: ErrorPolicy,
+    /// Frames with message `ID` that is not part of the dialect ([`FrameError::NotInDialect`]).
+    pub unknown: ErrorPolicy,
+    /// Frames with valid checksum that can't be decoded ([`Error::Spec`]).
+    pub decode: ErrorPolicy,
+}
+
+impl MessagePolicy {
+    /// Policy that returns all errors to the caller.
+    pub fn surface() -> Self {
+        Self {
+            checksum: ErrorPolicy::Surface,
+            unknown: ErrorPolicy::Surface,
+            decode: ErrorPolicy::Surface,
+        }
+    }
+
+    /// Policy that silently skips all invalid frames.
+    pub fn skip() -> Self {
+        Self {
+            checksum: ErrorPolicy::Skip,
+            unknown: ErrorPolicy::Skip,
+            decode: ErrorPolicy::Skip,
+        }
+    }
+
+    pub(crate) fn process<V: MaybeVersioned, D: Dialect>(
+        &self,
+        stats: &mut MessageStats,
+        frame: Frame<V>,
+        pass_unknown: bool,
+    ) -> Result<Option<Received<V, D>>> {
+        stats.frames = stats.frames.wrapping_add(1);
+
+        match frame.validate_checksum::<D>() {
+            Ok(_) => {}
+            Err(Error::Frame(FrameError::NotInDialect(_))) if pass_unknown => {
+                return Ok(Some(Received::Unknown(frame)));
+            }
+            Err(err @ Error::Frame(FrameError::NotInDialect(_))) => {
+                return Self::handle(self.unknown, &mut stats.unknown_messages, err);
+            }
+            Err(err) => {
+                return Self::handle(self.checksum, &mut stats.checksum_errors, err);
+            }
+        }
+
+        match D::decode(frame.payload()) {
+            Ok(message) => Ok(Some(Received::Message(frame, message))),
+            Err(err) => Self::handle(self.decode, &mut stats.decode_errors, err.into()),
+        }
+    }
+
+    fn handle<T>(policy: ErrorPolicy, counter: &mut u64, err: Error) -> Result<Option<T>> {
+        match policy {
+            ErrorPolicy::Skip => Ok(None),
+            ErrorPolicy::Surface => Err(err),
+            ErrorPolicy::Count => {
+                *counter = counter.wrapping_add(1);
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Statistics collected by [`MessageReceiver`] /
+/// [`AsyncMessageReceiver`](crate::io::AsyncMessageReceiver).
+///
+/// Error counters are incremented only for errors handled with [`ErrorPolicy::Count`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MessageStats {
+    /// Total number of received frames.
+    pub frames: u64,
+    /// Number of frames with invalid checksum.
+    pub checksum_errors: u64,
+    /// Number of frames with messages that are not part of the dialect.
+    pub unknown_messages: u64,
+    /// Number of frames that failed to decode.
+    pub decode_errors: u64,
+}
+
+/// Frame received by [`MessageReceiver::recv_any`] /
+/// [`AsyncMessageReceiver::recv_any`](crate::io::AsyncMessageReceiver::recv_any).
+#[derive(Clone, Debug)]
+pub enum Received<V: MaybeVersioned, D: Dialect> {
+    /// Frame with a message decoded into a dialect.
+    Message(Frame<V>, D),
+    /// Frame with a message that is not part of the dialect.
+    Unknown(Frame<V>),
+}
+
+impl<V: MaybeVersioned, D: Dialect> Received<V, D> {
+    /// Received frame.
+    pub fn frame(&self) -> &Frame<V> {
+        match self {
+            Received::Message(frame, _) => frame,
+            Received::Unknown(frame) => frame,
+        }
+    }
+
+    /// Decoded message, if frame contains a message from the dialect.
+    pub fn message(&self) -> Option<&D> {
+        match self {
+            Received::Message(_, message) => Some(message),
+            Received::Unknown(_) => None,
+        }
+    }
+}
+
+/// Receives MAVLink messages decoded into a dialect.
+///
+/// Wraps [`Receiver`], validates checksums of incoming frames and decodes them into a dialect `D`.
+/// Invalid frames are handled according to [`MessagePolicy`]. Frames with messages that are not
+/// part of the dialect are available through [`MessageReceiver::recv_any`].
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(not(all(feature = "dlct-minimal", feature = "extras", feature = "std")))]
+/// # fn main() {}
+/// # #[cfg(all(feature = "dlct-minimal", feature = "extras", feature = "std"))]
+/// # fn main() {
+/// use mavio::dialects::minimal::messages::Heartbeat;
+/// use mavio::dialects::Minimal;
+/// use mavio::utils::{SliceReader, SliceWriter};
+/// use mavio::prelude::*;
+///
+/// # let mut buf = [0u8; 64];
+/// # let endpoint = Endpoint::v2(MavLinkId::new(1, 1));
+/// # Sender::new(SliceWriter::new(&mut buf))
+/// #     .send(&endpoint.next_frame(&Heartbeat::default()).unwrap())
+/// #     .unwrap();
+/// let reader = SliceReader::new(&buf);
+/// let mut receiver = Receiver::versionless(reader).with_dialect::<Minimal>();
+///
+/// let (frame, message) = receiver.recv().unwrap();
+/// if let Minimal::Heartbeat(heartbeat) = message {
+///     println!("{} sent heartbeat {heartbeat:?}", frame.system_id());
+/// }
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct MessageReceiver<E: Into<Error>, R: Read<E>, V: MaybeVersioned, D: Dialect> {
+    receiver: Receiver<E, R, V>,
+    policy: MessagePolicy,
+    stats: MessageStats,
+    _dialect: PhantomData<D>,
+}
+
+impl<E: Into<Error>, R: Read<E>, V: MaybeVersioned, D: Dialect> MessageReceiver<E, R, V, D> {
+    /// Creates a message receiver from [`Receiver`] with the default [`MessagePolicy`].
+    pub fn new(receiver: Receiver<E, R, V>) -> Self {
+        Self {
+            receiver,
+            policy: MessagePolicy::default(),
+            stats: MessageStats::default(),
+            _dialect: PhantomData,
+        }
+    }
+
+    /// Sets error handling policy.
+    pub fn with_policy(self, policy: MessagePolicy) -> Self {
+        Self { policy, ..self }
+    }
+
+    /// Error handling policy.
+    #[inline(always)]
+    pub fn policy(&self) -> &MessagePolicy {
+        &self.policy
+    }
+
+    /// Collected statistics.
+    #[inline(always)]
+    pub fn stats(&self) -> &MessageStats {
+        &self.stats
+    }
+
+    /// Resets collected statistics.
+    pub fn reset_stats(&mut self) {
+        self.stats = MessageStats::default();
+    }
+
+    /// Underlying [`Receiver`].
+    #[inline(always)]
+    pub fn receiver(&mut self) -> &mut Receiver<E, R, V> {
+        &mut self.receiver
+    }
+
+    /// Consumes message receiver and returns the underlying [`Receiver`].
+    pub fn into_inner(self) -> Receiver<E, R, V> {
+        self.receiver
+    }
+
+    /// Receives a frame and a message decoded into dialect `D`.
+    ///
+    /// Blocks until a valid frame with a message from dialect `D` is received or until an error
+    /// that should be surfaced according to [`MessagePolicy`] occurs. I/O errors are always
+    /// returned.
+    pub fn recv(&mut self) -> Result<(Frame<V>, D)> {
+        loop {
+            let frame = self.receiver.recv()?;
+            if let Some(Received::Message(frame, message)) =
+                self.policy.process(&mut self.stats, frame, false)?
+            {
+                return Ok((frame, message));
+            }
+        }
+    }
+
+    /// Receives a frame with either a decoded message or a message that is not part of dialect `D`.
+    ///
+    /// Frames with unknown messages are returned as [`Received::Unknown`] regardless of
+    /// [`MessagePolicy::unknown`]. Other errors are handled according to [`MessagePolicy`].
+    pub fn recv_any(&mut self) -> Result<Received<V, D>> {
+        loop {
+            let frame = self.receiver.recv()?;
+            if let Some(received) = self.policy.process(&mut self.stats, frame, true)? {
+                return Ok(received);
+            }
+        }
+    }
+}
+
+impl<E: Into<Error>, R: Read<E>, V: MaybeVersioned> Receiver<E, R, V> {
+    /// Converts receiver into a [`MessageReceiver`] that decodes messages into dialect `D`.
+    pub fn with_dialect<D: Dialect>(self) -> MessageReceiver<E, R, V, D> {
+        MessageReceiver::new(self)
+    }
+}
@@ -55,6 +55,9 @@ mod async_read_write;
 pub use async_read_write::{AsyncRead, AsyncWrite};
 
 pub mod adapters;
+#[cfg(feature = "tokio-util")]
+#[doc(inline)]
+pub use adapters::MavlinkCodec;
 #[cfg(feature = "embedded-io-async")]
 #[doc(inline)]
 pub use adapters::{EmbeddedIoAsyncReader, EmbeddedIoAsyncWriter};
@@ -74,6 +77,9 @@ pub use adapters::{TokioReader, TokioWriter};
 mod receiver;
 pub use receiver::Receiver;
 
+mod frames;
+pub use frames::{FrameIteratorExt, Frames, FramesFrom, IntoFrames, Messages};
+
 mod sender;
 pub use sender::Sender;
 
@@ -82,3 +88,31 @@ pub use async_receiver::AsyncReceiver;
 
 mod async_sender;
 pub use async_sender::AsyncSender;
+
+mod negotiating_sender;
+pub use negotiating_sender::{AsyncNegotiatingSender, NegotiatingSender};
+
+mod connection;
+pub use connection::{Connection, SendHalf};
+
+mod async_connection;
+pub use async_connection::{AsyncConnection, AsyncSendHalf};
+
+#[cfg(feature = "std")]
+pub mod link;
+
+mod message_receiver;
+pub use message_receiver::{ErrorPolicy, MessagePolicy, MessageReceiver, MessageStats, Received};
+
+mod async_message_receiver;
+pub use async_message_receiver::AsyncMessageReceiver;
+
+mod dispatcher;
+#[cfg(feature = "std")]
+pub use dispatcher::MessageChannel;
+#[cfg(feature = "tokio-rt")]
+pub use dispatcher::TokioMessageChannel;
+pub use dispatcher::{
+    AsyncDispatcher, Dispatcher, Filter, SubscriptionId, SubscriptionStats, Subscriptions,
+    DEFAULT_SUBSCRIPTIONS_CAPACITY,
+};
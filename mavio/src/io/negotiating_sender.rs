@@ -0,0 +1,271 @@
+//! # MAVLink frame writer with protocol version negotiation
+
+use crate::io::{AsyncSender, AsyncWrite, Sender, Write};
+use crate::protocol::{
+    Frame, MavLinkId, MaybeVersioned, Message, NegotiatingEndpoint, PeerVersion, Versionless,
+    DEFAULT_NEGOTIATOR_CAPACITY,
+};
+
+use crate::prelude::*;
+
+/// Sends MAVLink messages in a protocol version negotiated with each peer.
+///
+/// Combines versionless [`Sender`] with [`NegotiatingEndpoint`]. Frames received from peers should
+/// be passed to [`NegotiatingSender::handle`] which updates negotiated versions and answers
+/// `MAV_CMD_REQUEST_PROTOCOL_VERSION` requests. Outgoing messages are wrapped into frames of the
+/// version negotiated with their target.
+///
+/// See [`AsyncNegotiatingSender`] for asynchronous counterpart.
+#[derive(Debug)]
+pub struct NegotiatingSender<
+    E: Into<Error>,
+    W: Write<E>,
+    const N: usize = DEFAULT_NEGOTIATOR_CAPACITY,
+> {
+    sender: Sender<E, W, Versionless>,
+    endpoint: NegotiatingEndpoint<N>,
+}
+
+impl<E: Into<Error>, W: Write<E>, const N: usize> NegotiatingSender<E, W, N> {
+    /// Creates a sender that writes to `writer` on behalf of `endpoint`.
+    pub fn new(writer: W, endpoint: NegotiatingEndpoint<N>) -> Self {
+        Self {
+            sender: Sender::versionless(writer),
+            endpoint,
+        }
+    }
+
+    /// Underlying [`NegotiatingEndpoint`].
+    #[inline(always)]
+    pub fn endpoint(&self) -> &NegotiatingEndpoint<N> {
+        &self.endpoint
+    }
+
+    /// Mutable reference to the underlying [`NegotiatingEndpoint`].
+    #[inline(always)]
+    pub fn endpoint_mut(&mut self) -> &mut NegotiatingEndpoint<N> {
+        &mut self.endpoint
+    }
+
+    /// Underlying [`Sender`].
+    #[inline(always)]
+    pub fn sender(&mut self) -> &mut Sender<E, W, Versionless> {
+        &mut self.sender
+    }
+
+    /// Handles an incoming frame.
+    ///
+    /// Updates negotiated protocol versions, see [`NegotiatingEndpoint::observe`]. If frame is a
+    /// `MAV_CMD_REQUEST_PROTOCOL_VERSION` request addressed to this endpoint, then
+    /// `PROTOCOL_VERSION` is sent in response.
+    ///
+    /// Returns the negotiated [`PeerVersion`] of the frame's sender.
+    pub fn handle<V: MaybeVersioned>(&mut self, frame: &Frame<V>) -> Result<Option<PeerVersion>> {
+        #[cfg(feature = "dlct-common")]
+        if self.endpoint.is_protocol_version_request(frame) {
+            let response = self.endpoint.protocol_version()?;
+            self.sender.send(&response.into_versionless())?;
+        }
+        Ok(self.endpoint.observe(frame))
+    }
+
+    /// Sends a message addressed to a specific peer.
+    ///
+    /// Returns the number of bytes sent. See [`NegotiatingEndpoint::next_frame_for`].
+    pub fn send_to(&mut self, target: MavLinkId, message: &dyn Message) -> Result<usize> {
+        let frame = self.endpoint.next_frame_for(target, message)?;
+        self.sender.send(&frame)
+    }
+
+    /// Sends a broadcast message.
+    ///
+    /// Returns the number of bytes sent. See [`NegotiatingEndpoint::next_frame`].
+    pub fn send(&mut self, message: &dyn Message) -> Result<usize> {
+        let frame = self.endpoint.next_frame(message)?;
+        self.sender.send(&frame)
+    }
+
+    /// Requests protocol version from `target`.
+    ///
+    /// See [`NegotiatingEndpoint::request_protocol_version`].
+    ///
+    /// Available with `dlct-common` Cargo feature.
+    #[cfg(feature = "dlct-common")]
+    pub fn request_protocol_version(&mut self, target: MavLinkId) -> Result<usize> {
+        let frame = self.endpoint.request_protocol_version(target)?;
+        self.sender.send(&frame)
+    }
+
+    /// Flushes all buffers.
+    #[inline]
+    pub fn flush(&mut self) -> Result<()> {
+        self.sender.flush()
+    }
+}
+
+/// Asynchronously sends MAVLink messages in a protocol version negotiated with each peer.
+///
+/// Asynchronous counterpart of [`NegotiatingSender`].
+#[derive(Debug)]
+pub struct AsyncNegotiatingSender<
+    E: Into<Error>,
+    W: AsyncWrite<E>,
+    const N: usize = DEFAULT_NEGOTIATOR_CAPACITY,
+> {
+    sender: AsyncSender<E, W, Versionless>,
+    endpoint: NegotiatingEndpoint<N>,
+}
+
+impl<E: Into<Error>, W: AsyncWrite<E>, const N: usize> AsyncNegotiatingSender<E, W, N> {
+    /// Creates a sender that writes to `writer` on behalf of `endpoint`.
+    pub fn new(writer: W, endpoint: NegotiatingEndpoint<N>) -> Self {
+        Self {
+            sender: AsyncSender::versionless(writer),
+            endpoint,
+        }
+    }
+
+    /// Underlying [`NegotiatingEndpoint`].
+    #[inline(always)]
+    pub fn endpoint(&self) -> &NegotiatingEndpoint<N> {
+        &self.endpoint
+    }
+
+    /// Mutable reference to the underlying [`NegotiatingEndpoint`].
+    #[inline(always)]
+    pub fn endpoint_mut(&mut self) -> &mut NegotiatingEndpoint<N> {
+        &mut self.endpoint
+    }
+
+    /// Underlying [`AsyncSender`].
+    #[inline(always)]
+    pub fn sender(&mut self) -> &mut AsyncSender<E, W, Versionless> {
+        &mut self.sender
+    }
+
+    /// Handles an incoming frame.
+    ///
+    /// See [`NegotiatingSender::handle`].
+    pub async fn handle<V: MaybeVersioned>(
+        &mut self,
+        frame: &Frame<V>,
+    ) -> Result<Option<PeerVersion>> {
+        #[cfg(feature = "dlct-common")]
+        if self.endpoint.is_protocol_version_request(frame) {
+            let response = self.endpoint.protocol_version()?;
+            self.sender.send(&response.into_versionless()).await?;
+        }
+        Ok(self.endpoint.observe(frame))
+    }
+
+    /// Sends a message addressed to a specific peer.
+    ///
+    /// See [`NegotiatingSender::send_to`].
+    pub async fn send_to(&mut self, target: MavLinkId, message: &dyn Message) -> Result<usize> {
+        let frame = self.endpoint.next_frame_for(target, message)?;
+        self.sender.send(&frame).await
+    }
+
+    /// Sends a broadcast message.
+    ///
+    /// See [`NegotiatingSender::send`].
+    pub async fn send(&mut self, message: &dyn Message) -> Result<usize> {
+        let frame = self.endpoint.next_frame(message)?;
+        self.sender.send(&frame).await
+    }
+
+    /// Requests protocol version from `target`.
+    ///
+    /// See [`NegotiatingEndpoint::request_protocol_version`].
+    ///
+    /// Available with `dlct-common` Cargo feature.
+    #[cfg(feature = "dlct-common")]
+    pub async fn request_protocol_version(&mut self, target: MavLinkId) -> Result<usize> {
+        let frame = self.endpoint.request_protocol_version(target)?;
+        self.sender.send(&frame).await
+    }
+
+    /// Flushes all buffers.
+    #[inline]
+    pub async fn flush(&mut self) -> Result<()> {
+        self.sender.flush().await
+    }
+}
+
+#[cfg(all(test, feature = "std", feature = "dlct-common"))]
+mod tests {
+    use super::*;
+    use crate::dialects::minimal::messages::{Heartbeat, ProtocolVersion};
+    use crate::io::{Receiver, StdIoReader, StdIoWriter};
+    use crate::protocol::{Endpoint, MavLinkVersion};
+
+    const GCS: MavLinkId = MavLinkId {
+        system: 255,
+        component: 190,
+    };
+    const VEHICLE: MavLinkId = MavLinkId {
+        system: 1,
+        component: 1,
+    };
+
+    /// Writer that keeps written bytes accessible to the test.
+    #[derive(Clone, Default)]
+    struct Buffer(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+    impl std::io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn sent(buffer: &Buffer) -> Frame<Versionless> {
+        let bytes = core::mem::take(&mut *buffer.0.borrow_mut());
+        let mut receiver = Receiver::versionless(StdIoReader::new(bytes.as_slice()));
+        receiver.recv().unwrap()
+    }
+
+    #[test]
+    fn switches_to_v2_after_handshake() {
+        let vehicle = Endpoint::v2(VEHICLE);
+        let buffer = Buffer::default();
+        let mut sender = NegotiatingSender::new(
+            StdIoWriter::new(buffer.clone()),
+            NegotiatingEndpoint::<4>::new(GCS, MavLinkVersion::V1),
+        );
+
+        sender.send_to(VEHICLE, &Heartbeat::default()).unwrap();
+        assert_eq!(sent(&buffer).version(), MavLinkVersion::V1);
+
+        sender.request_protocol_version(VEHICLE).unwrap();
+        assert_eq!(sent(&buffer).version(), MavLinkVersion::V1);
+
+        let response = vehicle.next_frame(&ProtocolVersion::default()).unwrap();
+        let peer = sender.handle(&response).unwrap().unwrap();
+        assert!(peer.confirmed);
+
+        sender.send_to(VEHICLE, &Heartbeat::default()).unwrap();
+        assert_eq!(sent(&buffer).version(), MavLinkVersion::V2);
+    }
+
+    #[test]
+    fn answers_protocol_version_requests() {
+        let gcs = NegotiatingEndpoint::<4>::new(GCS, MavLinkVersion::V1);
+        let buffer = Buffer::default();
+        let mut sender = NegotiatingSender::new(
+            StdIoWriter::new(buffer.clone()),
+            NegotiatingEndpoint::<4>::new(VEHICLE, MavLinkVersion::V1),
+        );
+
+        let request = gcs.request_protocol_version(VEHICLE).unwrap();
+        sender.handle(&request).unwrap();
+
+        let response = sent(&buffer);
+        assert_eq!(response.version(), MavLinkVersion::V2);
+        assert_eq!(response.message_id(), ProtocolVersion::ID);
+    }
+}
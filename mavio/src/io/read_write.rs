@@ -27,6 +27,30 @@ pub trait Read<Err: Into<Error>> {
     ///
     /// Returns generic error in case of I/O failure.
     fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Err>;
+
+    /// Pull some bytes from this source into the buffer, returning how many bytes were read.
+    ///
+    /// Mimics the corresponding method from [`std::io::Read`]. Returning `0` for a non-empty buffer
+    /// means that reader has reached end of file.
+    ///
+    /// This method is used by [`Receiver`] to keep partially received frames between calls. This
+    /// allows to receive frames from non-blocking readers or readers with timeouts without loosing
+    /// data.
+    ///
+    /// The default implementation fills the entire buffer with [`Read::read_exact`]. This is safe
+    /// for [`Receiver`] since it never requests more bytes than required to make progress with the
+    /// current frame. However, readers with timeouts may lose data consumed by a partially
+    /// completed [`Read::read_exact`], such readers should override this method.
+    ///
+    /// # Errors
+    ///
+    /// Returns generic error in case of I/O failure.
+    ///
+    /// [`Receiver`]: crate::io::Receiver
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Err> {
+        self.read_exact(buf)?;
+        Ok(buf.len())
+    }
 }
 
 /// Generic write trait similar to [`std::io::Write`].
@@ -2,8 +2,12 @@
 
 use core::marker::PhantomData;
 
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+use crate::error::{IoError, IoErrorKind};
 use crate::io::Read;
-use crate::protocol::{Frame, MaybeVersioned, Versioned, Versionless};
+use crate::protocol::{Frame, FrameParser, MaybeVersioned, Versioned, Versionless};
 
 use crate::prelude::*;
 
@@ -13,9 +17,14 @@ use crate::prelude::*;
 ///
 /// Instead of relying on a particular definition of read trait, we allow users to use any library
 /// with I/O capabilities. See [`Read`] for details.
+///
+/// Partially received frames are kept inside the receiver. When reader is non-blocking or has a
+/// read timeout, use [`Receiver::try_recv`] or [`Receiver::recv_timeout`] to receive frames without
+/// loosing synchronisation with the stream.
 #[derive(Clone, Debug)]
 pub struct Receiver<E: Into<Error>, R: Read<E>, V: MaybeVersioned> {
     reader: R,
+    parser: FrameParser<V>,
     _error_marker: PhantomData<E>,
     _marker_version: PhantomData<V>,
 }
@@ -25,6 +34,7 @@ impl<E: Into<Error>, R: Read<E>> Receiver<E, R, Versionless> {
     pub fn new<V: MaybeVersioned>(reader: R) -> Receiver<E, R, V> {
         Receiver {
             reader,
+            parser: FrameParser::new(),
             _error_marker: PhantomData,
             _marker_version: PhantomData,
         }
@@ -69,8 +79,89 @@ impl<E: Into<Error>, R: Read<E>, V: MaybeVersioned> Receiver<E, R, V> {
     /// [`Versioned`] receiver accepts only frames of a specific MAVLink protocol version.
     ///
     /// [`Versionless`] receiver accepts both `MAVLink 1` and `MAVLink 2` frames.
-    #[inline(always)]
+    ///
+    /// All I/O errors are returned to the caller. If reader returned an error in the middle of a
+    /// frame, the partially received frame is preserved and receiving will be resumed on the next
+    /// call.
     pub fn recv(&mut self) -> Result<Frame<V>> {
-        Frame::<V>::recv(&mut self.reader).map_err(E::into)
+        loop {
+            if let Some(frame) = self.read_next()? {
+                return Ok(frame);
+            }
+        }
+    }
+
+    /// Attempts to receive MAVLink [`Frame`] without blocking.
+    ///
+    /// Reads until a frame is complete or until reader returns an error that signals that no data
+    /// is available yet: [`WouldBlock`] or [`TimedOut`]. In the latter case returns [`None`]
+    /// and preserves the partially received frame. See [`IoError::is_would_block`].
+    ///
+    /// This is intended for non-blocking readers (i.e. [`std::net::TcpStream::set_nonblocking`])
+    /// and readers with timeouts driven by event loops.
+    ///
+    /// [`WouldBlock`]: std::io::ErrorKind::WouldBlock
+    /// [`TimedOut`]: std::io::ErrorKind::TimedOut
+    pub fn try_recv(&mut self) -> Result<Option<Frame<V>>> {
+        loop {
+            match self.read_next() {
+                Ok(Some(frame)) => return Ok(Some(frame)),
+                Ok(None) => continue,
+                Err(Error::Io(err)) if err.is_would_block() => return Ok(None),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Receives MAVLink [`Frame`] waiting at most for `timeout`.
+    ///
+    /// Returns [`None`] if frame wasn't received in time. Partially received frame is preserved.
+    ///
+    /// The reader is polled with [`Receiver::try_recv`], therefore it should be either non-blocking
+    /// or have a read timeout that is shorter than `timeout`. Otherwise, this method will block
+    /// until reader returns.
+    ///
+    /// Readers with a read timeout are polled back-to-back, relying on their timeout to wait for
+    /// data. Non-blocking readers, which return immediately, are polled every millisecond. This
+    /// adds up to a millisecond of latency, so prefer readers with timeouts where latency matters.
+    #[cfg(feature = "std")]
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<Option<Frame<V>>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let polled_at = Instant::now();
+            if let Some(frame) = self.try_recv()? {
+                return Ok(Some(frame));
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+
+            // Reader returned immediately, wait before the next attempt to avoid busy looping
+            let elapsed = now - polled_at;
+            if elapsed < RECV_TIMEOUT_POLL_INTERVAL {
+                std::thread::sleep((deadline - now).min(RECV_TIMEOUT_POLL_INTERVAL - elapsed));
+            }
+        }
+    }
+
+    /// Reads the next chunk of data and returns a frame if it is complete.
+    fn read_next(&mut self) -> Result<Option<Frame<V>>> {
+        let n = self
+            .reader
+            .read(self.parser.buffer_mut())
+            .map_err(E::into)?;
+
+        if n == 0 {
+            self.parser.reset();
+            return Err(IoError::from(IoErrorKind::UnexpectedEof).into());
+        }
+
+        Ok(self.parser.advance(n))
     }
 }
+
+/// How often [`Receiver::recv_timeout`] polls readers that return immediately.
+#[cfg(feature = "std")]
+const RECV_TIMEOUT_POLL_INTERVAL: Duration = Duration::from_millis(1);
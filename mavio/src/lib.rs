@@ -74,9 +74,10 @@
 //! There are few *stateful* features required by MAVLink protocol this library intentionally does
 //! not implement and leaves for the client:
 //!
-//! * Sending automatic [heartbeats](https://mavlink.io/en/services/heartbeat.html). This is
-//!   required by most of the clients which would consider nodes without heartbeats as inactive or
-//!   invalid.
+//! * Sending automatic [heartbeats](https://mavlink.io/en/services/heartbeat.html) out of the box.
+//!   This is required by most of the clients which would consider nodes without heartbeats as
+//!   inactive or invalid. An opt-in emitter is available in [`services::heartbeat`] when
+//!   `msrv-heartbeat` feature is enabled.
 //! * Stateful timestamp management for [message signing](https://mavlink.io/en/guide/message_signing.html)
 //!   (ensuring, that two messages are not sent with the same timestamp).
 //! * Retry logic for failed connections.
@@ -199,9 +200,21 @@
 //! - `std` → [`io::StdIoReader`] / [`io::StdIoWriter`]
 //! - `tokio` → [`io::TokioReader`] / [`io::TokioWriter`]
 //! - `futures` → [`io::FuturesReader`] / [`io::FuturesWriter`]
+//! - `tokio-util` → [`io::MavlinkCodec`] for [tokio-util](https://docs.rs/tokio-util/) codecs
 //!
 //! See [`adapters`](io::adapters) for details.
 //!
+//! With `futures` feature enabled, [`AsyncReceiver`] and [`AsyncSender`] based on Tokio and
+//! futures-rs adapters implement `Stream` and `Sink` of frames respectively.
+//!
+//! The `tokio-rt` feature adds Tokio runtime integration such as channel subscriptions in
+//! [`io::Subscriptions`]. The `tokio-net` feature adds asynchronous links.
+//!
+//! With `std` feature enabled, [`io::link`] creates receivers and senders from connection strings
+//! such as `udpin:0.0.0.0:14550` or `tcp:127.0.0.1:5760`. On Unix platforms, `serial` feature
+//! adds [`io::link::SerialPort`] that configures serial devices via termios and enables
+//! `serial:<path>[:<baud>]` links.
+//!
 //! # MAVLink protocol
 //!
 //! We use [MAVSpec](https://crates.io/crates/mavspec) to generate MAVLink entities and additional
@@ -296,6 +309,12 @@
 //! `msrv-utils-*` are considered unstable for now! Use `unstable` feature flag to enable them.
 //! </section>
 //!
+//! ### Microservice implementations
+//!
+//! Stateful implementations of microservices such as [heartbeat](services::heartbeat) emitter are
+//! available in [`services`] module. They are enabled by the same `msrv-*` feature flags as the
+//! corresponding microservices.
+//!
 //! ## Message definitions
 //!
 //! It is possible to bundle message definitions generated by [MAVInspect](https://crates.io/crates/mavinspect)
@@ -407,6 +426,8 @@ pub mod error;
 pub mod io;
 pub mod prelude;
 pub mod protocol;
+#[cfg(all(feature = "msrv", feature = "dlct-minimal"))]
+pub mod services;
 pub mod utils;
 
 #[cfg(feature = "tokio")]
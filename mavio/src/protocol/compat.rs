@@ -261,13 +261,13 @@ impl CompatProcessor {
                 }
 
                 match strategy {
-                    CompatStrategy::Reject | CompatStrategy::RejectSet => {
-                        if incompat_flags != frame.header.incompat_flags {
-                            return Err(IncompatFlagsError {
-                                expected: incompat_flags,
-                                actual: frame.header.incompat_flags,
-                            });
-                        }
+                    CompatStrategy::Reject | CompatStrategy::RejectSet
+                        if incompat_flags != frame.header.incompat_flags =>
+                    {
+                        return Err(IncompatFlagsError {
+                            expected: incompat_flags,
+                            actual: frame.header.incompat_flags,
+                        });
                     }
                     CompatStrategy::Enforce | CompatStrategy::EnforceProxy => {
                         frame.header.incompat_flags = incompat_flags;
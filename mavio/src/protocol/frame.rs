@@ -2,9 +2,9 @@
 
 use crc_any::CRCu16;
 
-use crate::consts::{CHECKSUM_SIZE, SIGNATURE_LENGTH};
+use crate::consts::{CHECKSUM_SIZE, FRAME_MAX_SIZE, SIGNATURE_LENGTH};
 use crate::error::{ChecksumError, SignatureError, VersionError};
-use crate::io::{AsyncRead, AsyncWrite, Read, Write};
+use crate::io::{AsyncWrite, Write};
 use crate::protocol::header::Header;
 use crate::protocol::marker::{
     HasCompId, HasMsgId, HasPayload, HasPayloadLen, HasSysId, Sequenced, Unset,
@@ -259,6 +259,34 @@ impl<V: MaybeVersioned> Frame<V> {
         self.header().body_length()
     }
 
+    /// Size of the entire encoded [`Frame`] in bytes.
+    ///
+    /// Includes [`Header`], payload, [`Checksum`], and optional [`Signature`].
+    ///
+    /// # Links
+    ///
+    /// * [`Frame::to_bytes`].
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.header.size() + self.body_length()
+    }
+
+    /// Encodes frame into a byte buffer.
+    ///
+    /// Returns the number of written bytes which is always equal to [`Frame::size`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` is smaller than [`Frame::size`]. Buffer of
+    /// [`FRAME_MAX_SIZE`](crate::consts::FRAME_MAX_SIZE) bytes is sufficient for any frame.
+    pub fn to_bytes(&self, buf: &mut [u8]) -> usize {
+        let header_size = self.header.size();
+        let size = self.size();
+        buf[..header_size].copy_from_slice(self.header.decode().as_slice());
+        self.fill_body_buffer(&mut buf[header_size..size]);
+        size
+    }
+
     /// Calculates CRC for frame within `crc_extra`.
     ///
     /// Provided `crc_extra` depends on a dialect and contains a digest of message XML definition.
@@ -442,78 +470,26 @@ impl<V: MaybeVersioned> Frame<V> {
         self.checksum = self.calculate_crc(crc_extra);
     }
 
-    pub(crate) fn recv<E: Into<Error>, R: Read<E>>(
-        reader: &mut R,
-    ) -> core::result::Result<Frame<V>, E> {
-        let header = Header::<V>::recv(reader)?;
-        let body_length = header.body_length();
-
-        #[cfg(feature = "std")]
-        let mut body_buf = vec![0u8; body_length];
-        #[cfg(not(feature = "std"))]
-        let mut body_buf =
-            [0u8; crate::consts::PAYLOAD_MAX_SIZE + CHECKSUM_SIZE + SIGNATURE_LENGTH];
-        let body_bytes = &mut body_buf[0..body_length];
-
-        reader.read_exact(body_bytes)?;
-        let frame = Self::from_raw_body(header, body_bytes);
-
-        Ok(frame)
-    }
-
-    pub(crate) async fn recv_async<E: Into<Error>, R: AsyncRead<E>>(
-        reader: &mut R,
-    ) -> core::result::Result<Frame<V>, E> {
-        let header = Header::<V>::recv_async(reader).await?;
-        let body_length = header.body_length();
-
-        #[cfg(feature = "std")]
-        let mut body_buf = vec![0u8; body_length];
-        #[cfg(not(feature = "std"))]
-        let mut body_buf =
-            [0u8; crate::consts::PAYLOAD_MAX_SIZE + CHECKSUM_SIZE + SIGNATURE_LENGTH];
-        let body_bytes = &mut body_buf[0..body_length];
-
-        reader.read_exact(body_bytes).await?;
-        let frame = Self::from_raw_body(header, body_bytes);
-
-        Ok(frame)
-    }
-
+    /// Sends frame with a single write, so datagram-based writers receive a whole frame.
     pub(crate) fn send<E: Into<Error>, W: Write<E>>(
         &self,
         writer: &mut W,
     ) -> core::result::Result<usize, E> {
-        let header_bytes_sent = self.header.send(writer)?;
-
-        #[cfg(not(feature = "alloc"))]
-        let mut buf = [0u8; crate::consts::PAYLOAD_MAX_SIZE + SIGNATURE_LENGTH];
-        #[cfg(feature = "alloc")]
-        let mut buf = alloc::vec![0u8; self.body_length()];
-        let body_bytes = &mut buf[..self.body_length()];
-
-        self.fill_body_buffer(body_bytes);
-        writer.write_all(body_bytes)?;
-
-        Ok(header_bytes_sent + self.body_length())
+        let mut buf = [0u8; FRAME_MAX_SIZE];
+        let size = self.to_bytes(&mut buf);
+        writer.write_all(&buf[..size])?;
+        Ok(size)
     }
 
+    /// Sends frame with a single write, so datagram-based writers receive a whole frame.
     pub(crate) async fn send_async<E: Into<Error>, W: AsyncWrite<E>>(
         &self,
         writer: &mut W,
     ) -> core::result::Result<usize, E> {
-        let header_bytes_sent = self.header.send_async(writer).await?;
-
-        #[cfg(not(feature = "alloc"))]
-        let mut buf = [0u8; crate::consts::PAYLOAD_MAX_SIZE + SIGNATURE_LENGTH];
-        #[cfg(feature = "alloc")]
-        let mut buf = alloc::vec![0u8; self.body_length()];
-        let body_bytes = &mut buf[..self.body_length()];
-
-        self.fill_body_buffer(body_bytes);
-        writer.write_all(body_bytes).await?;
-
-        Ok(header_bytes_sent + self.body_length())
+        let mut buf = [0u8; FRAME_MAX_SIZE];
+        let size = self.to_bytes(&mut buf);
+        writer.write_all(&buf[..size]).await?;
+        Ok(size)
     }
 
     fn fill_body_buffer(&self, buf: &mut [u8]) {
@@ -534,7 +510,7 @@ impl<V: MaybeVersioned> Frame<V> {
     }
 
     #[inline]
-    fn from_raw_body(header: Header<V>, body_bytes: &[u8]) -> Frame<V> {
+    pub(super) fn from_raw_body(header: Header<V>, body_bytes: &[u8]) -> Frame<V> {
         let payload_bytes = &body_bytes[0..header.payload_length() as usize];
         let payload = Payload::new(header.message_id(), payload_bytes, header.version());
 
@@ -695,7 +671,7 @@ impl Frame<V2> {
         };
         let mut signer = Signer::new(signer);
 
-        if !signer.validate(&self, &signature, key) {
+        if !signer.validate(self, &signature, key) {
             return Err(SignatureError);
         }
 
@@ -841,7 +817,7 @@ mod tests {
     #[cfg(feature = "std")]
     fn test_oversized_v2_payload() {
         use crate::consts::STX_V2;
-        use crate::protocol::{Frame, Versionless};
+        use crate::protocol::{FrameParser, Versionless};
 
         let payload_length = 3;
         let junk_bytes = 3;
@@ -867,10 +843,10 @@ mod tests {
         ];
         let expected_frame_size = in_buffer.len() - 3; // no junk
         let valid_bytes = in_buffer[junk_bytes..].to_vec();
-        let mut reader = StdIoReader::new(Cursor::new(in_buffer));
 
         // Read frame
-        let frame = Frame::<Versionless>::recv(&mut reader).unwrap();
+        let (_, frame) = FrameParser::<Versionless>::new().push(&in_buffer);
+        let frame = frame.unwrap();
 
         // We should preserve payload length for compatibility
         assert_eq!(frame.payload_length(), payload_length);
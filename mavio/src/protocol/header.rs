@@ -7,11 +7,9 @@ use core::marker::PhantomData;
 use tbytes::{TBytesReader, TBytesReaderFor};
 
 use crate::consts::{
-    CHECKSUM_SIZE, HEADER_MAX_SIZE, HEADER_MIN_SIZE, HEADER_V1_SIZE, HEADER_V2_SIZE,
-    SIGNATURE_LENGTH,
+    CHECKSUM_SIZE, HEADER_MAX_SIZE, HEADER_V1_SIZE, HEADER_V2_SIZE, SIGNATURE_LENGTH,
 };
 use crate::error::VersionError;
-use crate::io::{AsyncRead, AsyncWrite, Read, Write};
 use crate::protocol::marker::{HasCompId, HasMsgId, HasPayloadLen, HasSysId, Sequenced, Unset};
 use crate::protocol::{
     CompatFlags, ComponentId, HeaderBuilder, IncompatFlags, MavSTX, MaybeVersioned, PayloadLength,
@@ -19,8 +17,6 @@ use crate::protocol::{
 };
 use crate::protocol::{MavLinkVersion, MessageId};
 
-use crate::prelude::*;
-
 /// MAVLink frame header.
 ///
 /// Header contains information relevant to for `MAVLink 1` and `MAVLink 2` packet formats.
@@ -266,22 +262,6 @@ impl<V: MaybeVersioned> Header<V> {
         self.clone().into_versionless()
     }
 
-    pub(super) fn send<E: Into<Error>, W: Write<E>>(
-        &self,
-        writer: &mut W,
-    ) -> core::result::Result<usize, E> {
-        writer.write_all(self.decode().as_slice())?;
-        Ok(self.size())
-    }
-
-    pub(super) async fn send_async<E: Into<Error>, W: AsyncWrite<E>>(
-        &self,
-        writer: &mut W,
-    ) -> core::result::Result<usize, E> {
-        writer.write_all(self.decode().as_slice()).await?;
-        Ok(self.size())
-    }
-
     fn dump_bytes(&self, header_bytes: &mut HeaderBytes) {
         match self.version {
             MavLinkVersion::V1 => self.dump_v1_bytes(header_bytes),
@@ -313,51 +293,9 @@ impl<V: MaybeVersioned> Header<V> {
 }
 
 impl<V: MaybeVersioned> Header<V> {
-    pub(super) fn recv<E: Into<Error>, R: Read<E>>(
-        reader: &mut R,
-    ) -> core::result::Result<Header<V>, E> {
-        loop {
-            let mut buffer = [0u8; HEADER_MIN_SIZE];
-            reader.read_exact(&mut buffer)?;
-
-            if let Some(mut header_start) = HeaderStart::<V>::from_slice(&buffer) {
-                if !header_start.is_complete() {
-                    reader.read_exact(header_start.remaining_bytes_mut())?;
-                }
-                return Ok(unsafe {
-                    Header::<V>::try_from_slice_unchecked(header_start.header_bytes())
-                });
-            } else {
-                continue;
-            }
-        }
-    }
-
-    pub(super) async fn recv_async<E: Into<Error>, R: AsyncRead<E>>(
-        reader: &mut R,
-    ) -> core::result::Result<Header<V>, E> {
-        loop {
-            let mut buffer = [0u8; HEADER_MIN_SIZE];
-            reader.read_exact(&mut buffer).await?;
-
-            if let Some(mut header_start) = HeaderStart::<V>::from_slice(&buffer) {
-                if !header_start.is_complete() {
-                    reader
-                        .read_exact(header_start.remaining_bytes_mut())
-                        .await?;
-                }
-                return Ok(unsafe {
-                    Header::<V>::try_from_slice_unchecked(header_start.header_bytes())
-                });
-            } else {
-                continue;
-            }
-        }
-    }
-
     // This function does not use unsafe Rust but may panic if first byte is not STX or provided
     // slice has invalid size.
-    unsafe fn try_from_slice_unchecked(bytes: &[u8]) -> Header<V> {
+    pub(super) unsafe fn try_from_slice_unchecked(bytes: &[u8]) -> Header<V> {
         let reader = TBytesReader::from(bytes);
 
         let magic: u8 = reader.read().unwrap();
@@ -497,80 +435,26 @@ impl Header<Versionless> {
     }
 }
 
-struct HeaderStart<V: MaybeVersioned> {
-    buffer: [u8; HEADER_MAX_SIZE],
-    n_bytes_read: usize,
-    n_bytes_left: usize,
-    _marker_version: PhantomData<V>,
-}
-
-impl<V: MaybeVersioned> HeaderStart<V> {
-    fn from_slice(buffer: &[u8]) -> Option<Self> {
-        let mut mavlink_version: Option<MavLinkVersion> = None;
-        let mut header_start_idx = buffer.len();
-        for (i, &byte) in buffer.iter().enumerate() {
-            if V::is_magic_byte(byte) {
-                header_start_idx = i;
-                mavlink_version = MavSTX::from(byte).into();
-                break;
-            }
-        }
-
-        match mavlink_version {
-            None => None,
-            Some(version) => {
-                let header_size = match version {
-                    MavLinkVersion::V1 => HEADER_V1_SIZE,
-                    MavLinkVersion::V2 => HEADER_V2_SIZE,
-                };
-
-                let n_bytes_read = buffer.len() - header_start_idx;
-                let header_start_bytes = &buffer[header_start_idx..buffer.len()];
-
-                let mut header_bytes = [0u8; HEADER_MAX_SIZE];
-                header_bytes[0..n_bytes_read].copy_from_slice(header_start_bytes);
-
-                Some(Self {
-                    buffer: header_bytes,
-                    n_bytes_read,
-                    n_bytes_left: header_size - n_bytes_read,
-                    _marker_version: PhantomData,
-                })
-            }
-        }
-    }
-
-    fn header_bytes(&self) -> &[u8] {
-        &self.buffer[0..self.n_bytes_read + self.n_bytes_left]
-    }
-
-    fn is_complete(&self) -> bool {
-        self.n_bytes_left == 0
-    }
-
-    fn remaining_bytes_mut(&mut self) -> &mut [u8] {
-        &mut self.buffer[self.n_bytes_read..self.n_bytes_read + self.n_bytes_left]
-    }
-}
-
 #[cfg(test)]
 mod header_tests {
-    #[cfg(feature = "std")]
-    use std::io::Cursor;
-
-    #[cfg(feature = "std")]
     use crate::consts::{STX_V1, STX_V2};
-    #[cfg(feature = "std")]
-    use crate::io::StdIoReader;
-
-    use crate::protocol::V1;
+    use crate::protocol::{FrameParser, V1};
 
     use super::*;
 
+    /// Parses header by feeding zeroed body bytes until frame is complete.
+    fn parse<V: MaybeVersioned>(bytes: &[u8]) -> Header<V> {
+        let mut parser = FrameParser::<V>::new();
+        let (_, mut frame) = parser.push(bytes);
+        while frame.is_none() {
+            frame = parser.push(&[0]).1;
+        }
+        frame.unwrap().header().clone()
+    }
+
     #[test]
-    #[cfg(feature = "std")]
     fn read_v1_header() {
-        let buffer = vec![
+        let buffer = [
             12,     // \
             24,     //  | Junk bytes
             240,    // /
@@ -581,9 +465,7 @@ mod header_tests {
             255,    // component ID
             0,      // message ID
         ];
-        let mut reader = StdIoReader::new(Cursor::new(buffer));
-
-        let header = Header::<V1>::recv(&mut reader).unwrap();
+        let header = parse::<V1>(&buffer);
         let header = header.try_into_versioned::<V1>().unwrap();
 
         assert!(header.try_into_versioned::<V2>().is_err());
@@ -597,9 +479,8 @@ mod header_tests {
     }
 
     #[test]
-    #[cfg(feature = "std")]
     fn read_v2_header() {
-        let buffer = vec![
+        let buffer = [
             12,     // \
             24,     //  |Junk bytes
             240,    // /
@@ -614,9 +495,7 @@ mod header_tests {
             0,      //  | message ID
             0,      // /
         ];
-        let mut reader = StdIoReader::new(Cursor::new(buffer));
-
-        let header = Header::<Versionless>::recv(&mut reader).unwrap();
+        let header = parse::<Versionless>(&buffer);
         let header = header.try_into_versioned::<V2>().unwrap();
 
         assert!(header.try_into_versioned::<V1>().is_err());
@@ -632,9 +511,8 @@ mod header_tests {
     }
 
     #[test]
-    #[cfg(feature = "std")]
     fn read_v2_header_magic_bytes_in_sequence() {
-        let buffer = vec![
+        let buffer = [
             12,     // \
             24,     //  |Junk bytes
             240,    // /
@@ -649,9 +527,7 @@ mod header_tests {
             0,      //  | message ID
             0,      // /
         ];
-        let mut reader = StdIoReader::new(Cursor::new(buffer));
-
-        let header = Header::<V2>::recv(&mut reader).unwrap();
+        let header = parse::<V2>(&buffer);
 
         assert!(header.try_into_versioned::<V1>().is_err());
         assert!(matches!(header.version(), MavLinkVersion::V2));
@@ -709,4 +585,34 @@ mod header_tests {
         assert_eq!(header.component_id(), 240);
         assert_eq!(header.message_id(), 42);
     }
+
+    #[test]
+    fn signed_flag_of_v2_builder() {
+        let builder = Header::builder()
+            .payload_length(10)
+            .sequence(5)
+            .system_id(10)
+            .component_id(240)
+            .message_id(42)
+            .version(V2);
+
+        let header = builder.clone().signed(true).build();
+        assert!(header.is_signed());
+
+        let header = builder
+            .clone()
+            .incompat_flags(IncompatFlags::BIT_5)
+            .signed(true)
+            .build();
+        assert_eq!(
+            header.incompat_flags(),
+            IncompatFlags::MAVLINK_IFLAG_SIGNED | IncompatFlags::BIT_5
+        );
+
+        let header = builder
+            .incompat_flags(IncompatFlags::MAVLINK_IFLAG_SIGNED | IncompatFlags::BIT_5)
+            .signed(false)
+            .build();
+        assert_eq!(header.incompat_flags(), IncompatFlags::BIT_5);
+    }
 }
@@ -242,17 +242,16 @@ impl<L: IsPayloadLen, Seq: IsSequenced, S: IsSysId, C: IsCompId, M: IsMsgId>
 {
     /// Sets whether `MAVLink 2` frame body should contain signature.
     ///
-    /// Sets [`IncompatFlags::MAVLINK_IFLAG_SIGNED`] flag for
-    /// [`incompat_flags`](Header::incompat_flags).
+    /// Sets or drops [`IncompatFlags::MAVLINK_IFLAG_SIGNED`] flag for
+    /// [`incompat_flags`](Header::incompat_flags) keeping other flags intact.
     ///
     /// Sets MAVLink protocol version to [`V2`].
     pub fn signed(self, flag: bool) -> HeaderBuilder<V2, L, Seq, S, C, M> {
         let this = self.version(V2);
+        let mut flags = this.incompat_flags.unwrap_or_default();
+        flags.set(IncompatFlags::MAVLINK_IFLAG_SIGNED, flag);
         HeaderBuilder {
-            incompat_flags: this.incompat_flags.map(|flags| {
-                flags.clone().set(IncompatFlags::MAVLINK_IFLAG_SIGNED, flag);
-                flags
-            }),
+            incompat_flags: Some(flags),
             ..this
         }
     }
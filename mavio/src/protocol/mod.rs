@@ -45,6 +45,9 @@ pub(super) mod header;
 pub(super) mod header_builder;
 pub(super) mod marker;
 mod mav_frame;
+mod negotiation;
+mod parser;
+mod rewrite;
 mod sequencer;
 pub(super) mod signature;
 pub(crate) mod stx;
@@ -61,6 +64,12 @@ pub use header::{Header, HeaderBytes};
 pub use header_builder::HeaderBuilder;
 pub use marker::{MaybeVersioned, Unset, Versioned, Versionless, V1, V2};
 pub use mav_frame::MavFrame;
+pub use negotiation::{
+    NegotiatingEndpoint, PeerVersion, VersionNegotiator, DEFAULT_NEGOTIATOR_CAPACITY,
+    PROTOCOL_VERSION_V1, PROTOCOL_VERSION_V2,
+};
+pub use parser::FrameParser;
+pub use rewrite::FrameRewrite;
 pub use sequencer::{IntoSequencer, Sequencer};
 pub use signature::{MavTimestamp, SecretKey, Sign, Signature, Signer, SigningConf};
 pub use stx::MavSTX;
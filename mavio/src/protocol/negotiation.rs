@@ -0,0 +1,501 @@
+//! # MAVLink protocol version negotiation
+
+use crate::error::Result;
+use crate::protocol::{
+    Endpoint, Frame, MavLinkId, MavLinkVersion, MaybeVersioned, Message, SystemId, Versionless, V1,
+    V2,
+};
+
+#[cfg(feature = "dlct-common")]
+use crate::dialects::common::{
+    enums::{MavCmd, MavProtocolCapability},
+    messages::{AutopilotVersion, CommandLong},
+};
+#[cfg(feature = "dlct-minimal")]
+use crate::dialects::minimal::messages::ProtocolVersion;
+
+/// Default capacity of a [`VersionNegotiator`] peer table.
+pub const DEFAULT_NEGOTIATOR_CAPACITY: usize = 32;
+
+/// MAVLink protocol version number reported in `PROTOCOL_VERSION` for `MAVLink 1` (`v1.0`).
+pub const PROTOCOL_VERSION_V1: u16 = 100;
+/// MAVLink protocol version number reported in `PROTOCOL_VERSION` for `MAVLink 2` (`v2.0`).
+pub const PROTOCOL_VERSION_V2: u16 = 200;
+
+/// Protocol capabilities of a remote peer discovered by [`VersionNegotiator`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PeerVersion {
+    /// Peer `ID`.
+    pub id: MavLinkId,
+    /// Highest MAVLink protocol version this peer is known to support.
+    pub version: MavLinkVersion,
+    /// Whether `MAVLink 2` support was explicitly confirmed by a handshake.
+    ///
+    /// This happens when peer sends `PROTOCOL_VERSION` or reports
+    /// `MAV_PROTOCOL_CAPABILITY_MAVLINK2` in `AUTOPILOT_VERSION`.
+    pub confirmed: bool,
+}
+
+/// Tracks MAVLink protocol versions supported by remote peers.
+///
+/// Implements version negotiation as described in MAVLink
+/// [version handshake](https://mavlink.io/en/guide/mavlink_version.html#version_handshaking):
+///
+/// * Peers that were seen sending `MAVLink 2` frames are considered `MAVLink 2` capable. Once
+///   detected, `MAVLink 2` support is never downgraded.
+/// * Peers that send only `MAVLink 1` frames are talked to using `MAVLink 1`.
+/// * `PROTOCOL_VERSION` received in `MAVLink 2` and `AUTOPILOT_VERSION` with
+///   `MAV_PROTOCOL_CAPABILITY_MAVLINK2` confirm `MAVLink 2` support.
+/// * Unknown peers are addressed using the default version.
+///
+/// Negotiator has a fixed capacity `N` that defines the maximum number of tracked peers and
+/// therefore is suitable for `no_std` targets. When the table is full, information about new peers
+/// is discarded.
+///
+/// # Examples
+///
+/// ```rust
+/// use mavio::protocol::{VersionNegotiator, MavLinkVersion};
+/// use mavio::prelude::*;
+///
+/// let mut negotiator = VersionNegotiator::<8>::new(MavLinkVersion::V1);
+/// let peer = MavLinkId::new(1, 1);
+/// assert_eq!(negotiator.version_for(peer), MavLinkVersion::V1);
+///
+/// let frame = Frame::builder()
+///     .sequence(0)
+///     .system_id(1)
+///     .component_id(1)
+///     .version(V2)
+///     .message_id(0)
+///     .payload(&[0u8; 9])
+///     .crc_extra(50)
+///     .build();
+/// negotiator.observe(&frame);
+///
+/// assert_eq!(negotiator.version_for(peer), MavLinkVersion::V2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct VersionNegotiator<const N: usize = DEFAULT_NEGOTIATOR_CAPACITY> {
+    default_version: MavLinkVersion,
+    peers: [Option<PeerVersion>; N],
+}
+
+impl<const N: usize> VersionNegotiator<N> {
+    /// Creates a new negotiator that addresses unknown peers using `default_version`.
+    pub fn new(default_version: MavLinkVersion) -> Self {
+        Self {
+            default_version,
+            peers: [None; N],
+        }
+    }
+
+    /// MAVLink protocol version used for unknown peers.
+    #[inline(always)]
+    pub fn default_version(&self) -> MavLinkVersion {
+        self.default_version
+    }
+
+    /// Updates peer table based on an incoming frame.
+    ///
+    /// Returns the negotiated [`PeerVersion`] for the frame's sender or [`None`], if peer table is
+    /// full.
+    pub fn observe<V: MaybeVersioned>(&mut self, frame: &Frame<V>) -> Option<PeerVersion> {
+        let id = MavLinkId::new(frame.system_id(), frame.component_id());
+        let confirmed = Self::confirms_v2(frame);
+
+        let peer = self.entry(id)?;
+        if frame.version() == MavLinkVersion::V2 {
+            peer.version = MavLinkVersion::V2;
+        }
+        peer.confirmed |= confirmed;
+        Some(*peer)
+    }
+
+    /// Returns negotiated information about peer.
+    pub fn peer(&self, id: MavLinkId) -> Option<PeerVersion> {
+        self.peers().find(|peer| peer.id == id)
+    }
+
+    /// Iterates over all known peers.
+    pub fn peers(&self) -> impl Iterator<Item = PeerVersion> + '_ {
+        self.peers.iter().filter_map(|peer| *peer)
+    }
+
+    /// MAVLink protocol version that should be used to communicate with a peer.
+    ///
+    /// If the exact peer is unknown, then the lowest version among other components of the same
+    /// system is used. Falls back to the [`VersionNegotiator::default_version`].
+    ///
+    /// Messages addressed to system `0` are broadcast, therefore
+    /// [`VersionNegotiator::broadcast_version`] is used for them.
+    pub fn version_for(&self, id: MavLinkId) -> MavLinkVersion {
+        if id.system == 0 {
+            return self.broadcast_version();
+        }
+        if let Some(peer) = self.peer(id) {
+            return peer.version;
+        }
+        self.version_for_system(id.system)
+    }
+
+    /// MAVLink protocol version that should be used to communicate with all components of a system.
+    ///
+    /// Returns `MAVLink 1` if at least one known component of this system supports only `MAVLink 1`.
+    /// Falls back to the [`VersionNegotiator::default_version`] for unknown systems.
+    pub fn version_for_system(&self, system_id: SystemId) -> MavLinkVersion {
+        Self::lowest(
+            self.peers().filter(|peer| peer.id.system == system_id),
+            self.default_version,
+        )
+    }
+
+    /// MAVLink protocol version that should be used for broadcast messages.
+    ///
+    /// This is the lowest version among all known peers or the
+    /// [`VersionNegotiator::default_version`], if there are no known peers.
+    pub fn broadcast_version(&self) -> MavLinkVersion {
+        Self::lowest(self.peers(), self.default_version)
+    }
+
+    /// Removes peer from the table.
+    pub fn forget(&mut self, id: MavLinkId) {
+        for slot in self.peers.iter_mut() {
+            if matches!(slot, Some(peer) if peer.id == id) {
+                *slot = None;
+            }
+        }
+    }
+
+    /// Removes all peers.
+    pub fn clear(&mut self) {
+        self.peers = [None; N];
+    }
+
+    fn entry(&mut self, id: MavLinkId) -> Option<&mut PeerVersion> {
+        let idx = match self
+            .peers
+            .iter()
+            .position(|p| matches!(p, Some(p) if p.id == id))
+        {
+            Some(idx) => idx,
+            None => {
+                let idx = self.peers.iter().position(Option::is_none)?;
+                self.peers[idx] = Some(PeerVersion {
+                    id,
+                    version: MavLinkVersion::V1,
+                    confirmed: false,
+                });
+                idx
+            }
+        };
+        self.peers[idx].as_mut()
+    }
+
+    fn lowest(
+        mut peers: impl Iterator<Item = PeerVersion>,
+        default_version: MavLinkVersion,
+    ) -> MavLinkVersion {
+        let Some(first) = peers.next() else {
+            return default_version;
+        };
+        if first.version == MavLinkVersion::V1 || peers.any(|p| p.version == MavLinkVersion::V1) {
+            MavLinkVersion::V1
+        } else {
+            MavLinkVersion::V2
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn confirms_v2<V: MaybeVersioned>(frame: &Frame<V>) -> bool {
+        if frame.version() != MavLinkVersion::V2 {
+            return false;
+        }
+
+        #[cfg(feature = "dlct-minimal")]
+        if frame.message_id() == ProtocolVersion::ID {
+            return frame
+                .validate_checksum_with_crc_extra(ProtocolVersion::crc_extra())
+                .is_ok();
+        }
+
+        #[cfg(feature = "dlct-common")]
+        if frame.message_id() == AutopilotVersion::ID {
+            return frame
+                .validate_checksum_with_crc_extra(AutopilotVersion::crc_extra())
+                .ok()
+                .and_then(|_| AutopilotVersion::try_from(frame.payload()).ok())
+                .map(|msg| msg.capabilities.contains(MavProtocolCapability::MAVLINK2))
+                .unwrap_or(false);
+        }
+
+        false
+    }
+}
+
+impl<const N: usize> Default for VersionNegotiator<N> {
+    /// Creates a negotiator that addresses unknown peers using `MAVLink 1`, as recommended by the
+    /// MAVLink specification.
+    fn default() -> Self {
+        Self::new(MavLinkVersion::V1)
+    }
+}
+
+/// MAVLink device that negotiates protocol version with each peer.
+///
+/// Wraps a versionless [`Endpoint`] and a [`VersionNegotiator`]. Frames that were received from
+/// peers should be passed to [`NegotiatingEndpoint::observe`]. Outgoing frames will be produced in
+/// the protocol version negotiated with the target peer. Messages that can't be represented in
+/// `MAVLink 1` (with message `ID` greater than `255`) are always sent as `MAVLink 2`.
+///
+/// Use [`NegotiatingSender`](crate::io::NegotiatingSender) or
+/// [`AsyncNegotiatingSender`](crate::io::AsyncNegotiatingSender) to write produced frames and
+/// answer protocol version requests automatically.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(not(feature = "dlct-minimal"))]
+/// # fn main() {}
+/// # #[cfg(feature = "dlct-minimal")]
+/// # fn main() {
+/// use mavio::dialects::minimal::messages::Heartbeat;
+/// use mavio::protocol::{MavLinkVersion, NegotiatingEndpoint};
+/// use mavio::prelude::*;
+///
+/// let vehicle = Endpoint::v2(MavLinkId::new(1, 1));
+/// let gcs = NegotiatingEndpoint::<4>::new(MavLinkId::new(255, 190), MavLinkVersion::V1);
+///
+/// // Until we've heard from a vehicle, we talk `MAVLink 1`
+/// let frame = gcs.next_frame_for(vehicle.id(), &Heartbeat::default()).unwrap();
+/// assert_eq!(frame.version(), MavLinkVersion::V1);
+///
+/// // Vehicle speaks `MAVLink 2`
+/// let mut gcs = gcs;
+/// gcs.observe(&vehicle.next_frame(&Heartbeat::default()).unwrap());
+///
+/// let frame = gcs.next_frame_for(vehicle.id(), &Heartbeat::default()).unwrap();
+/// assert_eq!(frame.version(), MavLinkVersion::V2);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct NegotiatingEndpoint<const N: usize = DEFAULT_NEGOTIATOR_CAPACITY> {
+    endpoint: Endpoint<Versionless>,
+    negotiator: VersionNegotiator<N>,
+}
+
+impl<const N: usize> NegotiatingEndpoint<N> {
+    /// Creates a new negotiating endpoint that addresses unknown peers using `default_version`.
+    pub fn new(id: MavLinkId, default_version: MavLinkVersion) -> Self {
+        Self::from_parts(
+            Endpoint::versionless(id),
+            VersionNegotiator::new(default_version),
+        )
+    }
+
+    /// Creates a negotiating endpoint from an existing [`Endpoint`] and [`VersionNegotiator`].
+    pub fn from_parts(endpoint: Endpoint<Versionless>, negotiator: VersionNegotiator<N>) -> Self {
+        Self {
+            endpoint,
+            negotiator,
+        }
+    }
+
+    /// Underlying [`Endpoint`].
+    #[inline(always)]
+    pub fn endpoint(&self) -> &Endpoint<Versionless> {
+        &self.endpoint
+    }
+
+    /// Underlying [`VersionNegotiator`].
+    #[inline(always)]
+    pub fn negotiator(&self) -> &VersionNegotiator<N> {
+        &self.negotiator
+    }
+
+    /// Mutable reference to the underlying [`VersionNegotiator`].
+    #[inline(always)]
+    pub fn negotiator_mut(&mut self) -> &mut VersionNegotiator<N> {
+        &mut self.negotiator
+    }
+
+    /// Device `ID`.
+    #[inline(always)]
+    pub fn id(&self) -> MavLinkId {
+        self.endpoint.id()
+    }
+
+    /// Updates negotiated protocol versions from an incoming frame.
+    ///
+    /// See [`VersionNegotiator::observe`].
+    #[inline]
+    pub fn observe<V: MaybeVersioned>(&mut self, frame: &Frame<V>) -> Option<PeerVersion> {
+        self.negotiator.observe(frame)
+    }
+
+    /// Produces a next frame addressed to a specific peer.
+    ///
+    /// Protocol version is chosen by [`VersionNegotiator::version_for`].
+    pub fn next_frame_for(
+        &self,
+        target: MavLinkId,
+        message: &dyn Message,
+    ) -> Result<Frame<Versionless>> {
+        self.next_frame_with_version(self.negotiator.version_for(target), message)
+    }
+
+    /// Produces a next broadcast frame.
+    ///
+    /// Protocol version is chosen by [`VersionNegotiator::broadcast_version`].
+    pub fn next_frame(&self, message: &dyn Message) -> Result<Frame<Versionless>> {
+        self.next_frame_with_version(self.negotiator.broadcast_version(), message)
+    }
+
+    /// Produces a `COMMAND_LONG` with `MAV_CMD_REQUEST_PROTOCOL_VERSION` addressed to `target`.
+    ///
+    /// The request is sent using currently negotiated version, so `MAVLink 1` peers can understand
+    /// it. Peers that support `MAVLink 2` will respond with `PROTOCOL_VERSION` which will confirm
+    /// `MAVLink 2` support once passed to [`NegotiatingEndpoint::observe`].
+    ///
+    /// Available with `dlct-common` Cargo feature.
+    #[cfg(feature = "dlct-common")]
+    pub fn request_protocol_version(&self, target: MavLinkId) -> Result<Frame<Versionless>> {
+        let command = CommandLong {
+            target_system: target.system,
+            target_component: target.component,
+            command: MavCmd::RequestProtocolVersion,
+            param1: 1.0,
+            ..Default::default()
+        };
+        self.next_frame_for(target, &command)
+    }
+
+    /// Checks whether a frame is a `MAV_CMD_REQUEST_PROTOCOL_VERSION` request addressed to this
+    /// endpoint.
+    ///
+    /// Available with `dlct-common` Cargo feature.
+    #[cfg(feature = "dlct-common")]
+    pub fn is_protocol_version_request<V: MaybeVersioned>(&self, frame: &Frame<V>) -> bool {
+        if frame.message_id() != CommandLong::ID
+            || frame
+                .validate_checksum_with_crc_extra(CommandLong::crc_extra())
+                .is_err()
+        {
+            return false;
+        }
+        let Ok(command) = CommandLong::try_from(frame.payload()) else {
+            return false;
+        };
+        let id = self.id();
+        command.command == MavCmd::RequestProtocolVersion
+            && (command.target_system == 0 || command.target_system == id.system)
+            && (command.target_component == 0 || command.target_component == id.component)
+    }
+
+    /// Produces a `PROTOCOL_VERSION` response.
+    ///
+    /// The response is always a `MAVLink 2` frame as required by the MAVLink specification.
+    ///
+    /// Available with `dlct-minimal` Cargo feature.
+    #[cfg(feature = "dlct-minimal")]
+    pub fn protocol_version(&self) -> Result<Frame<V2>> {
+        let message = ProtocolVersion {
+            version: PROTOCOL_VERSION_V2,
+            min_version: PROTOCOL_VERSION_V1,
+            max_version: PROTOCOL_VERSION_V2,
+            ..Default::default()
+        };
+        self.endpoint
+            .next_frame::<V2>(&message)?
+            .try_into_versioned()
+            .map_err(crate::error::Error::from)
+    }
+
+    fn next_frame_with_version(
+        &self,
+        version: MavLinkVersion,
+        message: &dyn Message,
+    ) -> Result<Frame<Versionless>> {
+        match (version, message.min_supported_mavlink_version()) {
+            (MavLinkVersion::V1, MavLinkVersion::V1) => self.endpoint.next_frame::<V1>(message),
+            _ => self.endpoint.next_frame::<V2>(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::frame;
+
+    #[test]
+    fn v2_is_never_downgraded() {
+        let mut negotiator = VersionNegotiator::<4>::default();
+        let peer = MavLinkId::new(1, 1);
+
+        negotiator.observe(&frame::<V1>(peer, 0));
+        assert_eq!(negotiator.version_for(peer), MavLinkVersion::V1);
+
+        negotiator.observe(&frame::<V2>(peer, 0));
+        negotiator.observe(&frame::<V1>(peer, 0));
+        assert_eq!(negotiator.version_for(peer), MavLinkVersion::V2);
+        assert!(!negotiator.peer(peer).unwrap().confirmed);
+    }
+
+    #[test]
+    fn broadcast_uses_lowest_version() {
+        let mut negotiator = VersionNegotiator::<4>::new(MavLinkVersion::V2);
+        assert_eq!(negotiator.broadcast_version(), MavLinkVersion::V2);
+
+        negotiator.observe(&frame::<V2>(MavLinkId::new(1, 1), 0));
+        assert_eq!(negotiator.broadcast_version(), MavLinkVersion::V2);
+
+        negotiator.observe(&frame::<V1>(MavLinkId::new(2, 1), 0));
+        assert_eq!(negotiator.broadcast_version(), MavLinkVersion::V1);
+        assert_eq!(
+            negotiator.version_for(MavLinkId::new(1, 2)),
+            MavLinkVersion::V2
+        );
+
+        assert_eq!(
+            negotiator.version_for(MavLinkId::new(0, 0)),
+            MavLinkVersion::V1
+        );
+
+        negotiator.forget(MavLinkId::new(2, 1));
+        assert_eq!(negotiator.broadcast_version(), MavLinkVersion::V2);
+    }
+
+    #[test]
+    fn full_table_discards_new_peers() {
+        let mut negotiator = VersionNegotiator::<1>::default();
+        assert!(negotiator
+            .observe(&frame::<V2>(MavLinkId::new(1, 1), 0))
+            .is_some());
+        assert!(negotiator
+            .observe(&frame::<V2>(MavLinkId::new(2, 1), 0))
+            .is_none());
+        assert_eq!(negotiator.peers().count(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "dlct-common")]
+    fn protocol_version_handshake() {
+        let gcs = NegotiatingEndpoint::<4>::new(MavLinkId::new(255, 190), MavLinkVersion::V1);
+        let mut vehicle = NegotiatingEndpoint::<4>::new(MavLinkId::new(1, 1), MavLinkVersion::V1);
+
+        let request = gcs.request_protocol_version(vehicle.id()).unwrap();
+        assert_eq!(request.version(), MavLinkVersion::V1);
+        assert!(vehicle.is_protocol_version_request(&request));
+        assert!(!gcs.is_protocol_version_request(&request));
+        vehicle.observe(&request);
+
+        let response = vehicle.protocol_version().unwrap();
+        let mut gcs = gcs;
+        let peer = gcs.observe(&response).unwrap();
+        assert!(peer.confirmed);
+        assert_eq!(peer.version, MavLinkVersion::V2);
+    }
+}
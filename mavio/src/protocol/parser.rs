@@ -0,0 +1,266 @@
+//! # Incremental MAVLink frame parser
+
+use core::marker::PhantomData;
+
+use crate::consts::{FRAME_MAX_SIZE, HEADER_MIN_SIZE, HEADER_V1_SIZE, HEADER_V2_SIZE};
+use crate::protocol::header::Header;
+use crate::protocol::{Frame, MavSTX, MaybeVersioned, Versionless};
+
+/// Incremental (sans-I/O) MAVLink frame parser.
+///
+/// Accumulates incoming bytes in an internal buffer of [`FRAME_MAX_SIZE`] and produces frames once
+/// they are complete. Since partial frames are kept inside the parser, it can be fed by chunks of
+/// arbitrary size obtained from non-blocking or cancellable reads without loosing synchronisation
+/// with the stream.
+///
+/// Parser looks up for magic bytes in the same way as [`Receiver`](crate::Receiver). [`Versioned`]
+/// parsers will skip frames of other protocol versions.
+///
+/// There are two ways to feed the parser:
+///
+/// * [`FrameParser::push`] accepts a slice of already received bytes.
+/// * [`FrameParser::buffer_mut`] and [`FrameParser::advance`] allow reading directly into the
+///   parser's buffer. The buffer returned by [`FrameParser::buffer_mut`] never exceeds the number of
+///   bytes required to make progress, so reader never consumes bytes of the next frame.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(not(feature = "dlct-minimal"))]
+/// # fn main() {}
+/// # #[cfg(feature = "dlct-minimal")]
+/// # fn main() {
+/// use mavio::dialects::minimal::messages::Heartbeat;
+/// use mavio::protocol::FrameParser;
+/// use mavio::prelude::*;
+///
+/// # let frame = Endpoint::v2(MavLinkId::new(1, 1)).next_frame(&Heartbeat::default()).unwrap();
+/// # let mut bytes = [0u8; 32];
+/// # let len = frame.to_bytes(&mut bytes);
+/// # let bytes = &bytes[..len];
+/// let mut parser = FrameParser::<Versionless>::new();
+///
+/// // Feed the first half of the frame
+/// let (consumed, frame) = parser.push(&bytes[..10]);
+/// assert_eq!(consumed, 10);
+/// assert!(frame.is_none());
+///
+/// // Feed the rest
+/// let (_, frame) = parser.push(&bytes[10..]);
+/// assert!(frame.is_some());
+/// # }
+/// ```
+///
+/// [`Versioned`]: crate::protocol::Versioned
+#[derive(Clone, Debug)]
+pub struct FrameParser<V: MaybeVersioned = Versionless> {
+    buffer: [u8; FRAME_MAX_SIZE],
+    len: usize,
+    _version: PhantomData<V>,
+}
+
+impl<V: MaybeVersioned> FrameParser<V> {
+    /// Creates an empty parser.
+    pub fn new() -> Self {
+        Self {
+            buffer: [0u8; FRAME_MAX_SIZE],
+            len: 0,
+            _version: PhantomData,
+        }
+    }
+
+    /// Returns `true` if parser does not hold a partially received frame.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of bytes of a partially received frame.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Discards partially received frame.
+    pub fn reset(&mut self) {
+        self.len = 0;
+    }
+
+    /// Number of bytes required to make progress.
+    ///
+    /// This is never greater than the number of bytes left to complete the current frame.
+    pub fn remaining(&self) -> usize {
+        if self.len == 0 {
+            return HEADER_MIN_SIZE;
+        }
+
+        let header_size = self.header_size();
+        if self.len < header_size {
+            return header_size - self.len;
+        }
+
+        self.frame_size() - self.len
+    }
+
+    /// Buffer to read the next [`FrameParser::remaining`] bytes into.
+    ///
+    /// Once bytes are written, call [`FrameParser::advance`] to commit them.
+    pub fn buffer_mut(&mut self) -> &mut [u8] {
+        let remaining = self.remaining();
+        &mut self.buffer[self.len..self.len + remaining]
+    }
+
+    /// Commits `n` bytes written into [`FrameParser::buffer_mut`].
+    ///
+    /// Returns a frame if it is complete.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than [`FrameParser::remaining`].
+    pub fn advance(&mut self, n: usize) -> Option<Frame<V>> {
+        assert!(
+            n <= self.remaining(),
+            "advanced beyond the requested buffer"
+        );
+        self.len += n;
+        self.sync();
+
+        if self.len < self.header_size() || self.len < self.frame_size() {
+            return None;
+        }
+
+        let header_size = self.header_size();
+        let frame_size = self.frame_size();
+        self.len = 0;
+
+        // SAFETY: header starts with a valid magic byte and has the correct size.
+        let header = unsafe { Header::<V>::try_from_slice_unchecked(&self.buffer[..header_size]) };
+        Some(Frame::from_raw_body(
+            header,
+            &self.buffer[header_size..frame_size],
+        ))
+    }
+
+    /// Feeds bytes into parser.
+    ///
+    /// Consumes bytes until a frame is complete or until all bytes are consumed. Returns the number
+    /// of consumed bytes and a frame, if it is complete. Unconsumed bytes should be fed into
+    /// parser later.
+    pub fn push(&mut self, bytes: &[u8]) -> (usize, Option<Frame<V>>) {
+        let mut consumed = 0;
+        while consumed < bytes.len() {
+            let buf = self.buffer_mut();
+            let n = buf.len().min(bytes.len() - consumed);
+            buf[..n].copy_from_slice(&bytes[consumed..consumed + n]);
+            consumed += n;
+
+            if let Some(frame) = self.advance(n) {
+                return (consumed, Some(frame));
+            }
+        }
+        (consumed, None)
+    }
+
+    /// Drops bytes before the first magic byte.
+    fn sync(&mut self) {
+        if self.len == 0 || V::is_magic_byte(self.buffer[0]) {
+            return;
+        }
+        match self.buffer[..self.len]
+            .iter()
+            .position(|&b| V::is_magic_byte(b))
+        {
+            Some(start) => {
+                self.buffer.copy_within(start..self.len, 0);
+                self.len -= start;
+            }
+            None => self.len = 0,
+        }
+    }
+
+    /// Header size, must be called only when buffer starts with a magic byte.
+    fn header_size(&self) -> usize {
+        match MavSTX::from(self.buffer[0]) {
+            MavSTX::V2 => HEADER_V2_SIZE,
+            _ => HEADER_V1_SIZE,
+        }
+    }
+
+    /// Frame size, must be called only when header is complete.
+    fn frame_size(&self) -> usize {
+        let header_size = self.header_size();
+        // SAFETY: callers guarantee that buffer starts with a valid magic byte and holds at least
+        // `header_size` bytes.
+        let header = unsafe { Header::<V>::try_from_slice_unchecked(&self.buffer[..header_size]) };
+        header_size + header.body_length()
+    }
+}
+
+impl<V: MaybeVersioned> Default for FrameParser<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::{frame, SENDER};
+
+    use crate::protocol::{V1, V2};
+
+    fn frame_bytes<V: crate::protocol::Versioned>(buf: &mut [u8], sequence: u8) -> usize {
+        frame::<V>(SENDER, sequence).to_bytes(buf)
+    }
+
+    #[test]
+    fn byte_by_byte_with_junk() {
+        let mut buf = [0u8; 128];
+        buf[..3].copy_from_slice(&[1, 2, 3]);
+        let mut len = 3;
+        len += frame_bytes::<V1>(&mut buf[len..], 1);
+        buf[len..len + 4].copy_from_slice(&[9, 8, 7, 6]);
+        len += 4;
+        len += frame_bytes::<V2>(&mut buf[len..], 2);
+
+        let mut parser = FrameParser::<Versionless>::new();
+        let mut frames = [0u8; 2];
+        let mut n = 0;
+        for byte in &buf[..len] {
+            if let (_, Some(frame)) = parser.push(core::slice::from_ref(byte)) {
+                assert!(frame.validate_checksum_with_crc_extra(50).is_ok());
+                frames[n] = frame.sequence();
+                n += 1;
+            }
+        }
+        assert_eq!(frames, [1, 2]);
+        assert!(parser.is_empty());
+    }
+
+    #[test]
+    fn push_stops_after_frame() {
+        let mut buf = [0u8; 128];
+        let first = frame_bytes::<V2>(&mut buf, 1);
+        let len = first + frame_bytes::<V2>(&mut buf[first..], 2);
+
+        let mut parser = FrameParser::<V2>::new();
+        let (consumed, frame) = parser.push(&buf[..len]);
+        assert_eq!(consumed, first);
+        assert_eq!(frame.unwrap().sequence(), 1);
+
+        let (consumed, frame) = parser.push(&buf[first..len]);
+        assert_eq!(consumed, len - first);
+        assert_eq!(frame.unwrap().sequence(), 2);
+    }
+
+    #[test]
+    fn versioned_parser_skips_other_versions() {
+        let mut buf = [0u8; 128];
+        let first = frame_bytes::<V1>(&mut buf, 1);
+        let len = first + frame_bytes::<V2>(&mut buf[first..], 2);
+
+        let mut parser = FrameParser::<V2>::new();
+        let (_, frame) = parser.push(&buf[..len]);
+        assert_eq!(frame.unwrap().sequence(), 2);
+    }
+}
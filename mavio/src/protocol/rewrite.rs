@@ -0,0 +1,280 @@
+//! # Frame rewriting
+
+use crate::error::{Result, SignatureError};
+use crate::protocol::marker::{HasCrcExtra, IsCrcExtra, Unset};
+use crate::protocol::{
+    ComponentId, CrcExtra, Dialect, Frame, MaybeVersioned, Sequence, Sign, SigningConf, SystemId,
+    V2,
+};
+
+/// Rewrites header fields of an existing [`Frame`].
+///
+/// This is a safe alternative to `UpdateFrame` (requires `unsafe` feature) intended for routers
+/// and proxies that remap system and component `ID`s, renumber sequences per outbound link, or
+/// re-sign frames for another link. Created by [`Frame::rewrite`].
+///
+/// Since checksum depends on the header, [`FrameRewrite::apply`] is available only after a
+/// `CRC_EXTRA` was provided either directly by [`FrameRewrite::crc_extra`] or looked up in a
+/// dialect by [`FrameRewrite::dialect`].
+///
+/// Changing a header invalidates signature. Therefore, signed frames require an explicit decision:
+/// either [`FrameRewrite::resign`] or [`FrameRewrite::strip_signature`]. Otherwise,
+/// [`FrameRewrite::apply`] will return [`FrameError::Signature`] leaving the frame untouched.
+///
+/// [`FrameError::Signature`]: crate::error::FrameError::Signature
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(not(feature = "dlct-minimal"))]
+/// # fn main() {}
+/// # #[cfg(feature = "dlct-minimal")]
+/// # fn main() {
+/// use mavio::dialects::minimal::messages::Heartbeat;
+/// use mavio::dialects::Minimal;
+/// use mavio::prelude::*;
+///
+/// let endpoint = Endpoint::v2(MavLinkId::new(1, 1));
+/// let mut frame = endpoint.next_frame(&Heartbeat::default()).unwrap();
+///
+/// frame.rewrite()
+///     .system_id(10)
+///     .component_id(20)
+///     .sequence(42)
+///     .dialect::<Minimal>().unwrap()
+///     .apply().unwrap();
+///
+/// assert_eq!(frame.system_id(), 10);
+/// assert_eq!(frame.sequence(), 42);
+/// frame.validate_checksum::<Minimal>().unwrap();
+/// # }
+/// ```
+pub struct FrameRewrite<'a, V: MaybeVersioned, C: IsCrcExtra> {
+    frame: &'a mut Frame<V>,
+    sequence: Option<Sequence>,
+    system_id: Option<SystemId>,
+    component_id: Option<ComponentId>,
+    crc_extra: C,
+    signing: Signing<'a>,
+}
+
+enum Signing<'a> {
+    Unspecified,
+    Strip,
+    Resign(&'a mut dyn Sign, &'a SigningConf),
+}
+
+impl<V: MaybeVersioned> Frame<V> {
+    /// Starts rewriting of frame header.
+    ///
+    /// See [`FrameRewrite`] for details.
+    pub fn rewrite(&mut self) -> FrameRewrite<'_, V, Unset> {
+        FrameRewrite {
+            frame: self,
+            sequence: None,
+            system_id: None,
+            component_id: None,
+            crc_extra: Unset,
+            signing: Signing::Unspecified,
+        }
+    }
+}
+
+impl<'a, V: MaybeVersioned, C: IsCrcExtra> FrameRewrite<'a, V, C> {
+    /// Sets [`Frame::sequence`].
+    pub fn sequence(self, sequence: Sequence) -> Self {
+        Self {
+            sequence: Some(sequence),
+            ..self
+        }
+    }
+
+    /// Sets [`Frame::system_id`].
+    pub fn system_id(self, system_id: SystemId) -> Self {
+        Self {
+            system_id: Some(system_id),
+            ..self
+        }
+    }
+
+    /// Sets [`Frame::component_id`].
+    pub fn component_id(self, component_id: ComponentId) -> Self {
+        Self {
+            component_id: Some(component_id),
+            ..self
+        }
+    }
+
+    /// Sets `CRC_EXTRA` used to recalculate [`Frame::checksum`].
+    pub fn crc_extra(self, crc_extra: CrcExtra) -> FrameRewrite<'a, V, HasCrcExtra> {
+        FrameRewrite {
+            frame: self.frame,
+            sequence: self.sequence,
+            system_id: self.system_id,
+            component_id: self.component_id,
+            crc_extra: HasCrcExtra(crc_extra),
+            signing: self.signing,
+        }
+    }
+
+    /// Looks up `CRC_EXTRA` for the frame's message in a dialect `D`.
+    ///
+    /// Returns [`SpecError`](crate::error::SpecError) (wrapped by [`Error`](crate::error::Error))
+    /// if message is not part of the dialect.
+    pub fn dialect<D: Dialect>(self) -> Result<FrameRewrite<'a, V, HasCrcExtra>> {
+        let message_info = D::message_info(self.frame.message_id())?;
+        Ok(self.crc_extra(message_info.crc_extra()))
+    }
+
+    /// Re-signs frame with the provided `signer` and signing configuration.
+    ///
+    /// Unsigned `MAVLink 2` frames will be signed. `MAVLink 1` frames can't be signed and will be
+    /// left unsigned.
+    pub fn resign(self, signer: &'a mut dyn Sign, conf: &'a SigningConf) -> Self {
+        Self {
+            signing: Signing::Resign(signer, conf),
+            ..self
+        }
+    }
+
+    /// Removes signature from frame.
+    pub fn strip_signature(self) -> Self {
+        Self {
+            signing: Signing::Strip,
+            ..self
+        }
+    }
+}
+
+impl<V: MaybeVersioned> FrameRewrite<'_, V, HasCrcExtra> {
+    /// Applies changes to the frame.
+    ///
+    /// Recalculates checksum and re-signs or strips signature if requested.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FrameError::Signature`](crate::error::FrameError::Signature) if frame was signed,
+    /// but neither [`FrameRewrite::resign`] nor [`FrameRewrite::strip_signature`] was requested. In
+    /// such case, frame is kept unchanged.
+    pub fn apply(self) -> Result<()> {
+        let frame = self.frame;
+
+        if frame.is_signed() && matches!(self.signing, Signing::Unspecified) {
+            return Err(SignatureError.into());
+        }
+
+        if let Some(sequence) = self.sequence {
+            frame.header.sequence = sequence;
+        }
+        if let Some(system_id) = self.system_id {
+            frame.header.system_id = system_id;
+        }
+        if let Some(component_id) = self.component_id {
+            frame.header.component_id = component_id;
+        }
+
+        let signer = match self.signing {
+            Signing::Unspecified => None,
+            Signing::Strip => {
+                frame.remove_signature();
+                None
+            }
+            Signing::Resign(signer, conf) => {
+                frame.remove_signature();
+                frame.header.set_is_signed(frame.matches_version(V2));
+                Some((signer, conf))
+            }
+        };
+
+        frame.checksum = frame.calculate_crc(self.crc_extra.0);
+
+        if let Some((signer, conf)) = signer {
+            conf.apply(frame, signer);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "sha2")]
+mod tests {
+    use super::*;
+    use crate::error::{Error, FrameError};
+    use crate::protocol::{MavLinkId, MavTimestamp, SecretKey};
+    use crate::utils::test_utils::frame;
+    use crate::utils::MavSha256;
+
+    fn signed_frame(conf: &SigningConf) -> Frame<V2> {
+        let mut frame = frame::<V2>(MavLinkId::new(1, 1), 0);
+        frame.add_signature(&mut MavSha256::default(), conf);
+        frame
+    }
+
+    fn conf(link_id: u8, secret: &str) -> SigningConf {
+        SigningConf {
+            link_id,
+            timestamp: MavTimestamp::from_raw_u64(100),
+            secret: SecretKey::from(secret),
+        }
+    }
+
+    #[test]
+    fn signed_frame_requires_explicit_decision() {
+        let conf = conf(1, "abc");
+        let mut frame = signed_frame(&conf);
+
+        let result = frame.rewrite().system_id(2).crc_extra(50).apply();
+        assert!(matches!(result, Err(Error::Frame(FrameError::Signature))));
+        assert_eq!(frame.system_id(), 1);
+        assert!(frame
+            .validate_signature(&mut MavSha256::default(), &conf.secret)
+            .is_ok());
+    }
+
+    #[test]
+    fn strip_signature() {
+        let mut frame = signed_frame(&conf(1, "abc"));
+
+        frame
+            .rewrite()
+            .system_id(2)
+            .strip_signature()
+            .crc_extra(50)
+            .apply()
+            .unwrap();
+
+        assert!(!frame.is_signed());
+        assert!(frame.signature().is_none());
+        assert_eq!(frame.system_id(), 2);
+        assert!(frame.validate_checksum_with_crc_extra(50).is_ok());
+    }
+
+    #[test]
+    fn resign_with_another_key() {
+        let old_conf = conf(1, "abc");
+        let new_conf = conf(2, "def");
+        let mut frame = signed_frame(&old_conf);
+        let mut signer = MavSha256::default();
+
+        frame
+            .rewrite()
+            .component_id(7)
+            .sequence(9)
+            .resign(&mut signer, &new_conf)
+            .crc_extra(50)
+            .apply()
+            .unwrap();
+
+        assert_eq!(frame.component_id(), 7);
+        assert_eq!(frame.sequence(), 9);
+        assert_eq!(frame.link_id(), Some(2));
+        assert!(frame.validate_checksum_with_crc_extra(50).is_ok());
+        assert!(frame
+            .validate_signature(&mut MavSha256::default(), &new_conf.secret)
+            .is_ok());
+        assert!(frame
+            .validate_signature(&mut MavSha256::default(), &old_conf.secret)
+            .is_err());
+    }
+}
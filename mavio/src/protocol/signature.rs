@@ -139,7 +139,7 @@ impl<'a> Signer<'a> {
         signature: &Signature,
         key: &SecretKey,
     ) -> bool {
-        let expected_value = self.calculate(&frame, signature.link_id, signature.timestamp, key);
+        let expected_value = self.calculate(frame, signature.link_id, signature.timestamp, key);
 
         expected_value == signature.value
     }
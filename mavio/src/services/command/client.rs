@@ -0,0 +1,562 @@
+//! # Command protocol client
+//!
+//! Implements client side of MAVLink
+//! [command protocol](https://mavlink.io/en/services/command.html).
+//!
+//! [`CommandClient`] is a sans-I/O state machine that sends `COMMAND_LONG` or `COMMAND_INT` and
+//! waits for the corresponding `COMMAND_ACK`:
+//!
+//! * Acknowledgements are matched by command `ID`, sender of the acknowledgement (it should be the
+//!   target of the command), and the target of the acknowledgement (it should be this client).
+//! * If acknowledgement is not received in time, command is retransmitted. For `COMMAND_LONG` the
+//!   `confirmation` field is incremented with each retransmission.
+//! * Once `MAV_RESULT_IN_PROGRESS` is received, retransmissions stop and client waits for the final
+//!   result reporting progress updates.
+//!
+//! Blocking driver [`CommandClient::execute`] runs client over [`Connection`]. With `tokio-rt`
+//! feature enabled, [`CommandClient::execute_async`] does the same over [`AsyncConnection`].
+//!
+//! [`Connection`]: crate::io::Connection
+//! [`AsyncConnection`]: crate::io::AsyncConnection
+
+use core::time::Duration;
+
+use crate::microservices::command::enums::MavResult;
+use crate::microservices::command::messages::{CommandAck, CommandCancel};
+use crate::protocol::{Frame, MavLinkId, MaybeVersioned};
+use crate::services::command::Command;
+use crate::services::{decode, is_addressed_to, ServiceError};
+
+#[cfg(feature = "tokio-rt")]
+use crate::io::{AsyncConnection, AsyncRead, AsyncWrite};
+#[cfg(feature = "std")]
+use crate::io::{Connection, Read, Write};
+#[cfg(feature = "std")]
+use crate::protocol::{Endpoint, Versioned};
+#[cfg(feature = "tokio-rt")]
+use crate::services::run_async;
+#[cfg(feature = "std")]
+use crate::services::{run, Driven};
+#[cfg(feature = "std")]
+use crate::Error;
+
+/// Default time to wait for acknowledgement before retransmitting a command.
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_millis(1500);
+/// Default number of command retransmissions.
+pub const DEFAULT_COMMAND_RETRIES: u8 = 3;
+/// Default time to wait for a progress update or a final result once command is in progress.
+pub const DEFAULT_PROGRESS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Event produced by [`CommandClient::handle`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommandEvent {
+    /// Command is being executed.
+    ///
+    /// Contains acknowledgement with `MAV_RESULT_IN_PROGRESS` result. Its `progress` field is
+    /// either a percentage or `255` if progress is unknown.
+    InProgress(CommandAck),
+    /// Command was executed or rejected.
+    ///
+    /// Contains acknowledgement with the final result.
+    Completed(CommandAck),
+}
+
+#[derive(Clone, Debug)]
+struct Pending {
+    command: Command,
+    transmissions: u8,
+    deadline: Duration,
+    in_progress: bool,
+}
+
+/// Sans-I/O command protocol client.
+///
+/// Client executes one command at a time. Call [`CommandClient::start`] to obtain a command that
+/// should be sent, then pass incoming frames to [`CommandClient::handle`] and periodically call
+/// [`CommandClient::poll`] (for example, when [`CommandClient::timeout`] expires) to handle
+/// retransmissions.
+///
+/// See [module docs](self) for protocol details.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use mavio::microservices::command::enums::{MavCmd, MavResult};
+/// use mavio::microservices::command::messages::{CommandAck, CommandLong};
+/// use mavio::services::command::{CommandClient, CommandEvent};
+/// use mavio::prelude::*;
+///
+/// let mut client = CommandClient::new(MavLinkId::new(255, 190));
+/// let autopilot = Endpoint::v2(MavLinkId::new(1, 1));
+///
+/// let command = client
+///     .start(CommandLong {
+///         target_system: 1,
+///         target_component: 1,
+///         command: MavCmd::ComponentArmDisarm,
+///         param1: 1.0,
+///         ..Default::default()
+///     }, Duration::ZERO)
+///     .unwrap();
+/// assert_eq!(command.confirmation(), 0);
+///
+/// // Acknowledgement is lost, so command is retransmitted
+/// let command = client.poll(Duration::from_secs(2)).unwrap().unwrap();
+/// assert_eq!(command.confirmation(), 1);
+///
+/// let ack = autopilot.next_frame(&CommandAck {
+///     command: MavCmd::ComponentArmDisarm,
+///     result: MavResult::Accepted,
+///     ..Default::default()
+/// }).unwrap();
+///
+/// let event = client.handle(&ack, Duration::from_secs(3));
+/// assert!(matches!(event, Some(CommandEvent::Completed(_))));
+/// assert!(!client.is_pending());
+/// ```
+#[derive(Clone, Debug)]
+pub struct CommandClient {
+    id: MavLinkId,
+    timeout: Duration,
+    retries: u8,
+    progress_timeout: Duration,
+    pending: Option<Pending>,
+}
+
+impl CommandClient {
+    /// Creates a client for a component with a specified `id`.
+    pub fn new(id: MavLinkId) -> Self {
+        Self {
+            id,
+            timeout: DEFAULT_COMMAND_TIMEOUT,
+            retries: DEFAULT_COMMAND_RETRIES,
+            progress_timeout: DEFAULT_PROGRESS_TIMEOUT,
+            pending: None,
+        }
+    }
+
+    /// Sets time to wait for acknowledgement before retransmitting a command.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Sets the number of retransmissions after which command is considered failed.
+    pub fn with_retries(self, retries: u8) -> Self {
+        Self { retries, ..self }
+    }
+
+    /// Sets time to wait for a progress update or a final result once command is in progress.
+    pub fn with_progress_timeout(self, progress_timeout: Duration) -> Self {
+        Self {
+            progress_timeout,
+            ..self
+        }
+    }
+
+    /// `ID` of the component this client acts on behalf of.
+    #[inline(always)]
+    pub fn id(&self) -> MavLinkId {
+        self.id
+    }
+
+    /// Returns `true` if client waits for acknowledgement.
+    #[inline(always)]
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Command which is currently executed.
+    pub fn pending(&self) -> Option<&Command> {
+        self.pending.as_ref().map(|pending| &pending.command)
+    }
+
+    /// Starts command execution at `now`.
+    ///
+    /// Returns command that should be sent. The `confirmation` field of `COMMAND_LONG` is reset to
+    /// `0`.
+    ///
+    /// Returns [`ServiceError::Busy`] if another command is being executed.
+    pub fn start(
+        &mut self,
+        command: impl Into<Command>,
+        now: Duration,
+    ) -> core::result::Result<&Command, ServiceError> {
+        if self.pending.is_some() {
+            return Err(ServiceError::Busy);
+        }
+
+        let mut command = command.into();
+        command.set_confirmation(0);
+        let pending = self.pending.insert(Pending {
+            command,
+            transmissions: 1,
+            deadline: now + self.timeout,
+            in_progress: false,
+        });
+        Ok(&pending.command)
+    }
+
+    /// Handles incoming frame received at `now`.
+    ///
+    /// Returns [`CommandEvent`] if frame contains acknowledgement for the pending command.
+    pub fn handle<V: MaybeVersioned>(
+        &mut self,
+        frame: &Frame<V>,
+        now: Duration,
+    ) -> Option<CommandEvent> {
+        self.pending.as_ref()?;
+        let ack = decode::<CommandAck, V>(frame)?;
+        if !is_addressed_to(ack.target_system, ack.target_component, self.id) {
+            return None;
+        }
+
+        // Acknowledgement should come from the command target
+        let source = MavLinkId::new(frame.system_id(), frame.component_id());
+        let pending = self.pending.as_mut()?;
+        let command = &pending.command;
+        if ack.command != command.command()
+            || !is_addressed_to(command.target_system(), command.target_component(), source)
+        {
+            return None;
+        }
+
+        if ack.result == MavResult::InProgress {
+            pending.in_progress = true;
+            pending.deadline = now + self.progress_timeout;
+            return Some(CommandEvent::InProgress(ack));
+        }
+
+        self.pending = None;
+        Some(CommandEvent::Completed(ack))
+    }
+
+    /// Checks timeouts at `now`.
+    ///
+    /// Returns a command that should be retransmitted, if acknowledgement was not received in time.
+    ///
+    /// Returns [`ServiceError::TimedOut`] and aborts command, if all retries are exhausted or if
+    /// command in progress has not reported in time.
+    pub fn poll(&mut self, now: Duration) -> core::result::Result<Option<&Command>, ServiceError> {
+        let Some(pending) = &self.pending else {
+            return Ok(None);
+        };
+        if now < pending.deadline {
+            return Ok(None);
+        }
+        if pending.in_progress || pending.transmissions > self.retries {
+            self.pending = None;
+            return Err(ServiceError::TimedOut);
+        }
+
+        let Some(pending) = self.pending.as_mut() else {
+            return Ok(None);
+        };
+        pending
+            .command
+            .set_confirmation(pending.command.confirmation().wrapping_add(1));
+        pending.transmissions += 1;
+        pending.deadline = now + self.timeout;
+        Ok(Some(&pending.command))
+    }
+
+    /// Time left until [`CommandClient::poll`] should be called.
+    ///
+    /// Returns [`None`] if there is no pending command.
+    pub fn timeout(&self, now: Duration) -> Option<Duration> {
+        self.pending
+            .as_ref()
+            .map(|pending| pending.deadline.saturating_sub(now))
+    }
+
+    /// Aborts pending command.
+    ///
+    /// Returns `COMMAND_CANCEL` message that should be sent to the target to cancel a long-running
+    /// command or [`None`] if there is no pending command.
+    pub fn cancel(&mut self) -> Option<CommandCancel> {
+        let command = self.pending.take()?.command;
+        Some(CommandCancel {
+            target_system: command.target_system(),
+            target_component: command.target_component(),
+            command: command.command(),
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl CommandClient {
+    /// <sup>`std`</sup>
+    /// Executes command over a blocking [`Connection`] and returns the final acknowledgement.
+    ///
+    /// Progress updates are reported to `on_progress`. Frames that are not related to the command
+    /// are discarded. Use sans-I/O API of [`CommandClient`] if you want to process other frames
+    /// while the command is being executed.
+    ///
+    /// Returns [`ServiceError::TimedOut`] wrapped into [`Error::Service`] if command was not
+    /// acknowledged in time.
+    ///
+    /// Available only when `std` feature is enabled.
+    pub fn execute<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+        command: impl Into<Command>,
+        mut on_progress: impl FnMut(&CommandAck),
+    ) -> crate::Result<CommandAck>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        let command = self.start(command, Duration::ZERO)?.as_message();
+        if let Err(err) = connection.send_message(command) {
+            self.pending = None;
+            return Err(err);
+        }
+
+        run(self, connection, |client, frame, now| {
+            Ok(completed(client.handle(frame, now), &mut on_progress))
+        })
+    }
+}
+
+#[cfg(feature = "tokio-rt")]
+impl CommandClient {
+    /// <sup>`tokio-rt`</sup>
+    /// Executes command over [`AsyncConnection`] and returns the final acknowledgement.
+    ///
+    /// Asynchronous counterpart of [`CommandClient::execute`]. Must be called within Tokio
+    /// runtime.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    pub async fn execute_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+        command: impl Into<Command>,
+        mut on_progress: impl FnMut(&CommandAck),
+    ) -> crate::Result<CommandAck>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        let command = self.start(command, Duration::ZERO)?.as_message();
+        if let Err(err) = connection.send_message(command).await {
+            self.pending = None;
+            return Err(err);
+        }
+
+        run_async(self, connection, |client, frame, now| {
+            Ok(completed(client.handle(frame, now), &mut on_progress))
+        })
+        .await
+    }
+}
+
+#[cfg(feature = "std")]
+impl Driven for CommandClient {
+    fn poll_frame<V: Versioned>(
+        &mut self,
+        endpoint: &Endpoint<V>,
+        now: Duration,
+    ) -> crate::Result<Option<Frame<V>>> {
+        self.poll(now)?
+            .map(|command| endpoint.next_frame(command.as_message()))
+            .transpose()
+    }
+
+    fn poll_timeout(&self, now: Duration) -> Option<Duration> {
+        self.timeout(now)
+    }
+
+    fn abort(&mut self) {
+        self.pending = None;
+    }
+}
+
+/// Reports progress updates and returns the final acknowledgement.
+#[cfg(feature = "std")]
+fn completed(
+    event: Option<CommandEvent>,
+    on_progress: &mut impl FnMut(&CommandAck),
+) -> Option<CommandAck> {
+    match event? {
+        CommandEvent::InProgress(ack) => {
+            on_progress(&ack);
+            None
+        }
+        CommandEvent::Completed(ack) => Some(ack),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::microservices::command::enums::MavCmd;
+    use crate::microservices::command::messages::CommandLong;
+    use crate::utils::test_utils::message_frame;
+    #[cfg(feature = "std")]
+    use crate::utils::test_utils::spawn_remote;
+    #[cfg(feature = "tokio-rt")]
+    use crate::utils::test_utils::{async_connections, block_on};
+
+    const CLIENT: MavLinkId = MavLinkId {
+        system: 255,
+        component: 190,
+    };
+    const AUTOPILOT: MavLinkId = MavLinkId {
+        system: 1,
+        component: 1,
+    };
+
+    fn arm() -> CommandLong {
+        CommandLong {
+            target_system: AUTOPILOT.system,
+            target_component: AUTOPILOT.component,
+            command: MavCmd::ComponentArmDisarm,
+            confirmation: 5,
+            param1: 1.0,
+            ..Default::default()
+        }
+    }
+
+    fn ack(result: MavResult, progress: u8) -> CommandAck {
+        CommandAck {
+            command: MavCmd::ComponentArmDisarm,
+            result,
+            progress,
+            target_system: CLIENT.system,
+            target_component: CLIENT.component,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn retransmits_and_times_out() {
+        let ms = Duration::from_millis;
+        let mut client = CommandClient::new(CLIENT)
+            .with_timeout(ms(100))
+            .with_retries(2);
+
+        assert_eq!(client.start(arm(), ms(0)).unwrap().confirmation(), 0);
+        assert_eq!(client.start(arm(), ms(0)), Err(ServiceError::Busy));
+        assert_eq!(client.poll(ms(50)), Ok(None));
+        assert_eq!(client.timeout(ms(50)), Some(ms(50)));
+
+        assert_eq!(client.poll(ms(100)).unwrap().unwrap().confirmation(), 1);
+        assert_eq!(client.poll(ms(200)).unwrap().unwrap().confirmation(), 2);
+        assert_eq!(client.poll(ms(300)), Err(ServiceError::TimedOut));
+        assert!(!client.is_pending());
+    }
+
+    #[test]
+    fn matches_acknowledgements() {
+        let ms = Duration::from_millis;
+        let mut client = CommandClient::new(CLIENT);
+        client.start(arm(), ms(0)).unwrap();
+
+        // Wrong sender
+        let ack_frame = message_frame(MavLinkId::new(2, 1), &ack(MavResult::Accepted, 0));
+        assert!(client.handle(&ack_frame, ms(10)).is_none());
+        // Wrong recipient
+        let other = CommandAck {
+            target_system: 42,
+            ..ack(MavResult::Accepted, 0)
+        };
+        assert!(client
+            .handle(&message_frame(AUTOPILOT, &other), ms(10))
+            .is_none());
+        // Wrong command
+        let other = CommandAck {
+            command: MavCmd::DoSetMode,
+            ..ack(MavResult::Accepted, 0)
+        };
+        assert!(client
+            .handle(&message_frame(AUTOPILOT, &other), ms(10))
+            .is_none());
+        // Other message
+        assert!(client
+            .handle(&message_frame(AUTOPILOT, &arm()), ms(10))
+            .is_none());
+
+        let event = client.handle(
+            &message_frame(AUTOPILOT, &ack(MavResult::Denied, 0)),
+            ms(10),
+        );
+        assert_eq!(
+            event,
+            Some(CommandEvent::Completed(ack(MavResult::Denied, 0)))
+        );
+        assert!(!client.is_pending());
+    }
+
+    #[test]
+    fn in_progress_stops_retransmissions() {
+        let ms = Duration::from_millis;
+        let mut client = CommandClient::new(CLIENT)
+            .with_timeout(ms(100))
+            .with_progress_timeout(ms(1000));
+        client.start(arm(), ms(0)).unwrap();
+
+        let event = client.handle(
+            &message_frame(AUTOPILOT, &ack(MavResult::InProgress, 50)),
+            ms(50),
+        );
+        assert!(matches!(event, Some(CommandEvent::InProgress(ack)) if ack.progress == 50));
+        assert_eq!(client.poll(ms(500)), Ok(None));
+
+        let cancel = client.clone().cancel().unwrap();
+        assert_eq!(cancel.command, MavCmd::ComponentArmDisarm);
+        assert_eq!(cancel.target_system, AUTOPILOT.system);
+
+        assert_eq!(client.poll(ms(1050)), Err(ServiceError::TimedOut));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn blocking_execute() {
+        let (mut connection, server) = spawn_remote(CLIENT, AUTOPILOT, |mut server| {
+            // The first command is lost
+            server.recv().unwrap();
+            let frame = server.recv().unwrap();
+            let command = CommandLong::try_from(frame.payload()).unwrap();
+            assert_eq!(command.confirmation, 1);
+
+            server
+                .send_message(&ack(MavResult::InProgress, 10))
+                .unwrap();
+            server.send_message(&ack(MavResult::Accepted, 100)).unwrap();
+        });
+
+        let mut client = CommandClient::new(CLIENT).with_timeout(Duration::from_millis(20));
+        let mut progress = Vec::new();
+        let ack = client
+            .execute(&mut connection, arm(), |ack| progress.push(ack.progress))
+            .unwrap();
+
+        assert_eq!(ack.result, MavResult::Accepted);
+        assert_eq!(progress, vec![10]);
+        server.join().unwrap();
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[test]
+    fn async_execute_times_out() {
+        block_on(async {
+            let (mut connection, _server) = async_connections(CLIENT, AUTOPILOT);
+
+            let mut client = CommandClient::new(CLIENT)
+                .with_timeout(Duration::from_millis(10))
+                .with_retries(1);
+            let result = client.execute_async(&mut connection, arm(), |_| {}).await;
+
+            assert!(matches!(
+                result,
+                Err(Error::Service(ServiceError::TimedOut))
+            ));
+            assert!(!client.is_pending());
+        });
+    }
+}
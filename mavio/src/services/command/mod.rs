@@ -0,0 +1,128 @@
+//! # Command protocol
+//!
+//! Implements MAVLink [command protocol](https://mavlink.io/en/services/command.html).
+//!
+//! * [`CommandClient`] sends commands and waits for acknowledgements, see [`client`].
+//! * [`CommandServer`] dispatches incoming commands to handlers and acknowledges them, see
+//!   [`server`].
+//!
+//! Both are sans-I/O state machines suitable for `no_std` targets.
+
+use crate::microservices::command::enums::MavCmd;
+use crate::microservices::command::messages::{CommandInt, CommandLong};
+use crate::protocol::Message;
+
+pub mod client;
+pub mod server;
+
+#[doc(inline)]
+pub use client::{
+    CommandClient, CommandEvent, DEFAULT_COMMAND_RETRIES, DEFAULT_COMMAND_TIMEOUT,
+    DEFAULT_PROGRESS_TIMEOUT,
+};
+#[doc(inline)]
+pub use server::{
+    CommandFn, CommandHandler, CommandReply, CommandRequest, CommandServer,
+    DEFAULT_COMMAND_HANDLERS_CAPACITY, DEFAULT_DEDUP_WINDOW,
+};
+
+/// Command sent by [`CommandClient`] and handled by [`CommandServer`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// `COMMAND_LONG`.
+    Long(CommandLong),
+    /// `COMMAND_INT`.
+    Int(CommandInt),
+}
+
+impl Command {
+    /// Command `ID`.
+    pub fn command(&self) -> MavCmd {
+        match self {
+            Command::Long(cmd) => cmd.command,
+            Command::Int(cmd) => cmd.command,
+        }
+    }
+
+    /// System which should execute the command (`0` for broadcast).
+    pub fn target_system(&self) -> u8 {
+        match self {
+            Command::Long(cmd) => cmd.target_system,
+            Command::Int(cmd) => cmd.target_system,
+        }
+    }
+
+    /// Component which should execute the command (`0` for all components).
+    pub fn target_component(&self) -> u8 {
+        match self {
+            Command::Long(cmd) => cmd.target_component,
+            Command::Int(cmd) => cmd.target_component,
+        }
+    }
+
+    /// Confirmation counter.
+    ///
+    /// Always `0` for `COMMAND_INT` since it has no such field.
+    pub fn confirmation(&self) -> u8 {
+        match self {
+            Command::Long(cmd) => cmd.confirmation,
+            Command::Int(_) => 0,
+        }
+    }
+
+    /// Command as a MAVLink message.
+    pub fn as_message(&self) -> &dyn Message {
+        match self {
+            Command::Long(cmd) => cmd,
+            Command::Int(cmd) => cmd,
+        }
+    }
+
+    fn set_confirmation(&mut self, confirmation: u8) {
+        if let Command::Long(cmd) = self {
+            cmd.confirmation = confirmation;
+        }
+    }
+
+    /// Returns `true` if commands are equal up to the `confirmation` field.
+    ///
+    /// Parameters are compared bitwise since `NaN` is commonly used as a placeholder.
+    fn is_same(&self, other: &Command) -> bool {
+        let bits = |params: [f32; 4]| params.map(f32::to_bits);
+        match (self, other) {
+            (Command::Long(a), Command::Long(b)) => {
+                a.target_system == b.target_system
+                    && a.target_component == b.target_component
+                    && a.command == b.command
+                    && bits([a.param1, a.param2, a.param3, a.param4])
+                        == bits([b.param1, b.param2, b.param3, b.param4])
+                    && bits([a.param5, a.param6, a.param7, 0.0])
+                        == bits([b.param5, b.param6, b.param7, 0.0])
+            }
+            (Command::Int(a), Command::Int(b)) => {
+                a.target_system == b.target_system
+                    && a.target_component == b.target_component
+                    && a.frame == b.frame
+                    && a.command == b.command
+                    && a.current == b.current
+                    && a.autocontinue == b.autocontinue
+                    && bits([a.param1, a.param2, a.param3, a.param4])
+                        == bits([b.param1, b.param2, b.param3, b.param4])
+                    && (a.x, a.y, a.z.to_bits()) == (b.x, b.y, b.z.to_bits())
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<CommandLong> for Command {
+    fn from(value: CommandLong) -> Self {
+        Command::Long(value)
+    }
+}
+
+impl From<CommandInt> for Command {
+    fn from(value: CommandInt) -> Self {
+        Command::Int(value)
+    }
+}
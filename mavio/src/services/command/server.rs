@@ -0,0 +1,590 @@
+//! # Command protocol server
+//!
+//! Implements receiving side of MAVLink
+//! [command protocol](https://mavlink.io/en/services/command.html).
+//!
+//! [`CommandServer`] is a sans-I/O state machine that dispatches incoming `COMMAND_LONG` and
+//! `COMMAND_INT` to handlers registered for a particular [`MavCmd`] and produces `COMMAND_ACK`
+//! that should be sent back:
+//!
+//! * Commands addressed to other components are ignored.
+//! * Commands without handlers are acknowledged with `MAV_RESULT_UNSUPPORTED`.
+//! * Retransmissions of already handled commands are acknowledged with the same result without
+//!   calling handlers again. For `COMMAND_LONG`, retransmissions are detected by non-zero
+//!   `confirmation`, for `COMMAND_INT` by identical parameters within
+//!   [`CommandServer::dedup_window`].
+//! * Handlers may report `MAV_RESULT_IN_PROGRESS`. Such commands remain active until
+//!   [`CommandServer::complete`] is called or until they are cancelled by `COMMAND_CANCEL`. Use
+//!   [`CommandServer::progress`] to report progress updates. While command is active, the same
+//!   command from other peers is rejected with `MAV_RESULT_TEMPORARILY_REJECTED`.
+
+use core::time::Duration;
+
+use crate::microservices::command::enums::{MavCmd, MavResult};
+use crate::microservices::command::messages::{CommandAck, CommandCancel, CommandInt, CommandLong};
+use crate::protocol::{Frame, MavLinkId, MaybeVersioned};
+use crate::services::command::Command;
+use crate::services::{decode, is_addressed_to};
+
+/// Default capacity of a [`CommandServer`] handler and exchange tables.
+pub const DEFAULT_COMMAND_HANDLERS_CAPACITY: usize = 16;
+/// Default time during which retransmitted commands are considered duplicates.
+pub const DEFAULT_DEDUP_WINDOW: Duration = Duration::from_secs(3);
+
+/// Command handler that can be stored in a static table.
+///
+/// See [`CommandServer::with_static_handlers`].
+pub type CommandFn = fn(&CommandRequest) -> CommandReply;
+
+/// Command handler that can be registered by [`CommandServer::register`].
+pub type CommandHandler<'a> = &'a mut dyn FnMut(&CommandRequest) -> CommandReply;
+
+/// Command received by [`CommandServer`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommandRequest {
+    /// Sender of the command.
+    pub source: MavLinkId,
+    /// Received command.
+    pub command: Command,
+}
+
+/// Result of a command returned by handlers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CommandReply {
+    /// Command result.
+    pub result: MavResult,
+    /// Progress percentage for `MAV_RESULT_IN_PROGRESS` (`255` if unknown).
+    pub progress: u8,
+    /// Additional result information.
+    pub result_param2: i32,
+}
+
+impl CommandReply {
+    /// Creates a reply with a given result.
+    pub fn new(result: MavResult) -> Self {
+        Self {
+            result,
+            progress: 0,
+            result_param2: 0,
+        }
+    }
+
+    /// Creates a reply with `MAV_RESULT_ACCEPTED` result.
+    pub fn accepted() -> Self {
+        Self::new(MavResult::Accepted)
+    }
+
+    /// Creates a reply with `MAV_RESULT_IN_PROGRESS` result and a given `progress`.
+    pub fn in_progress(progress: u8) -> Self {
+        Self {
+            progress,
+            ..Self::new(MavResult::InProgress)
+        }
+    }
+
+    /// Sets additional result information.
+    pub fn with_result_param2(self, result_param2: i32) -> Self {
+        Self {
+            result_param2,
+            ..self
+        }
+    }
+}
+
+impl From<MavResult> for CommandReply {
+    fn from(value: MavResult) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Handled command and its latest acknowledgement.
+#[derive(Clone, Debug)]
+struct Exchange {
+    request: CommandRequest,
+    ack: CommandAck,
+    updated: Duration,
+}
+
+impl Exchange {
+    fn is_active(&self) -> bool {
+        self.ack.result == MavResult::InProgress
+    }
+
+    fn is_expired(&self, now: Duration, window: Duration) -> bool {
+        !self.is_active() && self.updated + window <= now
+    }
+}
+
+/// Sans-I/O command protocol server.
+///
+/// Handlers are either registered as closures by [`CommandServer::register`] or provided as a
+/// static table of [`CommandFn`] by [`CommandServer::with_static_handlers`]. The latter does not
+/// require mutable state and is convenient for `no_std` targets. Registered handlers take
+/// precedence over static ones.
+///
+/// Server has a fixed capacity `N` that limits the number of registered handlers and the number
+/// of remembered commands used for deduplication and progress tracking. If all `N` remembered
+/// commands are in progress, new commands are acknowledged with `MAV_RESULT_TEMPORARILY_REJECTED`
+/// without calling handlers.
+///
+/// See [module docs](self) for protocol details.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use mavio::microservices::command::enums::{MavCmd, MavResult};
+/// use mavio::microservices::command::messages::CommandLong;
+/// use mavio::services::command::{CommandFn, CommandReply, CommandRequest, CommandServer};
+/// use mavio::prelude::*;
+///
+/// fn arm(request: &CommandRequest) -> CommandReply {
+///     CommandReply::accepted()
+/// }
+///
+/// static HANDLERS: [(MavCmd, CommandFn); 1] = [(MavCmd::ComponentArmDisarm, arm)];
+///
+/// let mut server = CommandServer::<4>::new(MavLinkId::new(1, 1)).with_static_handlers(&HANDLERS);
+///
+/// let gcs = Endpoint::v2(MavLinkId::new(255, 190));
+/// let frame = gcs.next_frame(&CommandLong {
+///     target_system: 1,
+///     target_component: 1,
+///     command: MavCmd::ComponentArmDisarm,
+///     ..Default::default()
+/// }).unwrap();
+///
+/// let ack = server.handle(&frame, Duration::ZERO).unwrap();
+/// assert_eq!(ack.result, MavResult::Accepted);
+/// assert_eq!(ack.target_system, 255);
+/// ```
+pub struct CommandServer<'a, const N: usize = DEFAULT_COMMAND_HANDLERS_CAPACITY> {
+    id: MavLinkId,
+    dedup_window: Duration,
+    handlers: [Option<(MavCmd, CommandHandler<'a>)>; N],
+    static_handlers: &'a [(MavCmd, CommandFn)],
+    exchanges: [Option<Exchange>; N],
+}
+
+impl<'a, const N: usize> CommandServer<'a, N> {
+    /// Creates a server for a component with a specified `id`.
+    pub fn new(id: MavLinkId) -> Self {
+        Self {
+            id,
+            dedup_window: DEFAULT_DEDUP_WINDOW,
+            handlers: core::array::from_fn(|_| None),
+            static_handlers: &[],
+            exchanges: core::array::from_fn(|_| None),
+        }
+    }
+
+    /// Sets a static table of command handlers.
+    pub fn with_static_handlers(self, static_handlers: &'a [(MavCmd, CommandFn)]) -> Self {
+        Self {
+            static_handlers,
+            ..self
+        }
+    }
+
+    /// Sets time during which retransmitted commands are considered duplicates.
+    pub fn with_dedup_window(self, dedup_window: Duration) -> Self {
+        Self {
+            dedup_window,
+            ..self
+        }
+    }
+
+    /// `ID` of the component this server acts on behalf of.
+    #[inline(always)]
+    pub fn id(&self) -> MavLinkId {
+        self.id
+    }
+
+    /// Time during which retransmitted commands are considered duplicates.
+    #[inline(always)]
+    pub fn dedup_window(&self) -> Duration {
+        self.dedup_window
+    }
+
+    /// Registers handler for a command.
+    ///
+    /// Replaces previously registered handler for the same command. Returns `false` if there are
+    /// no free handler slots.
+    pub fn register(&mut self, command: MavCmd, handler: CommandHandler<'a>) -> bool {
+        let slot = match self
+            .handlers
+            .iter()
+            .position(|slot| matches!(slot, Some((cmd, _)) if *cmd == command))
+        {
+            Some(idx) => idx,
+            None => match self.handlers.iter().position(Option::is_none) {
+                Some(idx) => idx,
+                None => return false,
+            },
+        };
+        self.handlers[slot] = Some((command, handler));
+        true
+    }
+
+    /// Removes registered handler for a command.
+    ///
+    /// Returns `false` if there was no such handler. Static handlers are not affected.
+    pub fn unregister(&mut self, command: MavCmd) -> bool {
+        let Some(slot) = self
+            .handlers
+            .iter_mut()
+            .find(|slot| matches!(slot, Some((cmd, _)) if *cmd == command))
+        else {
+            return false;
+        };
+        *slot = None;
+        true
+    }
+
+    /// Handles incoming frame received at `now`.
+    ///
+    /// Returns `COMMAND_ACK` that should be sent or [`None`] if frame does not contain a command
+    /// (or a command cancellation) addressed to this server.
+    pub fn handle<V: MaybeVersioned>(
+        &mut self,
+        frame: &Frame<V>,
+        now: Duration,
+    ) -> Option<CommandAck> {
+        let source = MavLinkId::new(frame.system_id(), frame.component_id());
+
+        if let Some(cancel) = decode::<CommandCancel, V>(frame) {
+            return self.handle_cancel(source, cancel, now);
+        }
+
+        let command: Command = match decode::<CommandLong, V>(frame) {
+            Some(command) => command.into(),
+            None => decode::<CommandInt, V>(frame)?.into(),
+        };
+        if !is_addressed_to(command.target_system(), command.target_component(), self.id) {
+            return None;
+        }
+
+        let is_retransmission = match &command {
+            Command::Long(command) => command.confirmation > 0,
+            Command::Int(_) => true,
+        };
+        let window = self.dedup_window;
+        if let Some(exchange) = self.exchanges.iter().flatten().find(|exchange| {
+            exchange.request.source == source
+                && exchange.request.command.is_same(&command)
+                && (exchange.is_active() || is_retransmission && !exchange.is_expired(now, window))
+        }) {
+            return Some(exchange.ack.clone());
+        }
+
+        let request = CommandRequest { source, command };
+        if self.active(request.command.command()).is_some() {
+            return Some(Self::ack(&request, MavResult::TemporarilyRejected.into()));
+        }
+
+        let Some(slot) = self.free_slot(&request, now) else {
+            return Some(Self::ack(&request, MavResult::TemporarilyRejected.into()));
+        };
+
+        let reply = self
+            .dispatch(&request)
+            .unwrap_or(MavResult::Unsupported.into());
+        let ack = Self::ack(&request, reply);
+        self.exchanges[slot] = Some(Exchange {
+            request,
+            ack: ack.clone(),
+            updated: now,
+        });
+        Some(ack)
+    }
+
+    /// Returns command in progress with a given `ID`.
+    pub fn active(&self, command: MavCmd) -> Option<&CommandRequest> {
+        self.exchanges
+            .iter()
+            .flatten()
+            .find(|exchange| exchange.is_active() && exchange.request.command.command() == command)
+            .map(|exchange| &exchange.request)
+    }
+
+    /// Returns `true` if command with a given `ID` is in progress.
+    ///
+    /// Long-running handlers should check this to detect cancellation.
+    pub fn is_active(&self, command: MavCmd) -> bool {
+        self.active(command).is_some()
+    }
+
+    /// Reports progress of a command at `now`.
+    ///
+    /// Returns `COMMAND_ACK` with `MAV_RESULT_IN_PROGRESS` that should be sent or [`None`] if
+    /// command is not in progress.
+    pub fn progress(&mut self, command: MavCmd, progress: u8, now: Duration) -> Option<CommandAck> {
+        self.complete(command, CommandReply::in_progress(progress), now)
+    }
+
+    /// Reports the final result of a command at `now`.
+    ///
+    /// Returns `COMMAND_ACK` that should be sent or [`None`] if command is not in progress (for
+    /// example, if it was cancelled).
+    pub fn complete(
+        &mut self,
+        command: MavCmd,
+        reply: impl Into<CommandReply>,
+        now: Duration,
+    ) -> Option<CommandAck> {
+        let reply = reply.into();
+        let exchange = self.exchanges.iter_mut().flatten().find(|exchange| {
+            exchange.is_active() && exchange.request.command.command() == command
+        })?;
+
+        exchange.ack.result = reply.result;
+        exchange.ack.progress = reply.progress;
+        exchange.ack.result_param2 = reply.result_param2;
+        exchange.updated = now;
+        Some(exchange.ack.clone())
+    }
+
+    fn handle_cancel(
+        &mut self,
+        source: MavLinkId,
+        cancel: CommandCancel,
+        now: Duration,
+    ) -> Option<CommandAck> {
+        if !is_addressed_to(cancel.target_system, cancel.target_component, self.id) {
+            return None;
+        }
+
+        let exchange = self.exchanges.iter_mut().flatten().find(|exchange| {
+            exchange.is_active()
+                && exchange.request.source == source
+                && exchange.request.command.command() == cancel.command
+        })?;
+
+        exchange.ack.result = MavResult::Cancelled;
+        exchange.updated = now;
+        Some(exchange.ack.clone())
+    }
+
+    fn dispatch(&mut self, request: &CommandRequest) -> Option<CommandReply> {
+        let command = request.command.command();
+        if let Some((_, handler)) = self
+            .handlers
+            .iter_mut()
+            .flatten()
+            .find(|(cmd, _)| *cmd == command)
+        {
+            return Some(handler(request));
+        }
+        self.static_handlers
+            .iter()
+            .find(|(cmd, _)| *cmd == command)
+            .map(|(_, handler)| handler(request))
+    }
+
+    /// Finds a slot for a new exchange: a previous exchange for the same command, an expired
+    /// exchange, or the oldest completed exchange.
+    ///
+    /// Returns [`None`] if all slots are occupied by commands in progress.
+    fn free_slot(&self, request: &CommandRequest, now: Duration) -> Option<usize> {
+        let window = self.dedup_window;
+        self.exchanges
+            .iter()
+            .position(|slot| match slot {
+                None => true,
+                Some(exchange) => {
+                    exchange.is_expired(now, window)
+                        || !exchange.is_active()
+                            && exchange.request.source == request.source
+                            && exchange.request.command.command() == request.command.command()
+                }
+            })
+            .or_else(|| {
+                self.exchanges
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(idx, slot)| slot.as_ref().map(|exchange| (idx, exchange)))
+                    .filter(|(_, exchange)| !exchange.is_active())
+                    .min_by_key(|(_, exchange)| exchange.updated)
+                    .map(|(idx, _)| idx)
+            })
+    }
+
+    fn ack(request: &CommandRequest, reply: CommandReply) -> CommandAck {
+        CommandAck {
+            command: request.command.command(),
+            result: reply.result,
+            progress: reply.progress,
+            result_param2: reply.result_param2,
+            target_system: request.source.system,
+            target_component: request.source.component,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::command::{CommandClient, CommandEvent};
+    use crate::utils::test_utils::message_frame;
+
+    const SERVER: MavLinkId = MavLinkId {
+        system: 1,
+        component: 1,
+    };
+    const GCS: MavLinkId = MavLinkId {
+        system: 255,
+        component: 190,
+    };
+
+    fn command(command: MavCmd, confirmation: u8) -> CommandLong {
+        CommandLong {
+            target_system: SERVER.system,
+            target_component: SERVER.component,
+            command,
+            confirmation,
+            param1: f32::NAN,
+            ..Default::default()
+        }
+    }
+
+    fn arm(_: &CommandRequest) -> CommandReply {
+        CommandReply::accepted().with_result_param2(7)
+    }
+
+    static HANDLERS: [(MavCmd, CommandFn); 1] = [(MavCmd::ComponentArmDisarm, arm)];
+
+    #[test]
+    fn static_handlers() {
+        let ms = Duration::from_millis;
+        let mut server = CommandServer::<4>::new(SERVER).with_static_handlers(&HANDLERS);
+        let mut client = CommandClient::new(GCS);
+
+        let request = client
+            .start(command(MavCmd::ComponentArmDisarm, 0), ms(0))
+            .unwrap();
+        let ack = server
+            .handle(&message_frame(GCS, request.as_message()), ms(0))
+            .unwrap();
+        assert_eq!(ack.result_param2, 7);
+
+        let event = client.handle(&message_frame(SERVER, &ack), ms(1)).unwrap();
+        assert!(matches!(event, CommandEvent::Completed(ack) if ack.result == MavResult::Accepted));
+
+        let ack = server
+            .handle(&message_frame(GCS, &command(MavCmd::DoSetMode, 0)), ms(2))
+            .unwrap();
+        assert_eq!(ack.result, MavResult::Unsupported);
+
+        let other = CommandLong {
+            target_component: 2,
+            ..command(MavCmd::ComponentArmDisarm, 0)
+        };
+        assert!(server.handle(&message_frame(GCS, &other), ms(3)).is_none());
+    }
+
+    #[test]
+    fn deduplicates_retransmissions() {
+        let ms = Duration::from_millis;
+        let mut calls = 0;
+        let mut handler = |_: &CommandRequest| {
+            calls += 1;
+            CommandReply::accepted()
+        };
+        let mut server = CommandServer::<4>::new(SERVER).with_dedup_window(ms(100));
+        assert!(server.register(MavCmd::DoSetMode, &mut handler));
+
+        let first = message_frame(GCS, &command(MavCmd::DoSetMode, 0));
+        let retransmission = message_frame(GCS, &command(MavCmd::DoSetMode, 1));
+
+        assert!(server.handle(&first, ms(0)).is_some());
+        assert!(server.handle(&retransmission, ms(50)).is_some());
+        // A new command with the same parameters
+        assert!(server.handle(&first, ms(60)).is_some());
+        // Retransmission after dedup window
+        assert!(server.handle(&retransmission, ms(200)).is_some());
+
+        assert!(server.unregister(MavCmd::DoSetMode));
+        let ack = server.handle(&first, ms(300)).unwrap();
+        assert_eq!(ack.result, MavResult::Unsupported);
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn long_running_commands() {
+        let ms = Duration::from_millis;
+        let mut handler = |_: &CommandRequest| CommandReply::in_progress(0);
+        let mut server = CommandServer::<4>::new(SERVER);
+        server.register(MavCmd::PreflightCalibration, &mut handler);
+
+        let calibrate = message_frame(GCS, &command(MavCmd::PreflightCalibration, 0));
+        let ack = server.handle(&calibrate, ms(0)).unwrap();
+        assert_eq!(ack.result, MavResult::InProgress);
+        assert!(server.is_active(MavCmd::PreflightCalibration));
+
+        // Duplicates report current progress
+        let ack = server
+            .progress(MavCmd::PreflightCalibration, 40, ms(10))
+            .unwrap();
+        assert_eq!(ack.progress, 40);
+        assert_eq!(server.handle(&calibrate, ms(20)), Some(ack));
+
+        // Other peers are rejected
+        let other = message_frame(
+            MavLinkId::new(254, 1),
+            &command(MavCmd::PreflightCalibration, 0),
+        );
+        let ack = server.handle(&other, ms(30)).unwrap();
+        assert_eq!(ack.result, MavResult::TemporarilyRejected);
+        assert_eq!(ack.target_system, 254);
+
+        let cancel = CommandCancel {
+            target_system: SERVER.system,
+            target_component: SERVER.component,
+            command: MavCmd::PreflightCalibration,
+        };
+        let ack = server.handle(&message_frame(GCS, &cancel), ms(40)).unwrap();
+        assert_eq!(ack.result, MavResult::Cancelled);
+        assert!(!server.is_active(MavCmd::PreflightCalibration));
+        assert!(server
+            .complete(MavCmd::PreflightCalibration, MavResult::Accepted, ms(50))
+            .is_none());
+    }
+
+    #[test]
+    fn rejects_commands_when_all_slots_are_in_progress() {
+        let ms = Duration::from_millis;
+        fn start(_: &CommandRequest) -> CommandReply {
+            CommandReply::in_progress(0)
+        }
+        static LONG_RUNNING: [(MavCmd, CommandFn); 2] = [
+            (MavCmd::PreflightCalibration, start),
+            (MavCmd::DoSetMode, start),
+        ];
+        let mut calls = 0;
+        let mut arm = |_: &CommandRequest| {
+            calls += 1;
+            CommandReply::accepted()
+        };
+        let mut server = CommandServer::<2>::new(SERVER).with_static_handlers(&LONG_RUNNING);
+        assert!(server.register(MavCmd::ComponentArmDisarm, &mut arm));
+
+        for cmd in [MavCmd::PreflightCalibration, MavCmd::DoSetMode] {
+            let ack = server
+                .handle(&message_frame(GCS, &command(cmd, 0)), ms(0))
+                .unwrap();
+            assert_eq!(ack.result, MavResult::InProgress);
+        }
+
+        let arm = message_frame(GCS, &command(MavCmd::ComponentArmDisarm, 0));
+        let ack = server.handle(&arm, ms(10)).unwrap();
+        assert_eq!(ack.result, MavResult::TemporarilyRejected);
+        assert_eq!(ack.command, MavCmd::ComponentArmDisarm);
+
+        server.complete(MavCmd::PreflightCalibration, MavResult::Accepted, ms(20));
+        let ack = server.handle(&arm, ms(30)).unwrap();
+        assert_eq!(ack.result, MavResult::Accepted);
+
+        assert_eq!(calls, 1);
+    }
+}
@@ -0,0 +1,1455 @@
+//! # FTP client
+//!
+//! Implements GCS side of MAVLink
+//! [file transfer protocol](https://mavlink.io/en/services/ftp.html).
+//!
+//! [`FtpClient`] is a sans-I/O state machine that performs one of the following operations:
+//!
+//! * [Listing](https://mavlink.io/en/services/ftp.html#list_directory): sends `ListDirectory`
+//!   requests with increasing offsets until remote component reports the end of directory.
+//! * [Download](https://mavlink.io/en/services/ftp.html#reading-a-file-burstreadfile): opens file
+//!   by `OpenFileRO` and reads it by `BurstReadFile`. Once burst is completed, chunks that were
+//!   lost in the middle of the file are requested by `ReadFile`, while the rest of the file is
+//!   requested by another burst.
+//! * [Upload](https://mavlink.io/en/services/ftp.html#uploading-a-file): creates file by
+//!   `CreateFile` and writes it chunk by chunk by `WriteFile`.
+//! * Removal of files and directories, creation of directories, and calculation of CRC32.
+//!
+//! Each request is retransmitted with the same sequence number if response has not been received
+//! in time. Sessions opened for download or upload are closed by `TerminateSession` once transfer
+//! is completed, rejected, cancelled, or timed out. Transferred files are verified by
+//! `CalcFileCRC32` unless disabled by [`FtpClient::with_crc_check`].
+//!
+//! Blocking drivers, such as [`FtpClient::download`], run client over [`Connection`]. With
+//! `tokio-rt` feature enabled, the corresponding `*_async` methods do the same over
+//! [`AsyncConnection`].
+//!
+//! Available only when `alloc` feature is enabled.
+//!
+//! [`Connection`]: crate::io::Connection
+//! [`AsyncConnection`]: crate::io::AsyncConnection
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::microservices::ftp::messages::FileTransferProtocol;
+use crate::protocol::{Frame, MavLinkId, MaybeVersioned};
+use crate::services::ftp::{ftp_crc32, FtpEntryKind, FtpNak, FtpOpcode, FtpPayload, FTP_DATA_LEN};
+use crate::services::{decode, is_addressed_to, ServiceError};
+
+#[cfg(feature = "tokio-rt")]
+use crate::io::{AsyncConnection, AsyncRead, AsyncWrite};
+#[cfg(feature = "std")]
+use crate::io::{Connection, Read, Write};
+#[cfg(feature = "std")]
+use crate::protocol::{Endpoint, Versioned};
+#[cfg(feature = "tokio-rt")]
+use crate::services::run_async;
+#[cfg(feature = "std")]
+use crate::services::{run, Driven};
+#[cfg(feature = "std")]
+use crate::Error;
+
+/// Default time to wait for a response before retransmitting a request.
+pub const DEFAULT_FTP_TIMEOUT: Duration = Duration::from_millis(500);
+/// Default number of retransmissions of each request.
+pub const DEFAULT_FTP_RETRIES: u8 = 5;
+/// Default maximum size of a downloaded file in bytes.
+pub const DEFAULT_FTP_MAX_FILE_SIZE: u32 = 16 * 1024 * 1024;
+
+/// Entry of a directory listing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FtpEntry {
+    /// Kind of the entry.
+    pub kind: FtpEntryKind,
+    /// Name of the entry without a path.
+    pub name: String,
+    /// Size of a file in bytes, always `0` for directories.
+    pub size: u32,
+}
+
+impl FtpEntry {
+    /// Parses entry of a `ListDirectory` response without a trailing `NUL`.
+    ///
+    /// Entries are formatted as `F<name>\t<size>` for files and `D<name>` for directories.
+    /// Returns [`None`] for skipped (`S`) and malformed entries.
+    pub fn parse(entry: &[u8]) -> Option<Self> {
+        let (&kind, rest) = entry.split_first()?;
+        match kind {
+            b'F' => {
+                let tab = rest.iter().position(|&b| b == b'\t');
+                let (name, size) = match tab {
+                    Some(tab) => (&rest[..tab], &rest[tab + 1..]),
+                    None => (rest, &[][..]),
+                };
+                let size = core::str::from_utf8(size)
+                    .ok()
+                    .and_then(|size| size.parse().ok())
+                    .unwrap_or_default();
+                Some(Self {
+                    kind: FtpEntryKind::File,
+                    name: String::from_utf8_lossy(name).into_owned(),
+                    size,
+                })
+            }
+            b'D' => Some(Self {
+                kind: FtpEntryKind::Directory,
+                name: String::from_utf8_lossy(rest).into_owned(),
+                size: 0,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Event produced by [`FtpClient::handle`] once operation is completed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FtpEvent {
+    /// Directory was listed.
+    Listed(Vec<FtpEntry>),
+    /// File was downloaded.
+    Downloaded(Vec<u8>),
+    /// File of a given size was uploaded.
+    Uploaded(u32),
+    /// File or directory was removed, or directory was created.
+    Completed,
+    /// CRC32 of a file was calculated by remote component.
+    Crc32(u32),
+    /// Operation has failed.
+    Failed(ServiceError),
+}
+
+/// Sorted list of non-overlapping byte ranges received during download.
+#[derive(Clone, Debug, Default)]
+struct Ranges(Vec<(u32, u32)>);
+
+impl Ranges {
+    fn insert(&mut self, start: u32, end: u32) {
+        if start >= end {
+            return;
+        }
+        let index = self.0.partition_point(|&(_, e)| e < start);
+        let mut merged = (start, end);
+        while let Some(&(s, e)) = self.0.get(index) {
+            if s > merged.1 {
+                break;
+            }
+            merged = (merged.0.min(s), merged.1.max(e));
+            self.0.remove(index);
+        }
+        self.0.insert(index, merged);
+    }
+
+    fn len(&self) -> u32 {
+        self.0.iter().map(|(s, e)| e - s).sum()
+    }
+
+    fn end(&self) -> u32 {
+        self.0.last().map_or(0, |&(_, e)| e)
+    }
+
+    fn first_gap(&self, size: u32) -> Option<(u32, u32)> {
+        let mut start = 0;
+        for &(s, e) in &self.0 {
+            if s > start {
+                return Some((start, s));
+            }
+            start = e;
+        }
+        (start < size).then_some((start, size))
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Stage {
+    List {
+        entries: Vec<FtpEntry>,
+    },
+    Open {
+        path: String,
+    },
+    Read {
+        path: String,
+        data: Vec<u8>,
+        received: Ranges,
+    },
+    Create {
+        path: String,
+        data: Vec<u8>,
+    },
+    Write {
+        path: String,
+        data: Vec<u8>,
+        written: u32,
+    },
+    Terminate {
+        path: String,
+        crc: u32,
+        outcome: FtpEvent,
+    },
+    Verify {
+        crc: u32,
+        outcome: FtpEvent,
+    },
+    Single,
+    Crc,
+}
+
+#[derive(Clone, Debug)]
+struct Operation {
+    stage: Stage,
+    request: FtpPayload,
+    attempts: u8,
+    deadline: Option<Duration>,
+}
+
+// Steps are consumed immediately, boxing requests would only add allocations
+#[allow(clippy::large_enum_variant)]
+enum Step {
+    /// Response was consumed, more responses to the same request are expected.
+    Wait(Stage),
+    /// A new request should be sent.
+    Next(Stage, FtpPayload),
+    /// Operation is completed.
+    Done(FtpEvent),
+}
+
+/// Sans-I/O FTP client.
+///
+/// Client performs one operation at a time on a single remote component. Start operation by one
+/// of the `start_*` methods, such as [`FtpClient::start_download`], then pass incoming frames to
+/// [`FtpClient::handle`] and call [`FtpClient::poll`] to obtain messages that should be sent.
+/// Call [`FtpClient::poll`] after each [`FtpClient::handle`] and whenever [`FtpClient::timeout`]
+/// expires.
+///
+/// Available only when `alloc` feature is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use mavio::services::ftp::{FtpClient, FtpEvent, FtpNak, FtpOpcode, FtpPayload};
+/// use mavio::prelude::*;
+///
+/// let gcs = MavLinkId::new(255, 190);
+/// let vehicle = Endpoint::v2(MavLinkId::new(1, 1));
+/// let mut client = FtpClient::new(gcs, vehicle.id());
+///
+/// client.start_remove_file("/logs/old.ulg").unwrap();
+/// let request = client.poll(Duration::ZERO).unwrap().unwrap();
+/// let request = FtpPayload::decode(&request.payload);
+/// assert_eq!(request.opcode, FtpOpcode::RemoveFile);
+/// assert_eq!(request.path(), "/logs/old.ulg");
+///
+/// // Vehicle acknowledges removal
+/// let mut response = FtpPayload::new(FtpOpcode::Ack);
+/// response.seq_number = request.seq_number.wrapping_add(1);
+/// response.req_opcode = request.opcode;
+/// let frame = vehicle.next_frame(&response.to_message(gcs)).unwrap();
+/// assert_eq!(client.handle(&frame, Duration::from_millis(10)), Some(FtpEvent::Completed));
+/// ```
+#[derive(Clone, Debug)]
+pub struct FtpClient {
+    id: MavLinkId,
+    target: MavLinkId,
+    timeout: Duration,
+    retries: u8,
+    max_file_size: u32,
+    crc_check: bool,
+    seq: u16,
+    operation: Option<Operation>,
+    closing: Option<u8>,
+}
+
+impl FtpClient {
+    /// Creates a client for a component with a specified `id` that communicates with `target`.
+    pub fn new(id: MavLinkId, target: MavLinkId) -> Self {
+        Self {
+            id,
+            target,
+            timeout: DEFAULT_FTP_TIMEOUT,
+            retries: DEFAULT_FTP_RETRIES,
+            max_file_size: DEFAULT_FTP_MAX_FILE_SIZE,
+            crc_check: true,
+            seq: 0,
+            operation: None,
+            closing: None,
+        }
+    }
+
+    /// Sets time to wait for a response before retransmitting a request.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Sets the number of retransmissions of each request after which operation fails.
+    pub fn with_retries(self, retries: u8) -> Self {
+        Self { retries, ..self }
+    }
+
+    /// Sets the maximum size of a downloaded file in bytes.
+    ///
+    /// Download of a larger file fails with [`ServiceError::FileTooLarge`] before any data is
+    /// requested.
+    pub fn with_max_file_size(self, max_file_size: u32) -> Self {
+        Self {
+            max_file_size,
+            ..self
+        }
+    }
+
+    /// Enables or disables verification of transferred files by `CalcFileCRC32`.
+    ///
+    /// Verification is enabled by default. It is skipped if remote component does not support
+    /// `CalcFileCRC32`.
+    pub fn with_crc_check(self, crc_check: bool) -> Self {
+        Self { crc_check, ..self }
+    }
+
+    /// `ID` of the component this client acts on behalf of.
+    #[inline(always)]
+    pub fn id(&self) -> MavLinkId {
+        self.id
+    }
+
+    /// `ID` of the remote component.
+    #[inline(always)]
+    pub fn target(&self) -> MavLinkId {
+        self.target
+    }
+
+    /// Returns `true` if operation is in progress.
+    #[inline(always)]
+    pub fn is_busy(&self) -> bool {
+        self.operation.is_some()
+    }
+
+    /// Progress of the current download or upload as a number of transferred and total bytes.
+    ///
+    /// Returns [`None`] if there is no such operation or if file size is not yet known.
+    pub fn progress(&self) -> Option<(u32, u32)> {
+        match &self.operation.as_ref()?.stage {
+            Stage::Read { data, received, .. } => Some((received.len(), data.len() as u32)),
+            Stage::Create { data, .. } => Some((0, data.len() as u32)),
+            Stage::Write { data, written, .. } => Some((*written, data.len() as u32)),
+            _ => None,
+        }
+    }
+
+    /// Starts listing a directory.
+    ///
+    /// Returns [`ServiceError::InvalidPath`] if path is too long, or [`ServiceError::Busy`] if
+    /// another operation is in progress.
+    pub fn start_list(&mut self, path: &str) -> core::result::Result<(), ServiceError> {
+        let stage = Stage::List {
+            entries: Vec::new(),
+        };
+        self.start_path(stage, FtpOpcode::ListDirectory, path)
+    }
+
+    /// Starts downloading a file.
+    ///
+    /// Returns [`ServiceError::InvalidPath`] if path is too long, or [`ServiceError::Busy`] if
+    /// another operation is in progress.
+    pub fn start_download(&mut self, path: &str) -> core::result::Result<(), ServiceError> {
+        let stage = Stage::Open { path: path.into() };
+        self.start_path(stage, FtpOpcode::OpenFileRO, path)
+    }
+
+    /// Starts uploading `data` into a file.
+    ///
+    /// Returns [`ServiceError::InvalidPath`] if path is too long, or [`ServiceError::Busy`] if
+    /// another operation is in progress.
+    pub fn start_upload(
+        &mut self,
+        path: &str,
+        data: impl Into<Vec<u8>>,
+    ) -> core::result::Result<(), ServiceError> {
+        let stage = Stage::Create {
+            path: path.into(),
+            data: data.into(),
+        };
+        self.start_path(stage, FtpOpcode::CreateFile, path)
+    }
+
+    /// Starts removing a file.
+    ///
+    /// Returns [`ServiceError::InvalidPath`] if path is too long, or [`ServiceError::Busy`] if
+    /// another operation is in progress.
+    pub fn start_remove_file(&mut self, path: &str) -> core::result::Result<(), ServiceError> {
+        self.start_path(Stage::Single, FtpOpcode::RemoveFile, path)
+    }
+
+    /// Starts removing an empty directory.
+    ///
+    /// Returns [`ServiceError::InvalidPath`] if path is too long, or [`ServiceError::Busy`] if
+    /// another operation is in progress.
+    pub fn start_remove_directory(&mut self, path: &str) -> core::result::Result<(), ServiceError> {
+        self.start_path(Stage::Single, FtpOpcode::RemoveDirectory, path)
+    }
+
+    /// Starts creating a directory.
+    ///
+    /// Returns [`ServiceError::InvalidPath`] if path is too long, or [`ServiceError::Busy`] if
+    /// another operation is in progress.
+    pub fn start_create_directory(&mut self, path: &str) -> core::result::Result<(), ServiceError> {
+        self.start_path(Stage::Single, FtpOpcode::CreateDirectory, path)
+    }
+
+    /// Starts calculation of CRC32 of a file by remote component, see
+    /// [`ftp_crc32`].
+    ///
+    /// Returns [`ServiceError::InvalidPath`] if path is too long, or [`ServiceError::Busy`] if
+    /// another operation is in progress.
+    pub fn start_crc32(&mut self, path: &str) -> core::result::Result<(), ServiceError> {
+        self.start_path(Stage::Crc, FtpOpcode::CalcFileCRC32, path)
+    }
+
+    /// Aborts current operation.
+    ///
+    /// If operation has opened a session, the next call to [`FtpClient::poll`] returns
+    /// `TerminateSession` for this session. Response to this request is not awaited.
+    pub fn cancel(&mut self) {
+        let Some(operation) = self.operation.take() else {
+            return;
+        };
+        if let Stage::Read { .. } | Stage::Write { .. } | Stage::Terminate { .. } = operation.stage
+        {
+            self.closing = Some(operation.request.session);
+        }
+    }
+
+    /// Handles incoming frame received at `now`.
+    ///
+    /// Returns an event once operation is completed. Call [`FtpClient::poll`] afterward to obtain
+    /// the next request.
+    pub fn handle<V: MaybeVersioned>(
+        &mut self,
+        frame: &Frame<V>,
+        now: Duration,
+    ) -> Option<FtpEvent> {
+        if frame.system_id() != self.target.system
+            || (self.target.component != 0 && frame.component_id() != self.target.component)
+        {
+            return None;
+        }
+        let msg = decode::<FileTransferProtocol, V>(frame)?;
+        if !is_addressed_to(msg.target_system, msg.target_component, self.id) {
+            return None;
+        }
+        let payload = FtpPayload::decode(&msg.payload);
+
+        let request = self.operation.as_ref()?.request;
+        let is_response = matches!(payload.opcode, FtpOpcode::Ack | FtpOpcode::Nak)
+            && payload.req_opcode == request.opcode;
+        // Burst responses have their own sequence numbers
+        let is_expected = if request.opcode == FtpOpcode::BurstReadFile {
+            payload.session == request.session
+        } else {
+            payload.seq_number == request.seq_number.wrapping_add(1)
+        };
+        if !is_response || !is_expected {
+            return None;
+        }
+
+        let operation = self.operation.take()?;
+        match self.step(operation.stage, &request, &payload) {
+            Step::Wait(stage) => {
+                self.operation = Some(Operation {
+                    stage,
+                    attempts: 0,
+                    deadline: Some(now + self.timeout),
+                    ..operation
+                });
+                None
+            }
+            Step::Next(stage, request) => {
+                self.seq = self.seq.wrapping_add(1);
+                self.operation = Some(Operation {
+                    stage,
+                    request: FtpPayload {
+                        seq_number: self.seq,
+                        ..request
+                    },
+                    attempts: 0,
+                    deadline: None,
+                });
+                None
+            }
+            Step::Done(event) => Some(event),
+        }
+    }
+
+    /// Returns a message that should be sent at `now`.
+    ///
+    /// This is either a new request or a retransmission of the last request if response has not
+    /// been received in time. Interrupted bursts are restarted from the first missing byte.
+    ///
+    /// Returns [`ServiceError::TimedOut`] and aborts operation, if all retries are exhausted. Open
+    /// session is terminated on the next call, see [`FtpClient::cancel`].
+    pub fn poll(
+        &mut self,
+        now: Duration,
+    ) -> core::result::Result<Option<FileTransferProtocol>, ServiceError> {
+        if let Some(session) = self.closing.take() {
+            let mut request = FtpPayload::new(FtpOpcode::TerminateSession);
+            self.seq = self.seq.wrapping_add(1);
+            request.seq_number = self.seq;
+            request.session = session;
+            return Ok(Some(request.to_message(self.target)));
+        }
+        let Some(operation) = self.operation.as_mut() else {
+            return Ok(None);
+        };
+
+        match operation.deadline {
+            Some(deadline) if now < deadline => return Ok(None),
+            None => {
+                operation.deadline = Some(now + self.timeout);
+                return Ok(Some(operation.request.to_message(self.target)));
+            }
+            Some(_) => {}
+        }
+
+        if operation.attempts >= self.retries {
+            self.cancel();
+            return Err(ServiceError::TimedOut);
+        }
+        operation.attempts += 1;
+        operation.deadline = Some(now + self.timeout);
+
+        if let (FtpOpcode::BurstReadFile, Stage::Read { data, received, .. }) =
+            (operation.request.opcode, &operation.stage)
+        {
+            let (start, _) = received.first_gap(data.len() as u32).unwrap_or_default();
+            self.seq = self.seq.wrapping_add(1);
+            operation.request.seq_number = self.seq;
+            operation.request.offset = start;
+        }
+        Ok(Some(operation.request.to_message(self.target)))
+    }
+
+    /// Time left until [`FtpClient::poll`] should be called.
+    ///
+    /// Returns [`None`] if there is no operation in progress.
+    pub fn timeout(&self, now: Duration) -> Option<Duration> {
+        let operation = self.operation.as_ref()?;
+        Some(
+            operation
+                .deadline
+                .map_or(Duration::ZERO, |deadline| deadline.saturating_sub(now)),
+        )
+    }
+
+    fn start_path(
+        &mut self,
+        stage: Stage,
+        opcode: FtpOpcode,
+        path: &str,
+    ) -> core::result::Result<(), ServiceError> {
+        let request = FtpPayload::new(opcode)
+            .with_path(path)
+            .ok_or(ServiceError::InvalidPath)?;
+        if self.operation.is_some() {
+            return Err(ServiceError::Busy);
+        }
+        self.seq = self.seq.wrapping_add(1);
+        self.operation = Some(Operation {
+            stage,
+            request: FtpPayload {
+                seq_number: self.seq,
+                ..request
+            },
+            attempts: 0,
+            deadline: None,
+        });
+        Ok(())
+    }
+
+    fn step(&self, stage: Stage, request: &FtpPayload, response: &FtpPayload) -> Step {
+        let nak = (response.opcode == FtpOpcode::Nak).then(|| FtpNak::from_data(response.data()));
+
+        match stage {
+            Stage::List { mut entries } => match nak {
+                Some(FtpNak::Eof) => Step::Done(FtpEvent::Listed(entries)),
+                Some(nak) => Step::Done(FtpEvent::Failed(ServiceError::Ftp(nak))),
+                None => {
+                    let mut count = 0;
+                    for entry in response.data().split(|&b| b == 0) {
+                        if entry.is_empty() {
+                            continue;
+                        }
+                        count += 1;
+                        entries.extend(FtpEntry::parse(entry));
+                    }
+                    if count == 0 {
+                        return Step::Done(FtpEvent::Listed(entries));
+                    }
+                    let request = FtpPayload {
+                        offset: request.offset + count,
+                        ..*request
+                    };
+                    Step::Next(Stage::List { entries }, request)
+                }
+            },
+            Stage::Open { path } => match (nak, response.data_u32()) {
+                (Some(nak), _) => Step::Done(FtpEvent::Failed(ServiceError::Ftp(nak))),
+                (None, None) => Step::Done(FtpEvent::Failed(ServiceError::Ftp(FtpNak::Fail))),
+                (None, Some(size)) if size > self.max_file_size => {
+                    let outcome = FtpEvent::Failed(ServiceError::FileTooLarge);
+                    terminate(path, response.session, 0, outcome)
+                }
+                (None, Some(size)) => {
+                    let data = alloc::vec![0u8; size as usize];
+                    self.next_read(path, response.session, data, Ranges::default())
+                }
+            },
+            Stage::Read {
+                path,
+                mut data,
+                mut received,
+            } => {
+                let session = request.session;
+                match nak {
+                    // File is shorter than reported when opened
+                    Some(FtpNak::Eof) if request.opcode == FtpOpcode::BurstReadFile => {
+                        data.truncate(received.end() as usize);
+                    }
+                    Some(nak) => {
+                        let outcome = FtpEvent::Failed(ServiceError::Ftp(nak));
+                        return terminate(path, session, 0, outcome);
+                    }
+                    None => {
+                        let start = (response.offset as usize).min(data.len());
+                        let end = (start + response.data().len()).min(data.len());
+                        data[start..end].copy_from_slice(&response.data()[..end - start]);
+                        received.insert(start as u32, end as u32);
+
+                        let complete = received.first_gap(data.len() as u32).is_none();
+                        if request.opcode == FtpOpcode::BurstReadFile
+                            && !response.burst_complete
+                            && !complete
+                        {
+                            return Step::Wait(Stage::Read {
+                                path,
+                                data,
+                                received,
+                            });
+                        }
+                    }
+                }
+                self.next_read(path, session, data, received)
+            }
+            Stage::Create { path, data } => match nak {
+                Some(nak) => Step::Done(FtpEvent::Failed(ServiceError::Ftp(nak))),
+                None => self.next_write(path, response.session, data, 0),
+            },
+            Stage::Write {
+                path,
+                data,
+                written,
+            } => match nak {
+                Some(nak) => terminate(
+                    path,
+                    request.session,
+                    0,
+                    FtpEvent::Failed(ServiceError::Ftp(nak)),
+                ),
+                None => {
+                    let written = written + request.data().len() as u32;
+                    self.next_write(path, request.session, data, written)
+                }
+            },
+            Stage::Terminate { path, crc, outcome } => {
+                if !self.crc_check || matches!(outcome, FtpEvent::Failed(_)) {
+                    return Step::Done(outcome);
+                }
+                let request = FtpPayload::new(FtpOpcode::CalcFileCRC32)
+                    .with_path(&path)
+                    .unwrap_or_default();
+                Step::Next(Stage::Verify { crc, outcome }, request)
+            }
+            Stage::Verify { crc, outcome } => match (nak, response.data_u32()) {
+                // Verification is optional for remote components
+                (Some(FtpNak::UnknownCommand), _) => Step::Done(outcome),
+                (Some(nak), _) => Step::Done(FtpEvent::Failed(ServiceError::Ftp(nak))),
+                (None, Some(remote)) if remote == crc => Step::Done(outcome),
+                (None, _) => Step::Done(FtpEvent::Failed(ServiceError::ChecksumMismatch)),
+            },
+            Stage::Single => match nak {
+                Some(nak) => Step::Done(FtpEvent::Failed(ServiceError::Ftp(nak))),
+                None => Step::Done(FtpEvent::Completed),
+            },
+            Stage::Crc => match (nak, response.data_u32()) {
+                (Some(nak), _) => Step::Done(FtpEvent::Failed(ServiceError::Ftp(nak))),
+                (None, Some(crc)) => Step::Done(FtpEvent::Crc32(crc)),
+                (None, None) => Step::Done(FtpEvent::Failed(ServiceError::Ftp(FtpNak::Fail))),
+            },
+        }
+    }
+
+    /// Requests the first missing chunk or terminates session once file is downloaded.
+    fn next_read(&self, path: String, session: u8, data: Vec<u8>, received: Ranges) -> Step {
+        let size = data.len() as u32;
+        let Some((start, end)) = received.first_gap(size) else {
+            let crc = ftp_crc32(0, &data);
+            return terminate(path, session, crc, FtpEvent::Downloaded(data));
+        };
+
+        let mut request = FtpPayload::new(FtpOpcode::ReadFile);
+        request.session = session;
+        request.offset = start;
+        if start >= received.end() {
+            // The rest of the file is requested by another burst
+            request.opcode = FtpOpcode::BurstReadFile;
+            request.set_size(FTP_DATA_LEN as u8);
+        } else {
+            request.set_size((end - start).min(FTP_DATA_LEN as u32) as u8);
+        }
+        let stage = Stage::Read {
+            path,
+            data,
+            received,
+        };
+        Step::Next(stage, request)
+    }
+
+    /// Writes the next chunk or terminates session once file is uploaded.
+    fn next_write(&self, path: String, session: u8, data: Vec<u8>, written: u32) -> Step {
+        let Some(chunk) = data.get(written as usize..).filter(|rest| !rest.is_empty()) else {
+            let crc = ftp_crc32(0, &data);
+            return terminate(path, session, crc, FtpEvent::Uploaded(data.len() as u32));
+        };
+
+        let mut request = FtpPayload::new(FtpOpcode::WriteFile)
+            .with_data(&chunk[..chunk.len().min(FTP_DATA_LEN)]);
+        request.session = session;
+        request.offset = written;
+        let stage = Stage::Write {
+            path,
+            data,
+            written,
+        };
+        Step::Next(stage, request)
+    }
+}
+
+/// Terminates session before reporting `outcome`.
+fn terminate(path: String, session: u8, crc: u32, outcome: FtpEvent) -> Step {
+    let mut request = FtpPayload::new(FtpOpcode::TerminateSession);
+    request.session = session;
+    Step::Next(Stage::Terminate { path, crc, outcome }, request)
+}
+
+#[cfg(feature = "std")]
+impl FtpClient {
+    /// <sup>`std`</sup>
+    /// Lists a directory over a blocking [`Connection`].
+    ///
+    /// Frames that are not related to the file transfer protocol are discarded.
+    ///
+    /// Returns [`ServiceError::TimedOut`] wrapped into [`Error::Service`] if remote component has
+    /// stopped responding, or [`ServiceError::Ftp`] if request was rejected.
+    ///
+    /// Available only when `std` feature is enabled.
+    pub fn list<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+        path: &str,
+    ) -> crate::Result<Vec<FtpEntry>>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        self.start_list(path)?;
+        match self.drive(connection)? {
+            FtpEvent::Listed(entries) => Ok(entries),
+            _ => unreachable!("listing is completed only by `FtpEvent::Listed`"),
+        }
+    }
+
+    /// <sup>`std`</sup>
+    /// Downloads a file over a blocking [`Connection`].
+    ///
+    /// Returns [`ServiceError::ChecksumMismatch`] wrapped into [`Error::Service`] if downloaded
+    /// file is corrupted. See [`FtpClient::list`] for other details.
+    ///
+    /// Available only when `std` feature is enabled.
+    pub fn download<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+        path: &str,
+    ) -> crate::Result<Vec<u8>>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        self.start_download(path)?;
+        match self.drive(connection)? {
+            FtpEvent::Downloaded(data) => Ok(data),
+            _ => unreachable!("download is completed only by `FtpEvent::Downloaded`"),
+        }
+    }
+
+    /// <sup>`std`</sup>
+    /// Uploads `data` into a file over a blocking [`Connection`].
+    ///
+    /// Returns [`ServiceError::ChecksumMismatch`] wrapped into [`Error::Service`] if uploaded file
+    /// is corrupted. See [`FtpClient::list`] for other details.
+    ///
+    /// Available only when `std` feature is enabled.
+    pub fn upload<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+        path: &str,
+        data: impl Into<Vec<u8>>,
+    ) -> crate::Result<()>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        self.start_upload(path, data)?;
+        self.drive(connection).map(|_| ())
+    }
+
+    /// <sup>`std`</sup>
+    /// Removes a file over a blocking [`Connection`].
+    ///
+    /// See [`FtpClient::list`] for details.
+    ///
+    /// Available only when `std` feature is enabled.
+    pub fn remove_file<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+        path: &str,
+    ) -> crate::Result<()>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        self.start_remove_file(path)?;
+        self.drive(connection).map(|_| ())
+    }
+
+    /// <sup>`std`</sup>
+    /// Removes an empty directory over a blocking [`Connection`].
+    ///
+    /// See [`FtpClient::list`] for details.
+    ///
+    /// Available only when `std` feature is enabled.
+    pub fn remove_directory<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+        path: &str,
+    ) -> crate::Result<()>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        self.start_remove_directory(path)?;
+        self.drive(connection).map(|_| ())
+    }
+
+    /// <sup>`std`</sup>
+    /// Creates a directory over a blocking [`Connection`].
+    ///
+    /// See [`FtpClient::list`] for details.
+    ///
+    /// Available only when `std` feature is enabled.
+    pub fn create_directory<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+        path: &str,
+    ) -> crate::Result<()>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        self.start_create_directory(path)?;
+        self.drive(connection).map(|_| ())
+    }
+
+    /// <sup>`std`</sup>
+    /// Calculates CRC32 of a file by remote component over a blocking [`Connection`].
+    ///
+    /// See [`FtpClient::list`] for details.
+    ///
+    /// Available only when `std` feature is enabled.
+    pub fn crc32<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+        path: &str,
+    ) -> crate::Result<u32>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        self.start_crc32(path)?;
+        match self.drive(connection)? {
+            FtpEvent::Crc32(crc) => Ok(crc),
+            _ => unreachable!("CRC32 calculation is completed only by `FtpEvent::Crc32`"),
+        }
+    }
+
+    fn drive<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+    ) -> crate::Result<FtpEvent>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        run(self, connection, |client, frame, now| {
+            client.handle(frame, now).map(completed).transpose()
+        })
+    }
+}
+
+#[cfg(feature = "tokio-rt")]
+impl FtpClient {
+    /// <sup>`tokio-rt`</sup>
+    /// Lists a directory over [`AsyncConnection`].
+    ///
+    /// Asynchronous counterpart of [`FtpClient::list`]. Must be called within Tokio runtime.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    pub async fn list_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+        path: &str,
+    ) -> crate::Result<Vec<FtpEntry>>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        self.start_list(path)?;
+        match self.drive_async(connection).await? {
+            FtpEvent::Listed(entries) => Ok(entries),
+            _ => unreachable!("listing is completed only by `FtpEvent::Listed`"),
+        }
+    }
+
+    /// <sup>`tokio-rt`</sup>
+    /// Downloads a file over [`AsyncConnection`].
+    ///
+    /// Asynchronous counterpart of [`FtpClient::download`]. Must be called within Tokio runtime.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    pub async fn download_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+        path: &str,
+    ) -> crate::Result<Vec<u8>>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        self.start_download(path)?;
+        match self.drive_async(connection).await? {
+            FtpEvent::Downloaded(data) => Ok(data),
+            _ => unreachable!("download is completed only by `FtpEvent::Downloaded`"),
+        }
+    }
+
+    /// <sup>`tokio-rt`</sup>
+    /// Uploads `data` into a file over [`AsyncConnection`].
+    ///
+    /// Asynchronous counterpart of [`FtpClient::upload`]. Must be called within Tokio runtime.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    pub async fn upload_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+        path: &str,
+        data: impl Into<Vec<u8>>,
+    ) -> crate::Result<()>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        self.start_upload(path, data)?;
+        self.drive_async(connection).await.map(|_| ())
+    }
+
+    /// <sup>`tokio-rt`</sup>
+    /// Removes a file over [`AsyncConnection`].
+    ///
+    /// Asynchronous counterpart of [`FtpClient::remove_file`]. Must be called within Tokio
+    /// runtime.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    pub async fn remove_file_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+        path: &str,
+    ) -> crate::Result<()>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        self.start_remove_file(path)?;
+        self.drive_async(connection).await.map(|_| ())
+    }
+
+    /// <sup>`tokio-rt`</sup>
+    /// Removes an empty directory over [`AsyncConnection`].
+    ///
+    /// Asynchronous counterpart of [`FtpClient::remove_directory`]. Must be called within Tokio
+    /// runtime.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    pub async fn remove_directory_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+        path: &str,
+    ) -> crate::Result<()>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        self.start_remove_directory(path)?;
+        self.drive_async(connection).await.map(|_| ())
+    }
+
+    /// <sup>`tokio-rt`</sup>
+    /// Creates a directory over [`AsyncConnection`].
+    ///
+    /// Asynchronous counterpart of [`FtpClient::create_directory`]. Must be called within Tokio
+    /// runtime.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    pub async fn create_directory_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+        path: &str,
+    ) -> crate::Result<()>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        self.start_create_directory(path)?;
+        self.drive_async(connection).await.map(|_| ())
+    }
+
+    /// <sup>`tokio-rt`</sup>
+    /// Calculates CRC32 of a file by remote component over [`AsyncConnection`].
+    ///
+    /// Asynchronous counterpart of [`FtpClient::crc32`]. Must be called within Tokio runtime.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    pub async fn crc32_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+        path: &str,
+    ) -> crate::Result<u32>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        self.start_crc32(path)?;
+        match self.drive_async(connection).await? {
+            FtpEvent::Crc32(crc) => Ok(crc),
+            _ => unreachable!("CRC32 calculation is completed only by `FtpEvent::Crc32`"),
+        }
+    }
+
+    async fn drive_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+    ) -> crate::Result<FtpEvent>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        run_async(self, connection, |client, frame, now| {
+            client.handle(frame, now).map(completed).transpose()
+        })
+        .await
+    }
+}
+
+#[cfg(feature = "std")]
+impl Driven for FtpClient {
+    fn poll_frame<V: Versioned>(
+        &mut self,
+        endpoint: &Endpoint<V>,
+        now: Duration,
+    ) -> crate::Result<Option<Frame<V>>> {
+        self.poll(now)?
+            .map(|message| endpoint.next_frame(&message))
+            .transpose()
+    }
+
+    fn poll_timeout(&self, now: Duration) -> Option<Duration> {
+        self.timeout(now)
+    }
+
+    fn abort(&mut self) {
+        // Open session is terminated by the next frame on a best-effort basis
+        self.cancel();
+    }
+}
+
+/// Converts a final event into a result of a driver.
+#[cfg(feature = "std")]
+fn completed(event: FtpEvent) -> crate::Result<FtpEvent> {
+    match event {
+        FtpEvent::Failed(err) => Err(err.into()),
+        event => Ok(event),
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::protocol::V2;
+    use crate::utils::test_utils::message_frame;
+
+    const GCS: MavLinkId = MavLinkId {
+        system: 255,
+        component: 190,
+    };
+    const VEHICLE: MavLinkId = MavLinkId {
+        system: 1,
+        component: 1,
+    };
+
+    fn request(client: &mut FtpClient, now: Duration) -> FtpPayload {
+        let message = client.poll(now).unwrap().expect("expected request");
+        FtpPayload::decode(&message.payload)
+    }
+
+    fn respond(request: &FtpPayload, response: FtpPayload) -> Frame<V2> {
+        let response = FtpPayload {
+            seq_number: request.seq_number.wrapping_add(1),
+            req_opcode: request.opcode,
+            ..response
+        };
+        message_frame(VEHICLE, &response.to_message(GCS))
+    }
+
+    fn ack(request: &FtpPayload, data: &[u8]) -> Frame<V2> {
+        let mut response = FtpPayload::new(FtpOpcode::Ack).with_data(data);
+        response.session = request.session;
+        respond(request, response)
+    }
+
+    fn nak(request: &FtpPayload, nak: FtpNak) -> Frame<V2> {
+        let mut buf = [0u8; 2];
+        let len = nak.to_data(&mut buf);
+        let mut response = FtpPayload::new(FtpOpcode::Nak).with_data(&buf[..len]);
+        response.session = request.session;
+        respond(request, response)
+    }
+
+    #[test]
+    fn list_directory() {
+        let ms = Duration::from_millis;
+        let mut client = FtpClient::new(GCS, VEHICLE);
+        client.start_list("/logs").unwrap();
+
+        let first = request(&mut client, ms(0));
+        assert_eq!(
+            (first.opcode, first.path(), first.offset),
+            (FtpOpcode::ListDirectory, "/logs", 0)
+        );
+        // Retransmission keeps sequence number
+        let retry = request(&mut client, ms(500));
+        assert_eq!(retry.seq_number, first.seq_number);
+
+        assert!(client
+            .handle(&ack(&first, b"D2024\0Flog.ulg\t1024\0S\0"), ms(510))
+            .is_none());
+        let second = request(&mut client, ms(510));
+        assert_eq!(second.offset, 3);
+        assert_eq!(second.seq_number, first.seq_number.wrapping_add(1));
+
+        let Some(FtpEvent::Listed(entries)) = client.handle(&nak(&second, FtpNak::Eof), ms(520))
+        else {
+            panic!("expected listing");
+        };
+        assert_eq!(
+            entries,
+            [
+                FtpEntry {
+                    kind: FtpEntryKind::Directory,
+                    name: "2024".into(),
+                    size: 0
+                },
+                FtpEntry {
+                    kind: FtpEntryKind::File,
+                    name: "log.ulg".into(),
+                    size: 1024
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn download_fills_gaps() {
+        let ms = Duration::from_millis;
+        let file: Vec<u8> = (0..600u32).map(|i| i as u8).collect();
+        let mut client = FtpClient::new(GCS, VEHICLE);
+        client.start_download("/fs/params.bin").unwrap();
+
+        let open = request(&mut client, ms(0));
+        assert_eq!(open.opcode, FtpOpcode::OpenFileRO);
+        let mut opened = FtpPayload::new(FtpOpcode::Ack).with_data(&600u32.to_le_bytes());
+        opened.session = 3;
+        client.handle(&respond(&open, opened), ms(10));
+
+        let burst = request(&mut client, ms(10));
+        assert_eq!(
+            (burst.opcode, burst.session, burst.offset),
+            (FtpOpcode::BurstReadFile, 3, 0)
+        );
+        // The second chunk is lost, the burst ends prematurely
+        for (offset, complete) in [(0usize, false), (478, true)] {
+            let chunk = &file[offset..(offset + FTP_DATA_LEN).min(file.len())];
+            let mut response = FtpPayload::new(FtpOpcode::Ack).with_data(chunk);
+            response.offset = offset as u32;
+            response.session = burst.session;
+            response.burst_complete = complete;
+            client.handle(&respond(&burst, response), ms(20));
+        }
+        assert_eq!(client.progress(), Some((239 + 122, 600)));
+
+        let read = request(&mut client, ms(20));
+        assert_eq!(
+            (read.opcode, read.offset, read.size()),
+            (FtpOpcode::ReadFile, 239, 239)
+        );
+        let mut response = FtpPayload::new(FtpOpcode::Ack).with_data(&file[239..478]);
+        response.session = read.session;
+        response.offset = 239;
+        client.handle(&respond(&read, response), ms(30));
+
+        let terminate = request(&mut client, ms(30));
+        assert_eq!(
+            (terminate.opcode, terminate.session),
+            (FtpOpcode::TerminateSession, 3)
+        );
+        client.handle(&ack(&terminate, &[]), ms(40));
+
+        let verify = request(&mut client, ms(40));
+        assert_eq!(
+            (verify.opcode, verify.path()),
+            (FtpOpcode::CalcFileCRC32, "/fs/params.bin")
+        );
+        let crc = ftp_crc32(0, &file);
+        let event = client.handle(&ack(&verify, &crc.to_le_bytes()), ms(50));
+        assert_eq!(event, Some(FtpEvent::Downloaded(file)));
+    }
+
+    #[test]
+    fn interrupted_burst_is_restarted() {
+        let ms = Duration::from_millis;
+        let mut client = FtpClient::new(GCS, VEHICLE)
+            .with_timeout(ms(100))
+            .with_crc_check(false);
+        client.start_download("/a").unwrap();
+        let open = request(&mut client, ms(0));
+        let opened = FtpPayload::new(FtpOpcode::Ack).with_data(&300u32.to_le_bytes());
+        client.handle(&respond(&open, opened), ms(0));
+
+        let burst = request(&mut client, ms(0));
+        let response = FtpPayload::new(FtpOpcode::Ack).with_data(&[7; FTP_DATA_LEN]);
+        client.handle(&respond(&burst, response), ms(50));
+        assert_eq!(client.poll(ms(120)), Ok(None));
+
+        let restarted = request(&mut client, ms(150));
+        assert_eq!(
+            (restarted.opcode, restarted.offset),
+            (FtpOpcode::BurstReadFile, 239)
+        );
+        assert_ne!(restarted.seq_number, burst.seq_number);
+        let mut response = FtpPayload::new(FtpOpcode::Ack).with_data(&[7; 61]);
+        response.offset = 239;
+        client.handle(&respond(&restarted, response), ms(160));
+
+        let terminate = request(&mut client, ms(160));
+        let event = client.handle(&nak(&terminate, FtpNak::InvalidSession), ms(170));
+        assert_eq!(event, Some(FtpEvent::Downloaded(alloc::vec![7; 300])));
+    }
+
+    #[test]
+    fn early_eof_truncates_download() {
+        let ms = Duration::from_millis;
+        let mut client = FtpClient::new(GCS, VEHICLE).with_crc_check(false);
+        client.start_download("/a").unwrap();
+        let open = request(&mut client, ms(0));
+        let opened = FtpPayload::new(FtpOpcode::Ack).with_data(&500u32.to_le_bytes());
+        client.handle(&respond(&open, opened), ms(0));
+
+        // File has shrunk after it was opened
+        let burst = request(&mut client, ms(0));
+        let response = FtpPayload::new(FtpOpcode::Ack).with_data(&[7; 100]);
+        client.handle(&respond(&burst, response), ms(10));
+        client.handle(&nak(&burst, FtpNak::Eof), ms(20));
+
+        let terminate = request(&mut client, ms(20));
+        assert_eq!(terminate.opcode, FtpOpcode::TerminateSession);
+        let event = client.handle(&ack(&terminate, &[]), ms(30));
+        assert_eq!(event, Some(FtpEvent::Downloaded(alloc::vec![7; 100])));
+    }
+
+    #[test]
+    fn large_files_are_rejected() {
+        let ms = Duration::from_millis;
+        let mut client = FtpClient::new(GCS, VEHICLE).with_max_file_size(1024);
+        client.start_download("/a").unwrap();
+        let open = request(&mut client, ms(0));
+        let mut opened = FtpPayload::new(FtpOpcode::Ack).with_data(&u32::MAX.to_le_bytes());
+        opened.session = 2;
+        client.handle(&respond(&open, opened), ms(0));
+
+        let terminate = request(&mut client, ms(0));
+        assert_eq!(
+            (terminate.opcode, terminate.session),
+            (FtpOpcode::TerminateSession, 2)
+        );
+        let event = client.handle(&ack(&terminate, &[]), ms(10));
+        assert_eq!(event, Some(FtpEvent::Failed(ServiceError::FileTooLarge)));
+    }
+
+    #[test]
+    fn cancel_terminates_session() {
+        let ms = Duration::from_millis;
+        let mut client = FtpClient::new(GCS, VEHICLE)
+            .with_timeout(ms(100))
+            .with_retries(0);
+        client.start_upload("/a", [1, 2, 3]).unwrap();
+        let create = request(&mut client, ms(0));
+        let mut created = FtpPayload::new(FtpOpcode::Ack);
+        created.session = 4;
+        client.handle(&respond(&create, created), ms(0));
+        let write = request(&mut client, ms(0));
+        assert_eq!(write.opcode, FtpOpcode::WriteFile);
+
+        client.cancel();
+        assert!(!client.is_busy());
+        let terminate = request(&mut client, ms(10));
+        assert_eq!(
+            (terminate.opcode, terminate.session),
+            (FtpOpcode::TerminateSession, 4)
+        );
+        assert_eq!(client.poll(ms(10)), Ok(None));
+
+        // Session is terminated when operation times out as well
+        client.start_download("/b").unwrap();
+        let open = request(&mut client, ms(20));
+        let mut opened = FtpPayload::new(FtpOpcode::Ack).with_data(&300u32.to_le_bytes());
+        opened.session = 5;
+        client.handle(&respond(&open, opened), ms(20));
+        request(&mut client, ms(20));
+        assert_eq!(client.poll(ms(120)), Err(ServiceError::TimedOut));
+        let terminate = request(&mut client, ms(120));
+        assert_eq!(
+            (terminate.opcode, terminate.session),
+            (FtpOpcode::TerminateSession, 5)
+        );
+    }
+
+    #[test]
+    fn upload_and_verify() {
+        let ms = Duration::from_millis;
+        let file = alloc::vec![1u8; 300];
+        let mut client = FtpClient::new(GCS, VEHICLE);
+        assert_eq!(
+            client.start_upload(core::str::from_utf8(&[b'a'; 240]).unwrap(), []),
+            Err(ServiceError::InvalidPath)
+        );
+        client.start_upload("/mission.plan", file.clone()).unwrap();
+        assert_eq!(client.start_list("/"), Err(ServiceError::Busy));
+
+        let create = request(&mut client, ms(0));
+        assert_eq!(create.opcode, FtpOpcode::CreateFile);
+        let mut created = FtpPayload::new(FtpOpcode::Ack);
+        created.session = 1;
+        client.handle(&respond(&create, created), ms(0));
+
+        let mut offsets = Vec::new();
+        loop {
+            let write = request(&mut client, ms(0));
+            if write.opcode != FtpOpcode::WriteFile {
+                assert_eq!(write.opcode, FtpOpcode::TerminateSession);
+                client.handle(&ack(&write, &[]), ms(0));
+                break;
+            }
+            offsets.push((write.offset, write.data().len()));
+            client.handle(&ack(&write, &[]), ms(0));
+        }
+        assert_eq!(offsets, [(0, 239), (239, 61)]);
+
+        let verify = request(&mut client, ms(0));
+        let event = client.handle(&ack(&verify, &0u32.to_le_bytes()), ms(0));
+        assert_eq!(
+            event,
+            Some(FtpEvent::Failed(ServiceError::ChecksumMismatch))
+        );
+    }
+
+    #[test]
+    fn failures() {
+        let ms = Duration::from_millis;
+        let mut client = FtpClient::new(GCS, VEHICLE)
+            .with_timeout(ms(100))
+            .with_retries(1);
+
+        client.start_remove_directory("/logs").unwrap();
+        let remove = request(&mut client, ms(0));
+        // Responses to other requests are ignored
+        let stale = FtpPayload {
+            seq_number: remove.seq_number.wrapping_sub(1),
+            ..remove
+        };
+        assert!(client
+            .handle(&nak(&stale, FtpNak::FileNotFound), ms(10))
+            .is_none());
+        let event = client.handle(&nak(&remove, FtpNak::FailErrno(39)), ms(10));
+        assert_eq!(
+            event,
+            Some(FtpEvent::Failed(ServiceError::Ftp(FtpNak::FailErrno(39))))
+        );
+
+        client.start_crc32("/fs/missing").unwrap();
+        request(&mut client, ms(0));
+        request(&mut client, ms(100));
+        assert_eq!(client.poll(ms(200)), Err(ServiceError::TimedOut));
+        assert!(!client.is_busy());
+    }
+}
@@ -0,0 +1,777 @@
+//! # FTP file systems
+//!
+//! [`FtpFileSystem`] is a storage backend of an [`FtpServer`](super::FtpServer).
+//!
+//! This module provides two implementations:
+//!
+//! * [`MemoryFileSystem`] keeps files and directories in memory. Available only when `alloc`
+//!   feature is enabled.
+//! * [`DirectoryFileSystem`] serves files of a local directory. Available only when `std` feature
+//!   is enabled.
+//!
+//! Both implementations treat paths received from remote systems as relative to their root, with
+//! or without a leading `/`. Paths with `..` components are rejected with [`FtpNak::Fail`].
+//! [`MemoryFileSystem`] also limits the size of files written by remote systems, see
+//! [`MemoryFileSystem::with_max_file_size`].
+
+use crate::services::ftp::{ftp_crc32, FtpEntryKind, FtpNak};
+
+/// Entry of a directory visited by [`FtpFileSystem::list`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FtpDirEntry<'a> {
+    /// Kind of the entry.
+    pub kind: FtpEntryKind,
+    /// Name of the entry without a path.
+    pub name: &'a str,
+    /// Size of a file in bytes, ignored for directories.
+    pub size: u32,
+}
+
+/// File system served by [`FtpServer`](super::FtpServer).
+///
+/// Errors are reported to the remote system as `NAK` responses. Files opened by
+/// [`FtpFileSystem::open_read`] and [`FtpFileSystem::open_write`] are kept by server sessions
+/// and passed back to [`FtpFileSystem::close`] once session is terminated.
+pub trait FtpFileSystem {
+    /// Handle of an open file.
+    type File;
+
+    /// Visits entries of a directory starting from an entry with index `offset`.
+    ///
+    /// Entries should be visited in the same order on each call. Visiting stops once `visit`
+    /// returns `false`.
+    fn list(
+        &mut self,
+        path: &str,
+        offset: u32,
+        visit: &mut dyn FnMut(FtpDirEntry<'_>) -> bool,
+    ) -> Result<(), FtpNak>;
+
+    /// Opens an existing file for reading, returns its handle and size.
+    fn open_read(&mut self, path: &str) -> Result<(Self::File, u32), FtpNak>;
+
+    /// Opens a file for writing, creates file if it does not exist.
+    ///
+    /// Existing file is truncated if `truncate` is `true`.
+    fn open_write(&mut self, path: &str, truncate: bool) -> Result<Self::File, FtpNak>;
+
+    /// Reads file from `offset` into `buf`, returns the number of bytes read.
+    ///
+    /// Returns `0` once `offset` reaches the end of file.
+    fn read(&mut self, file: &mut Self::File, offset: u32, buf: &mut [u8])
+        -> Result<usize, FtpNak>;
+
+    /// Writes `data` into file at `offset`.
+    fn write(&mut self, file: &mut Self::File, offset: u32, data: &[u8]) -> Result<(), FtpNak>;
+
+    /// Closes file.
+    ///
+    /// Default implementation drops file handle.
+    fn close(&mut self, file: Self::File) {
+        drop(file);
+    }
+
+    /// Truncates or extends file to `len` bytes.
+    fn truncate(&mut self, path: &str, len: u32) -> Result<(), FtpNak>;
+
+    /// Removes file.
+    fn remove_file(&mut self, path: &str) -> Result<(), FtpNak>;
+
+    /// Creates directory. Parent directory should exist.
+    fn create_directory(&mut self, path: &str) -> Result<(), FtpNak>;
+
+    /// Removes empty directory.
+    fn remove_directory(&mut self, path: &str) -> Result<(), FtpNak>;
+
+    /// Renames file or directory. Fails with [`FtpNak::FileExists`] if target exists.
+    fn rename(&mut self, from: &str, to: &str) -> Result<(), FtpNak>;
+
+    /// Calculates CRC32 of a file, see [`ftp_crc32`].
+    ///
+    /// Default implementation reads file by [`FtpFileSystem::read`].
+    fn crc32(&mut self, path: &str) -> Result<u32, FtpNak> {
+        let (mut file, _) = self.open_read(path)?;
+        let mut buf = [0u8; 256];
+        let mut crc = 0;
+        let mut offset = 0u32;
+        let result = loop {
+            match self.read(&mut file, offset, &mut buf) {
+                Ok(0) => break Ok(crc),
+                Ok(len) => {
+                    crc = ftp_crc32(crc, &buf[..len]);
+                    offset += len as u32;
+                }
+                Err(err) => break Err(err),
+            }
+        };
+        self.close(file);
+        result
+    }
+}
+
+impl<F: FtpFileSystem + ?Sized> FtpFileSystem for &mut F {
+    type File = F::File;
+
+    fn list(
+        &mut self,
+        path: &str,
+        offset: u32,
+        visit: &mut dyn FnMut(FtpDirEntry<'_>) -> bool,
+    ) -> Result<(), FtpNak> {
+        (**self).list(path, offset, visit)
+    }
+
+    fn open_read(&mut self, path: &str) -> Result<(Self::File, u32), FtpNak> {
+        (**self).open_read(path)
+    }
+
+    fn open_write(&mut self, path: &str, truncate: bool) -> Result<Self::File, FtpNak> {
+        (**self).open_write(path, truncate)
+    }
+
+    fn read(
+        &mut self,
+        file: &mut Self::File,
+        offset: u32,
+        buf: &mut [u8],
+    ) -> Result<usize, FtpNak> {
+        (**self).read(file, offset, buf)
+    }
+
+    fn write(&mut self, file: &mut Self::File, offset: u32, data: &[u8]) -> Result<(), FtpNak> {
+        (**self).write(file, offset, data)
+    }
+
+    fn close(&mut self, file: Self::File) {
+        (**self).close(file)
+    }
+
+    fn truncate(&mut self, path: &str, len: u32) -> Result<(), FtpNak> {
+        (**self).truncate(path, len)
+    }
+
+    fn remove_file(&mut self, path: &str) -> Result<(), FtpNak> {
+        (**self).remove_file(path)
+    }
+
+    fn create_directory(&mut self, path: &str) -> Result<(), FtpNak> {
+        (**self).create_directory(path)
+    }
+
+    fn remove_directory(&mut self, path: &str) -> Result<(), FtpNak> {
+        (**self).remove_directory(path)
+    }
+
+    fn rename(&mut self, from: &str, to: &str) -> Result<(), FtpNak> {
+        (**self).rename(from, to)
+    }
+
+    fn crc32(&mut self, path: &str) -> Result<u32, FtpNak> {
+        (**self).crc32(path)
+    }
+}
+
+/// Splits path into components relative to the root of a file system.
+#[cfg(feature = "alloc")]
+fn components(path: &str) -> Result<impl Iterator<Item = &str> + Clone, FtpNak> {
+    let components = path
+        .split('/')
+        .filter(|component| !component.is_empty() && *component != ".");
+    if components.clone().any(|component| component == "..") {
+        return Err(FtpNak::Fail);
+    }
+    Ok(components)
+}
+
+#[cfg(feature = "alloc")]
+pub use memory::{MemoryFileSystem, DEFAULT_FTP_MEMORY_FILE_SIZE};
+
+#[cfg(feature = "alloc")]
+mod memory {
+    use alloc::collections::BTreeMap;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    /// Default maximum size of a file written into [`MemoryFileSystem`] by remote systems.
+    pub const DEFAULT_FTP_MEMORY_FILE_SIZE: u32 = 1024 * 1024;
+
+    /// `errno` reported when file exceeds the maximum size.
+    const ENOSPC: u8 = 28;
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Node {
+        File(Vec<u8>),
+        Directory,
+    }
+
+    /// <sup>`alloc`</sup>
+    /// In-memory file system.
+    ///
+    /// Open files are referenced by their paths, therefore removed or renamed files can't be read
+    /// or written through sessions opened before.
+    ///
+    /// Writes and truncations beyond the maximum file size fail with [`FtpNak::FailErrno`]
+    /// containing `ENOSPC`.
+    ///
+    /// Available only when `alloc` feature is enabled.
+    #[derive(Clone, Debug)]
+    pub struct MemoryFileSystem {
+        nodes: BTreeMap<String, Node>,
+        max_file_size: u32,
+    }
+
+    impl Default for MemoryFileSystem {
+        fn default() -> Self {
+            Self {
+                nodes: BTreeMap::new(),
+                max_file_size: DEFAULT_FTP_MEMORY_FILE_SIZE,
+            }
+        }
+    }
+
+    impl MemoryFileSystem {
+        /// Creates an empty file system.
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Sets the maximum size of a file written by remote systems in bytes.
+        ///
+        /// Files added by [`MemoryFileSystem::insert`] are not limited.
+        pub fn with_max_file_size(self, max_file_size: u32) -> Self {
+            Self {
+                max_file_size,
+                ..self
+            }
+        }
+
+        /// Adds a file, see [`MemoryFileSystem::insert`].
+        pub fn with_file(mut self, path: &str, data: impl Into<Vec<u8>>) -> Self {
+            self.insert(path, data);
+            self
+        }
+
+        /// Creates or replaces a file along with its parent directories.
+        ///
+        /// Returns `false` if path is invalid or one of its parents is a file.
+        pub fn insert(&mut self, path: &str, data: impl Into<Vec<u8>>) -> bool {
+            let Ok(key) = normalize(path) else {
+                return false;
+            };
+            if key.is_empty() || self.nodes.get(&key) == Some(&Node::Directory) {
+                return false;
+            }
+
+            let mut parent = String::new();
+            for component in key.split('/').take(key.split('/').count() - 1) {
+                if !parent.is_empty() {
+                    parent.push('/');
+                }
+                parent.push_str(component);
+                match self.nodes.get(&parent) {
+                    Some(Node::File(_)) => return false,
+                    Some(Node::Directory) => {}
+                    None => {
+                        self.nodes.insert(parent.clone(), Node::Directory);
+                    }
+                }
+            }
+            self.nodes.insert(key, Node::File(data.into()));
+            true
+        }
+
+        /// Contents of a file.
+        pub fn get(&self, path: &str) -> Option<&[u8]> {
+            match self.nodes.get(&normalize(path).ok()?)? {
+                Node::File(data) => Some(data),
+                Node::Directory => None,
+            }
+        }
+
+        fn is_directory(&self, key: &str) -> bool {
+            key.is_empty() || self.nodes.get(key) == Some(&Node::Directory)
+        }
+
+        /// Checks that file of `len` bytes doesn't exceed the maximum size.
+        fn check_size(&self, len: Option<usize>) -> Result<usize, FtpNak> {
+            len.filter(|&len| len <= self.max_file_size as usize)
+                .ok_or(FtpNak::FailErrno(ENOSPC))
+        }
+
+        fn file_mut(&mut self, key: &str) -> Result<&mut Vec<u8>, FtpNak> {
+            match self.nodes.get_mut(key) {
+                Some(Node::File(data)) => Ok(data),
+                Some(Node::Directory) => Err(FtpNak::Fail),
+                None => Err(FtpNak::FileNotFound),
+            }
+        }
+
+        fn children<'a>(&'a self, key: &'a str) -> impl Iterator<Item = (&'a str, &'a Node)> {
+            self.nodes.iter().filter_map(move |(path, node)| {
+                let name = if key.is_empty() {
+                    path.as_str()
+                } else {
+                    path.strip_prefix(key)?.strip_prefix('/')?
+                };
+                (!name.contains('/')).then_some((name, node))
+            })
+        }
+    }
+
+    impl FtpFileSystem for MemoryFileSystem {
+        type File = String;
+
+        fn list(
+            &mut self,
+            path: &str,
+            offset: u32,
+            visit: &mut dyn FnMut(FtpDirEntry<'_>) -> bool,
+        ) -> Result<(), FtpNak> {
+            let key = normalize(path)?;
+            if !self.is_directory(&key) {
+                self.file_mut(&key)?;
+                return Err(FtpNak::Fail);
+            }
+            for (name, node) in self.children(&key).skip(offset as usize) {
+                let entry = match node {
+                    Node::File(data) => FtpDirEntry {
+                        kind: FtpEntryKind::File,
+                        name,
+                        size: data.len() as u32,
+                    },
+                    Node::Directory => FtpDirEntry {
+                        kind: FtpEntryKind::Directory,
+                        name,
+                        size: 0,
+                    },
+                };
+                if !visit(entry) {
+                    break;
+                }
+            }
+            Ok(())
+        }
+
+        fn open_read(&mut self, path: &str) -> Result<(Self::File, u32), FtpNak> {
+            let key = normalize(path)?;
+            let size = self.file_mut(&key)?.len() as u32;
+            Ok((key, size))
+        }
+
+        fn open_write(&mut self, path: &str, truncate: bool) -> Result<Self::File, FtpNak> {
+            let key = normalize(path)?;
+            let parent = key.rsplit_once('/').map_or("", |(parent, _)| parent);
+            if key.is_empty() || !self.is_directory(parent) {
+                return Err(FtpNak::FileNotFound);
+            }
+            match self.nodes.get_mut(&key) {
+                Some(Node::Directory) => return Err(FtpNak::FileExists),
+                Some(Node::File(data)) if truncate => data.clear(),
+                Some(Node::File(_)) => {}
+                None => {
+                    self.nodes.insert(key.clone(), Node::File(Vec::new()));
+                }
+            }
+            Ok(key)
+        }
+
+        fn read(
+            &mut self,
+            file: &mut Self::File,
+            offset: u32,
+            buf: &mut [u8],
+        ) -> Result<usize, FtpNak> {
+            let data = self.file_mut(file)?;
+            let rest = data.get(offset as usize..).unwrap_or_default();
+            let len = rest.len().min(buf.len());
+            buf[..len].copy_from_slice(&rest[..len]);
+            Ok(len)
+        }
+
+        fn write(&mut self, file: &mut Self::File, offset: u32, data: &[u8]) -> Result<(), FtpNak> {
+            let end = self.check_size((offset as usize).checked_add(data.len()))?;
+            let file = self.file_mut(file)?;
+            if file.len() < end {
+                file.resize(end, 0);
+            }
+            file[offset as usize..end].copy_from_slice(data);
+            Ok(())
+        }
+
+        fn truncate(&mut self, path: &str, len: u32) -> Result<(), FtpNak> {
+            let len = self.check_size(Some(len as usize))?;
+            self.file_mut(&normalize(path)?)?.resize(len, 0);
+            Ok(())
+        }
+
+        fn remove_file(&mut self, path: &str) -> Result<(), FtpNak> {
+            let key = normalize(path)?;
+            self.file_mut(&key)?;
+            self.nodes.remove(&key);
+            Ok(())
+        }
+
+        fn create_directory(&mut self, path: &str) -> Result<(), FtpNak> {
+            let key = normalize(path)?;
+            let parent = key.rsplit_once('/').map_or("", |(parent, _)| parent);
+            if key.is_empty() || self.nodes.contains_key(&key) {
+                return Err(FtpNak::FileExists);
+            }
+            if !self.is_directory(parent) {
+                return Err(FtpNak::FileNotFound);
+            }
+            self.nodes.insert(key, Node::Directory);
+            Ok(())
+        }
+
+        fn remove_directory(&mut self, path: &str) -> Result<(), FtpNak> {
+            let key = normalize(path)?;
+            if key.is_empty() {
+                return Err(FtpNak::FileProtected);
+            }
+            match self.nodes.get(&key) {
+                Some(Node::Directory) if self.children(&key).next().is_some() => Err(FtpNak::Fail),
+                Some(Node::Directory) => {
+                    self.nodes.remove(&key);
+                    Ok(())
+                }
+                Some(Node::File(_)) => Err(FtpNak::Fail),
+                None => Err(FtpNak::FileNotFound),
+            }
+        }
+
+        fn rename(&mut self, from: &str, to: &str) -> Result<(), FtpNak> {
+            let (from, to) = (normalize(from)?, normalize(to)?);
+            let parent = to.rsplit_once('/').map_or("", |(parent, _)| parent);
+            if from.is_empty() || !self.nodes.contains_key(&from) {
+                return Err(FtpNak::FileNotFound);
+            }
+            if to.is_empty() || self.nodes.contains_key(&to) {
+                return Err(FtpNak::FileExists);
+            }
+            let prefix = alloc::format!("{from}/");
+            // Directories can't be moved into themselves
+            if !self.is_directory(parent) || parent == from || parent.starts_with(&prefix) {
+                return Err(FtpNak::Fail);
+            }
+
+            let moved: Vec<String> = self
+                .nodes
+                .keys()
+                .filter(|key| **key == from || key.starts_with(&prefix))
+                .cloned()
+                .collect();
+            for key in moved {
+                if let Some(node) = self.nodes.remove(&key) {
+                    self.nodes
+                        .insert(alloc::format!("{to}{}", &key[from.len()..]), node);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Normalized path without leading, trailing, and repeated `/`.
+    fn normalize(path: &str) -> Result<String, FtpNak> {
+        let mut key = String::new();
+        for component in components(path)? {
+            if !key.is_empty() {
+                key.push('/');
+            }
+            key.push_str(component);
+        }
+        Ok(key)
+    }
+}
+
+#[cfg(feature = "std")]
+pub use directory::DirectoryFileSystem;
+
+#[cfg(feature = "std")]
+mod directory {
+    use std::fs::{self, File, OpenOptions};
+    use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
+    use std::path::{Component, Path, PathBuf};
+
+    use super::*;
+
+    /// <sup>`std`</sup>
+    /// File system that serves a local directory.
+    ///
+    /// Remote systems can't access files outside the root directory. Symbolic links within the
+    /// root directory are followed only if they lead to files within the root directory.
+    ///
+    /// Available only when `std` feature is enabled.
+    #[derive(Clone, Debug)]
+    pub struct DirectoryFileSystem {
+        root: PathBuf,
+    }
+
+    impl DirectoryFileSystem {
+        /// Creates a file system with a specified root directory.
+        pub fn new(root: impl Into<PathBuf>) -> Self {
+            Self { root: root.into() }
+        }
+
+        /// Root directory.
+        pub fn root(&self) -> &Path {
+            &self.root
+        }
+
+        fn resolve(&self, path: &str) -> Result<PathBuf, FtpNak> {
+            let mut resolved = self.root.clone();
+            for component in Path::new(path.trim_start_matches('/')).components() {
+                match component {
+                    Component::Normal(name) => resolved.push(name),
+                    Component::CurDir => {}
+                    Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                        return Err(FtpNak::Fail);
+                    }
+                }
+            }
+
+            // Symbolic links may lead outside the root, path itself may not exist yet
+            let root = self.root.canonicalize().map_err(nak)?;
+            let existing = resolved
+                .ancestors()
+                .find(|path| fs::symlink_metadata(path).is_ok());
+            if let Some(existing) = existing {
+                if !existing.canonicalize().map_err(nak)?.starts_with(&root) {
+                    return Err(FtpNak::Fail);
+                }
+            }
+            Ok(resolved)
+        }
+    }
+
+    impl FtpFileSystem for DirectoryFileSystem {
+        type File = File;
+
+        fn list(
+            &mut self,
+            path: &str,
+            offset: u32,
+            visit: &mut dyn FnMut(FtpDirEntry<'_>) -> bool,
+        ) -> Result<(), FtpNak> {
+            let mut entries = Vec::new();
+            for entry in fs::read_dir(self.resolve(path)?).map_err(nak)? {
+                let entry = entry.map_err(nak)?;
+                let (Ok(name), Ok(metadata)) = (entry.file_name().into_string(), entry.metadata())
+                else {
+                    continue;
+                };
+                let (kind, size) = if metadata.is_dir() {
+                    (FtpEntryKind::Directory, 0)
+                } else {
+                    let size = metadata.len().min(u32::MAX as u64) as u32;
+                    (FtpEntryKind::File, size)
+                };
+                entries.push((name, kind, size));
+            }
+            // Directory iteration order is not specified, while offsets should be stable
+            entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+            for (name, kind, size) in entries.iter().skip(offset as usize) {
+                let entry = FtpDirEntry {
+                    kind: *kind,
+                    name,
+                    size: *size,
+                };
+                if !visit(entry) {
+                    break;
+                }
+            }
+            Ok(())
+        }
+
+        fn open_read(&mut self, path: &str) -> Result<(Self::File, u32), FtpNak> {
+            let file = File::open(self.resolve(path)?).map_err(nak)?;
+            let metadata = file.metadata().map_err(nak)?;
+            if metadata.is_dir() {
+                return Err(FtpNak::Fail);
+            }
+            Ok((file, metadata.len().min(u32::MAX as u64) as u32))
+        }
+
+        fn open_write(&mut self, path: &str, truncate: bool) -> Result<Self::File, FtpNak> {
+            OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(truncate)
+                .open(self.resolve(path)?)
+                .map_err(nak)
+        }
+
+        fn read(
+            &mut self,
+            file: &mut Self::File,
+            offset: u32,
+            buf: &mut [u8],
+        ) -> Result<usize, FtpNak> {
+            file.seek(SeekFrom::Start(offset as u64)).map_err(nak)?;
+            let mut len = 0;
+            while len < buf.len() {
+                match file.read(&mut buf[len..]) {
+                    Ok(0) => break,
+                    Ok(read) => len += read,
+                    Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                    Err(err) => return Err(nak(err)),
+                }
+            }
+            Ok(len)
+        }
+
+        fn write(&mut self, file: &mut Self::File, offset: u32, data: &[u8]) -> Result<(), FtpNak> {
+            file.seek(SeekFrom::Start(offset as u64)).map_err(nak)?;
+            file.write_all(data).map_err(nak)
+        }
+
+        fn truncate(&mut self, path: &str, len: u32) -> Result<(), FtpNak> {
+            let file = OpenOptions::new()
+                .write(true)
+                .open(self.resolve(path)?)
+                .map_err(nak)?;
+            file.set_len(len as u64).map_err(nak)
+        }
+
+        fn remove_file(&mut self, path: &str) -> Result<(), FtpNak> {
+            fs::remove_file(self.resolve(path)?).map_err(nak)
+        }
+
+        fn create_directory(&mut self, path: &str) -> Result<(), FtpNak> {
+            fs::create_dir(self.resolve(path)?).map_err(nak)
+        }
+
+        fn remove_directory(&mut self, path: &str) -> Result<(), FtpNak> {
+            let path = self.resolve(path)?;
+            if path == self.root {
+                return Err(FtpNak::FileProtected);
+            }
+            fs::remove_dir(path).map_err(nak)
+        }
+
+        fn rename(&mut self, from: &str, to: &str) -> Result<(), FtpNak> {
+            let (from, to) = (self.resolve(from)?, self.resolve(to)?);
+            // Unlike `NAK` semantics, `rename` silently replaces existing files on some platforms
+            if to.exists() {
+                return Err(FtpNak::FileExists);
+            }
+            fs::rename(from, to).map_err(nak)
+        }
+    }
+
+    /// Converts I/O error into a `NAK` error code.
+    fn nak(err: io::Error) -> FtpNak {
+        match err.kind() {
+            ErrorKind::NotFound => FtpNak::FileNotFound,
+            ErrorKind::AlreadyExists => FtpNak::FileExists,
+            ErrorKind::PermissionDenied => FtpNak::FileProtected,
+            _ => err
+                .raw_os_error()
+                .map_or(FtpNak::Fail, |errno| FtpNak::FailErrno(errno as u8)),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    fn names<F: FtpFileSystem>(fs: &mut F, path: &str, offset: u32) -> Vec<String> {
+        let mut names = Vec::new();
+        fs.list(path, offset, &mut |entry| {
+            names.push(format!("{:?}:{}:{}", entry.kind, entry.name, entry.size));
+            true
+        })
+        .unwrap();
+        names
+    }
+
+    fn exercise<F: FtpFileSystem>(fs: &mut F) {
+        fs.create_directory("/logs").unwrap();
+        assert_eq!(fs.create_directory("logs"), Err(FtpNak::FileExists));
+        assert_eq!(fs.create_directory("/a/b"), Err(FtpNak::FileNotFound));
+
+        let mut file = fs.open_write("/logs/1.ulg", true).unwrap();
+        fs.write(&mut file, 0, b"hello").unwrap();
+        fs.write(&mut file, 5, b" world").unwrap();
+        fs.close(file);
+
+        let (mut file, size) = fs.open_read("logs//1.ulg").unwrap();
+        assert_eq!(size, 11);
+        let mut buf = [0u8; 8];
+        assert_eq!(fs.read(&mut file, 6, &mut buf), Ok(5));
+        assert_eq!(&buf[..5], b"world");
+        assert_eq!(fs.read(&mut file, 11, &mut buf), Ok(0));
+        fs.close(file);
+        assert_eq!(fs.crc32("/logs/1.ulg"), Ok(ftp_crc32(0, b"hello world")));
+
+        assert_eq!(fs.open_read("/../etc/passwd").err(), Some(FtpNak::Fail));
+        assert_eq!(
+            fs.open_read("/logs/2.ulg").err(),
+            Some(FtpNak::FileNotFound)
+        );
+
+        fs.truncate("/logs/1.ulg", 5).unwrap();
+        assert_eq!(fs.rename("/logs/1.ulg", "/logs"), Err(FtpNak::FileExists));
+        fs.rename("/logs/1.ulg", "/logs/2.ulg").unwrap();
+        fs.create_directory("/logs/old").unwrap();
+        assert_eq!(names(fs, "/logs", 0), ["File:2.ulg:5", "Directory:old:0"]);
+        assert_eq!(names(fs, "/logs", 1), ["Directory:old:0"]);
+
+        assert!(fs.remove_directory("/logs").is_err());
+        fs.remove_file("/logs/2.ulg").unwrap();
+        fs.remove_directory("/logs/old").unwrap();
+        fs.remove_directory("/logs").unwrap();
+        assert_eq!(fs.remove_file("/logs/2.ulg"), Err(FtpNak::FileNotFound));
+        assert!(names(fs, "/", 0).is_empty());
+    }
+
+    #[test]
+    fn memory_file_system() {
+        let mut fs = MemoryFileSystem::new();
+        exercise(&mut fs);
+
+        let mut fs = MemoryFileSystem::new().with_max_file_size(4);
+        let mut file = fs.open_write("/a.bin", true).unwrap();
+        assert_eq!(fs.write(&mut file, 2, b"ab"), Ok(()));
+        assert_eq!(fs.write(&mut file, 3, b"ab"), Err(FtpNak::FailErrno(28)));
+        assert_eq!(
+            fs.write(&mut file, u32::MAX, b"ab"),
+            Err(FtpNak::FailErrno(28))
+        );
+        assert_eq!(fs.truncate("/a.bin", 5), Err(FtpNak::FailErrno(28)));
+        assert_eq!(fs.get("/a.bin"), Some(&b"\0\0ab"[..]));
+
+        let mut fs = MemoryFileSystem::new().with_file("/a/b/c.txt", "abc");
+        assert_eq!(fs.get("a/b/c.txt"), Some(&b"abc"[..]));
+        assert!(!fs.insert("/a/b/c.txt/d", "d"));
+        fs.rename("/a", "/x").unwrap();
+        assert_eq!(fs.get("/x/b/c.txt"), Some(&b"abc"[..]));
+        assert_eq!(fs.rename("/x", "/x/b/y"), Err(FtpNak::Fail));
+    }
+
+    #[test]
+    fn directory_file_system() {
+        let root = std::env::temp_dir().join(format!("mavio-ftp-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let mut fs = DirectoryFileSystem::new(&root);
+        exercise(&mut fs);
+        assert_eq!(fs.remove_directory("/"), Err(FtpNak::FileProtected));
+
+        #[cfg(unix)]
+        {
+            let outside = root.with_extension("outside");
+            std::fs::create_dir_all(&outside).unwrap();
+            std::fs::write(outside.join("secret"), "secret").unwrap();
+            std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+            assert_eq!(fs.open_read("/link/secret").err(), Some(FtpNak::Fail));
+            assert_eq!(fs.open_write("/link/new", true).err(), Some(FtpNak::Fail));
+            assert!(!outside.join("new").exists());
+            std::fs::remove_dir_all(&outside).unwrap();
+        }
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
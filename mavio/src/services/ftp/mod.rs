@@ -0,0 +1,441 @@
+//! # File transfer protocol
+//!
+//! Implements MAVLink [file transfer protocol](https://mavlink.io/en/services/ftp.html) (FTP).
+//!
+//! * [`FtpClient`] lists directories, downloads, uploads, and removes files of a remote component,
+//!   see [`client`]. Available only when `alloc` feature is enabled.
+//! * [`FtpServer`] serves files of an [`FtpFileSystem`] to remote components, see [`server`] and
+//!   [`fs`].
+//!
+//! FTP messages are carried in the `payload` field of `FILE_TRANSFER_PROTOCOL`. This module
+//! provides [`FtpPayload`] that encodes and decodes this field, as well as [`FtpOpcode`] and
+//! [`FtpNak`] codes defined by the protocol.
+
+use crate::microservices::ftp::messages::FileTransferProtocol;
+use crate::protocol::MavLinkId;
+
+#[cfg(feature = "alloc")]
+pub mod client;
+pub mod fs;
+pub mod server;
+
+#[cfg(feature = "alloc")]
+#[doc(inline)]
+pub use client::{
+    FtpClient, FtpEntry, FtpEvent, DEFAULT_FTP_MAX_FILE_SIZE, DEFAULT_FTP_RETRIES,
+    DEFAULT_FTP_TIMEOUT,
+};
+#[cfg(feature = "std")]
+#[doc(inline)]
+pub use fs::DirectoryFileSystem;
+#[doc(inline)]
+pub use fs::{FtpDirEntry, FtpFileSystem};
+#[cfg(feature = "alloc")]
+#[doc(inline)]
+pub use fs::{MemoryFileSystem, DEFAULT_FTP_MEMORY_FILE_SIZE};
+#[doc(inline)]
+pub use server::{
+    FtpServer, DEFAULT_FTP_BURST_INTERVAL, DEFAULT_FTP_SESSIONS, DEFAULT_FTP_SESSION_TIMEOUT,
+};
+
+/// Length of `payload` field of `FILE_TRANSFER_PROTOCOL` in bytes.
+pub const FTP_PAYLOAD_LEN: usize = 251;
+/// Length of FTP header within `payload` field in bytes.
+pub const FTP_HEADER_LEN: usize = 12;
+/// Maximum length of FTP data in bytes.
+pub const FTP_DATA_LEN: usize = FTP_PAYLOAD_LEN - FTP_HEADER_LEN;
+
+/// Kind of a directory entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FtpEntryKind {
+    /// Regular file.
+    File,
+    /// Directory.
+    Directory,
+}
+
+/// FTP operation code.
+///
+/// See [opcodes](https://mavlink.io/en/services/ftp.html#opcodes) in MAVLink documentation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FtpOpcode {
+    /// Ignored, always ACKed.
+    #[default]
+    None,
+    /// Terminates open read session.
+    TerminateSession,
+    /// Terminates all open read sessions.
+    ResetSessions,
+    /// Lists files and directories in a path from a specified offset.
+    ListDirectory,
+    /// Opens file at path for reading, returns session.
+    OpenFileRO,
+    /// Reads `size` bytes from `offset` in session.
+    ReadFile,
+    /// Creates file at path for writing, returns session.
+    CreateFile,
+    /// Writes `size` bytes to `offset` in session.
+    WriteFile,
+    /// Removes file at path.
+    RemoveFile,
+    /// Creates directory at path.
+    CreateDirectory,
+    /// Removes directory at path. The directory must be empty.
+    RemoveDirectory,
+    /// Opens file at path for writing, returns session.
+    OpenFileWO,
+    /// Truncates file at path to `offset` length.
+    TruncateFile,
+    /// Renames path to another path, both are separated by `NUL` in data.
+    Rename,
+    /// Calculates CRC32 for file at path.
+    CalcFileCRC32,
+    /// Burst download session file.
+    BurstReadFile,
+    /// Acknowledgement of a successful request.
+    Ack,
+    /// Negative acknowledgement of a failed request, see [`FtpNak`].
+    Nak,
+    /// Opcode that is not defined by the protocol.
+    Unknown(u8),
+}
+
+impl From<u8> for FtpOpcode {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => FtpOpcode::None,
+            1 => FtpOpcode::TerminateSession,
+            2 => FtpOpcode::ResetSessions,
+            3 => FtpOpcode::ListDirectory,
+            4 => FtpOpcode::OpenFileRO,
+            5 => FtpOpcode::ReadFile,
+            6 => FtpOpcode::CreateFile,
+            7 => FtpOpcode::WriteFile,
+            8 => FtpOpcode::RemoveFile,
+            9 => FtpOpcode::CreateDirectory,
+            10 => FtpOpcode::RemoveDirectory,
+            11 => FtpOpcode::OpenFileWO,
+            12 => FtpOpcode::TruncateFile,
+            13 => FtpOpcode::Rename,
+            14 => FtpOpcode::CalcFileCRC32,
+            15 => FtpOpcode::BurstReadFile,
+            128 => FtpOpcode::Ack,
+            129 => FtpOpcode::Nak,
+            other => FtpOpcode::Unknown(other),
+        }
+    }
+}
+
+impl From<FtpOpcode> for u8 {
+    fn from(value: FtpOpcode) -> Self {
+        match value {
+            FtpOpcode::None => 0,
+            FtpOpcode::TerminateSession => 1,
+            FtpOpcode::ResetSessions => 2,
+            FtpOpcode::ListDirectory => 3,
+            FtpOpcode::OpenFileRO => 4,
+            FtpOpcode::ReadFile => 5,
+            FtpOpcode::CreateFile => 6,
+            FtpOpcode::WriteFile => 7,
+            FtpOpcode::RemoveFile => 8,
+            FtpOpcode::CreateDirectory => 9,
+            FtpOpcode::RemoveDirectory => 10,
+            FtpOpcode::OpenFileWO => 11,
+            FtpOpcode::TruncateFile => 12,
+            FtpOpcode::Rename => 13,
+            FtpOpcode::CalcFileCRC32 => 14,
+            FtpOpcode::BurstReadFile => 15,
+            FtpOpcode::Ack => 128,
+            FtpOpcode::Nak => 129,
+            FtpOpcode::Unknown(other) => other,
+        }
+    }
+}
+
+/// Error code of a negative acknowledgement.
+///
+/// See [NAK error codes](https://mavlink.io/en/services/ftp.html#error_codes) in MAVLink
+/// documentation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(all(feature = "specta", feature = "unstable"), derive(specta::Type))]
+#[cfg_attr(
+    all(feature = "serde", feature = "unstable"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum FtpNak {
+    /// No error.
+    None,
+    /// Unknown failure.
+    Fail,
+    /// Command failed, contains `errno` reported by remote system.
+    FailErrno(u8),
+    /// Payload size is invalid.
+    InvalidDataSize,
+    /// Session is not currently open.
+    InvalidSession,
+    /// All available sessions are already in use.
+    NoSessionsAvailable,
+    /// Offset past end of file for read commands, or the end of directory listing.
+    Eof,
+    /// Unknown command or opcode.
+    UnknownCommand,
+    /// File or directory already exists.
+    FileExists,
+    /// File or directory is write protected.
+    FileProtected,
+    /// File or directory not found.
+    FileNotFound,
+    /// Error code that is not defined by the protocol.
+    Unknown(u8),
+}
+
+impl FtpNak {
+    /// Decodes error code from data of a `NAK` response.
+    pub fn from_data(data: &[u8]) -> Self {
+        match data.first().copied().unwrap_or(1) {
+            0 => FtpNak::None,
+            1 => FtpNak::Fail,
+            2 => FtpNak::FailErrno(data.get(1).copied().unwrap_or_default()),
+            3 => FtpNak::InvalidDataSize,
+            4 => FtpNak::InvalidSession,
+            5 => FtpNak::NoSessionsAvailable,
+            6 => FtpNak::Eof,
+            7 => FtpNak::UnknownCommand,
+            8 => FtpNak::FileExists,
+            9 => FtpNak::FileProtected,
+            10 => FtpNak::FileNotFound,
+            other => FtpNak::Unknown(other),
+        }
+    }
+
+    /// Encodes error code into `buf` and returns the length of encoded data.
+    pub fn to_data(&self, buf: &mut [u8; 2]) -> usize {
+        let code = match *self {
+            FtpNak::None => 0,
+            FtpNak::Fail => 1,
+            FtpNak::FailErrno(errno) => {
+                *buf = [2, errno];
+                return 2;
+            }
+            FtpNak::InvalidDataSize => 3,
+            FtpNak::InvalidSession => 4,
+            FtpNak::NoSessionsAvailable => 5,
+            FtpNak::Eof => 6,
+            FtpNak::UnknownCommand => 7,
+            FtpNak::FileExists => 8,
+            FtpNak::FileProtected => 9,
+            FtpNak::FileNotFound => 10,
+            FtpNak::Unknown(other) => other,
+        };
+        buf[0] = code;
+        1
+    }
+}
+
+/// Content of `payload` field of `FILE_TRANSFER_PROTOCOL`.
+///
+/// See [payload format](https://mavlink.io/en/services/ftp.html#payload) in MAVLink documentation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FtpPayload {
+    /// Sequence number of the message.
+    pub seq_number: u16,
+    /// Session ID.
+    pub session: u8,
+    /// Operation code.
+    pub opcode: FtpOpcode,
+    /// Opcode of a request this message responds to, only for [`FtpOpcode::Ack`] and
+    /// [`FtpOpcode::Nak`].
+    pub req_opcode: FtpOpcode,
+    /// Set on the last message of a burst.
+    pub burst_complete: bool,
+    /// Offset in a file or an index of a directory entry.
+    pub offset: u32,
+    size: u8,
+    data: [u8; FTP_DATA_LEN],
+}
+
+impl Default for FtpPayload {
+    fn default() -> Self {
+        Self {
+            seq_number: 0,
+            session: 0,
+            opcode: FtpOpcode::None,
+            req_opcode: FtpOpcode::None,
+            burst_complete: false,
+            offset: 0,
+            size: 0,
+            data: [0; FTP_DATA_LEN],
+        }
+    }
+}
+
+impl FtpPayload {
+    /// Creates a payload with a specified opcode and no data.
+    pub fn new(opcode: FtpOpcode) -> Self {
+        Self {
+            opcode,
+            ..Default::default()
+        }
+    }
+
+    /// Sets data.
+    ///
+    /// Data longer than [`FTP_DATA_LEN`] is truncated.
+    pub fn with_data(self, data: &[u8]) -> Self {
+        let mut payload = self;
+        payload.set_data(data);
+        payload
+    }
+
+    /// Sets data to a path or any other string.
+    ///
+    /// Returns [`None`] if `path` is longer than [`FTP_DATA_LEN`] bytes.
+    pub fn with_path(self, path: &str) -> Option<Self> {
+        if path.len() > FTP_DATA_LEN {
+            return None;
+        }
+        Some(self.with_data(path.as_bytes()))
+    }
+
+    /// Data of the message.
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.size as usize]
+    }
+
+    /// Data interpreted as a `NUL`-terminated string, usually a path.
+    ///
+    /// Invalid UTF-8 sequences truncate the string.
+    pub fn path(&self) -> &str {
+        let data = self.data();
+        let len = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+        match core::str::from_utf8(&data[..len]) {
+            Ok(path) => path,
+            Err(err) => core::str::from_utf8(&data[..err.valid_up_to()]).unwrap_or_default(),
+        }
+    }
+
+    /// The `size` field.
+    ///
+    /// Normally this is the length of [`FtpPayload::data`]. For [`FtpOpcode::ReadFile`] and
+    /// [`FtpOpcode::BurstReadFile`] requests, this is the number of bytes to read.
+    #[inline(always)]
+    pub fn size(&self) -> u8 {
+        self.size
+    }
+
+    /// Sets data.
+    ///
+    /// Data longer than [`FTP_DATA_LEN`] is truncated.
+    pub fn set_data(&mut self, data: &[u8]) {
+        let len = data.len().min(FTP_DATA_LEN);
+        self.data = [0; FTP_DATA_LEN];
+        self.data[..len].copy_from_slice(&data[..len]);
+        self.size = len as u8;
+    }
+
+    /// Sets the `size` field without changing data.
+    ///
+    /// Used by read requests. Values greater than [`FTP_DATA_LEN`] are clamped.
+    pub fn set_size(&mut self, size: u8) {
+        self.size = size.min(FTP_DATA_LEN as u8);
+    }
+
+    /// Data interpreted as a little-endian `u32`, used by responses carrying file size or CRC32.
+    pub fn data_u32(&self) -> Option<u32> {
+        let bytes = self.data().get(..4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Decodes payload from `payload` field of `FILE_TRANSFER_PROTOCOL`.
+    pub fn decode(raw: &[u8; FTP_PAYLOAD_LEN]) -> Self {
+        let mut data = [0u8; FTP_DATA_LEN];
+        data.copy_from_slice(&raw[FTP_HEADER_LEN..]);
+        Self {
+            seq_number: u16::from_le_bytes([raw[0], raw[1]]),
+            session: raw[2],
+            opcode: FtpOpcode::from(raw[3]),
+            size: raw[4].min(FTP_DATA_LEN as u8),
+            req_opcode: FtpOpcode::from(raw[5]),
+            burst_complete: raw[6] != 0,
+            offset: u32::from_le_bytes([raw[8], raw[9], raw[10], raw[11]]),
+            data,
+        }
+    }
+
+    /// Encodes payload into `payload` field of `FILE_TRANSFER_PROTOCOL`.
+    pub fn encode(&self) -> [u8; FTP_PAYLOAD_LEN] {
+        let mut raw = [0u8; FTP_PAYLOAD_LEN];
+        raw[0..2].copy_from_slice(&self.seq_number.to_le_bytes());
+        raw[2] = self.session;
+        raw[3] = self.opcode.into();
+        raw[4] = self.size;
+        raw[5] = self.req_opcode.into();
+        raw[6] = self.burst_complete as u8;
+        raw[8..12].copy_from_slice(&self.offset.to_le_bytes());
+        raw[FTP_HEADER_LEN..].copy_from_slice(&self.data);
+        raw
+    }
+
+    /// Wraps payload into `FILE_TRANSFER_PROTOCOL` addressed to `target`.
+    pub fn to_message(&self, target: MavLinkId) -> FileTransferProtocol {
+        FileTransferProtocol {
+            target_network: 0,
+            target_system: target.system,
+            target_component: target.component,
+            payload: self.encode(),
+        }
+    }
+}
+
+/// Updates `crc` with `data` using the CRC32 algorithm of MAVLink FTP.
+///
+/// Start with `0` and feed data in the order of file contents. This is the result expected from
+/// [`FtpOpcode::CalcFileCRC32`].
+pub fn ftp_crc32(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_roundtrip() {
+        let mut payload = FtpPayload::new(FtpOpcode::Ack).with_data(b"hello");
+        payload.seq_number = 513;
+        payload.session = 2;
+        payload.req_opcode = FtpOpcode::BurstReadFile;
+        payload.burst_complete = true;
+        payload.offset = 0x01020304;
+
+        let raw = payload.encode();
+        assert_eq!(&raw[..12], &[1, 2, 2, 128, 5, 15, 1, 0, 4, 3, 2, 1]);
+        let decoded = FtpPayload::decode(&raw);
+        assert_eq!(decoded, payload);
+        assert_eq!(decoded.data(), b"hello");
+
+        let mut buf = [0u8; 2];
+        let len = FtpNak::FailErrno(13).to_data(&mut buf);
+        assert_eq!(FtpNak::from_data(&buf[..len]), FtpNak::FailErrno(13));
+        assert_eq!(FtpOpcode::from(200), FtpOpcode::Unknown(200));
+        assert!(FtpPayload::new(FtpOpcode::OpenFileRO)
+            .with_path(core::str::from_utf8(&[b'a'; FTP_DATA_LEN + 1]).unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn crc32() {
+        // Reference value computed by the table-driven implementation of PX4 and ArduPilot
+        assert_eq!(ftp_crc32(0, b"123456789"), 0x2DFD2D88);
+        let crc = ftp_crc32(0, b"1234");
+        assert_eq!(ftp_crc32(crc, b"56789"), 0x2DFD2D88);
+    }
+}
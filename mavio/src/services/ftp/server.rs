@@ -0,0 +1,771 @@
+//! # FTP server
+//!
+//! Implements drone side of MAVLink
+//! [file transfer protocol](https://mavlink.io/en/services/ftp.html).
+//!
+//! [`FtpServer`] is a sans-I/O state machine that serves files of an [`FtpFileSystem`]:
+//!
+//! * Answers each request with `ACK` or `NAK` carrying an error code, see [`FtpNak`].
+//! * Keeps up to `N` sessions opened by `OpenFileRO`, `OpenFileWO`, and `CreateFile` until they
+//!   are closed by `TerminateSession` or `ResetSessions`, or expire after a period of inactivity,
+//!   see [`FtpServer::with_session_timeout`].
+//! * Streams file contents on `BurstReadFile` until the end of file, see
+//!   [`FtpServer::with_burst_interval`].
+//! * Detects retransmitted requests by their sequence numbers and answers them with the last
+//!   response instead of executing them again, as required by the
+//!   [specification](https://mavlink.io/en/services/ftp.html#timeouts-and-retries).
+
+use core::fmt::Write;
+use core::time::Duration;
+
+use crate::microservices::ftp::messages::FileTransferProtocol;
+use crate::protocol::{Frame, MavLinkId, MaybeVersioned};
+use crate::services::ftp::{
+    FtpDirEntry, FtpEntryKind, FtpFileSystem, FtpNak, FtpOpcode, FtpPayload, FTP_DATA_LEN,
+};
+use crate::services::{decode, is_addressed_to};
+
+/// Default number of concurrent sessions of [`FtpServer`].
+pub const DEFAULT_FTP_SESSIONS: usize = 4;
+/// Default interval between messages of a burst.
+pub const DEFAULT_FTP_BURST_INTERVAL: Duration = Duration::ZERO;
+/// Default time after which an idle session is closed.
+pub const DEFAULT_FTP_SESSION_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+struct Session<H> {
+    file: H,
+    size: u32,
+    writable: bool,
+    updated: Duration,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Burst {
+    peer: MavLinkId,
+    request_seq: u16,
+    seq: u16,
+    session: u8,
+    offset: u32,
+    chunk: usize,
+    due: Duration,
+}
+
+/// Response and sequence number of the last request.
+#[derive(Clone, Copy, Debug)]
+struct LastResponse {
+    peer: MavLinkId,
+    request_seq: u16,
+    response: FtpPayload,
+}
+
+/// Sans-I/O FTP server.
+///
+/// Pass incoming frames to [`FtpServer::handle`] and send messages returned by
+/// [`FtpServer::poll`] until it returns [`None`]. Call [`FtpServer::poll`] again once
+/// [`FtpServer::timeout`] expires.
+///
+/// Server supports up to `N` concurrent sessions, defaults to [`DEFAULT_FTP_SESSIONS`].
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(feature = "alloc")] {
+/// use std::time::Duration;
+///
+/// use mavio::services::ftp::{FtpOpcode, FtpPayload, FtpServer, MemoryFileSystem};
+/// use mavio::prelude::*;
+///
+/// let gcs = Endpoint::v2(MavLinkId::new(255, 190));
+/// let vehicle = MavLinkId::new(1, 1);
+/// let fs = MemoryFileSystem::new().with_file("/params.txt", "SYSID_THISMAV 1");
+/// let mut server: FtpServer<_> = FtpServer::new(vehicle, fs);
+///
+/// let request = FtpPayload::new(FtpOpcode::OpenFileRO).with_path("/params.txt").unwrap();
+/// let frame = gcs.next_frame(&request.to_message(vehicle)).unwrap();
+/// server.handle(&frame, Duration::ZERO);
+///
+/// let response = server.poll(Duration::ZERO).unwrap();
+/// let response = FtpPayload::decode(&response.payload);
+/// assert_eq!(response.opcode, FtpOpcode::Ack);
+/// assert_eq!(response.data_u32(), Some(15));
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FtpServer<F: FtpFileSystem, const N: usize = DEFAULT_FTP_SESSIONS> {
+    id: MavLinkId,
+    fs: F,
+    sessions: [Option<Session<F::File>>; N],
+    session_timeout: Duration,
+    burst_interval: Duration,
+    burst: Option<Burst>,
+    response: Option<(MavLinkId, FtpPayload)>,
+    last: Option<LastResponse>,
+}
+
+impl<F: FtpFileSystem, const N: usize> FtpServer<F, N> {
+    /// Creates a server for a component with a specified `id` that serves a file system.
+    pub fn new(id: MavLinkId, fs: F) -> Self {
+        Self {
+            id,
+            fs,
+            sessions: core::array::from_fn(|_| None),
+            session_timeout: DEFAULT_FTP_SESSION_TIMEOUT,
+            burst_interval: DEFAULT_FTP_BURST_INTERVAL,
+            burst: None,
+            response: None,
+            last: None,
+        }
+    }
+
+    /// Sets interval between messages of a burst.
+    ///
+    /// By default, the whole burst is returned by consecutive calls to [`FtpServer::poll`].
+    /// Non-zero interval limits the bandwidth consumed by bursts.
+    pub fn with_burst_interval(self, burst_interval: Duration) -> Self {
+        Self {
+            burst_interval,
+            ..self
+        }
+    }
+
+    /// Sets time after which a session without requests is closed.
+    ///
+    /// Sessions are left open by remote systems that were disconnected or have given up a
+    /// transfer. Idle sessions are closed by [`FtpServer::handle`] and [`FtpServer::poll`].
+    pub fn with_session_timeout(self, session_timeout: Duration) -> Self {
+        Self {
+            session_timeout,
+            ..self
+        }
+    }
+
+    /// `ID` of the component this server acts on behalf of.
+    #[inline(always)]
+    pub fn id(&self) -> MavLinkId {
+        self.id
+    }
+
+    /// Served file system.
+    #[inline(always)]
+    pub fn fs(&self) -> &F {
+        &self.fs
+    }
+
+    /// Mutable reference to the served file system.
+    ///
+    /// Changes made while files are open may affect sessions of remote systems.
+    #[inline(always)]
+    pub fn fs_mut(&mut self) -> &mut F {
+        &mut self.fs
+    }
+
+    /// Number of open sessions.
+    pub fn sessions(&self) -> usize {
+        self.sessions.iter().flatten().count()
+    }
+
+    /// Closes all sessions and stops burst.
+    pub fn reset(&mut self) {
+        self.burst = None;
+        for session in self.sessions.iter_mut() {
+            if let Some(session) = session.take() {
+                self.fs.close(session.file);
+            }
+        }
+    }
+
+    /// Handles incoming frame received at `now`.
+    ///
+    /// Call [`FtpServer::poll`] afterward to obtain a response.
+    pub fn handle<V: MaybeVersioned>(&mut self, frame: &Frame<V>, now: Duration) {
+        self.expire(now);
+        let Some(msg) = decode::<FileTransferProtocol, V>(frame) else {
+            return;
+        };
+        if !is_addressed_to(msg.target_system, msg.target_component, self.id) {
+            return;
+        }
+        let peer = MavLinkId::new(frame.system_id(), frame.component_id());
+        let request = FtpPayload::decode(&msg.payload);
+        if matches!(request.opcode, FtpOpcode::Ack | FtpOpcode::Nak) {
+            return;
+        }
+
+        if let Some(last) = self.last {
+            if last.peer == peer && last.request_seq == request.seq_number {
+                // Retransmitted requests are not executed twice
+                let in_burst = self
+                    .burst
+                    .is_some_and(|burst| burst.request_seq == request.seq_number);
+                if !in_burst {
+                    self.response = Some((peer, last.response));
+                }
+                return;
+            }
+        }
+
+        let response = match self.execute(&request, peer, now) {
+            Ok(None) => return,
+            Ok(Some(response)) => response,
+            Err(nak) => nak_response(nak),
+        };
+        let response = FtpPayload {
+            seq_number: request.seq_number.wrapping_add(1),
+            req_opcode: request.opcode,
+            offset: request.offset,
+            ..response
+        };
+        self.response = Some((peer, response));
+        self.last = Some(LastResponse {
+            peer,
+            request_seq: request.seq_number,
+            response,
+        });
+    }
+
+    /// Returns a message that should be sent at `now`.
+    ///
+    /// Call this method until it returns [`None`].
+    pub fn poll(&mut self, now: Duration) -> Option<FileTransferProtocol> {
+        self.expire(now);
+        if let Some((peer, response)) = self.response.take() {
+            return Some(response.to_message(peer));
+        }
+
+        let burst = self.burst.as_mut()?;
+        if now < burst.due {
+            return None;
+        }
+        burst.due = now + self.burst_interval;
+
+        let mut buf = [0u8; FTP_DATA_LEN];
+        let result = open_session(&mut self.sessions, burst.session, now).and_then(|session| {
+            self.fs
+                .read(&mut session.file, burst.offset, &mut buf[..burst.chunk])
+                .map(|len| (len, session.size))
+        });
+        let mut response = match result {
+            Ok((0, _)) => nak_response(FtpNak::Eof),
+            Ok((len, size)) => {
+                let mut response = FtpPayload::new(FtpOpcode::Ack).with_data(&buf[..len]);
+                response.offset = burst.offset;
+                burst.offset += len as u32;
+                response.burst_complete = len < burst.chunk || burst.offset >= size;
+                response
+            }
+            Err(nak) => nak_response(nak),
+        };
+        response.seq_number = burst.seq;
+        response.session = burst.session;
+        response.req_opcode = FtpOpcode::BurstReadFile;
+        if response.opcode == FtpOpcode::Nak {
+            response.burst_complete = true;
+        }
+        burst.seq = burst.seq.wrapping_add(1);
+
+        let (peer, request_seq) = (burst.peer, burst.request_seq);
+        if response.burst_complete {
+            self.burst = None;
+        }
+        self.last = Some(LastResponse {
+            peer,
+            request_seq,
+            response,
+        });
+        Some(response.to_message(peer))
+    }
+
+    /// Time left until [`FtpServer::poll`] should be called.
+    ///
+    /// Returns [`None`] if there is nothing to send.
+    pub fn timeout(&self, now: Duration) -> Option<Duration> {
+        if self.response.is_some() {
+            return Some(Duration::ZERO);
+        }
+        self.burst.map(|burst| burst.due.saturating_sub(now))
+    }
+
+    fn execute(
+        &mut self,
+        request: &FtpPayload,
+        peer: MavLinkId,
+        now: Duration,
+    ) -> Result<Option<FtpPayload>, FtpNak> {
+        let ack = FtpPayload {
+            session: request.session,
+            ..FtpPayload::new(FtpOpcode::Ack)
+        };
+
+        match request.opcode {
+            FtpOpcode::None => {}
+            FtpOpcode::TerminateSession => {
+                let session = self
+                    .sessions
+                    .get_mut(request.session as usize)
+                    .and_then(Option::take)
+                    .ok_or(FtpNak::InvalidSession)?;
+                self.fs.close(session.file);
+                if self
+                    .burst
+                    .is_some_and(|burst| burst.session == request.session)
+                {
+                    self.burst = None;
+                }
+            }
+            FtpOpcode::ResetSessions => self.reset(),
+            FtpOpcode::ListDirectory => {
+                let mut data = [0u8; FTP_DATA_LEN];
+                let len = self.list(request.path(), request.offset, &mut data)?;
+                return Ok(Some(ack.with_data(&data[..len])));
+            }
+            FtpOpcode::OpenFileRO => {
+                let slot = self.free_slot()?;
+                let (file, size) = self.fs.open_read(request.path())?;
+                self.sessions[slot] = Some(Session {
+                    file,
+                    size,
+                    writable: false,
+                    updated: now,
+                });
+                let response = FtpPayload {
+                    session: slot as u8,
+                    ..ack.with_data(&size.to_le_bytes())
+                };
+                return Ok(Some(response));
+            }
+            FtpOpcode::CreateFile | FtpOpcode::OpenFileWO => {
+                let slot = self.free_slot()?;
+                let truncate = request.opcode == FtpOpcode::CreateFile;
+                let file = self.fs.open_write(request.path(), truncate)?;
+                self.sessions[slot] = Some(Session {
+                    file,
+                    size: 0,
+                    writable: true,
+                    updated: now,
+                });
+                return Ok(Some(FtpPayload {
+                    session: slot as u8,
+                    ..ack
+                }));
+            }
+            FtpOpcode::ReadFile => {
+                let session = open_session(&mut self.sessions, request.session, now)?;
+                let mut buf = [0u8; FTP_DATA_LEN];
+                let len = chunk_len(request);
+                let len = self
+                    .fs
+                    .read(&mut session.file, request.offset, &mut buf[..len])?;
+                if len == 0 {
+                    return Err(FtpNak::Eof);
+                }
+                return Ok(Some(ack.with_data(&buf[..len])));
+            }
+            FtpOpcode::WriteFile => {
+                let session = open_session(&mut self.sessions, request.session, now)?;
+                if !session.writable {
+                    return Err(FtpNak::InvalidSession);
+                }
+                self.fs
+                    .write(&mut session.file, request.offset, request.data())?;
+            }
+            FtpOpcode::BurstReadFile => {
+                open_session(&mut self.sessions, request.session, now)?;
+                self.burst = Some(Burst {
+                    peer,
+                    request_seq: request.seq_number,
+                    seq: request.seq_number.wrapping_add(1),
+                    session: request.session,
+                    offset: request.offset,
+                    chunk: chunk_len(request),
+                    due: now,
+                });
+                return Ok(None);
+            }
+            FtpOpcode::RemoveFile => self.fs.remove_file(request.path())?,
+            FtpOpcode::CreateDirectory => self.fs.create_directory(request.path())?,
+            FtpOpcode::RemoveDirectory => self.fs.remove_directory(request.path())?,
+            FtpOpcode::TruncateFile => self.fs.truncate(request.path(), request.offset)?,
+            FtpOpcode::Rename => {
+                let mut paths = request.data().split(|&b| b == 0);
+                let mut next = || {
+                    paths
+                        .next()
+                        .and_then(|path| core::str::from_utf8(path).ok())
+                        .filter(|path| !path.is_empty())
+                        .ok_or(FtpNak::Fail)
+                };
+                let (from, to) = (next()?, next()?);
+                self.fs.rename(from, to)?;
+            }
+            FtpOpcode::CalcFileCRC32 => {
+                let crc = self.fs.crc32(request.path())?;
+                return Ok(Some(ack.with_data(&crc.to_le_bytes())));
+            }
+            FtpOpcode::Ack | FtpOpcode::Nak | FtpOpcode::Unknown(_) => {
+                return Err(FtpNak::UnknownCommand)
+            }
+        }
+        Ok(Some(ack))
+    }
+
+    /// Writes directory entries starting from `offset` into `data`, returns length of data.
+    fn list(&mut self, path: &str, offset: u32, data: &mut [u8]) -> Result<usize, FtpNak> {
+        let mut len = 0;
+        let mut count = 0;
+        let mut overflow = false;
+        self.fs.list(path, offset, &mut |entry: FtpDirEntry<'_>| {
+            let mut cursor = Cursor {
+                buf: &mut data[len..],
+                len: 0,
+            };
+            let written = match entry.kind {
+                FtpEntryKind::File => write!(cursor, "F{}\t{}\0", entry.name, entry.size),
+                FtpEntryKind::Directory => write!(cursor, "D{}\0", entry.name),
+            };
+            if written.is_err() {
+                overflow = true;
+                return false;
+            }
+            len += cursor.len;
+            count += 1;
+            true
+        })?;
+
+        match (count, overflow) {
+            // The first entry doesn't fit, listing can't proceed
+            (0, true) => Err(FtpNak::Fail),
+            (0, false) => Err(FtpNak::Eof),
+            _ => Ok(len),
+        }
+    }
+
+    /// Closes sessions that have been idle for longer than session timeout.
+    fn expire(&mut self, now: Duration) {
+        for (index, slot) in self.sessions.iter_mut().enumerate() {
+            let expired = slot
+                .as_ref()
+                .is_some_and(|session| now.saturating_sub(session.updated) >= self.session_timeout);
+            if !expired {
+                continue;
+            }
+            if let Some(session) = slot.take() {
+                self.fs.close(session.file);
+            }
+            if self
+                .burst
+                .is_some_and(|burst| burst.session as usize == index)
+            {
+                self.burst = None;
+            }
+        }
+    }
+
+    fn free_slot(&self) -> Result<usize, FtpNak> {
+        self.sessions
+            .iter()
+            .position(Option::is_none)
+            .ok_or(FtpNak::NoSessionsAvailable)
+    }
+}
+
+/// Returns an open session and marks it as active at `now`.
+fn open_session<H>(
+    sessions: &mut [Option<Session<H>>],
+    session: u8,
+    now: Duration,
+) -> Result<&mut Session<H>, FtpNak> {
+    let session = sessions
+        .get_mut(session as usize)
+        .and_then(Option::as_mut)
+        .ok_or(FtpNak::InvalidSession)?;
+    session.updated = now;
+    Ok(session)
+}
+
+/// Number of bytes requested by `ReadFile` or `BurstReadFile`.
+fn chunk_len(request: &FtpPayload) -> usize {
+    match request.size() as usize {
+        0 => FTP_DATA_LEN,
+        size => size.min(FTP_DATA_LEN),
+    }
+}
+
+fn nak_response(nak: FtpNak) -> FtpPayload {
+    let mut buf = [0u8; 2];
+    let len = nak.to_data(&mut buf);
+    FtpPayload::new(FtpOpcode::Nak).with_data(&buf[..len])
+}
+
+/// Writer of formatted directory entries that fails once buffer is full.
+struct Cursor<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl Write for Cursor<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.len + s.len();
+        if end > self.buf.len() {
+            return Err(core::fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::services::ftp::{ftp_crc32, FtpClient, FtpEntry, MemoryFileSystem};
+    use crate::services::ServiceError;
+    use crate::utils::test_utils::{message_frame, spawn_remote};
+
+    const GCS: MavLinkId = MavLinkId {
+        system: 255,
+        component: 190,
+    };
+    const VEHICLE: MavLinkId = MavLinkId {
+        system: 1,
+        component: 1,
+    };
+
+    fn exchange<F: FtpFileSystem, const N: usize>(
+        server: &mut FtpServer<F, N>,
+        request: FtpPayload,
+    ) -> FtpPayload {
+        server.handle(
+            &message_frame(GCS, &request.to_message(VEHICLE)),
+            Duration::ZERO,
+        );
+        let message = server.poll(Duration::ZERO).expect("expected response");
+        assert_eq!(
+            (message.target_system, message.target_component),
+            (GCS.system, GCS.component)
+        );
+        FtpPayload::decode(&message.payload)
+    }
+
+    fn request(seq_number: u16, opcode: FtpOpcode, path: &str) -> FtpPayload {
+        FtpPayload {
+            seq_number,
+            ..FtpPayload::new(opcode).with_path(path).unwrap()
+        }
+    }
+
+    #[test]
+    fn sessions_and_naks() {
+        let fs = MemoryFileSystem::new().with_file("/a.txt", "abc");
+        let mut server: FtpServer<_, 1> = FtpServer::new(VEHICLE, fs);
+
+        let open = exchange(&mut server, request(1, FtpOpcode::OpenFileRO, "/a.txt"));
+        assert_eq!(
+            (open.opcode, open.seq_number, open.req_opcode),
+            (FtpOpcode::Ack, 2, FtpOpcode::OpenFileRO)
+        );
+        assert_eq!(open.data_u32(), Some(3));
+
+        let busy = exchange(&mut server, request(2, FtpOpcode::OpenFileRO, "/a.txt"));
+        assert_eq!(busy.opcode, FtpOpcode::Nak);
+        assert_eq!(FtpNak::from_data(busy.data()), FtpNak::NoSessionsAvailable);
+
+        let mut read = request(3, FtpOpcode::ReadFile, "");
+        read.session = open.session;
+        read.offset = 1;
+        let data = exchange(&mut server, read);
+        assert_eq!((data.data(), data.offset), (&b"bc"[..], 1));
+        read.seq_number = 4;
+        read.offset = 3;
+        let eof = exchange(&mut server, read);
+        assert_eq!(FtpNak::from_data(eof.data()), FtpNak::Eof);
+
+        let mut write = request(5, FtpOpcode::WriteFile, "");
+        write.session = open.session;
+        let write = exchange(&mut server, write.with_data(b"x"));
+        assert_eq!(FtpNak::from_data(write.data()), FtpNak::InvalidSession);
+
+        let missing = exchange(&mut server, request(6, FtpOpcode::RemoveFile, "/b.txt"));
+        assert_eq!(FtpNak::from_data(missing.data()), FtpNak::FileNotFound);
+        let unknown = exchange(&mut server, request(7, FtpOpcode::Unknown(42), ""));
+        assert_eq!(FtpNak::from_data(unknown.data()), FtpNak::UnknownCommand);
+
+        let reset = exchange(&mut server, request(8, FtpOpcode::ResetSessions, ""));
+        assert_eq!(reset.opcode, FtpOpcode::Ack);
+        assert_eq!(server.sessions(), 0);
+    }
+
+    #[test]
+    fn idle_sessions_expire() {
+        let ms = Duration::from_millis;
+        let fs = MemoryFileSystem::new().with_file("/a.txt", "abc");
+        let mut server: FtpServer<_, 2> = FtpServer::new(VEHICLE, fs).with_session_timeout(ms(100));
+        let send = |server: &mut FtpServer<_, 2>, request: FtpPayload, now| {
+            server.handle(&message_frame(GCS, &request.to_message(VEHICLE)), now);
+            FtpPayload::decode(&server.poll(now).unwrap().payload)
+        };
+
+        let idle = send(
+            &mut server,
+            request(1, FtpOpcode::OpenFileRO, "/a.txt"),
+            ms(0),
+        );
+        let active = send(
+            &mut server,
+            request(2, FtpOpcode::OpenFileRO, "/a.txt"),
+            ms(50),
+        );
+        assert_eq!(server.sessions(), 2);
+
+        // Reads keep session alive
+        let mut read = request(3, FtpOpcode::ReadFile, "");
+        read.session = active.session;
+        assert_eq!(send(&mut server, read, ms(120)).opcode, FtpOpcode::Ack);
+        assert_eq!(server.sessions(), 1);
+
+        read.seq_number = 4;
+        read.session = idle.session;
+        let expired = send(&mut server, read, ms(130));
+        assert_eq!(FtpNak::from_data(expired.data()), FtpNak::InvalidSession);
+
+        assert!(server.poll(ms(220)).is_none());
+        assert_eq!(server.sessions(), 0);
+    }
+
+    #[test]
+    fn duplicate_requests() {
+        let mut server: FtpServer<_> = FtpServer::new(VEHICLE, MemoryFileSystem::new());
+
+        let created = exchange(
+            &mut server,
+            request(10, FtpOpcode::CreateDirectory, "/logs"),
+        );
+        assert_eq!(created.opcode, FtpOpcode::Ack);
+        // Retransmission is answered with the same response without creating directory again
+        let repeated = exchange(
+            &mut server,
+            request(10, FtpOpcode::CreateDirectory, "/logs"),
+        );
+        assert_eq!(repeated, created);
+        let again = exchange(
+            &mut server,
+            request(11, FtpOpcode::CreateDirectory, "/logs"),
+        );
+        assert_eq!(FtpNak::from_data(again.data()), FtpNak::FileExists);
+    }
+
+    #[test]
+    fn burst_and_listing() {
+        let file: Vec<u8> = (0..500u32).map(|i| i as u8).collect();
+        let fs = MemoryFileSystem::new()
+            .with_file("/logs/b.ulg", file.clone())
+            .with_file("/logs/a.ulg", "a");
+        let mut server: FtpServer<_> =
+            FtpServer::new(VEHICLE, fs).with_burst_interval(Duration::from_millis(10));
+
+        let list = exchange(&mut server, request(1, FtpOpcode::ListDirectory, "/logs"));
+        assert_eq!(list.data(), b"Fa.ulg\t1\0Fb.ulg\t500\0");
+        let mut next = request(2, FtpOpcode::ListDirectory, "/logs");
+        next.offset = 2;
+        let eof = exchange(&mut server, next);
+        assert_eq!(FtpNak::from_data(eof.data()), FtpNak::Eof);
+
+        // Entry that doesn't fit into a single response is not mistaken for the end of directory
+        let long = "x".repeat(FTP_DATA_LEN);
+        server.fs_mut().insert(&format!("/long/{long}"), "");
+        let long = exchange(&mut server, request(5, FtpOpcode::ListDirectory, "/long"));
+        assert_eq!(FtpNak::from_data(long.data()), FtpNak::Fail);
+
+        let open = exchange(
+            &mut server,
+            request(3, FtpOpcode::OpenFileRO, "/logs/b.ulg"),
+        );
+        let mut burst = request(4, FtpOpcode::BurstReadFile, "");
+        burst.session = open.session;
+        burst.offset = 100;
+        server.handle(
+            &message_frame(GCS, &burst.to_message(VEHICLE)),
+            Duration::ZERO,
+        );
+        assert_eq!(server.timeout(Duration::ZERO), Some(Duration::ZERO));
+
+        let mut received = Vec::new();
+        let mut now = Duration::ZERO;
+        while let Some(timeout) = server.timeout(now) {
+            now += timeout;
+            let Some(message) = server.poll(now) else {
+                continue;
+            };
+            let packet = FtpPayload::decode(&message.payload);
+            assert_eq!(packet.req_opcode, FtpOpcode::BurstReadFile);
+            assert_eq!(packet.offset as usize, 100 + received.len());
+            received.extend_from_slice(packet.data());
+            if packet.burst_complete {
+                assert_eq!(server.timeout(now), None);
+            } else {
+                // Retransmitted burst request is ignored while burst is in progress
+                server.handle(&message_frame(GCS, &burst.to_message(VEHICLE)), now);
+            }
+        }
+        assert_eq!(received, file[100..]);
+        assert_eq!(now, Duration::from_millis(10));
+    }
+
+    #[test]
+    fn blocking_client_with_server() {
+        let data: Vec<u8> = (0..2000usize).map(|i| (i * 7) as u8).collect();
+        let fs = MemoryFileSystem::new().with_file("/logs/flight.ulg", data.clone());
+
+        let (mut connection, vehicle) = spawn_remote(GCS, VEHICLE, |mut vehicle| {
+            // Bursts are sent at once, so server is polled only after incoming frames
+            let mut server: FtpServer<_> =
+                FtpServer::new(VEHICLE, fs).with_burst_interval(Duration::ZERO);
+            let start = std::time::Instant::now();
+            // Serve until client disconnects
+            while let Ok(frame) = vehicle.recv() {
+                server.handle(&frame, start.elapsed());
+                while let Some(message) = server.poll(start.elapsed()) {
+                    vehicle.send_message(&message).unwrap();
+                }
+            }
+            server
+        });
+
+        let mut client = FtpClient::new(GCS, VEHICLE).with_timeout(Duration::from_millis(100));
+        assert_eq!(
+            client.list(&mut connection, "/logs").unwrap(),
+            [FtpEntry {
+                kind: FtpEntryKind::File,
+                name: "flight.ulg".into(),
+                size: 2000
+            }]
+        );
+        assert_eq!(
+            client
+                .download(&mut connection, "/logs/flight.ulg")
+                .unwrap(),
+            data
+        );
+        assert_eq!(
+            client.crc32(&mut connection, "/logs/flight.ulg").unwrap(),
+            ftp_crc32(0, &data)
+        );
+        client.create_directory(&mut connection, "/plans").unwrap();
+        client
+            .upload(&mut connection, "/plans/a.plan", &data[..300])
+            .unwrap();
+        assert!(matches!(
+            client.remove_directory(&mut connection, "/plans"),
+            Err(crate::Error::Service(ServiceError::Ftp(FtpNak::Fail)))
+        ));
+        client
+            .remove_file(&mut connection, "/logs/flight.ulg")
+            .unwrap();
+
+        drop(connection);
+        let server = vehicle.join().unwrap();
+        assert_eq!(server.fs().get("/plans/a.plan"), Some(&data[..300]));
+        assert_eq!(server.fs().get("/logs/flight.ulg"), None);
+        assert_eq!(server.sessions(), 0);
+    }
+}
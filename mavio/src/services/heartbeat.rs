@@ -0,0 +1,626 @@
+//! # Heartbeat service
+//!
+//! Every MAVLink component has to broadcast
+//! [`HEARTBEAT`](https://mavlink.io/en/services/heartbeat.html) messages at a regular rate
+//! (usually `1 Hz`), otherwise other participants will consider it disconnected.
+//!
+//! [`HeartbeatEmitter`] is a sans-I/O state machine that decides when the next heartbeat is due.
+//! With `std` feature enabled, [`HeartbeatEmitter::spawn`] runs it in a background thread over a
+//! [`Sender`], and with `tokio-rt` feature enabled, [`HeartbeatEmitter::spawn_tokio`] runs it as a
+//! Tokio task over an [`AsyncSender`]. Both return handles that allow to update heartbeat state
+//! on the fly.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
+//! # #[cfg(feature = "std")]
+//! # fn main() -> mavio::error::Result<()> {
+//! use std::net::TcpStream;
+//!
+//! use mavio::microservices::heartbeat::enums::{MavAutopilot, MavState, MavType};
+//! use mavio::microservices::heartbeat::messages::Heartbeat;
+//! use mavio::services::heartbeat::HeartbeatEmitter;
+//! use mavio::prelude::*;
+//!
+//! let stream = TcpStream::connect("0.0.0.0:5600")?;
+//! let sender = Sender::versioned(StdIoWriter::new(stream), V2);
+//!
+//! let heartbeat = Heartbeat {
+//!     type_: MavType::OnboardController,
+//!     autopilot: MavAutopilot::Invalid,
+//!     system_status: MavState::Standby,
+//!     ..Default::default()
+//! };
+//! let handle =
+//!     HeartbeatEmitter::new(heartbeat).spawn(Endpoint::v2(MavLinkId::new(1, 191)), sender);
+//!
+//! // Update state on the fly
+//! handle.set_system_status(MavState::Active);
+//!
+//! // Stop emitting heartbeats and get the sender back
+//! let sender = handle.stop()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`AsyncSender`]: crate::AsyncSender
+
+use core::time::Duration;
+
+use crate::microservices::heartbeat::enums::{MavModeFlag, MavState};
+use crate::microservices::heartbeat::messages::Heartbeat;
+
+#[cfg(feature = "std")]
+use crate::io::Write;
+#[cfg(feature = "std")]
+use crate::prelude::*;
+
+/// Default interval between heartbeats.
+pub const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Minimum interval between heartbeats.
+///
+/// Shorter intervals passed to [`HeartbeatEmitter::with_interval`] are clamped to this value.
+pub const MIN_HEARTBEAT_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Value of `mavlink_version` field of `HEARTBEAT` message.
+///
+/// This field is not writable by users and always reflects the MAVLink protocol version.
+pub const HEARTBEAT_MAVLINK_VERSION: u8 = 3;
+
+/// Sans-I/O heartbeat emitter.
+///
+/// Emits configured [`Heartbeat`] message every [`HeartbeatEmitter::interval`]. The first
+/// heartbeat is emitted immediately.
+///
+/// Emitter keeps a steady cadence: if [`HeartbeatEmitter::poll`] is called slightly later than the
+/// deadline, the next deadline is not shifted. If emitter has missed the whole interval, the
+/// cadence is restarted from the current moment instead of sending a burst of heartbeats.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use mavio::microservices::heartbeat::enums::MavState;
+/// use mavio::microservices::heartbeat::messages::Heartbeat;
+/// use mavio::services::heartbeat::HeartbeatEmitter;
+///
+/// let mut emitter = HeartbeatEmitter::new(Heartbeat::default());
+///
+/// assert!(emitter.poll(Duration::from_millis(0)).is_some());
+/// assert!(emitter.poll(Duration::from_millis(500)).is_none());
+///
+/// emitter.set_system_status(MavState::Active);
+/// let heartbeat = emitter.poll(Duration::from_millis(1000)).unwrap();
+/// assert_eq!(heartbeat.system_status, MavState::Active);
+/// ```
+#[derive(Clone, Debug)]
+pub struct HeartbeatEmitter {
+    heartbeat: Heartbeat,
+    interval: Duration,
+    deadline: Option<Duration>,
+}
+
+impl HeartbeatEmitter {
+    /// Creates emitter of a given heartbeat with [`DEFAULT_HEARTBEAT_INTERVAL`].
+    ///
+    /// The `mavlink_version` field is set to [`HEARTBEAT_MAVLINK_VERSION`].
+    pub fn new(heartbeat: Heartbeat) -> Self {
+        Self {
+            heartbeat: Heartbeat {
+                mavlink_version: HEARTBEAT_MAVLINK_VERSION,
+                ..heartbeat
+            },
+            interval: DEFAULT_HEARTBEAT_INTERVAL,
+            deadline: None,
+        }
+    }
+
+    /// Sets interval between heartbeats.
+    ///
+    /// Interval is clamped to [`MIN_HEARTBEAT_INTERVAL`], otherwise emitter would produce an
+    /// endless burst of heartbeats.
+    pub fn with_interval(self, interval: Duration) -> Self {
+        Self {
+            interval: interval.max(MIN_HEARTBEAT_INTERVAL),
+            ..self
+        }
+    }
+
+    /// Interval between heartbeats.
+    #[inline(always)]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Heartbeat message which is emitted.
+    #[inline(always)]
+    pub fn heartbeat(&self) -> &Heartbeat {
+        &self.heartbeat
+    }
+
+    /// Replaces heartbeat message.
+    ///
+    /// The `mavlink_version` field is set to [`HEARTBEAT_MAVLINK_VERSION`].
+    pub fn set_heartbeat(&mut self, heartbeat: Heartbeat) {
+        self.heartbeat = Heartbeat {
+            mavlink_version: HEARTBEAT_MAVLINK_VERSION,
+            ..heartbeat
+        };
+    }
+
+    /// Sets system status.
+    pub fn set_system_status(&mut self, system_status: MavState) {
+        self.heartbeat.system_status = system_status;
+    }
+
+    /// Sets base mode flags.
+    pub fn set_base_mode(&mut self, base_mode: MavModeFlag) {
+        self.heartbeat.base_mode = base_mode;
+    }
+
+    /// Sets autopilot-specific custom mode.
+    pub fn set_custom_mode(&mut self, custom_mode: u32) {
+        self.heartbeat.custom_mode = custom_mode;
+    }
+
+    /// Moment when the next heartbeat is due.
+    ///
+    /// Returns [`None`] if heartbeat should be emitted immediately.
+    #[inline(always)]
+    pub fn deadline(&self) -> Option<Duration> {
+        self.deadline
+    }
+
+    /// Time left until the next heartbeat is due.
+    pub fn timeout(&self, now: Duration) -> Duration {
+        self.deadline
+            .map_or(Duration::ZERO, |deadline| deadline.saturating_sub(now))
+    }
+
+    /// Returns heartbeat if it is due and schedules the next one.
+    pub fn poll(&mut self, now: Duration) -> Option<&Heartbeat> {
+        let next = match self.deadline {
+            Some(deadline) if now < deadline => return None,
+            Some(deadline) if now < deadline + self.interval => deadline + self.interval,
+            _ => now + self.interval,
+        };
+        self.deadline = Some(next);
+        Some(&self.heartbeat)
+    }
+}
+
+impl Default for HeartbeatEmitter {
+    fn default() -> Self {
+        Self::new(Heartbeat::default())
+    }
+}
+
+#[cfg(feature = "std")]
+pub use driver::HeartbeatHandle;
+#[cfg(feature = "tokio-rt")]
+pub use tokio_driver::AsyncHeartbeatHandle;
+
+/// State shared between heartbeat drivers and their handles.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct Shared {
+    state: std::sync::Mutex<State>,
+    wakeup: std::sync::Condvar,
+    #[cfg(feature = "tokio-rt")]
+    notify: tokio::sync::Notify,
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct State {
+    emitter: HeartbeatEmitter,
+    stopped: bool,
+}
+
+#[cfg(feature = "std")]
+impl Shared {
+    fn new(emitter: HeartbeatEmitter) -> std::sync::Arc<Self> {
+        std::sync::Arc::new(Self {
+            state: std::sync::Mutex::new(State {
+                emitter,
+                stopped: false,
+            }),
+            wakeup: std::sync::Condvar::new(),
+            #[cfg(feature = "tokio-rt")]
+            notify: tokio::sync::Notify::new(),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut HeartbeatEmitter) -> T) -> T {
+        f(&mut self.lock().emitter)
+    }
+
+    fn stop(&self) {
+        self.lock().stopped = true;
+        self.wakeup.notify_all();
+        #[cfg(feature = "tokio-rt")]
+        self.notify.notify_one();
+    }
+
+    /// Returns heartbeat if it is due, or [`None`] if emitter is stopped.
+    fn next(&self, now: Duration) -> Option<(Option<Heartbeat>, Duration)> {
+        let mut state = self.lock();
+        if state.stopped {
+            return None;
+        }
+        let heartbeat = state.emitter.poll(now).cloned();
+        Some((heartbeat, state.emitter.timeout(now)))
+    }
+}
+
+/// Implements methods that update heartbeat state for driver handles.
+#[cfg(feature = "std")]
+macro_rules! impl_handle_updates {
+    () => {
+        /// Current heartbeat message.
+        pub fn heartbeat(&self) -> Heartbeat {
+            self.shared.update(|emitter| emitter.heartbeat().clone())
+        }
+
+        /// Updates emitter state.
+        ///
+        /// Changes take effect starting from the next heartbeat.
+        pub fn update<T>(&self, f: impl FnOnce(&mut HeartbeatEmitter) -> T) -> T {
+            self.shared.update(f)
+        }
+
+        /// Sets system status.
+        pub fn set_system_status(&self, system_status: MavState) {
+            self.update(|emitter| emitter.set_system_status(system_status))
+        }
+
+        /// Sets base mode flags.
+        pub fn set_base_mode(&self, base_mode: MavModeFlag) {
+            self.update(|emitter| emitter.set_base_mode(base_mode))
+        }
+
+        /// Sets autopilot-specific custom mode.
+        pub fn set_custom_mode(&self, custom_mode: u32) {
+            self.update(|emitter| emitter.set_custom_mode(custom_mode))
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+mod driver {
+    use std::sync::Arc;
+    use std::thread::JoinHandle;
+    use std::time::Instant;
+
+    use super::*;
+
+    /// <sup>`std`</sup>
+    /// Handle of a heartbeat emitter running in a background thread.
+    ///
+    /// Created by [`HeartbeatEmitter::spawn`]. Emitter stops once [`HeartbeatHandle::stop`] is
+    /// called, the handle is dropped, or if sending fails.
+    #[derive(Debug)]
+    pub struct HeartbeatHandle<E: Into<Error>, W: Write<E>, V: Versioned> {
+        pub(super) shared: Arc<Shared>,
+        thread: Option<JoinHandle<Result<Sender<E, W, V>>>>,
+    }
+
+    impl HeartbeatEmitter {
+        /// <sup>`std`</sup>
+        /// Emits heartbeats in a background thread.
+        ///
+        /// Frames are produced by `endpoint` and sent by `sender`. Use [`HeartbeatHandle`] to
+        /// update heartbeat state and to stop the emitter.
+        ///
+        /// Available only when `std` feature is enabled.
+        pub fn spawn<E, W, V>(
+            self,
+            endpoint: Endpoint<V>,
+            mut sender: Sender<E, W, V>,
+        ) -> HeartbeatHandle<E, W, V>
+        where
+            E: Into<Error> + Send + 'static,
+            W: Write<E> + Send + 'static,
+            V: Versioned + Send + 'static,
+        {
+            let shared = Shared::new(self);
+            let thread_shared = shared.clone();
+
+            let thread = std::thread::spawn(move || {
+                let shared = thread_shared;
+                let start = Instant::now();
+
+                while let Some((heartbeat, timeout)) = shared.next(start.elapsed()) {
+                    if let Some(heartbeat) = heartbeat {
+                        sender.send(&endpoint.next_frame(&heartbeat)?)?;
+                        continue;
+                    }
+
+                    let state = shared.lock();
+                    if !state.stopped {
+                        drop(shared.wakeup.wait_timeout(state, timeout).unwrap());
+                    }
+                }
+                Ok(sender)
+            });
+
+            HeartbeatHandle {
+                shared,
+                thread: Some(thread),
+            }
+        }
+    }
+
+    impl<E: Into<Error>, W: Write<E>, V: Versioned> HeartbeatHandle<E, W, V> {
+        impl_handle_updates!();
+
+        /// Returns `true` if emitter is still running.
+        ///
+        /// Emitter stops if sending a heartbeat fails.
+        pub fn is_running(&self) -> bool {
+            self.thread
+                .as_ref()
+                .is_some_and(|thread| !thread.is_finished())
+        }
+
+        /// Stops emitter and returns [`Sender`].
+        ///
+        /// Returns an error if emitter has been stopped because sending failed.
+        pub fn stop(mut self) -> Result<Sender<E, W, V>> {
+            self.shared.stop();
+            let thread = self.thread.take().expect("thread is joined only once");
+            match thread.join() {
+                Ok(result) => result,
+                Err(panic) => std::panic::resume_unwind(panic),
+            }
+        }
+    }
+
+    impl<E: Into<Error>, W: Write<E>, V: Versioned> Drop for HeartbeatHandle<E, W, V> {
+        fn drop(&mut self) {
+            self.shared.stop();
+        }
+    }
+}
+
+#[cfg(feature = "tokio-rt")]
+mod tokio_driver {
+    use std::sync::Arc;
+
+    use tokio::task::JoinHandle;
+    use tokio::time::Instant;
+
+    use crate::io::TokioWriter;
+
+    use super::*;
+
+    /// [`AsyncSender`] used by [`HeartbeatEmitter::spawn_tokio`].
+    type TokioSender<W, V> = AsyncSender<std::io::Error, TokioWriter<W>, V>;
+
+    /// <sup>`tokio-rt`</sup>
+    /// Handle of a heartbeat emitter running as a Tokio task.
+    ///
+    /// Created by [`HeartbeatEmitter::spawn_tokio`]. Emitter stops once
+    /// [`AsyncHeartbeatHandle::stop`] is called, the handle is dropped, or if sending fails.
+    pub struct AsyncHeartbeatHandle<W: tokio::io::AsyncWrite + Unpin, V: Versioned> {
+        pub(super) shared: Arc<Shared>,
+        task: Option<JoinHandle<Result<TokioSender<W, V>>>>,
+    }
+
+    impl HeartbeatEmitter {
+        /// <sup>`tokio-rt`</sup>
+        /// Emits heartbeats in a Tokio task.
+        ///
+        /// Asynchronous counterpart of [`HeartbeatEmitter::spawn`]. Must be called within Tokio
+        /// runtime.
+        ///
+        /// Available only when `tokio-rt` feature is enabled.
+        pub fn spawn_tokio<W, V>(
+            self,
+            endpoint: Endpoint<V>,
+            mut sender: TokioSender<W, V>,
+        ) -> AsyncHeartbeatHandle<W, V>
+        where
+            W: tokio::io::AsyncWrite + Unpin + Send + 'static,
+            V: Versioned + Send + 'static,
+        {
+            let shared = Shared::new(self);
+            let task_shared = shared.clone();
+
+            let task = tokio::spawn(async move {
+                let shared = task_shared;
+                let start = Instant::now();
+
+                while let Some((heartbeat, timeout)) = shared.next(start.elapsed()) {
+                    if let Some(heartbeat) = heartbeat {
+                        let frame = endpoint.next_frame(&heartbeat)?;
+                        sender.send(&frame).await?;
+                        continue;
+                    }
+                    let _ = tokio::time::timeout(timeout, shared.notify.notified()).await;
+                }
+                Ok(sender)
+            });
+
+            AsyncHeartbeatHandle {
+                shared,
+                task: Some(task),
+            }
+        }
+    }
+
+    impl<W: tokio::io::AsyncWrite + Unpin, V: Versioned> AsyncHeartbeatHandle<W, V> {
+        impl_handle_updates!();
+
+        /// Returns `true` if emitter is still running.
+        ///
+        /// Emitter stops if sending a heartbeat fails.
+        pub fn is_running(&self) -> bool {
+            self.task.as_ref().is_some_and(|task| !task.is_finished())
+        }
+
+        /// Stops emitter and returns [`AsyncSender`].
+        ///
+        /// Returns an error if emitter has been stopped because sending failed.
+        pub async fn stop(mut self) -> Result<TokioSender<W, V>> {
+            self.shared.stop();
+            let task = self.task.take().expect("task is awaited only once");
+            match task.await {
+                Ok(result) => result,
+                Err(err) => std::panic::resume_unwind(err.into_panic()),
+            }
+        }
+    }
+
+    impl<W: tokio::io::AsyncWrite + Unpin, V: Versioned> Drop for AsyncHeartbeatHandle<W, V> {
+        fn drop(&mut self) {
+            self.shared.stop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emitter_keeps_cadence() {
+        let ms = Duration::from_millis;
+        let mut emitter = HeartbeatEmitter::default().with_interval(ms(100));
+        assert_eq!(
+            emitter.heartbeat().mavlink_version,
+            HEARTBEAT_MAVLINK_VERSION
+        );
+
+        assert!(emitter.poll(ms(5)).is_some());
+        assert_eq!(emitter.deadline(), Some(ms(105)));
+        assert_eq!(
+            emitter.clone().with_interval(Duration::ZERO).interval(),
+            MIN_HEARTBEAT_INTERVAL
+        );
+        assert!(emitter.poll(ms(50)).is_none());
+        assert_eq!(emitter.timeout(ms(50)), ms(55));
+
+        // Late poll does not shift cadence
+        assert!(emitter.poll(ms(120)).is_some());
+        assert_eq!(emitter.deadline(), Some(ms(205)));
+
+        // Missed interval restarts cadence without bursts
+        assert!(emitter.poll(ms(500)).is_some());
+        assert!(emitter.poll(ms(500)).is_none());
+        assert_eq!(emitter.deadline(), Some(ms(600)));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn thread_emitter() {
+        use crate::io::link::pipe;
+
+        let (reader, writer) = pipe();
+        let mut receiver = Receiver::versioned(reader, V2);
+        let handle = HeartbeatEmitter::default()
+            .with_interval(Duration::from_millis(10))
+            .spawn(
+                Endpoint::v2(MavLinkId::new(1, 1)),
+                Sender::versioned(writer, V2),
+            );
+
+        let frame = receiver.recv().unwrap();
+        let heartbeat = Heartbeat::try_from(frame.payload()).unwrap();
+        assert_eq!(heartbeat.system_status, MavState::Uninit);
+
+        handle.set_system_status(MavState::Active);
+        handle.set_custom_mode(42);
+        loop {
+            let frame = receiver.recv().unwrap();
+            let heartbeat = Heartbeat::try_from(frame.payload()).unwrap();
+            if heartbeat.system_status == MavState::Active {
+                assert_eq!(heartbeat.custom_mode, 42);
+                break;
+            }
+        }
+
+        assert!(handle.is_running());
+        handle.stop().unwrap();
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[test]
+    fn tokio_emitter() {
+        use crate::io::{TokioReader, TokioWriter};
+        use crate::utils::test_utils::block_on;
+
+        block_on(async {
+            let (client, server) = tokio::io::duplex(1024);
+            let mut receiver = AsyncReceiver::versioned(TokioReader::new(server), V2);
+            let handle = HeartbeatEmitter::default()
+                .with_interval(Duration::from_millis(10))
+                .spawn_tokio(
+                    Endpoint::v2(MavLinkId::new(1, 1)),
+                    AsyncSender::versioned(TokioWriter::new(client), V2),
+                );
+
+            handle.set_system_status(MavState::Standby);
+            for _ in 0..3 {
+                let frame = receiver.recv().await.unwrap();
+                let heartbeat = Heartbeat::try_from(frame.payload()).unwrap();
+                assert_eq!(frame.system_id(), 1);
+                assert_eq!(heartbeat.mavlink_version, HEARTBEAT_MAVLINK_VERSION);
+            }
+            assert_eq!(handle.heartbeat().system_status, MavState::Standby);
+
+            handle.stop().await.unwrap();
+        });
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn dropped_handle_stops_emitter() {
+        use crate::io::link::pipe;
+
+        let (reader, writer) = pipe();
+        let mut receiver = Receiver::versioned(reader, V2);
+        let handle = HeartbeatEmitter::default()
+            .with_interval(Duration::from_millis(10))
+            .spawn(
+                Endpoint::v2(MavLinkId::new(1, 1)),
+                Sender::versioned(writer, V2),
+            );
+        receiver.recv().unwrap();
+
+        // Stopped emitter drops the sender, so the pipe reaches end of file
+        drop(handle);
+        while receiver.recv().is_ok() {}
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[test]
+    fn dropped_async_handle_stops_emitter() {
+        use crate::io::{TokioReader, TokioWriter};
+        use crate::utils::test_utils::block_on;
+
+        block_on(async {
+            let (client, server) = tokio::io::duplex(1024);
+            let mut receiver = AsyncReceiver::versioned(TokioReader::new(server), V2);
+            let handle = HeartbeatEmitter::default()
+                .with_interval(Duration::from_millis(10))
+                .spawn_tokio(
+                    Endpoint::v2(MavLinkId::new(1, 1)),
+                    AsyncSender::versioned(TokioWriter::new(client), V2),
+                );
+            receiver.recv().await.unwrap();
+
+            // Stopped emitter drops the sender, so the stream reaches end of file
+            drop(handle);
+            while receiver.recv().await.is_ok() {}
+        });
+    }
+}
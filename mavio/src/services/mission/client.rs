@@ -0,0 +1,1082 @@
+//! # Mission protocol client
+//!
+//! Implements GCS side of MAVLink [mission protocol](https://mavlink.io/en/services/mission.html).
+//!
+//! [`MissionClient`] is a sans-I/O state machine that performs one of the following operations
+//! with a remote system:
+//!
+//! * [Upload](https://mavlink.io/en/services/mission.html#uploading_mission): sends
+//!   `MISSION_COUNT` and then `MISSION_ITEM_INT` for each `MISSION_REQUEST_INT` until
+//!   `MISSION_ACK` is received.
+//! * [Download](https://mavlink.io/en/services/mission.html#download_mission): sends
+//!   `MISSION_REQUEST_LIST`, receives `MISSION_COUNT`, requests each item by
+//!   `MISSION_REQUEST_INT`, and sends `MISSION_ACK` once all items are received.
+//! * [Clear](https://mavlink.io/en/services/mission.html#clear_mission): sends
+//!   `MISSION_CLEAR_ALL` and waits for `MISSION_ACK`.
+//!
+//! If expected response is not received in time, the last message is retransmitted. Operation
+//! fails once all retries are exhausted.
+//!
+//! Blocking drivers [`MissionClient::upload`], [`MissionClient::download`], and
+//! [`MissionClient::clear`] run client over [`Connection`]. With `tokio-rt` feature enabled, the
+//! corresponding `*_async` methods do the same over [`AsyncConnection`].
+//!
+//! [`Connection`]: crate::io::Connection
+//! [`AsyncConnection`]: crate::io::AsyncConnection
+
+use core::time::Duration;
+
+use crate::microservices::mission::enums::{MavMissionResult, MavMissionType};
+use crate::microservices::mission::messages::{
+    MissionAck, MissionClearAll, MissionCount, MissionItemInt, MissionRequestInt,
+    MissionRequestList,
+};
+use crate::protocol::{Frame, MavLinkId, MaybeVersioned};
+use crate::services::mission::MissionMessage;
+use crate::services::{decode, is_addressed_to, ServiceError};
+
+#[cfg(feature = "tokio-rt")]
+use crate::io::{AsyncConnection, AsyncRead, AsyncWrite};
+#[cfg(feature = "std")]
+use crate::io::{Connection, Read, Write};
+#[cfg(feature = "std")]
+use crate::protocol::{Endpoint, Versioned};
+#[cfg(feature = "tokio-rt")]
+use crate::services::run_async;
+#[cfg(feature = "std")]
+use crate::services::{run, Driven};
+#[cfg(feature = "std")]
+use crate::Error;
+
+/// Default time to wait for a response before retransmitting a message.
+///
+/// This is a value recommended by MAVLink mission protocol.
+pub const DEFAULT_MISSION_TIMEOUT: Duration = Duration::from_millis(1500);
+/// Default number of retransmissions of each message.
+pub const DEFAULT_MISSION_RETRIES: u8 = 5;
+
+/// Result of a successful mission operation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MissionTransfer {
+    /// Mission type.
+    pub mission_type: MavMissionType,
+    /// Number of transferred items (always `0` for clear operation).
+    pub count: u16,
+    /// Opaque identifier of the mission reported by remote system (`0` if not supported).
+    pub opaque_id: u32,
+}
+
+/// Event produced by [`MissionClient::handle`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum MissionEvent {
+    /// Mission item was downloaded.
+    Item(MissionItemInt),
+    /// Operation completed successfully.
+    Completed(MissionTransfer),
+    /// Operation was rejected by remote system.
+    Failed(MavMissionResult),
+}
+
+/// Iterator over events produced by [`MissionClient::handle`].
+#[derive(Clone, Debug, Default)]
+pub struct MissionEvents {
+    first: Option<MissionEvent>,
+    second: Option<MissionEvent>,
+}
+
+impl MissionEvents {
+    fn new(first: Option<MissionEvent>, second: Option<MissionEvent>) -> Self {
+        Self { first, second }
+    }
+}
+
+impl Iterator for MissionEvents {
+    type Item = MissionEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.first.take().or_else(|| self.second.take())
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Stage<'a> {
+    Upload {
+        items: &'a [MissionItemInt],
+        requested: u16,
+    },
+    Download {
+        count: Option<u16>,
+        next: u16,
+        opaque_id: u32,
+    },
+    Clear,
+}
+
+#[derive(Clone, Debug)]
+struct Operation<'a> {
+    mission_type: MavMissionType,
+    stage: Stage<'a>,
+    last: MissionMessage,
+    attempts: u8,
+    deadline: Option<Duration>,
+}
+
+impl Operation<'_> {
+    /// Replaces the last message with a new one that should be sent immediately.
+    fn respond(&mut self, message: MissionMessage) {
+        self.last = message;
+        self.attempts = 0;
+        self.deadline = None;
+    }
+}
+
+/// Sans-I/O mission protocol client.
+///
+/// Client performs one operation at a time with a single remote system. Start operation by
+/// [`MissionClient::start_upload`], [`MissionClient::start_download`], or
+/// [`MissionClient::start_clear`], then pass incoming frames to [`MissionClient::handle`] and call
+/// [`MissionClient::poll`] to obtain messages that should be sent. Call [`MissionClient::poll`]
+/// after each [`MissionClient::handle`] and whenever [`MissionClient::timeout`] expires.
+///
+/// Items to upload are borrowed for the lifetime `'a`. Downloaded items are not stored and are
+/// reported as [`MissionEvent::Item`], therefore client is suitable for `no_std` targets.
+///
+/// See [module docs](self) for protocol details.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use mavio::microservices::mission::enums::MavMissionType;
+/// use mavio::microservices::mission::messages::MissionCount;
+/// use mavio::services::mission::{MissionClient, MissionEvent, MissionMessage};
+/// use mavio::prelude::*;
+///
+/// let vehicle = Endpoint::v2(MavLinkId::new(1, 1));
+/// let mut client = MissionClient::new(MavLinkId::new(255, 190), vehicle.id());
+///
+/// client.start_download(MavMissionType::Fence).unwrap();
+/// let message = client.poll(Duration::ZERO).unwrap().unwrap();
+/// assert!(matches!(message, MissionMessage::RequestList(_)));
+///
+/// let count = vehicle.next_frame(&MissionCount {
+///     count: 0,
+///     mission_type: MavMissionType::Fence,
+///     ..Default::default()
+/// }).unwrap();
+/// let mut events = client.handle(&count, Duration::from_millis(100));
+/// assert!(matches!(events.next(), Some(MissionEvent::Completed(_))));
+///
+/// // Download is acknowledged
+/// let message = client.poll(Duration::from_millis(100)).unwrap().unwrap();
+/// assert!(matches!(message, MissionMessage::Ack(_)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct MissionClient<'a> {
+    id: MavLinkId,
+    target: MavLinkId,
+    timeout: Duration,
+    retries: u8,
+    operation: Option<Operation<'a>>,
+    outgoing: Option<MissionMessage>,
+}
+
+impl<'a> MissionClient<'a> {
+    /// Creates a client for a component with a specified `id` that communicates with `target`.
+    ///
+    /// Use `0` as a target component `ID` to accept responses from any component of the target
+    /// system.
+    pub fn new(id: MavLinkId, target: MavLinkId) -> Self {
+        Self {
+            id,
+            target,
+            timeout: DEFAULT_MISSION_TIMEOUT,
+            retries: DEFAULT_MISSION_RETRIES,
+            operation: None,
+            outgoing: None,
+        }
+    }
+
+    /// Sets time to wait for a response before retransmitting a message.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Sets the number of retransmissions of each message after which operation fails.
+    pub fn with_retries(self, retries: u8) -> Self {
+        Self { retries, ..self }
+    }
+
+    /// `ID` of the component this client acts on behalf of.
+    #[inline(always)]
+    pub fn id(&self) -> MavLinkId {
+        self.id
+    }
+
+    /// `ID` of the remote component.
+    #[inline(always)]
+    pub fn target(&self) -> MavLinkId {
+        self.target
+    }
+
+    /// Returns `true` if operation is in progress.
+    #[inline(always)]
+    pub fn is_busy(&self) -> bool {
+        self.operation.is_some()
+    }
+
+    /// Progress of the current upload or download as a number of transferred and total items.
+    ///
+    /// Returns [`None`] if there is no such operation or if total number of items is not yet known.
+    pub fn progress(&self) -> Option<(u16, u16)> {
+        match self.operation.as_ref()?.stage {
+            Stage::Upload { items, requested } => Some((requested, items.len() as u16)),
+            Stage::Download {
+                count: Some(count),
+                next,
+                ..
+            } => Some((next, count)),
+            _ => None,
+        }
+    }
+
+    /// Starts uploading `items` of a given `mission_type`.
+    ///
+    /// Fields `target_system`, `target_component`, `seq`, and `mission_type` of items are set by
+    /// the client.
+    ///
+    /// Returns [`ServiceError::Busy`] if another operation is in progress.
+    pub fn start_upload(
+        &mut self,
+        items: &'a [MissionItemInt],
+        mission_type: MavMissionType,
+    ) -> core::result::Result<(), ServiceError> {
+        let count = u16::try_from(items.len())
+            .map_err(|_| ServiceError::Mission(MavMissionResult::MavMissionNoSpace))?;
+        let message = MissionMessage::Count(MissionCount {
+            target_system: self.target.system,
+            target_component: self.target.component,
+            count,
+            mission_type,
+            opaque_id: 0,
+        });
+        self.start(
+            mission_type,
+            Stage::Upload {
+                items,
+                requested: 0,
+            },
+            message,
+        )
+    }
+
+    /// Starts downloading items of a given `mission_type`.
+    ///
+    /// Returns [`ServiceError::Busy`] if another operation is in progress.
+    pub fn start_download(
+        &mut self,
+        mission_type: MavMissionType,
+    ) -> core::result::Result<(), ServiceError> {
+        let message = MissionMessage::RequestList(MissionRequestList {
+            target_system: self.target.system,
+            target_component: self.target.component,
+            mission_type,
+        });
+        self.start(
+            mission_type,
+            Stage::Download {
+                count: None,
+                next: 0,
+                opaque_id: 0,
+            },
+            message,
+        )
+    }
+
+    /// Starts clearing items of a given `mission_type`.
+    ///
+    /// Use [`MavMissionType::All`] to clear missions, geofences, and rally points at once.
+    ///
+    /// Returns [`ServiceError::Busy`] if another operation is in progress.
+    pub fn start_clear(
+        &mut self,
+        mission_type: MavMissionType,
+    ) -> core::result::Result<(), ServiceError> {
+        let message = MissionMessage::ClearAll(MissionClearAll {
+            target_system: self.target.system,
+            target_component: self.target.component,
+            mission_type,
+        });
+        self.start(mission_type, Stage::Clear, message)
+    }
+
+    /// Aborts current operation.
+    ///
+    /// The `MISSION_ACK` with `MAV_MISSION_OPERATION_CANCELLED` will be returned by the next
+    /// [`MissionClient::poll`] to notify remote system.
+    pub fn cancel(&mut self) {
+        if let Some(operation) = self.operation.take() {
+            self.outgoing = Some(self.ack(
+                operation.mission_type,
+                MavMissionResult::MavMissionOperationCancelled,
+            ));
+        }
+    }
+
+    /// Handles incoming frame received at `now`.
+    ///
+    /// Returns an iterator over produced events. Call [`MissionClient::poll`] afterward to obtain
+    /// a response.
+    pub fn handle<V: MaybeVersioned>(&mut self, frame: &Frame<V>, now: Duration) -> MissionEvents {
+        if frame.system_id() != self.target.system
+            || (self.target.component != 0 && frame.component_id() != self.target.component)
+        {
+            return MissionEvents::default();
+        }
+        let Some(operation) = self.operation.as_ref() else {
+            return MissionEvents::default();
+        };
+        let mission_type = operation.mission_type;
+
+        if let Some(ack) = decode::<MissionAck, V>(frame) {
+            if !is_addressed_to(ack.target_system, ack.target_component, self.id)
+                || ack.mission_type != mission_type
+            {
+                return MissionEvents::default();
+            }
+            return MissionEvents::new(self.handle_ack(ack), None);
+        }
+
+        match operation.stage {
+            Stage::Upload { .. } => MissionEvents::new(self.handle_upload(frame), None),
+            Stage::Download { .. } => self.handle_download(frame, now),
+            Stage::Clear => MissionEvents::default(),
+        }
+    }
+
+    /// Returns a message that should be sent at `now`.
+    ///
+    /// This is either a response to the recently handled frame or a retransmission of the last
+    /// message if response has not been received in time. Call this method until it returns
+    /// [`None`].
+    ///
+    /// Returns [`ServiceError::TimedOut`] and aborts operation, if all retries are exhausted.
+    pub fn poll(
+        &mut self,
+        now: Duration,
+    ) -> core::result::Result<Option<MissionMessage>, ServiceError> {
+        if let Some(message) = self.outgoing.take() {
+            return Ok(Some(message));
+        }
+
+        let Some(operation) = &self.operation else {
+            return Ok(None);
+        };
+        match operation.deadline {
+            Some(deadline) if now < deadline => return Ok(None),
+            Some(_) if operation.attempts >= self.retries => {
+                self.operation = None;
+                return Err(ServiceError::TimedOut);
+            }
+            _ => {}
+        }
+
+        let Some(operation) = self.operation.as_mut() else {
+            return Ok(None);
+        };
+        if operation.deadline.is_some() {
+            operation.attempts += 1;
+        }
+        operation.deadline = Some(now + self.timeout);
+        Ok(Some(operation.last.clone()))
+    }
+
+    /// Time left until [`MissionClient::poll`] should be called.
+    ///
+    /// Returns [`None`] if there is nothing to send and no operation in progress.
+    pub fn timeout(&self, now: Duration) -> Option<Duration> {
+        if self.outgoing.is_some() {
+            return Some(Duration::ZERO);
+        }
+        let operation = self.operation.as_ref()?;
+        Some(
+            operation
+                .deadline
+                .map_or(Duration::ZERO, |deadline| deadline.saturating_sub(now)),
+        )
+    }
+
+    fn start(
+        &mut self,
+        mission_type: MavMissionType,
+        stage: Stage<'a>,
+        message: MissionMessage,
+    ) -> core::result::Result<(), ServiceError> {
+        if self.operation.is_some() {
+            return Err(ServiceError::Busy);
+        }
+        self.outgoing = None;
+        self.operation = Some(Operation {
+            mission_type,
+            stage,
+            last: message,
+            attempts: 0,
+            deadline: None,
+        });
+        Ok(())
+    }
+
+    fn handle_ack(&mut self, ack: MissionAck) -> Option<MissionEvent> {
+        let operation = self.operation.as_ref()?;
+        let count = match operation.stage {
+            Stage::Upload { items, .. } => items.len() as u16,
+            // Remote system may reject download, successful downloads are acknowledged by client
+            Stage::Download { .. } if ack.type_ == MavMissionResult::MavMissionAccepted => {
+                return None
+            }
+            Stage::Download { .. } | Stage::Clear => 0,
+        };
+        let mission_type = operation.mission_type;
+        self.operation = None;
+
+        if ack.type_ != MavMissionResult::MavMissionAccepted {
+            return Some(MissionEvent::Failed(ack.type_));
+        }
+        Some(MissionEvent::Completed(MissionTransfer {
+            mission_type,
+            count,
+            opaque_id: ack.opaque_id,
+        }))
+    }
+
+    fn handle_upload<V: MaybeVersioned>(&mut self, frame: &Frame<V>) -> Option<MissionEvent> {
+        let request = decode::<MissionRequestInt, V>(frame)?;
+        let (id, target) = (self.id, self.target);
+        let operation = self.operation.as_mut()?;
+        let Stage::Upload { items, requested } = &mut operation.stage else {
+            return None;
+        };
+        if !is_addressed_to(request.target_system, request.target_component, id)
+            || request.mission_type != operation.mission_type
+        {
+            return None;
+        }
+
+        let item = items.get(request.seq as usize)?;
+        *requested = (*requested).max(request.seq + 1);
+        let item = MissionItemInt {
+            target_system: target.system,
+            target_component: target.component,
+            seq: request.seq,
+            mission_type: operation.mission_type,
+            ..item.clone()
+        };
+        operation.respond(MissionMessage::ItemInt(item));
+        None
+    }
+
+    fn handle_download<V: MaybeVersioned>(
+        &mut self,
+        frame: &Frame<V>,
+        now: Duration,
+    ) -> MissionEvents {
+        let (id, target, timeout) = (self.id, self.target, self.timeout);
+        let Some(operation) = self.operation.as_mut() else {
+            return MissionEvents::default();
+        };
+        let mission_type = operation.mission_type;
+        let Stage::Download {
+            count,
+            next,
+            opaque_id,
+        } = &mut operation.stage
+        else {
+            return MissionEvents::default();
+        };
+
+        let request = |seq: u16| {
+            MissionMessage::RequestInt(MissionRequestInt {
+                target_system: target.system,
+                target_component: target.component,
+                seq,
+                mission_type,
+            })
+        };
+
+        let item = match *count {
+            None => {
+                let Some(msg) = decode::<MissionCount, V>(frame) else {
+                    return MissionEvents::default();
+                };
+                if !is_addressed_to(msg.target_system, msg.target_component, id)
+                    || msg.mission_type != mission_type
+                {
+                    return MissionEvents::default();
+                }
+                *count = Some(msg.count);
+                *opaque_id = msg.opaque_id;
+                None
+            }
+            Some(_) => {
+                let Some(item) = decode::<MissionItemInt, V>(frame) else {
+                    return MissionEvents::default();
+                };
+                if !is_addressed_to(item.target_system, item.target_component, id)
+                    || item.mission_type != mission_type
+                {
+                    return MissionEvents::default();
+                }
+                if item.seq != *next {
+                    // Remote system answered a previous request, the latest one is likely in
+                    // flight, so postpone retransmission
+                    if item.seq < *next && operation.deadline.is_some() {
+                        operation.deadline = Some(now + timeout);
+                    }
+                    return MissionEvents::default();
+                }
+                *next += 1;
+                Some(MissionEvent::Item(item))
+            }
+        };
+
+        if Some(*next) != *count {
+            let seq = *next;
+            operation.respond(request(seq));
+            return MissionEvents::new(item, None);
+        }
+
+        let completed = MissionEvent::Completed(MissionTransfer {
+            mission_type,
+            count: *next,
+            opaque_id: *opaque_id,
+        });
+        self.operation = None;
+        self.outgoing = Some(self.ack(mission_type, MavMissionResult::MavMissionAccepted));
+        MissionEvents::new(item, Some(completed))
+    }
+
+    fn ack(&self, mission_type: MavMissionType, result: MavMissionResult) -> MissionMessage {
+        MissionMessage::Ack(MissionAck {
+            target_system: self.target.system,
+            target_component: self.target.component,
+            type_: result,
+            mission_type,
+            opaque_id: 0,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> MissionClient<'a> {
+    /// <sup>`std`</sup>
+    /// Uploads `items` of a given `mission_type` over a blocking [`Connection`].
+    ///
+    /// Frames that are not related to the mission protocol are discarded.
+    ///
+    /// Returns [`ServiceError::Mission`] wrapped into [`Error::Service`] if upload was rejected,
+    /// or [`ServiceError::TimedOut`] if remote system has stopped responding.
+    ///
+    /// Available only when `std` feature is enabled.
+    pub fn upload<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+        items: &'a [MissionItemInt],
+        mission_type: MavMissionType,
+    ) -> crate::Result<MissionTransfer>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        self.start_upload(items, mission_type)?;
+        self.transfer(connection, |_| {})
+    }
+
+    /// <sup>`std`</sup>
+    /// Downloads items of a given `mission_type` over a blocking [`Connection`].
+    ///
+    /// See [`MissionClient::upload`] for details.
+    ///
+    /// Available only when `std` feature is enabled.
+    pub fn download<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+        mission_type: MavMissionType,
+    ) -> crate::Result<(Vec<MissionItemInt>, MissionTransfer)>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        self.start_download(mission_type)?;
+        let mut items = Vec::new();
+        let transfer = self.transfer(connection, |item| items.push(item))?;
+        Ok((items, transfer))
+    }
+
+    /// <sup>`std`</sup>
+    /// Clears items of a given `mission_type` over a blocking [`Connection`].
+    ///
+    /// See [`MissionClient::upload`] for details.
+    ///
+    /// Available only when `std` feature is enabled.
+    pub fn clear<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+        mission_type: MavMissionType,
+    ) -> crate::Result<MissionTransfer>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        self.start_clear(mission_type)?;
+        self.transfer(connection, |_| {})
+    }
+
+    fn transfer<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+        mut on_item: impl FnMut(MissionItemInt),
+    ) -> crate::Result<MissionTransfer>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        run(self, connection, |client, frame, now| {
+            completed(client.handle(frame, now), &mut on_item)
+        })
+    }
+}
+
+#[cfg(feature = "tokio-rt")]
+impl<'a> MissionClient<'a> {
+    /// <sup>`tokio-rt`</sup>
+    /// Uploads `items` of a given `mission_type` over [`AsyncConnection`].
+    ///
+    /// Asynchronous counterpart of [`MissionClient::upload`]. Must be called within Tokio runtime.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    pub async fn upload_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+        items: &'a [MissionItemInt],
+        mission_type: MavMissionType,
+    ) -> crate::Result<MissionTransfer>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        self.start_upload(items, mission_type)?;
+        self.transfer_async(connection, |_| {}).await
+    }
+
+    /// <sup>`tokio-rt`</sup>
+    /// Downloads items of a given `mission_type` over [`AsyncConnection`].
+    ///
+    /// Asynchronous counterpart of [`MissionClient::download`]. Must be called within Tokio
+    /// runtime.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    pub async fn download_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+        mission_type: MavMissionType,
+    ) -> crate::Result<(Vec<MissionItemInt>, MissionTransfer)>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        self.start_download(mission_type)?;
+        let mut items = Vec::new();
+        let transfer = self
+            .transfer_async(connection, |item| items.push(item))
+            .await?;
+        Ok((items, transfer))
+    }
+
+    /// <sup>`tokio-rt`</sup>
+    /// Clears items of a given `mission_type` over [`AsyncConnection`].
+    ///
+    /// Asynchronous counterpart of [`MissionClient::clear`]. Must be called within Tokio runtime.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    pub async fn clear_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+        mission_type: MavMissionType,
+    ) -> crate::Result<MissionTransfer>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        self.start_clear(mission_type)?;
+        self.transfer_async(connection, |_| {}).await
+    }
+
+    async fn transfer_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+        mut on_item: impl FnMut(MissionItemInt),
+    ) -> crate::Result<MissionTransfer>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        run_async(self, connection, |client, frame, now| {
+            completed(client.handle(frame, now), &mut on_item)
+        })
+        .await
+    }
+}
+
+#[cfg(feature = "std")]
+impl Driven for MissionClient<'_> {
+    fn poll_frame<V: Versioned>(
+        &mut self,
+        endpoint: &Endpoint<V>,
+        now: Duration,
+    ) -> crate::Result<Option<Frame<V>>> {
+        self.poll(now)?
+            .map(|message| endpoint.next_frame(message.as_message()))
+            .transpose()
+    }
+
+    fn poll_timeout(&self, now: Duration) -> Option<Duration> {
+        self.timeout(now)
+    }
+
+    fn abort(&mut self) {
+        self.operation = None;
+    }
+}
+
+/// Reports downloaded items and returns the result of a completed operation.
+#[cfg(feature = "std")]
+fn completed(
+    events: MissionEvents,
+    on_item: &mut impl FnMut(MissionItemInt),
+) -> crate::Result<Option<MissionTransfer>> {
+    for event in events {
+        match event {
+            MissionEvent::Item(item) => on_item(item),
+            MissionEvent::Completed(transfer) => return Ok(Some(transfer)),
+            MissionEvent::Failed(result) => return Err(ServiceError::Mission(result).into()),
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::microservices::mission::enums::MavCmd;
+    use crate::protocol::V2;
+    #[cfg(feature = "tokio-rt")]
+    use crate::utils::test_utils::{async_connections, block_on};
+    use crate::utils::test_utils::{message_frame, spawn_remote};
+
+    const GCS: MavLinkId = MavLinkId {
+        system: 255,
+        component: 190,
+    };
+    const VEHICLE: MavLinkId = MavLinkId {
+        system: 1,
+        component: 1,
+    };
+
+    fn items(count: u16) -> Vec<MissionItemInt> {
+        (0..count)
+            .map(|seq| MissionItemInt {
+                command: MavCmd::NavWaypoint,
+                x: seq as i32,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn request(seq: u16, mission_type: MavMissionType) -> MissionRequestInt {
+        MissionRequestInt {
+            target_system: GCS.system,
+            target_component: GCS.component,
+            seq,
+            mission_type,
+        }
+    }
+
+    fn ack(result: MavMissionResult, mission_type: MavMissionType) -> MissionAck {
+        MissionAck {
+            target_system: GCS.system,
+            target_component: GCS.component,
+            type_: result,
+            mission_type,
+            opaque_id: 42,
+        }
+    }
+
+    #[test]
+    fn upload_with_retries() {
+        let ms = Duration::from_millis;
+        let items = items(2);
+        let mut client = MissionClient::new(GCS, VEHICLE)
+            .with_timeout(ms(100))
+            .with_retries(1);
+        client.start_upload(&items, MavMissionType::Rally).unwrap();
+        assert_eq!(
+            client.start_clear(MavMissionType::Rally),
+            Err(ServiceError::Busy)
+        );
+
+        let Some(MissionMessage::Count(count)) = client.poll(ms(0)).unwrap() else {
+            panic!("expected mission count");
+        };
+        assert_eq!(count.count, 2);
+        assert_eq!(client.poll(ms(50)), Ok(None));
+        assert!(matches!(
+            client.poll(ms(100)),
+            Ok(Some(MissionMessage::Count(_)))
+        ));
+
+        // Requests for other mission types are ignored
+        let other = message_frame(VEHICLE, &request(0, MavMissionType::Fence));
+        assert_eq!(client.handle(&other, ms(110)).count(), 0);
+        assert_eq!(client.poll(ms(110)), Ok(None));
+
+        for seq in [0, 1, 1] {
+            let req = message_frame(VEHICLE, &request(seq, MavMissionType::Rally));
+            assert_eq!(client.handle(&req, ms(120)).count(), 0);
+            let Some(MissionMessage::ItemInt(item)) = client.poll(ms(120)).unwrap() else {
+                panic!("expected mission item");
+            };
+            assert_eq!(item.seq, seq);
+            assert_eq!(item.x, seq as i32);
+            assert_eq!(item.mission_type, MavMissionType::Rally);
+            assert_eq!(item.target_system, VEHICLE.system);
+        }
+        assert_eq!(client.progress(), Some((2, 2)));
+
+        let done = message_frame(
+            VEHICLE,
+            &ack(MavMissionResult::MavMissionAccepted, MavMissionType::Rally),
+        );
+        let events: Vec<_> = client.handle(&done, ms(130)).collect();
+        assert_eq!(
+            events,
+            vec![MissionEvent::Completed(MissionTransfer {
+                mission_type: MavMissionType::Rally,
+                count: 2,
+                opaque_id: 42,
+            })]
+        );
+        assert!(!client.is_busy());
+    }
+
+    #[test]
+    fn download_requests_items_in_order() {
+        let ms = Duration::from_millis;
+        let mut client = MissionClient::new(GCS, MavLinkId::new(1, 0)).with_timeout(ms(100));
+        client.start_download(MavMissionType::Mission).unwrap();
+        assert!(matches!(
+            client.poll(ms(0)),
+            Ok(Some(MissionMessage::RequestList(_)))
+        ));
+
+        let count = MissionCount {
+            target_system: GCS.system,
+            target_component: GCS.component,
+            count: 2,
+            mission_type: MavMissionType::Mission,
+            opaque_id: 7,
+        };
+        assert_eq!(
+            client
+                .handle(&message_frame(VEHICLE, &count), ms(10))
+                .count(),
+            0
+        );
+        assert!(matches!(
+            client.poll(ms(10)),
+            Ok(Some(MissionMessage::RequestInt(MissionRequestInt {
+                seq: 0,
+                ..
+            })))
+        ));
+
+        // Out-of-order item is ignored and the request is retransmitted
+        let item = |seq: u16| MissionItemInt {
+            target_system: GCS.system,
+            target_component: GCS.component,
+            seq,
+            ..Default::default()
+        };
+        assert_eq!(
+            client
+                .handle(&message_frame(VEHICLE, &item(1)), ms(20))
+                .count(),
+            0
+        );
+        assert!(matches!(
+            client.poll(ms(110)),
+            Ok(Some(MissionMessage::RequestInt(MissionRequestInt {
+                seq: 0,
+                ..
+            })))
+        ));
+
+        let events: Vec<_> = client
+            .handle(&message_frame(VEHICLE, &item(0)), ms(120))
+            .collect();
+        assert_eq!(events, vec![MissionEvent::Item(item(0))]);
+        assert_eq!(client.progress(), Some((1, 2)));
+        client.poll(ms(120)).unwrap();
+
+        // Duplicate of a received item postpones retransmission
+        assert_eq!(
+            client
+                .handle(&message_frame(VEHICLE, &item(0)), ms(200))
+                .count(),
+            0
+        );
+        assert_eq!(client.poll(ms(250)), Ok(None));
+
+        let events: Vec<_> = client
+            .handle(&message_frame(VEHICLE, &item(1)), ms(260))
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[1],
+            MissionEvent::Completed(MissionTransfer {
+                mission_type: MavMissionType::Mission,
+                count: 2,
+                opaque_id: 7,
+            })
+        );
+        let Ok(Some(MissionMessage::Ack(ack))) = client.poll(ms(260)) else {
+            panic!("expected mission ack");
+        };
+        assert_eq!(ack.type_, MavMissionResult::MavMissionAccepted);
+        assert_eq!(client.poll(ms(260)), Ok(None));
+    }
+
+    #[test]
+    fn clear_failures() {
+        let ms = Duration::from_millis;
+        let mut client = MissionClient::new(GCS, VEHICLE)
+            .with_timeout(ms(100))
+            .with_retries(1);
+
+        client.start_clear(MavMissionType::All).unwrap();
+        let denied = message_frame(
+            VEHICLE,
+            &ack(MavMissionResult::MavMissionDenied, MavMissionType::All),
+        );
+        // Acknowledgements from other systems are ignored
+        let other = message_frame(
+            MavLinkId::new(2, 1),
+            &ack(MavMissionResult::MavMissionAccepted, MavMissionType::All),
+        );
+        assert_eq!(client.handle(&other, ms(10)).count(), 0);
+        let events: Vec<_> = client.handle(&denied, ms(10)).collect();
+        assert_eq!(
+            events,
+            vec![MissionEvent::Failed(MavMissionResult::MavMissionDenied)]
+        );
+
+        client.start_clear(MavMissionType::All).unwrap();
+        client.poll(ms(0)).unwrap();
+        client.poll(ms(100)).unwrap();
+        assert_eq!(client.poll(ms(200)), Err(ServiceError::TimedOut));
+
+        client.start_download(MavMissionType::Fence).unwrap();
+        client.cancel();
+        let Ok(Some(MissionMessage::Ack(ack))) = client.poll(ms(300)) else {
+            panic!("expected mission ack");
+        };
+        assert_eq!(ack.type_, MavMissionResult::MavMissionOperationCancelled);
+        assert!(!client.is_busy());
+    }
+
+    #[test]
+    fn blocking_download() {
+        let (mut connection, vehicle) = spawn_remote(GCS, VEHICLE, |mut vehicle| {
+            let frame = vehicle.recv().unwrap();
+            assert!(decode::<MissionRequestList, V2>(&frame).is_some());
+            vehicle
+                .send_message(&MissionCount {
+                    count: 3,
+                    ..Default::default()
+                })
+                .unwrap();
+
+            // The first request is lost
+            vehicle.recv().unwrap();
+            loop {
+                let frame = vehicle.recv().unwrap();
+                if let Some(request) = decode::<MissionRequestInt, V2>(&frame) {
+                    let mut item = items(3)[request.seq as usize].clone();
+                    item.seq = request.seq;
+                    vehicle.send_message(&item).unwrap();
+                } else {
+                    let ack = decode::<MissionAck, V2>(&frame).unwrap();
+                    assert_eq!(ack.type_, MavMissionResult::MavMissionAccepted);
+                    break;
+                }
+            }
+        });
+
+        let mut client = MissionClient::new(GCS, VEHICLE).with_timeout(Duration::from_millis(20));
+        let (downloaded, transfer) = client
+            .download(&mut connection, MavMissionType::Mission)
+            .unwrap();
+        assert_eq!(transfer.count, 3);
+        assert_eq!(
+            downloaded.iter().map(|i| i.x).collect::<Vec<_>>(),
+            [0, 1, 2]
+        );
+        vehicle.join().unwrap();
+    }
+
+    #[cfg(feature = "tokio-rt")]
+    #[test]
+    fn async_upload_rejected() {
+        block_on(async {
+            let (mut connection, mut vehicle) = async_connections(GCS, VEHICLE);
+
+            let vehicle = tokio::spawn(async move {
+                let frame = vehicle.recv().await.unwrap();
+                let count = decode::<MissionCount, V2>(&frame).unwrap();
+                assert_eq!(count.count, 5);
+                let no_space = ack(MavMissionResult::MavMissionNoSpace, MavMissionType::Mission);
+                let frame = vehicle.endpoint().next_frame(&no_space).unwrap();
+                vehicle.send(&frame).await.unwrap();
+            });
+
+            let items = items(5);
+            let mut client = MissionClient::new(GCS, VEHICLE);
+            let result = client
+                .upload_async(&mut connection, &items, MavMissionType::Mission)
+                .await;
+            assert!(matches!(
+                result,
+                Err(Error::Service(ServiceError::Mission(
+                    MavMissionResult::MavMissionNoSpace
+                )))
+            ));
+            vehicle.await.unwrap();
+        });
+    }
+}
@@ -0,0 +1,70 @@
+//! # Mission protocol
+//!
+//! Implements MAVLink [mission protocol](https://mavlink.io/en/services/mission.html) for all
+//! mission types (missions, geofences, and rally points).
+//!
+//! * [`MissionClient`] uploads, downloads, and clears missions on a remote system, see [`client`].
+//! * [`MissionServer`] serves missions kept in a [`MissionStore`], see [`server`] and [`store`].
+//!
+//! Only `MISSION_ITEM_INT` based protocol is supported: deprecated `MISSION_REQUEST` and
+//! `MISSION_ITEM` messages are ignored.
+
+use crate::microservices::mission::messages::{
+    MissionAck, MissionClearAll, MissionCount, MissionCurrent, MissionItemInt, MissionItemReached,
+    MissionRequestInt, MissionRequestList,
+};
+use crate::protocol::Message;
+
+pub mod client;
+pub mod server;
+pub mod store;
+
+#[doc(inline)]
+pub use client::{
+    MissionClient, MissionEvent, MissionEvents, MissionTransfer, DEFAULT_MISSION_RETRIES,
+    DEFAULT_MISSION_TIMEOUT,
+};
+#[doc(inline)]
+pub use server::{MissionServer, MissionServerEvent, DEFAULT_MISSION_CURRENT_INTERVAL};
+#[cfg(feature = "std")]
+#[doc(inline)]
+pub use store::MemoryMissionStore;
+#[doc(inline)]
+pub use store::{ArrayMissionStore, MissionStore, DEFAULT_MISSION_STORE_CAPACITY};
+
+/// Message of a mission protocol.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MissionMessage {
+    /// `MISSION_REQUEST_LIST`.
+    RequestList(MissionRequestList),
+    /// `MISSION_COUNT`.
+    Count(MissionCount),
+    /// `MISSION_REQUEST_INT`.
+    RequestInt(MissionRequestInt),
+    /// `MISSION_ITEM_INT`.
+    ItemInt(MissionItemInt),
+    /// `MISSION_ACK`.
+    Ack(MissionAck),
+    /// `MISSION_CLEAR_ALL`.
+    ClearAll(MissionClearAll),
+    /// `MISSION_CURRENT`.
+    Current(MissionCurrent),
+    /// `MISSION_ITEM_REACHED`.
+    ItemReached(MissionItemReached),
+}
+
+impl MissionMessage {
+    /// Mission protocol message as a MAVLink message.
+    pub fn as_message(&self) -> &dyn Message {
+        match self {
+            MissionMessage::RequestList(msg) => msg,
+            MissionMessage::Count(msg) => msg,
+            MissionMessage::RequestInt(msg) => msg,
+            MissionMessage::ItemInt(msg) => msg,
+            MissionMessage::Ack(msg) => msg,
+            MissionMessage::ClearAll(msg) => msg,
+            MissionMessage::Current(msg) => msg,
+            MissionMessage::ItemReached(msg) => msg,
+        }
+    }
+}
@@ -0,0 +1,899 @@
+//! # Mission protocol server
+//!
+//! Implements vehicle side of MAVLink
+//! [mission protocol](https://mavlink.io/en/services/mission.html).
+//!
+//! [`MissionServer`] is a sans-I/O state machine that serves items of a [`MissionStore`]:
+//!
+//! * Answers `MISSION_REQUEST_LIST` with `MISSION_COUNT` and each `MISSION_REQUEST_INT` with
+//!   `MISSION_ITEM_INT`.
+//! * Receives uploads: on `MISSION_COUNT` requests items one by one by `MISSION_REQUEST_INT`,
+//!   validates their sequence numbers, and responds with `MISSION_ACK` once all items are received
+//!   or if upload was rejected by store.
+//! * Clears items on `MISSION_CLEAR_ALL`.
+//! * Changes current mission item on `MISSION_SET_CURRENT`.
+//! * Streams `MISSION_CURRENT` and emits `MISSION_ITEM_REACHED`.
+
+use core::time::Duration;
+
+use crate::microservices::mission::enums::{MavMissionResult, MavMissionType, MissionState};
+use crate::microservices::mission::messages::{
+    MissionAck, MissionClearAll, MissionCount, MissionCurrent, MissionItemInt, MissionItemReached,
+    MissionRequestInt, MissionRequestList, MissionSetCurrent,
+};
+use crate::protocol::{Frame, MavLinkId, MaybeVersioned};
+use crate::services::mission::{MissionMessage, MissionStore, MissionTransfer};
+use crate::services::{decode, is_addressed_to};
+
+/// Default interval between `MISSION_CURRENT` messages.
+pub const DEFAULT_MISSION_CURRENT_INTERVAL: Duration = Duration::from_secs(1);
+/// Maximum number of responses waiting for [`MissionServer::poll`].
+const RESPONSES_CAPACITY: usize = 4;
+
+/// Event produced by [`MissionServer::handle`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MissionServerEvent {
+    /// Items were uploaded by remote system.
+    Uploaded(MissionTransfer),
+    /// Items of a given type were cleared by remote system.
+    Cleared(MavMissionType),
+    /// Current mission item was changed by remote system.
+    CurrentChanged(u16),
+}
+
+#[derive(Clone, Debug)]
+struct Upload {
+    peer: MavLinkId,
+    mission_type: MavMissionType,
+    count: u16,
+    next: u16,
+    attempts: u8,
+    deadline: Option<Duration>,
+}
+
+/// Result of the latest upload used to acknowledge retransmitted final items.
+#[derive(Clone, Debug)]
+struct Completed {
+    peer: MavLinkId,
+    mission_type: MavMissionType,
+    last: u16,
+    ack: MissionMessage,
+}
+
+/// Sans-I/O mission protocol server.
+///
+/// Serves items of a [`MissionStore`] `S` on behalf of a component with a specified `ID`. Pass
+/// incoming frames to [`MissionServer::handle`] and call [`MissionServer::poll`] to obtain
+/// messages that should be sent. Call [`MissionServer::poll`] after each
+/// [`MissionServer::handle`] and whenever [`MissionServer::timeout`] expires.
+///
+/// Server accepts one upload at a time. Upload requests from other systems are rejected with
+/// [`MavMissionResult::MavMissionDenied`] until current upload is completed or timed out. If the
+/// final item of the latest upload is retransmitted (for example, because `MISSION_ACK` was lost),
+/// it is acknowledged again with the same result.
+///
+/// Up to four responses are kept until [`MissionServer::poll`] is called, the oldest response is
+/// dropped if more frames are handled without polling.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use mavio::microservices::mission::enums::MavMissionType;
+/// use mavio::microservices::mission::messages::MissionRequestList;
+/// use mavio::services::mission::{ArrayMissionStore, MissionMessage, MissionServer};
+/// use mavio::prelude::*;
+///
+/// let gcs = Endpoint::v2(MavLinkId::new(255, 190));
+/// let mut server = MissionServer::new(MavLinkId::new(1, 1), ArrayMissionStore::<16>::new());
+///
+/// let request = gcs.next_frame(&MissionRequestList {
+///     target_system: 1,
+///     target_component: 1,
+///     mission_type: MavMissionType::Mission,
+/// }).unwrap();
+/// server.handle(&request, Duration::ZERO);
+///
+/// let Some(MissionMessage::Count(count)) = server.poll(Duration::ZERO) else {
+///     panic!("expected mission count");
+/// };
+/// assert_eq!(count.count, 0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MissionServer<S: MissionStore> {
+    id: MavLinkId,
+    store: S,
+    timeout: Duration,
+    retries: u8,
+    current_interval: Duration,
+    current: u16,
+    state: MissionState,
+    upload: Option<Upload>,
+    completed: Option<Completed>,
+    responses: [Option<MissionMessage>; RESPONSES_CAPACITY],
+    reached: Option<u16>,
+    current_due: Duration,
+}
+
+impl<S: MissionStore> MissionServer<S> {
+    /// Creates a server for a component with a specified `id` backed by `store`.
+    pub fn new(id: MavLinkId, store: S) -> Self {
+        Self {
+            id,
+            store,
+            timeout: super::DEFAULT_MISSION_TIMEOUT,
+            retries: super::DEFAULT_MISSION_RETRIES,
+            current_interval: DEFAULT_MISSION_CURRENT_INTERVAL,
+            current: 0,
+            state: MissionState::NotStarted,
+            upload: None,
+            completed: None,
+            responses: core::array::from_fn(|_| None),
+            reached: None,
+            current_due: Duration::ZERO,
+        }
+    }
+
+    /// Sets time to wait for an item before requesting it again during upload.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Sets the number of item requests after which upload is aborted.
+    pub fn with_retries(self, retries: u8) -> Self {
+        Self { retries, ..self }
+    }
+
+    /// Sets interval between `MISSION_CURRENT` messages.
+    pub fn with_current_interval(self, current_interval: Duration) -> Self {
+        Self {
+            current_interval,
+            ..self
+        }
+    }
+
+    /// `ID` of the component this server acts on behalf of.
+    #[inline(always)]
+    pub fn id(&self) -> MavLinkId {
+        self.id
+    }
+
+    /// Mission store.
+    #[inline(always)]
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Mutable reference to mission store.
+    ///
+    /// Changes made directly to the store are not announced to remote systems.
+    #[inline(always)]
+    pub fn store_mut(&mut self) -> &mut S {
+        &mut self.store
+    }
+
+    /// Returns `true` if upload is in progress.
+    #[inline(always)]
+    pub fn is_uploading(&self) -> bool {
+        self.upload.is_some()
+    }
+
+    /// Sequence number of the current mission item.
+    #[inline(always)]
+    pub fn current(&self) -> u16 {
+        self.current
+    }
+
+    /// Sets current mission item and announces it by `MISSION_CURRENT`.
+    ///
+    /// Returns `false` if there is no mission item with a specified sequence number.
+    pub fn set_current(&mut self, seq: u16) -> bool {
+        if seq >= self.store.count(MavMissionType::Mission) {
+            return false;
+        }
+        self.current = seq;
+        self.current_due = Duration::ZERO;
+        true
+    }
+
+    /// Mission execution state reported in `MISSION_CURRENT`.
+    ///
+    /// Always [`MissionState::NoMission`] if there are no mission items.
+    pub fn state(&self) -> MissionState {
+        if self.store.count(MavMissionType::Mission) == 0 {
+            MissionState::NoMission
+        } else {
+            self.state
+        }
+    }
+
+    /// Sets mission execution state and announces it by `MISSION_CURRENT`.
+    pub fn set_state(&mut self, state: MissionState) {
+        self.state = state;
+        self.current_due = Duration::ZERO;
+    }
+
+    /// Announces that mission item with a specified sequence number has been reached.
+    ///
+    /// The `MISSION_ITEM_REACHED` will be returned by the next [`MissionServer::poll`].
+    pub fn reached(&mut self, seq: u16) {
+        self.reached = Some(seq);
+    }
+
+    /// The `MISSION_CURRENT` message describing current state of the mission.
+    pub fn mission_current(&self) -> MissionCurrent {
+        MissionCurrent {
+            seq: self.current,
+            total: self.store.count(MavMissionType::Mission),
+            mission_state: self.state(),
+            mission_mode: 0,
+            mission_id: self.store.opaque_id(MavMissionType::Mission),
+            fence_id: self.store.opaque_id(MavMissionType::Fence),
+            rally_points_id: self.store.opaque_id(MavMissionType::Rally),
+        }
+    }
+
+    /// Handles incoming frame received at `now`.
+    ///
+    /// Call [`MissionServer::poll`] afterward to obtain a response.
+    pub fn handle<V: MaybeVersioned>(
+        &mut self,
+        frame: &Frame<V>,
+        now: Duration,
+    ) -> Option<MissionServerEvent> {
+        let peer = MavLinkId::new(frame.system_id(), frame.component_id());
+
+        if let Some(msg) = decode::<MissionRequestList, V>(frame) {
+            if is_addressed_to(msg.target_system, msg.target_component, self.id) {
+                self.handle_request_list(peer, msg.mission_type);
+            }
+        } else if let Some(msg) = decode::<MissionRequestInt, V>(frame) {
+            if is_addressed_to(msg.target_system, msg.target_component, self.id) {
+                self.handle_request(peer, msg);
+            }
+        } else if let Some(msg) = decode::<MissionCount, V>(frame) {
+            if is_addressed_to(msg.target_system, msg.target_component, self.id) {
+                return self.handle_count(peer, msg);
+            }
+        } else if let Some(msg) = decode::<MissionItemInt, V>(frame) {
+            if is_addressed_to(msg.target_system, msg.target_component, self.id) {
+                return self.handle_item(peer, msg, now);
+            }
+        } else if let Some(msg) = decode::<MissionAck, V>(frame) {
+            if is_addressed_to(msg.target_system, msg.target_component, self.id) {
+                self.handle_ack(peer, msg);
+            }
+        } else if let Some(msg) = decode::<MissionClearAll, V>(frame) {
+            if is_addressed_to(msg.target_system, msg.target_component, self.id) {
+                return self.handle_clear(peer, msg.mission_type);
+            }
+        } else if let Some(msg) = decode::<MissionSetCurrent, V>(frame) {
+            if is_addressed_to(msg.target_system, msg.target_component, self.id) {
+                // Invalid sequence numbers are answered with unchanged current item
+                self.current_due = Duration::ZERO;
+                if self.set_current(msg.seq) {
+                    return Some(MissionServerEvent::CurrentChanged(msg.seq));
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns a message that should be sent at `now`.
+    ///
+    /// Call this method until it returns [`None`].
+    pub fn poll(&mut self, now: Duration) -> Option<MissionMessage> {
+        if let Some(response) = self.responses[0].take() {
+            self.responses.rotate_left(1);
+            return Some(response);
+        }
+
+        if let Some(upload) = &self.upload {
+            match upload.deadline {
+                Some(deadline) if now < deadline => {}
+                Some(_) if upload.attempts >= self.retries => {
+                    let (peer, mission_type) = (upload.peer, upload.mission_type);
+                    self.abort_upload();
+                    return Some(self.ack(
+                        peer,
+                        mission_type,
+                        MavMissionResult::MavMissionOperationCancelled,
+                    ));
+                }
+                _ => return self.request_next(now),
+            }
+        }
+
+        if let Some(seq) = self.reached.take() {
+            return Some(MissionMessage::ItemReached(MissionItemReached { seq }));
+        }
+
+        if now >= self.current_due {
+            self.current_due = now + self.current_interval;
+            return Some(MissionMessage::Current(self.mission_current()));
+        }
+
+        None
+    }
+
+    /// Time left until [`MissionServer::poll`] should be called.
+    pub fn timeout(&self, now: Duration) -> Duration {
+        if self.responses[0].is_some() || self.reached.is_some() {
+            return Duration::ZERO;
+        }
+        let timeout = self.current_due.saturating_sub(now);
+        match &self.upload {
+            Some(upload) => upload
+                .deadline
+                .map_or(Duration::ZERO, |deadline| deadline.saturating_sub(now))
+                .min(timeout),
+            None => timeout,
+        }
+    }
+
+    fn handle_request_list(&mut self, peer: MavLinkId, mission_type: MavMissionType) {
+        if mission_type == MavMissionType::All {
+            self.respond(self.ack(peer, mission_type, MavMissionResult::MavMissionUnsupported));
+            return;
+        }
+        self.respond(MissionMessage::Count(MissionCount {
+            target_system: peer.system,
+            target_component: peer.component,
+            count: self.store.count(mission_type),
+            mission_type,
+            opaque_id: self.store.opaque_id(mission_type),
+        }));
+    }
+
+    fn handle_request(&mut self, peer: MavLinkId, request: MissionRequestInt) {
+        self.respond(match self.store.item(request.mission_type, request.seq) {
+            Some(item) => MissionMessage::ItemInt(MissionItemInt {
+                target_system: peer.system,
+                target_component: peer.component,
+                seq: request.seq,
+                mission_type: request.mission_type,
+                ..item
+            }),
+            None => self.ack(
+                peer,
+                request.mission_type,
+                MavMissionResult::MavMissionInvalidSequence,
+            ),
+        });
+    }
+
+    fn handle_count(&mut self, peer: MavLinkId, msg: MissionCount) -> Option<MissionServerEvent> {
+        match &self.upload {
+            Some(upload) if upload.peer == peer && upload.mission_type == msg.mission_type => {
+                // Upload is restarted by the same peer
+                self.abort_upload();
+            }
+            Some(_) => {
+                self.respond(self.ack(peer, msg.mission_type, MavMissionResult::MavMissionDenied));
+                return None;
+            }
+            None => {}
+        }
+
+        if let Err(result) = self.store.begin_upload(msg.mission_type, msg.count) {
+            self.respond(self.ack(peer, msg.mission_type, result));
+            return None;
+        }
+        self.completed = None;
+        if msg.count == 0 {
+            self.store.finish_upload(msg.mission_type, true);
+            return Some(self.uploaded(peer, msg.mission_type, 0, None));
+        }
+
+        self.upload = Some(Upload {
+            peer,
+            mission_type: msg.mission_type,
+            count: msg.count,
+            next: 0,
+            attempts: 0,
+            deadline: None,
+        });
+        None
+    }
+
+    fn handle_item(
+        &mut self,
+        peer: MavLinkId,
+        item: MissionItemInt,
+        now: Duration,
+    ) -> Option<MissionServerEvent> {
+        if self.upload.is_none() {
+            // Final item is retransmitted if acknowledgement was lost
+            if let Some(completed) = &self.completed {
+                if completed.peer == peer
+                    && completed.mission_type == item.mission_type
+                    && completed.last == item.seq
+                {
+                    self.respond(completed.ack.clone());
+                }
+            }
+            return None;
+        }
+
+        let timeout = self.timeout;
+        let upload = self.upload.as_mut()?;
+        if upload.peer != peer || upload.mission_type != item.mission_type {
+            return None;
+        }
+        if item.seq < upload.next {
+            // Answer to a previous request, the latest one is likely in flight
+            if upload.deadline.is_some() {
+                upload.deadline = Some(now + timeout);
+            }
+            return None;
+        }
+        if item.seq > upload.next {
+            // Request the expected item again immediately
+            upload.deadline = None;
+            return None;
+        }
+
+        let (mission_type, count) = (upload.mission_type, upload.count);
+        if let Err(result) = self.store.write_item(&item) {
+            self.abort_upload();
+            let ack = self.ack(peer, mission_type, result);
+            self.complete(peer, mission_type, item.seq, ack);
+            return None;
+        }
+
+        let upload = self.upload.as_mut()?;
+        upload.next += 1;
+        upload.attempts = 0;
+        upload.deadline = None;
+        if upload.next < count {
+            return None;
+        }
+
+        self.upload = None;
+        self.store.finish_upload(mission_type, true);
+        Some(self.uploaded(peer, mission_type, count, Some(item.seq)))
+    }
+
+    fn handle_ack(&mut self, peer: MavLinkId, ack: MissionAck) {
+        let upload = self.upload.as_ref();
+        if upload
+            .is_some_and(|upload| upload.peer == peer && upload.mission_type == ack.mission_type)
+        {
+            self.abort_upload();
+        }
+    }
+
+    fn handle_clear(
+        &mut self,
+        peer: MavLinkId,
+        mission_type: MavMissionType,
+    ) -> Option<MissionServerEvent> {
+        let upload = self.upload.as_ref();
+        if upload.is_some_and(|upload| {
+            mission_type == MavMissionType::All || upload.mission_type == mission_type
+        }) {
+            self.abort_upload();
+        }
+
+        self.completed = None;
+        let result = match self.store.clear(mission_type) {
+            Ok(_) => MavMissionResult::MavMissionAccepted,
+            Err(result) => result,
+        };
+        self.respond(self.ack(peer, mission_type, result));
+        if result != MavMissionResult::MavMissionAccepted {
+            return None;
+        }
+
+        if matches!(mission_type, MavMissionType::Mission | MavMissionType::All) {
+            self.current = 0;
+        }
+        self.current_due = Duration::ZERO;
+        Some(MissionServerEvent::Cleared(mission_type))
+    }
+
+    fn request_next(&mut self, now: Duration) -> Option<MissionMessage> {
+        let upload = self.upload.as_mut()?;
+        if upload.deadline.is_some() {
+            upload.attempts += 1;
+        }
+        upload.deadline = Some(now + self.timeout);
+        Some(MissionMessage::RequestInt(MissionRequestInt {
+            target_system: upload.peer.system,
+            target_component: upload.peer.component,
+            seq: upload.next,
+            mission_type: upload.mission_type,
+        }))
+    }
+
+    /// Acknowledges successful upload.
+    ///
+    /// If upload was completed by an item with a `last` sequence number, the acknowledgement is
+    /// remembered and sent again if this item is retransmitted.
+    fn uploaded(
+        &mut self,
+        peer: MavLinkId,
+        mission_type: MavMissionType,
+        count: u16,
+        last: Option<u16>,
+    ) -> MissionServerEvent {
+        let opaque_id = self.store.opaque_id(mission_type);
+        let mut ack = self.ack(peer, mission_type, MavMissionResult::MavMissionAccepted);
+        if let MissionMessage::Ack(ack) = &mut ack {
+            ack.opaque_id = opaque_id;
+        }
+        match last {
+            Some(last) => self.complete(peer, mission_type, last, ack),
+            None => self.respond(ack),
+        }
+
+        if mission_type == MavMissionType::Mission {
+            self.current = 0;
+        }
+        self.current_due = Duration::ZERO;
+        MissionServerEvent::Uploaded(MissionTransfer {
+            mission_type,
+            count,
+            opaque_id,
+        })
+    }
+
+    /// Sends final acknowledgement of an upload and remembers it.
+    fn complete(
+        &mut self,
+        peer: MavLinkId,
+        mission_type: MavMissionType,
+        last: u16,
+        ack: MissionMessage,
+    ) {
+        self.respond(ack.clone());
+        self.completed = Some(Completed {
+            peer,
+            mission_type,
+            last,
+            ack,
+        });
+    }
+
+    /// Queues response, the oldest response is dropped if queue is full.
+    fn respond(&mut self, message: MissionMessage) {
+        if self.responses[RESPONSES_CAPACITY - 1].is_some() {
+            self.responses.rotate_left(1);
+            self.responses[RESPONSES_CAPACITY - 1] = None;
+        }
+        if let Some(slot) = self.responses.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some(message);
+        }
+    }
+
+    fn abort_upload(&mut self) {
+        if let Some(upload) = self.upload.take() {
+            self.store.finish_upload(upload.mission_type, false);
+        }
+    }
+
+    fn ack(
+        &self,
+        peer: MavLinkId,
+        mission_type: MavMissionType,
+        result: MavMissionResult,
+    ) -> MissionMessage {
+        MissionMessage::Ack(MissionAck {
+            target_system: peer.system,
+            target_component: peer.component,
+            type_: result,
+            mission_type,
+            opaque_id: 0,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::protocol::Endpoint;
+    use crate::services::mission::{
+        ArrayMissionStore, MemoryMissionStore, MissionClient, MissionEvent,
+    };
+    use crate::utils::test_utils::message_frame;
+
+    const GCS: MavLinkId = MavLinkId {
+        system: 255,
+        component: 190,
+    };
+    const VEHICLE: MavLinkId = MavLinkId {
+        system: 1,
+        component: 1,
+    };
+
+    fn item(seq: u16) -> MissionItemInt {
+        MissionItemInt {
+            target_system: VEHICLE.system,
+            target_component: VEHICLE.component,
+            seq,
+            x: seq as i32,
+            ..Default::default()
+        }
+    }
+
+    fn count(count: u16) -> MissionMessage {
+        MissionMessage::Count(MissionCount {
+            target_system: VEHICLE.system,
+            target_component: VEHICLE.component,
+            count,
+            mission_type: MavMissionType::Mission,
+            opaque_id: 0,
+        })
+    }
+
+    /// Drains all messages except `MISSION_CURRENT`.
+    fn drain<S: MissionStore>(server: &mut MissionServer<S>, now: Duration) -> Vec<MissionMessage> {
+        core::iter::from_fn(|| server.poll(now))
+            .filter(|msg| !matches!(msg, MissionMessage::Current(_)))
+            .collect()
+    }
+
+    #[test]
+    fn upload_validates_sequence() {
+        let ms = Duration::from_millis;
+        let mut server = MissionServer::new(VEHICLE, ArrayMissionStore::<4>::new())
+            .with_timeout(ms(100))
+            .with_retries(1);
+        drain(&mut server, ms(0));
+
+        assert_eq!(
+            server.handle(&message_frame(GCS, count(2).as_message()), ms(0)),
+            None
+        );
+        let Some(MissionMessage::RequestInt(request)) = server.poll(ms(0)) else {
+            panic!("expected mission request");
+        };
+        assert_eq!((request.seq, request.target_system), (0, GCS.system));
+
+        // Other systems can't upload concurrently
+        let other = MavLinkId::new(254, 1);
+        server.handle(&message_frame(other, count(1).as_message()), ms(10));
+        let Some(MissionMessage::Ack(ack)) = server.poll(ms(10)) else {
+            panic!("expected mission ack");
+        };
+        assert_eq!(ack.type_, MavMissionResult::MavMissionDenied);
+        assert_eq!(ack.target_system, other.system);
+
+        // Out-of-order item is requested again
+        let item1 = MissionMessage::ItemInt(item(1));
+        server.handle(&message_frame(GCS, item1.as_message()), ms(20));
+        assert!(matches!(
+            server.poll(ms(20)),
+            Some(MissionMessage::RequestInt(MissionRequestInt { seq: 0, .. }))
+        ));
+
+        server.handle(
+            &message_frame(GCS, MissionMessage::ItemInt(item(0)).as_message()),
+            ms(30),
+        );
+        assert!(matches!(
+            server.poll(ms(30)),
+            Some(MissionMessage::RequestInt(MissionRequestInt { seq: 1, .. }))
+        ));
+        assert_eq!(server.poll(ms(30)), None);
+
+        // Duplicate of a received item postpones the request
+        server.handle(
+            &message_frame(GCS, MissionMessage::ItemInt(item(0)).as_message()),
+            ms(35),
+        );
+        assert_eq!(server.timeout(ms(35)), ms(100));
+        assert_eq!(server.poll(ms(130)), None);
+
+        let event = server.handle(&message_frame(GCS, item1.as_message()), ms(40));
+        assert_eq!(
+            event,
+            Some(MissionServerEvent::Uploaded(MissionTransfer {
+                mission_type: MavMissionType::Mission,
+                count: 2,
+                opaque_id: 1,
+            }))
+        );
+        let Some(MissionMessage::Ack(ack)) = server.poll(ms(40)) else {
+            panic!("expected mission ack");
+        };
+        assert_eq!(ack.type_, MavMissionResult::MavMissionAccepted);
+        assert_eq!(ack.opaque_id, 1);
+
+        let Some(MissionMessage::Current(current)) = server.poll(ms(40)) else {
+            panic!("expected mission current");
+        };
+        assert_eq!((current.total, current.mission_id), (2, 1));
+        assert_eq!(current.mission_state, MissionState::NotStarted);
+        assert_eq!(server.store().items(MavMissionType::Mission)[1].x, 1);
+
+        // Final item is acknowledged again if acknowledgement was lost
+        assert_eq!(
+            server.handle(&message_frame(GCS, item1.as_message()), ms(50)),
+            None
+        );
+        assert_eq!(server.poll(ms(50)), Some(MissionMessage::Ack(ack)));
+        server.handle(
+            &message_frame(GCS, MissionMessage::ItemInt(item(0)).as_message()),
+            ms(60),
+        );
+        assert_eq!(server.poll(ms(60)), None);
+    }
+
+    #[test]
+    fn responses_are_queued() {
+        let ms = Duration::from_millis;
+        let mut store = MemoryMissionStore::new();
+        store.begin_upload(MavMissionType::Mission, 2).unwrap();
+        store.write_item(&item(0)).unwrap();
+        store.write_item(&item(1)).unwrap();
+        store.finish_upload(MavMissionType::Mission, true);
+        let mut server = MissionServer::new(VEHICLE, store);
+        drain(&mut server, ms(0));
+
+        let request = |seq| {
+            MissionMessage::RequestInt(MissionRequestInt {
+                target_system: VEHICLE.system,
+                target_component: VEHICLE.component,
+                seq,
+                mission_type: MavMissionType::Mission,
+            })
+        };
+        for seq in 0..6 {
+            server.handle(&message_frame(GCS, request(seq % 2).as_message()), ms(0));
+        }
+
+        // The oldest responses are dropped
+        let seqs: Vec<_> = drain(&mut server, ms(0))
+            .into_iter()
+            .map(|msg| match msg {
+                MissionMessage::ItemInt(item) => item.seq,
+                msg => panic!("unexpected message: {msg:?}"),
+            })
+            .collect();
+        assert_eq!(seqs, vec![0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn upload_rejected_and_timed_out() {
+        let ms = Duration::from_millis;
+        let mut server = MissionServer::new(VEHICLE, ArrayMissionStore::<1>::new())
+            .with_timeout(ms(100))
+            .with_retries(1);
+        drain(&mut server, ms(0));
+
+        server.handle(&message_frame(GCS, count(2).as_message()), ms(0));
+        let Some(MissionMessage::Ack(ack)) = server.poll(ms(0)) else {
+            panic!("expected mission ack");
+        };
+        assert_eq!(ack.type_, MavMissionResult::MavMissionNoSpace);
+
+        server.handle(&message_frame(GCS, count(1).as_message()), ms(0));
+        assert!(server.is_uploading());
+        assert!(matches!(
+            server.poll(ms(0)),
+            Some(MissionMessage::RequestInt(_))
+        ));
+        assert_eq!(server.timeout(ms(50)), ms(50));
+        assert!(matches!(
+            server.poll(ms(100)),
+            Some(MissionMessage::RequestInt(_))
+        ));
+        let Some(MissionMessage::Ack(ack)) = server.poll(ms(200)) else {
+            panic!("expected mission ack");
+        };
+        assert_eq!(ack.type_, MavMissionResult::MavMissionOperationCancelled);
+        assert!(!server.is_uploading());
+    }
+
+    #[test]
+    fn download_clear_and_current() {
+        let ms = Duration::from_millis;
+        let mut store = MemoryMissionStore::new();
+        store.set_items(MavMissionType::Mission, [item(0), item(0), item(0)]);
+        let mut server = MissionServer::new(VEHICLE, store).with_current_interval(ms(1000));
+
+        let Some(MissionMessage::Current(current)) = server.poll(ms(0)) else {
+            panic!("expected mission current");
+        };
+        assert_eq!(current.total, 3);
+        assert_eq!(server.poll(ms(0)), None);
+        assert_eq!(server.timeout(ms(400)), ms(600));
+
+        let request = MissionMessage::RequestInt(MissionRequestInt {
+            target_system: VEHICLE.system,
+            target_component: VEHICLE.component,
+            seq: 2,
+            mission_type: MavMissionType::Mission,
+        });
+        server.handle(&message_frame(GCS, request.as_message()), ms(10));
+        let Some(MissionMessage::ItemInt(item)) = server.poll(ms(10)) else {
+            panic!("expected mission item");
+        };
+        assert_eq!((item.seq, item.target_system), (2, GCS.system));
+
+        let stray = MissionMessage::ItemInt(MissionItemInt::default());
+        assert_eq!(
+            server.handle(&message_frame(GCS, stray.as_message()), ms(10)),
+            None
+        );
+        let set_current = MissionSetCurrent {
+            target_system: VEHICLE.system,
+            target_component: VEHICLE.component,
+            seq: 1,
+        };
+        let set_current = Endpoint::v2(GCS).next_frame(&set_current).unwrap();
+        assert_eq!(
+            server.handle(&set_current, ms(20)),
+            Some(MissionServerEvent::CurrentChanged(1))
+        );
+        server.reached(0);
+        assert!(matches!(
+            server.poll(ms(20)),
+            Some(MissionMessage::ItemReached(MissionItemReached { seq: 0 }))
+        ));
+        let Some(MissionMessage::Current(current)) = server.poll(ms(20)) else {
+            panic!("expected mission current");
+        };
+        assert_eq!(current.seq, 1);
+
+        let clear = MissionMessage::ClearAll(MissionClearAll {
+            target_system: VEHICLE.system,
+            target_component: 0,
+            mission_type: MavMissionType::All,
+        });
+        assert_eq!(
+            server.handle(&message_frame(GCS, clear.as_message()), ms(30)),
+            Some(MissionServerEvent::Cleared(MavMissionType::All))
+        );
+        let messages = drain(&mut server, ms(30));
+        assert!(matches!(
+            &messages[..],
+            [MissionMessage::Ack(ack)] if ack.type_ == MavMissionResult::MavMissionAccepted
+        ));
+        assert_eq!(server.current(), 0);
+        assert_eq!(server.state(), MissionState::NoMission);
+    }
+
+    #[test]
+    fn client_and_server() {
+        let ms = Duration::from_millis;
+        let items: Vec<_> = (0..5).map(item).collect();
+        let mut server = MissionServer::new(VEHICLE, MemoryMissionStore::new());
+        let mut client = MissionClient::new(GCS, VEHICLE);
+
+        let transfer = |client: &mut MissionClient, server: &mut MissionServer<_>| {
+            let mut downloaded = Vec::new();
+            for step in 0..100 {
+                let now = ms(step);
+                while let Some(msg) = client.poll(now).unwrap() {
+                    server.handle(&message_frame(GCS, msg.as_message()), now);
+                }
+                while let Some(msg) = server.poll(now) {
+                    for event in client.handle(&message_frame(VEHICLE, msg.as_message()), now) {
+                        match event {
+                            MissionEvent::Item(item) => downloaded.push(item),
+                            MissionEvent::Completed(transfer) => return (downloaded, transfer),
+                            MissionEvent::Failed(result) => panic!("failed: {result:?}"),
+                        }
+                    }
+                }
+            }
+            panic!("transfer is not completed");
+        };
+
+        client.start_upload(&items, MavMissionType::Rally).unwrap();
+        let (_, uploaded) = transfer(&mut client, &mut server);
+        assert_eq!(uploaded.count, 5);
+        assert_eq!(server.store().count(MavMissionType::Rally), 5);
+
+        client.start_download(MavMissionType::Rally).unwrap();
+        let (downloaded, transfer) = transfer(&mut client, &mut server);
+        assert_eq!(transfer.opaque_id, uploaded.opaque_id);
+        assert_eq!(
+            downloaded.iter().map(|item| item.x).collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4]
+        );
+    }
+}
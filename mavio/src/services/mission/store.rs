@@ -0,0 +1,419 @@
+//! # Mission storage
+//!
+//! [`MissionStore`] is a storage backend of a [`MissionServer`](super::MissionServer).
+//!
+//! This module provides two implementations:
+//!
+//! * [`ArrayMissionStore`] keeps a fixed number of items of each mission type and is suitable for
+//!   `no_std` and `no_alloc` targets.
+//! * [`MemoryMissionStore`] keeps an arbitrary number of items in memory and replaces mission
+//!   atomically once upload is completed. Available only when `std` feature is enabled.
+
+use crate::microservices::mission::enums::{MavMissionResult, MavMissionType};
+use crate::microservices::mission::messages::MissionItemInt;
+
+/// Default number of items of each mission type stored by [`ArrayMissionStore`].
+pub const DEFAULT_MISSION_STORE_CAPACITY: usize = 32;
+
+/// Storage of mission items.
+///
+/// Items are stored separately for each mission type: [`MavMissionType::Mission`],
+/// [`MavMissionType::Fence`], and [`MavMissionType::Rally`]. The [`MavMissionType::All`] is used
+/// only to clear all mission types at once.
+///
+/// During upload, [`MissionServer`](super::MissionServer) calls [`MissionStore::begin_upload`],
+/// then [`MissionStore::write_item`] for each item in order, and finally
+/// [`MissionStore::finish_upload`]. Errors returned by store are reported to the remote system in
+/// `MISSION_ACK`.
+pub trait MissionStore {
+    /// Number of stored items of a given type.
+    fn count(&self, mission_type: MavMissionType) -> u16;
+
+    /// Returns an item of a given type with a specified sequence number.
+    fn item(&self, mission_type: MavMissionType, seq: u16) -> Option<MissionItemInt>;
+
+    /// Opaque identifier of the stored items of a given type.
+    ///
+    /// Identifier should change whenever items are changed. Default implementation returns `0`
+    /// which means that opaque identifiers are not supported.
+    fn opaque_id(&self, mission_type: MavMissionType) -> u32 {
+        let _ = mission_type;
+        0
+    }
+
+    /// Prepares store for receiving `count` items of a given type.
+    fn begin_upload(
+        &mut self,
+        mission_type: MavMissionType,
+        count: u16,
+    ) -> Result<(), MavMissionResult>;
+
+    /// Writes an uploaded item.
+    ///
+    /// Items are written in order of their sequence numbers.
+    fn write_item(&mut self, item: &MissionItemInt) -> Result<(), MavMissionResult>;
+
+    /// Finishes upload of a given type.
+    ///
+    /// The `completed` is `false` if upload was aborted.
+    fn finish_upload(&mut self, mission_type: MavMissionType, completed: bool);
+
+    /// Removes all items of a given type.
+    fn clear(&mut self, mission_type: MavMissionType) -> Result<(), MavMissionResult>;
+}
+
+impl<S: MissionStore + ?Sized> MissionStore for &mut S {
+    fn count(&self, mission_type: MavMissionType) -> u16 {
+        (**self).count(mission_type)
+    }
+
+    fn item(&self, mission_type: MavMissionType, seq: u16) -> Option<MissionItemInt> {
+        (**self).item(mission_type, seq)
+    }
+
+    fn opaque_id(&self, mission_type: MavMissionType) -> u32 {
+        (**self).opaque_id(mission_type)
+    }
+
+    fn begin_upload(
+        &mut self,
+        mission_type: MavMissionType,
+        count: u16,
+    ) -> Result<(), MavMissionResult> {
+        (**self).begin_upload(mission_type, count)
+    }
+
+    fn write_item(&mut self, item: &MissionItemInt) -> Result<(), MavMissionResult> {
+        (**self).write_item(item)
+    }
+
+    fn finish_upload(&mut self, mission_type: MavMissionType, completed: bool) {
+        (**self).finish_upload(mission_type, completed)
+    }
+
+    fn clear(&mut self, mission_type: MavMissionType) -> Result<(), MavMissionResult> {
+        (**self).clear(mission_type)
+    }
+}
+
+/// Fixed-capacity mission store.
+///
+/// Stores up to `N` items of each mission type. Upload with a larger number of items is rejected
+/// with [`MavMissionResult::MavMissionNoSpace`].
+///
+/// Items are written in place, therefore previously stored items of the uploaded type are removed
+/// once upload is started and an aborted upload leaves the store empty.
+#[derive(Clone, Debug)]
+pub struct ArrayMissionStore<const N: usize = DEFAULT_MISSION_STORE_CAPACITY> {
+    items: [[MissionItemInt; N]; 3],
+    counts: [u16; 3],
+    opaque_ids: [u32; 3],
+    upload: Option<(usize, u16)>,
+}
+
+impl<const N: usize> Default for ArrayMissionStore<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> ArrayMissionStore<N> {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self {
+            items: core::array::from_fn(|_| core::array::from_fn(|_| MissionItemInt::default())),
+            counts: [0; 3],
+            opaque_ids: [0; 3],
+            upload: None,
+        }
+    }
+
+    /// Maximum number of items of each mission type.
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Stored items of a given type.
+    pub fn items(&self, mission_type: MavMissionType) -> &[MissionItemInt] {
+        match slot(mission_type) {
+            Some(slot) => &self.items[slot][..self.counts[slot] as usize],
+            None => &[],
+        }
+    }
+}
+
+impl<const N: usize> MissionStore for ArrayMissionStore<N> {
+    fn count(&self, mission_type: MavMissionType) -> u16 {
+        slot(mission_type).map_or(0, |slot| self.counts[slot])
+    }
+
+    fn item(&self, mission_type: MavMissionType, seq: u16) -> Option<MissionItemInt> {
+        self.items(mission_type).get(seq as usize).cloned()
+    }
+
+    fn opaque_id(&self, mission_type: MavMissionType) -> u32 {
+        slot(mission_type).map_or(0, |slot| self.opaque_ids[slot])
+    }
+
+    fn begin_upload(
+        &mut self,
+        mission_type: MavMissionType,
+        count: u16,
+    ) -> Result<(), MavMissionResult> {
+        let slot = slot(mission_type).ok_or(MavMissionResult::MavMissionUnsupported)?;
+        if count as usize > N {
+            return Err(MavMissionResult::MavMissionNoSpace);
+        }
+        self.counts[slot] = 0;
+        self.upload = Some((slot, count));
+        Ok(())
+    }
+
+    fn write_item(&mut self, item: &MissionItemInt) -> Result<(), MavMissionResult> {
+        let Some((index, count)) = self.upload else {
+            return Err(MavMissionResult::MavMissionError);
+        };
+        if slot(item.mission_type) != Some(index) {
+            return Err(MavMissionResult::MavMissionError);
+        }
+        if item.seq >= count || item.seq != self.counts[index] {
+            return Err(MavMissionResult::MavMissionInvalidSequence);
+        }
+        self.items[index][item.seq as usize] = item.clone();
+        self.counts[index] += 1;
+        Ok(())
+    }
+
+    fn finish_upload(&mut self, mission_type: MavMissionType, completed: bool) {
+        let Some((index, _)) = self.upload else {
+            return;
+        };
+        if Some(index) != slot(mission_type) {
+            return;
+        }
+        self.upload = None;
+        if !completed {
+            self.counts[index] = 0;
+        }
+        self.opaque_ids[index] = next_opaque_id(self.opaque_ids[index]);
+    }
+
+    fn clear(&mut self, mission_type: MavMissionType) -> Result<(), MavMissionResult> {
+        for slot in slots(mission_type) {
+            self.counts[slot] = 0;
+            self.opaque_ids[slot] = next_opaque_id(self.opaque_ids[slot]);
+            if matches!(self.upload, Some((upload, _)) if upload == slot) {
+                self.upload = None;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+pub use memory::MemoryMissionStore;
+
+#[cfg(feature = "std")]
+mod memory {
+    use super::*;
+
+    /// <sup>`std`</sup>
+    /// In-memory mission store.
+    ///
+    /// Stores an arbitrary number of items of each mission type. Uploaded items are kept aside
+    /// until upload is completed, therefore an aborted upload leaves previously stored items
+    /// intact.
+    ///
+    /// Available only when `std` feature is enabled.
+    #[derive(Clone, Debug, Default)]
+    pub struct MemoryMissionStore {
+        items: [Vec<MissionItemInt>; 3],
+        opaque_ids: [u32; 3],
+        upload: Option<(usize, u16, Vec<MissionItemInt>)>,
+    }
+
+    impl MemoryMissionStore {
+        /// Creates an empty store.
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Stored items of a given type.
+        pub fn items(&self, mission_type: MavMissionType) -> &[MissionItemInt] {
+            match slot(mission_type) {
+                Some(slot) => &self.items[slot],
+                None => &[],
+            }
+        }
+
+        /// Replaces items of a given type.
+        ///
+        /// Fields `seq` and `mission_type` of items are updated accordingly. Items beyond
+        /// [`u16::MAX`] are discarded.
+        pub fn set_items(
+            &mut self,
+            mission_type: MavMissionType,
+            items: impl IntoIterator<Item = MissionItemInt>,
+        ) {
+            let Some(slot) = slot(mission_type) else {
+                return;
+            };
+            self.items[slot] = items
+                .into_iter()
+                .zip(0..=u16::MAX)
+                .map(|(item, seq)| MissionItemInt {
+                    seq,
+                    mission_type,
+                    ..item
+                })
+                .collect();
+            self.opaque_ids[slot] = next_opaque_id(self.opaque_ids[slot]);
+        }
+    }
+
+    impl MissionStore for MemoryMissionStore {
+        fn count(&self, mission_type: MavMissionType) -> u16 {
+            self.items(mission_type).len() as u16
+        }
+
+        fn item(&self, mission_type: MavMissionType, seq: u16) -> Option<MissionItemInt> {
+            self.items(mission_type).get(seq as usize).cloned()
+        }
+
+        fn opaque_id(&self, mission_type: MavMissionType) -> u32 {
+            slot(mission_type).map_or(0, |slot| self.opaque_ids[slot])
+        }
+
+        fn begin_upload(
+            &mut self,
+            mission_type: MavMissionType,
+            count: u16,
+        ) -> Result<(), MavMissionResult> {
+            let slot = slot(mission_type).ok_or(MavMissionResult::MavMissionUnsupported)?;
+            self.upload = Some((slot, count, Vec::with_capacity(count as usize)));
+            Ok(())
+        }
+
+        fn write_item(&mut self, item: &MissionItemInt) -> Result<(), MavMissionResult> {
+            let Some((index, count, items)) = &mut self.upload else {
+                return Err(MavMissionResult::MavMissionError);
+            };
+            if slot(item.mission_type) != Some(*index) {
+                return Err(MavMissionResult::MavMissionError);
+            }
+            if item.seq >= *count || item.seq as usize != items.len() {
+                return Err(MavMissionResult::MavMissionInvalidSequence);
+            }
+            items.push(item.clone());
+            Ok(())
+        }
+
+        fn finish_upload(&mut self, mission_type: MavMissionType, completed: bool) {
+            if !matches!(self.upload, Some((index, _, _)) if Some(index) == slot(mission_type)) {
+                return;
+            }
+            let Some((index, _, items)) = self.upload.take() else {
+                return;
+            };
+            if completed {
+                self.items[index] = items;
+                self.opaque_ids[index] = next_opaque_id(self.opaque_ids[index]);
+            }
+        }
+
+        fn clear(&mut self, mission_type: MavMissionType) -> Result<(), MavMissionResult> {
+            for slot in slots(mission_type) {
+                self.items[slot].clear();
+                self.opaque_ids[slot] = next_opaque_id(self.opaque_ids[slot]);
+                if matches!(self.upload, Some((upload, _, _)) if upload == slot) {
+                    self.upload = None;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Index of the storage slot of a given mission type.
+fn slot(mission_type: MavMissionType) -> Option<usize> {
+    match mission_type {
+        MavMissionType::Mission => Some(0),
+        MavMissionType::Fence => Some(1),
+        MavMissionType::Rally => Some(2),
+        MavMissionType::All => None,
+    }
+}
+
+/// Storage slots affected by an operation on a given mission type.
+fn slots(mission_type: MavMissionType) -> core::ops::Range<usize> {
+    match slot(mission_type) {
+        Some(slot) => slot..slot + 1,
+        None => 0..3,
+    }
+}
+
+/// Next opaque identifier skipping `0`, which is reserved for stores that do not support them.
+fn next_opaque_id(opaque_id: u32) -> u32 {
+    opaque_id.wrapping_add(1).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(seq: u16, mission_type: MavMissionType) -> MissionItemInt {
+        MissionItemInt {
+            seq,
+            mission_type,
+            x: seq as i32,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn array_store_upload() {
+        let mut store = ArrayMissionStore::<2>::new();
+        assert_eq!(
+            store.begin_upload(MavMissionType::Fence, 3),
+            Err(MavMissionResult::MavMissionNoSpace)
+        );
+        assert_eq!(
+            store.begin_upload(MavMissionType::All, 1),
+            Err(MavMissionResult::MavMissionUnsupported)
+        );
+
+        store.begin_upload(MavMissionType::Fence, 2).unwrap();
+        assert_eq!(
+            store.write_item(&item(1, MavMissionType::Fence)),
+            Err(MavMissionResult::MavMissionInvalidSequence)
+        );
+        store.write_item(&item(0, MavMissionType::Fence)).unwrap();
+        store.write_item(&item(1, MavMissionType::Fence)).unwrap();
+        store.finish_upload(MavMissionType::Fence, true);
+
+        assert_eq!(store.count(MavMissionType::Fence), 2);
+        assert_eq!(store.count(MavMissionType::Mission), 0);
+        assert_eq!(store.item(MavMissionType::Fence, 1).unwrap().x, 1);
+        assert_eq!(store.opaque_id(MavMissionType::Fence), 1);
+
+        store.clear(MavMissionType::All).unwrap();
+        assert_eq!(store.count(MavMissionType::Fence), 0);
+        assert_eq!(store.opaque_id(MavMissionType::Fence), 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn memory_store_keeps_items_on_aborted_upload() {
+        let mut store = MemoryMissionStore::new();
+        store.set_items(MavMissionType::Mission, [item(5, MavMissionType::Rally)]);
+        assert_eq!(store.items(MavMissionType::Mission)[0].seq, 0);
+
+        store.begin_upload(MavMissionType::Mission, 2).unwrap();
+        store.write_item(&item(0, MavMissionType::Mission)).unwrap();
+        assert_eq!(store.count(MavMissionType::Mission), 1);
+        store.finish_upload(MavMissionType::Mission, false);
+        assert_eq!(store.count(MavMissionType::Mission), 1);
+        assert_eq!(store.opaque_id(MavMissionType::Mission), 1);
+    }
+}
@@ -0,0 +1,249 @@
+//! # MAVLink microservices
+//!
+//! Stateful implementations of MAVLink [microservices](https://mavlink.io/en/services/) built on
+//! top of the message definitions from [`microservices`](crate::microservices).
+//!
+//! Each service is a sans-I/O state machine that does not own a reader, a writer, or a clock.
+//! Instead, it receives the current time as `now: Duration` measured from an arbitrary point (for
+//! example, since the application start) and returns messages that should be sent. This makes
+//! services usable in `no_std` environments and allows to test them deterministically.
+//!
+//! With `std` and `tokio-rt` features, services are accompanied by drivers that run state
+//! machines over [`Sender`](crate::Sender) / [`AsyncSender`](crate::AsyncSender) or over
+//! [`Connection`] / [`AsyncConnection`].
+//!
+//! Client drivers send requests, wait for responses, and handle retransmissions until the
+//! operation is completed. Frames that are not related to the operation are discarded. Reader of
+//! a blocking connection should be either non-blocking or have a read timeout, see
+//! [`Receiver::recv_timeout`](crate::Receiver::recv_timeout).
+//!
+//! Services are enabled by the corresponding `msrv-*` feature flags:
+//!
+//! - `msrv-heartbeat` → [`heartbeat`], [`peers`]
+//! - `msrv-command` → [`command`]
+//! - `msrv-ftp` → [`ftp`]
+//! - `msrv-mission` → [`mission`]
+//! - `msrv-parameter` → [`parameter`]
+//! - `msrv-parameter-ext` → [`parameter_ext`] (enables `msrv-parameter`)
+//!
+//! [`Connection`]: crate::io::Connection
+//! [`AsyncConnection`]: crate::io::AsyncConnection
+
+#[cfg(feature = "msrv-command")]
+pub mod command;
+#[cfg(feature = "msrv-ftp")]
+pub mod ftp;
+#[cfg(feature = "msrv-heartbeat")]
+pub mod heartbeat;
+#[cfg(feature = "msrv-mission")]
+pub mod mission;
+#[cfg(feature = "msrv-parameter")]
+pub mod parameter;
+#[cfg(feature = "msrv-parameter-ext")]
+pub mod parameter_ext;
+#[cfg(feature = "msrv-heartbeat")]
+pub mod peers;
+
+use crate::error::SpecError;
+use crate::mavspec::rust::spec::MessageSpecStatic;
+#[cfg(feature = "msrv-mission")]
+use crate::microservices::mission::enums::MavMissionResult;
+#[cfg(feature = "msrv-parameter-ext")]
+use crate::microservices::parameter_ext::enums::ParamAck;
+use crate::protocol::{Frame, MavLinkId, MaybeVersioned, Payload};
+#[cfg(feature = "msrv-ftp")]
+use crate::services::ftp::FtpNak;
+
+#[cfg(feature = "std")]
+use core::time::Duration;
+
+#[cfg(feature = "tokio-rt")]
+use crate::io::{AsyncConnection, AsyncRead, AsyncWrite};
+#[cfg(feature = "std")]
+use crate::io::{Connection, Read, Write};
+#[cfg(feature = "std")]
+use crate::protocol::{Endpoint, Versioned};
+
+/// Errors returned by MAVLink microservices.
+///
+/// Can be converted into [`Error::Service`](crate::error::Error::Service).
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(all(feature = "specta", feature = "unstable"), derive(specta::Type))]
+#[cfg_attr(
+    all(feature = "serde", feature = "unstable"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum ServiceError {
+    /// Service is already performing another operation.
+    Busy,
+    /// Remote peer has not responded in time.
+    TimedOut,
+    /// Mission operation was rejected by remote peer.
+    #[cfg(feature = "msrv-mission")]
+    Mission(MavMissionResult),
+    /// Parameter name is empty or too long.
+    #[cfg(feature = "msrv-parameter")]
+    InvalidParamId,
+    /// Parameter index can't be requested since it exceeds `i16::MAX`.
+    #[cfg(feature = "msrv-parameter")]
+    InvalidParamIndex,
+    /// Remote peer has not set parameter to the requested value.
+    #[cfg(feature = "msrv-parameter")]
+    ParamRejected,
+    /// Extended parameter was not set by remote peer.
+    #[cfg(feature = "msrv-parameter-ext")]
+    ParamExt(ParamAck),
+    /// FTP request was rejected by remote peer.
+    #[cfg(feature = "msrv-ftp")]
+    Ftp(FtpNak),
+    /// Path is too long to fit into FTP payload.
+    #[cfg(feature = "msrv-ftp")]
+    InvalidPath,
+    /// CRC32 of a transferred file differs from the one calculated by remote peer.
+    #[cfg(feature = "msrv-ftp")]
+    ChecksumMismatch,
+    /// Remote file is larger than the maximum size accepted by FTP client.
+    #[cfg(feature = "msrv-ftp")]
+    FileTooLarge,
+}
+
+/// Decodes message `M` from a frame.
+///
+/// Returns [`None`] if frame contains a different message, has invalid checksum, or can't be
+/// decoded.
+#[allow(dead_code)]
+pub(crate) fn decode<M, V>(frame: &Frame<V>) -> Option<M>
+where
+    M: MessageSpecStatic + for<'a> TryFrom<&'a Payload, Error = SpecError>,
+    V: MaybeVersioned,
+{
+    if frame.message_id() != M::message_id() {
+        return None;
+    }
+    frame
+        .validate_checksum_with_crc_extra(M::crc_extra())
+        .ok()?;
+    M::try_from(frame.payload()).ok()
+}
+
+/// Returns `true` if message with given target fields is addressed to `id`.
+///
+/// Zero target system or component means broadcast.
+#[allow(dead_code)]
+pub(crate) fn is_addressed_to(target_system: u8, target_component: u8, id: MavLinkId) -> bool {
+    (target_system == 0 || target_system == id.system)
+        && (target_component == 0 || target_component == id.component)
+}
+
+/// Sans-I/O client that can be run over a connection by [`run`] or [`run_async`].
+#[cfg(feature = "std")]
+#[allow(dead_code)]
+pub(crate) trait Driven {
+    /// Returns the next frame that should be sent at `now`.
+    ///
+    /// Driver calls this method until it returns [`None`].
+    fn poll_frame<V: Versioned>(
+        &mut self,
+        endpoint: &Endpoint<V>,
+        now: Duration,
+    ) -> crate::Result<Option<Frame<V>>>;
+
+    /// Time left until [`Driven::poll_frame`] should be called.
+    fn poll_timeout(&self, now: Duration) -> Option<Duration>;
+
+    /// Aborts current operation once driver has failed.
+    fn abort(&mut self);
+}
+
+/// Runs `client` over a blocking [`Connection`] until `handle` returns an output.
+///
+/// Incoming frames are passed to `handle` along with the current time. Frames produced by the
+/// client after completion are sent before returning. On failure, operation is aborted and the
+/// frame that client produces afterward (if any) is sent on a best-effort basis.
+#[cfg(feature = "std")]
+#[allow(dead_code)]
+pub(crate) fn run<C, T, E, R, W, V>(
+    client: &mut C,
+    connection: &mut Connection<E, R, W, V>,
+    mut handle: impl FnMut(&mut C, &Frame<V>, Duration) -> crate::Result<Option<T>>,
+) -> crate::Result<T>
+where
+    C: Driven,
+    E: Into<crate::Error>,
+    R: Read<E>,
+    W: Write<E>,
+    V: Versioned,
+{
+    let start = std::time::Instant::now();
+    let result = (|| loop {
+        while let Some(frame) = client.poll_frame(connection.endpoint(), start.elapsed())? {
+            connection.send(&frame)?;
+        }
+
+        let timeout = client.poll_timeout(start.elapsed()).unwrap_or_default();
+        let Some(frame) = connection.receiver().recv_timeout(timeout)? else {
+            continue;
+        };
+        if let Some(output) = handle(client, &frame, start.elapsed())? {
+            while let Some(frame) = client.poll_frame(connection.endpoint(), start.elapsed())? {
+                connection.send(&frame)?;
+            }
+            return Ok(output);
+        }
+    })();
+
+    if result.is_err() {
+        client.abort();
+        if let Ok(Some(frame)) = client.poll_frame(connection.endpoint(), start.elapsed()) {
+            let _ = connection.send(&frame);
+        }
+    }
+    result
+}
+
+/// Runs `client` over [`AsyncConnection`] until `handle` returns an output.
+///
+/// Asynchronous counterpart of [`run`].
+#[cfg(feature = "tokio-rt")]
+#[allow(dead_code)]
+pub(crate) async fn run_async<C, T, E, R, W, V>(
+    client: &mut C,
+    connection: &mut AsyncConnection<E, R, W, V>,
+    mut handle: impl FnMut(&mut C, &Frame<V>, Duration) -> crate::Result<Option<T>>,
+) -> crate::Result<T>
+where
+    C: Driven,
+    E: Into<crate::Error>,
+    R: AsyncRead<E>,
+    W: AsyncWrite<E>,
+    V: Versioned,
+{
+    let start = tokio::time::Instant::now();
+    let result = async {
+        loop {
+            while let Some(frame) = client.poll_frame(connection.endpoint(), start.elapsed())? {
+                connection.send(&frame).await?;
+            }
+
+            let timeout = client.poll_timeout(start.elapsed()).unwrap_or_default();
+            let Ok(frame) = tokio::time::timeout(timeout, connection.recv()).await else {
+                continue;
+            };
+            if let Some(output) = handle(client, &frame?, start.elapsed())? {
+                while let Some(frame) = client.poll_frame(connection.endpoint(), start.elapsed())? {
+                    connection.send(&frame).await?;
+                }
+                return Ok(output);
+            }
+        }
+    }
+    .await;
+
+    if result.is_err() {
+        client.abort();
+        if let Ok(Some(frame)) = client.poll_frame(connection.endpoint(), start.elapsed()) {
+            let _ = connection.send(&frame).await;
+        }
+    }
+    result
+}
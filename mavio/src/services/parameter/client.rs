@@ -0,0 +1,1126 @@
+//! # Parameter protocol client
+//!
+//! Implements GCS side of MAVLink
+//! [parameter protocol](https://mavlink.io/en/services/parameter.html).
+//!
+//! [`ParamClient`] is a sans-I/O state machine that keeps a [`ParamCache`] of remote components and
+//! performs one of the following operations:
+//!
+//! * [Synchronization](https://mavlink.io/en/services/parameter.html#read_all): sends
+//!   `PARAM_REQUEST_LIST` and collects streamed `PARAM_VALUE` messages tracking `param_index` and
+//!   `param_count`. Once stream stops, missing parameters are requested one by one by
+//!   `PARAM_REQUEST_READ`.
+//! * [Read](https://mavlink.io/en/services/parameter.html#read_single): sends
+//!   `PARAM_REQUEST_READ` by name or by index and waits for `PARAM_VALUE`.
+//! * [Write](https://mavlink.io/en/services/parameter.html#write): sends `PARAM_SET` and waits
+//!   for `PARAM_VALUE` echoed by the remote component. Parameter is considered set only if echoed
+//!   value matches the requested one.
+//!
+//! Unsolicited `PARAM_VALUE` messages (for example, parameter changes broadcast by the remote
+//! component) update the cache at any time.
+//!
+//! Values are decoded according to [`ParamEncoding`] of the remote component, see
+//! [`ParamClient::with_encoding`].
+//!
+//! Blocking drivers [`ParamClient::sync`], [`ParamClient::read`], and [`ParamClient::set`] run
+//! client over [`Connection`]. With `tokio-rt` feature enabled, the corresponding `*_async`
+//! methods do the same over [`AsyncConnection`].
+//!
+//! Available only when `alloc` feature is enabled.
+//!
+//! [`Connection`]: crate::io::Connection
+//! [`AsyncConnection`]: crate::io::AsyncConnection
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::microservices::parameter::enums::MavParamType;
+use crate::microservices::parameter::messages::{
+    ParamRequestList, ParamRequestRead, ParamSet, ParamValue,
+};
+use crate::protocol::{Frame, MavLinkId, MaybeVersioned};
+use crate::services::parameter::request::{Request, Synchronization};
+use crate::services::parameter::{
+    param_id, Param, ParamEncoding, ParamMessage, TypedValue, PARAM_ID_LEN,
+};
+use crate::services::{decode, ServiceError};
+
+#[cfg(feature = "tokio-rt")]
+use crate::io::{AsyncConnection, AsyncRead, AsyncWrite};
+#[cfg(feature = "std")]
+use crate::io::{Connection, Read, Write};
+#[cfg(feature = "std")]
+use crate::protocol::{Endpoint, Versioned};
+#[cfg(feature = "tokio-rt")]
+use crate::services::run_async;
+#[cfg(feature = "std")]
+use crate::services::{run, Driven};
+#[cfg(feature = "std")]
+use crate::Error;
+
+/// Default time to wait for a response before retransmitting a request.
+///
+/// During synchronization this is the time of silence after which the stream of parameters is
+/// considered finished.
+pub const DEFAULT_PARAM_TIMEOUT: Duration = Duration::from_secs(1);
+/// Default number of retransmissions of each request.
+pub const DEFAULT_PARAM_RETRIES: u8 = 3;
+
+/// Event produced by [`ParamClient::handle`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamEvent {
+    /// Parameter was received and cached.
+    ///
+    /// Produced for streamed parameters during synchronization and for unsolicited `PARAM_VALUE`
+    /// messages.
+    Received(Param),
+    /// All parameters were received. Contains the total number of parameters.
+    Synced(u16),
+    /// Requested parameter was read.
+    Read(Param),
+    /// Parameter was set to the requested value.
+    Set(Param),
+    /// Remote component has responded with a value that differs from the requested one.
+    Rejected(Param),
+}
+
+/// Parameter that can be stored in a [`ParamCache`].
+///
+/// Implemented by [`Param`] and, with `msrv-parameter-ext` feature enabled, by
+/// [`ExtParam`](crate::services::parameter_ext::ExtParam).
+pub trait CachedParam: Copy {
+    /// Parameter name as transmitted in `param_id` field.
+    fn id(&self) -> &[u8; PARAM_ID_LEN];
+
+    /// Index of the parameter.
+    fn index(&self) -> u16;
+
+    /// Total number of parameters reported by remote component.
+    fn count(&self) -> u16;
+
+    /// Returns parameter with a different index.
+    fn with_index(self, index: u16) -> Self;
+}
+
+impl CachedParam for Param {
+    #[inline(always)]
+    fn id(&self) -> &[u8; PARAM_ID_LEN] {
+        &self.id
+    }
+
+    #[inline(always)]
+    fn index(&self) -> u16 {
+        self.index
+    }
+
+    #[inline(always)]
+    fn count(&self) -> u16 {
+        self.count
+    }
+
+    #[inline(always)]
+    fn with_index(self, index: u16) -> Self {
+        Self { index, ..self }
+    }
+}
+
+/// Parameters of a single remote component.
+///
+/// Parameters are indexed both by their index and by their name.
+#[derive(Clone, Debug)]
+pub struct ComponentParams<P: CachedParam = Param> {
+    params: Vec<Option<P>>,
+    names: BTreeMap<[u8; PARAM_ID_LEN], u16>,
+    count: Option<u16>,
+    received: usize,
+}
+
+impl<P: CachedParam> Default for ComponentParams<P> {
+    fn default() -> Self {
+        Self {
+            params: Vec::new(),
+            names: BTreeMap::new(),
+            count: None,
+            received: 0,
+        }
+    }
+}
+
+impl<P: CachedParam> ComponentParams<P> {
+    /// Total number of parameters reported by remote component.
+    ///
+    /// Returns [`None`] if no parameters have been received yet.
+    #[inline(always)]
+    pub fn count(&self) -> Option<u16> {
+        self.count
+    }
+
+    /// Number of cached parameters.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.received
+    }
+
+    /// Returns `true` if there are no cached parameters.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.received == 0
+    }
+
+    /// Returns `true` if all parameters of remote component are cached.
+    pub fn is_complete(&self) -> bool {
+        self.count
+            .is_some_and(|count| self.received == count as usize)
+    }
+
+    /// Returns a parameter with a specified name.
+    pub fn get(&self, name: &str) -> Option<&P> {
+        let index = *self.names.get(&param_id(name)?)?;
+        self.get_index(index)
+    }
+
+    /// Returns a parameter with a specified index.
+    pub fn get_index(&self, index: u16) -> Option<&P> {
+        self.params.get(index as usize)?.as_ref()
+    }
+
+    /// Iterator over cached parameters ordered by their indices.
+    pub fn iter(&self) -> impl Iterator<Item = &P> {
+        self.params.iter().flatten()
+    }
+
+    /// Iterator over indices of parameters that have not been received yet.
+    pub fn missing(&self) -> impl Iterator<Item = u16> + '_ {
+        self.params
+            .iter()
+            .enumerate()
+            .filter(|(_, param)| param.is_none())
+            .map(|(index, _)| index as u16)
+    }
+
+    fn get_id_mut(&mut self, id: &[u8; PARAM_ID_LEN]) -> Option<&mut P> {
+        let index = *self.names.get(id)?;
+        self.params.get_mut(index as usize)?.as_mut()
+    }
+
+    fn insert(&mut self, param: P) -> Option<P> {
+        if self.count != Some(param.count()) {
+            *self = Self::default();
+            self.count = Some(param.count());
+            self.params.resize(param.count() as usize, None);
+        }
+
+        let index = if param.index() < param.count() {
+            param.index()
+        } else {
+            *self.names.get(param.id())?
+        };
+        let param = param.with_index(index);
+
+        match self.params[index as usize].replace(param) {
+            Some(old) if old.id() != param.id() => {
+                self.names.remove(old.id());
+            }
+            Some(_) => {}
+            None => self.received += 1,
+        }
+        self.names.insert(*param.id(), index);
+        Some(param)
+    }
+}
+
+/// Cache of parameters of remote components.
+///
+/// Parameters are kept separately for each component, see [`ComponentParams`].
+#[derive(Clone, Debug)]
+pub struct ParamCache<P: CachedParam = Param> {
+    components: BTreeMap<(u8, u8), ComponentParams<P>>,
+}
+
+impl<P: CachedParam> Default for ParamCache<P> {
+    fn default() -> Self {
+        Self {
+            components: BTreeMap::new(),
+        }
+    }
+}
+
+impl<P: CachedParam> ParamCache<P> {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns parameters of a component with a specified `ID`.
+    pub fn component(&self, id: MavLinkId) -> Option<&ComponentParams<P>> {
+        self.components.get(&(id.system, id.component))
+    }
+
+    /// Iterator over components with cached parameters.
+    pub fn components(&self) -> impl Iterator<Item = (MavLinkId, &ComponentParams<P>)> {
+        self.components
+            .iter()
+            .map(|(&(system, component), params)| (MavLinkId::new(system, component), params))
+    }
+
+    /// Returns a parameter of a component with a specified `ID` and name.
+    pub fn get(&self, id: MavLinkId, name: &str) -> Option<&P> {
+        self.component(id)?.get(name)
+    }
+
+    /// Returns a parameter of a component with a specified `ID` and index.
+    pub fn get_index(&self, id: MavLinkId, index: u16) -> Option<&P> {
+        self.component(id)?.get_index(index)
+    }
+
+    /// Number of cached parameters of all components.
+    pub fn len(&self) -> usize {
+        self.components.values().map(ComponentParams::len).sum()
+    }
+
+    /// Total number of parameters reported by all components.
+    pub fn count(&self) -> u16 {
+        self.components
+            .values()
+            .filter_map(ComponentParams::count)
+            .fold(0, u16::saturating_add)
+    }
+
+    /// Returns `true` if there are no cached parameters.
+    pub fn is_empty(&self) -> bool {
+        self.components.values().all(ComponentParams::is_empty)
+    }
+
+    /// Returns `true` if parameters were received from at least one component and all parameters
+    /// of each such component are cached.
+    pub fn is_complete(&self) -> bool {
+        !self.components.is_empty() && self.components.values().all(ComponentParams::is_complete)
+    }
+
+    /// Iterator over components and indices of parameters that have not been received yet.
+    pub fn missing(&self) -> impl Iterator<Item = (MavLinkId, u16)> + '_ {
+        self.components()
+            .flat_map(|(id, params)| params.missing().map(move |index| (id, index)))
+    }
+
+    /// Removes all parameters.
+    pub fn clear(&mut self) {
+        self.components.clear();
+    }
+
+    /// Stores parameter received from a component with a specified `ID`.
+    ///
+    /// Parameters of a component are reset if parameter reports a different total number of
+    /// parameters, other components are not affected. Parameters with an index out of range (some
+    /// autopilots send `65535` when echoing `PARAM_SET`) are matched by name.
+    ///
+    /// Returns stored parameter with a resolved index, or [`None`] if index can't be resolved.
+    pub fn insert(&mut self, id: MavLinkId, param: P) -> Option<P> {
+        self.components
+            .entry((id.system, id.component))
+            .or_default()
+            .insert(param)
+    }
+
+    /// Returns a mutable reference to a cached parameter of a component with a specified `ID` and
+    /// `param_id`.
+    #[cfg_attr(not(feature = "msrv-parameter-ext"), allow(dead_code))]
+    pub(crate) fn get_id_mut(
+        &mut self,
+        id: MavLinkId,
+        param_id: &[u8; PARAM_ID_LEN],
+    ) -> Option<&mut P> {
+        self.components
+            .get_mut(&(id.system, id.component))?
+            .get_id_mut(param_id)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Operation {
+    Sync(Synchronization<ParamMessage>),
+    Read {
+        id: Option<[u8; PARAM_ID_LEN]>,
+        index: u16,
+        request: Request<ParamMessage>,
+    },
+    Set {
+        id: [u8; PARAM_ID_LEN],
+        raw: f32,
+        param_type: MavParamType,
+        request: Request<ParamMessage>,
+    },
+}
+
+/// Sans-I/O parameter protocol client.
+///
+/// Client communicates with a single remote component or, if `target` component is `0`, with all
+/// components of the remote system. Parameters are cached separately for each component that
+/// responds, see [`ParamCache`].
+///
+/// Start operation by [`ParamClient::start_sync`], [`ParamClient::start_read`],
+/// [`ParamClient::start_read_index`], or [`ParamClient::start_set`], then pass incoming frames to
+/// [`ParamClient::handle`] and call [`ParamClient::poll`] to obtain messages that should be sent.
+/// Call [`ParamClient::poll`] after each [`ParamClient::handle`] and whenever
+/// [`ParamClient::timeout`] expires.
+///
+/// Available only when `alloc` feature is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use mavio::microservices::parameter::messages::ParamValue;
+/// use mavio::services::parameter::{param_id, ParamClient, ParamEvent, ParamMessage, TypedValue};
+/// use mavio::prelude::*;
+///
+/// let vehicle = Endpoint::v2(MavLinkId::new(1, 1));
+/// let mut client = ParamClient::new(MavLinkId::new(255, 190), vehicle.id());
+///
+/// client.start_set("SYSID_THISMAV", 2u8).unwrap();
+/// let Ok(Some(ParamMessage::Set(set))) = client.poll(Duration::ZERO) else {
+///     panic!("expected PARAM_SET");
+/// };
+///
+/// // Vehicle echoes the new value
+/// let echo = vehicle.next_frame(&ParamValue {
+///     param_id: set.param_id,
+///     param_value: set.param_value,
+///     param_type: set.param_type,
+///     param_count: 100,
+///     param_index: 10,
+/// }).unwrap();
+/// let Some(ParamEvent::Set(param)) = client.handle(&echo, Duration::from_millis(10)) else {
+///     panic!("expected parameter to be set");
+/// };
+/// assert_eq!(param.value, TypedValue::U8(2));
+/// assert_eq!(client.cache().get(vehicle.id(), "SYSID_THISMAV"), Some(&param));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ParamClient {
+    id: MavLinkId,
+    target: MavLinkId,
+    encoding: ParamEncoding,
+    timeout: Duration,
+    retries: u8,
+    cache: ParamCache,
+    operation: Option<Operation>,
+}
+
+impl ParamClient {
+    /// Creates a client for a component with a specified `id` that communicates with `target`.
+    pub fn new(id: MavLinkId, target: MavLinkId) -> Self {
+        Self {
+            id,
+            target,
+            encoding: ParamEncoding::default(),
+            timeout: DEFAULT_PARAM_TIMEOUT,
+            retries: DEFAULT_PARAM_RETRIES,
+            cache: ParamCache::new(),
+            operation: None,
+        }
+    }
+
+    /// Sets encoding of parameter values used by remote component.
+    pub fn with_encoding(self, encoding: ParamEncoding) -> Self {
+        Self { encoding, ..self }
+    }
+
+    /// Sets time to wait for a response before retransmitting a request.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Sets the number of retransmissions of each request after which operation fails.
+    pub fn with_retries(self, retries: u8) -> Self {
+        Self { retries, ..self }
+    }
+
+    /// `ID` of the component this client acts on behalf of.
+    #[inline(always)]
+    pub fn id(&self) -> MavLinkId {
+        self.id
+    }
+
+    /// `ID` of the remote component.
+    #[inline(always)]
+    pub fn target(&self) -> MavLinkId {
+        self.target
+    }
+
+    /// Encoding of parameter values.
+    #[inline(always)]
+    pub fn encoding(&self) -> ParamEncoding {
+        self.encoding
+    }
+
+    /// Changes encoding of parameter values.
+    ///
+    /// Affects only parameters received afterward. Use this method when encoding becomes known
+    /// from `AUTOPILOT_VERSION`, see [`ParamEncoding::from_capabilities`].
+    pub fn set_encoding(&mut self, encoding: ParamEncoding) {
+        self.encoding = encoding;
+    }
+
+    /// Cached parameters of remote components.
+    #[inline(always)]
+    pub fn cache(&self) -> &ParamCache {
+        &self.cache
+    }
+
+    /// Returns `true` if operation is in progress.
+    #[inline(always)]
+    pub fn is_busy(&self) -> bool {
+        self.operation.is_some()
+    }
+
+    /// Starts synchronization of all parameters.
+    ///
+    /// Cache is cleared.
+    ///
+    /// Returns [`ServiceError::Busy`] if another operation is in progress.
+    pub fn start_sync(&mut self) -> core::result::Result<(), ServiceError> {
+        let message = ParamMessage::RequestList(ParamRequestList {
+            target_system: self.target.system,
+            target_component: self.target.component,
+        });
+        self.start(Operation::Sync(Synchronization::new(message, read_request)))?;
+        self.cache.clear();
+        Ok(())
+    }
+
+    /// Starts reading a parameter with a specified name.
+    ///
+    /// Returns [`ServiceError::InvalidParamId`] if name is too long, or [`ServiceError::Busy`] if
+    /// another operation is in progress.
+    pub fn start_read(&mut self, name: &str) -> core::result::Result<(), ServiceError> {
+        let id = param_id(name).ok_or(ServiceError::InvalidParamId)?;
+        let message = ParamMessage::RequestRead(ParamRequestRead {
+            target_system: self.target.system,
+            target_component: self.target.component,
+            param_id: id,
+            param_index: -1,
+        });
+        self.start(Operation::Read {
+            id: Some(id),
+            index: 0,
+            request: Request::new(message),
+        })
+    }
+
+    /// Starts reading a parameter with a specified index.
+    ///
+    /// Returns [`ServiceError::InvalidParamIndex`] if index exceeds `i16::MAX`, or
+    /// [`ServiceError::Busy`] if another operation is in progress.
+    pub fn start_read_index(&mut self, index: u16) -> core::result::Result<(), ServiceError> {
+        if index > i16::MAX as u16 {
+            return Err(ServiceError::InvalidParamIndex);
+        }
+        self.start(Operation::Read {
+            id: None,
+            index,
+            request: Request::new(read_request(self.target, index)),
+        })
+    }
+
+    /// Starts setting a parameter with a specified name.
+    ///
+    /// Type of the parameter is defined by the type of the `value`.
+    ///
+    /// Returns [`ServiceError::InvalidParamId`] if name is too long, or [`ServiceError::Busy`] if
+    /// another operation is in progress.
+    pub fn start_set(
+        &mut self,
+        name: &str,
+        value: impl Into<TypedValue>,
+    ) -> core::result::Result<(), ServiceError> {
+        let id = param_id(name).ok_or(ServiceError::InvalidParamId)?;
+        let value = value.into();
+        let raw = value.encode(self.encoding);
+        let param_type = value.param_type();
+        let message = ParamMessage::Set(ParamSet {
+            target_system: self.target.system,
+            target_component: self.target.component,
+            param_id: id,
+            param_value: raw,
+            param_type,
+        });
+        self.start(Operation::Set {
+            id,
+            raw,
+            param_type,
+            request: Request::new(message),
+        })
+    }
+
+    /// Aborts current operation.
+    pub fn cancel(&mut self) {
+        self.operation = None;
+    }
+
+    /// Handles incoming frame received at `now`.
+    ///
+    /// Every `PARAM_VALUE` from remote component updates the cache. Call [`ParamClient::poll`]
+    /// afterward to obtain a response.
+    pub fn handle<V: MaybeVersioned>(
+        &mut self,
+        frame: &Frame<V>,
+        now: Duration,
+    ) -> Option<ParamEvent> {
+        if frame.system_id() != self.target.system
+            || (self.target.component != 0 && frame.component_id() != self.target.component)
+        {
+            return None;
+        }
+        let msg = decode::<ParamValue, V>(frame)?;
+        let source = MavLinkId::new(frame.system_id(), frame.component_id());
+        let param = Param::from_value(&msg, self.encoding);
+        let param = self.cache.insert(source, param).unwrap_or(param);
+
+        let Some(operation) = self.operation.as_mut() else {
+            return Some(ParamEvent::Received(param));
+        };
+        match operation {
+            Operation::Sync(sync) => match sync.received(&self.cache, now, self.timeout) {
+                Some(count) => {
+                    self.operation = None;
+                    Some(ParamEvent::Synced(count))
+                }
+                None => Some(ParamEvent::Received(param)),
+            },
+            Operation::Read { id: Some(id), .. } if *id == param.id => {
+                self.operation = None;
+                Some(ParamEvent::Read(param))
+            }
+            Operation::Read {
+                id: None, index, ..
+            } if *index == param.index => {
+                self.operation = None;
+                Some(ParamEvent::Read(param))
+            }
+            Operation::Set {
+                id,
+                raw,
+                param_type,
+                ..
+            } if *id == param.id => {
+                let accepted =
+                    msg.param_value.to_bits() == raw.to_bits() && msg.param_type == *param_type;
+                self.operation = None;
+                if accepted {
+                    Some(ParamEvent::Set(param))
+                } else {
+                    Some(ParamEvent::Rejected(param))
+                }
+            }
+            _ => Some(ParamEvent::Received(param)),
+        }
+    }
+
+    /// Returns a message that should be sent at `now`.
+    ///
+    /// This is either a new request or a retransmission of the last request if response has not
+    /// been received in time.
+    ///
+    /// Returns [`ServiceError::TimedOut`] and aborts operation, if all retries are exhausted.
+    pub fn poll(
+        &mut self,
+        now: Duration,
+    ) -> core::result::Result<Option<ParamMessage>, ServiceError> {
+        let result = match self.operation.as_mut() {
+            None => return Ok(None),
+            Some(Operation::Sync(sync)) => sync.poll(&self.cache, now, self.timeout, self.retries),
+            Some(Operation::Read { request, .. } | Operation::Set { request, .. }) => {
+                request.poll(now, self.timeout, self.retries)
+            }
+        };
+        if result.is_err() {
+            self.operation = None;
+        }
+        result
+    }
+
+    /// Time left until [`ParamClient::poll`] should be called.
+    ///
+    /// Returns [`None`] if there is no operation in progress.
+    pub fn timeout(&self, now: Duration) -> Option<Duration> {
+        Some(match self.operation.as_ref()? {
+            Operation::Sync(sync) => sync.timeout(now),
+            Operation::Read { request, .. } | Operation::Set { request, .. } => {
+                request.timeout(now)
+            }
+        })
+    }
+
+    fn start(&mut self, operation: Operation) -> core::result::Result<(), ServiceError> {
+        if self.operation.is_some() {
+            return Err(ServiceError::Busy);
+        }
+        self.operation = Some(operation);
+        Ok(())
+    }
+}
+
+fn read_request(target: MavLinkId, index: u16) -> ParamMessage {
+    ParamMessage::RequestRead(ParamRequestRead {
+        target_system: target.system,
+        target_component: target.component,
+        param_id: [0; PARAM_ID_LEN],
+        param_index: index as i16,
+    })
+}
+
+#[cfg(feature = "std")]
+impl ParamClient {
+    /// <sup>`std`</sup>
+    /// Synchronizes all parameters over a blocking [`Connection`].
+    ///
+    /// Frames that are not related to the parameter protocol are discarded.
+    ///
+    /// Returns [`ServiceError::TimedOut`] wrapped into [`Error::Service`] if remote component has
+    /// stopped responding.
+    ///
+    /// Available only when `std` feature is enabled.
+    pub fn sync<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+    ) -> crate::Result<&ParamCache>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        self.start_sync()?;
+        self.drive(connection)?;
+        Ok(&self.cache)
+    }
+
+    /// <sup>`std`</sup>
+    /// Reads a parameter with a specified name over a blocking [`Connection`].
+    ///
+    /// See [`ParamClient::sync`] for details.
+    ///
+    /// Available only when `std` feature is enabled.
+    pub fn read<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+        name: &str,
+    ) -> crate::Result<Param>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        self.start_read(name)?;
+        match self.drive(connection)? {
+            ParamEvent::Read(param) => Ok(param),
+            _ => unreachable!("read is completed only by `ParamEvent::Read`"),
+        }
+    }
+
+    /// <sup>`std`</sup>
+    /// Sets a parameter with a specified name over a blocking [`Connection`] and verifies the
+    /// echoed value.
+    ///
+    /// Returns [`ServiceError::ParamRejected`] wrapped into [`Error::Service`] if echoed value
+    /// differs from the requested one. See [`ParamClient::sync`] for other details.
+    ///
+    /// Available only when `std` feature is enabled.
+    pub fn set<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+        name: &str,
+        value: impl Into<TypedValue>,
+    ) -> crate::Result<Param>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        self.start_set(name, value)?;
+        match self.drive(connection)? {
+            ParamEvent::Set(param) => Ok(param),
+            _ => unreachable!("set is completed only by `ParamEvent::Set`"),
+        }
+    }
+
+    fn drive<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+    ) -> crate::Result<ParamEvent>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        run(self, connection, |client, frame, now| {
+            Ok(completed(client.handle(frame, now))?)
+        })
+    }
+}
+
+#[cfg(feature = "tokio-rt")]
+impl ParamClient {
+    /// <sup>`tokio-rt`</sup>
+    /// Synchronizes all parameters over [`AsyncConnection`].
+    ///
+    /// Asynchronous counterpart of [`ParamClient::sync`]. Must be called within Tokio runtime.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    pub async fn sync_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+    ) -> crate::Result<&ParamCache>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        self.start_sync()?;
+        self.drive_async(connection).await?;
+        Ok(&self.cache)
+    }
+
+    /// <sup>`tokio-rt`</sup>
+    /// Reads a parameter with a specified name over [`AsyncConnection`].
+    ///
+    /// Asynchronous counterpart of [`ParamClient::read`]. Must be called within Tokio runtime.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    pub async fn read_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+        name: &str,
+    ) -> crate::Result<Param>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        self.start_read(name)?;
+        match self.drive_async(connection).await? {
+            ParamEvent::Read(param) => Ok(param),
+            _ => unreachable!("read is completed only by `ParamEvent::Read`"),
+        }
+    }
+
+    /// <sup>`tokio-rt`</sup>
+    /// Sets a parameter with a specified name over [`AsyncConnection`] and verifies the echoed
+    /// value.
+    ///
+    /// Asynchronous counterpart of [`ParamClient::set`]. Must be called within Tokio runtime.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    pub async fn set_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+        name: &str,
+        value: impl Into<TypedValue>,
+    ) -> crate::Result<Param>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        self.start_set(name, value)?;
+        match self.drive_async(connection).await? {
+            ParamEvent::Set(param) => Ok(param),
+            _ => unreachable!("set is completed only by `ParamEvent::Set`"),
+        }
+    }
+
+    async fn drive_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+    ) -> crate::Result<ParamEvent>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        run_async(self, connection, |client, frame, now| {
+            Ok(completed(client.handle(frame, now))?)
+        })
+        .await
+    }
+}
+
+#[cfg(feature = "std")]
+impl Driven for ParamClient {
+    fn poll_frame<V: Versioned>(
+        &mut self,
+        endpoint: &Endpoint<V>,
+        now: Duration,
+    ) -> crate::Result<Option<Frame<V>>> {
+        self.poll(now)?
+            .map(|message| endpoint.next_frame(message.as_message()))
+            .transpose()
+    }
+
+    fn poll_timeout(&self, now: Duration) -> Option<Duration> {
+        self.timeout(now)
+    }
+
+    fn abort(&mut self) {
+        self.operation = None;
+    }
+}
+
+/// Returns an event if it completes operation.
+#[cfg(feature = "std")]
+fn completed(event: Option<ParamEvent>) -> core::result::Result<Option<ParamEvent>, ServiceError> {
+    match event {
+        Some(ParamEvent::Rejected(_)) => Err(ServiceError::ParamRejected),
+        Some(ParamEvent::Received(_)) | None => Ok(None),
+        event => Ok(event),
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::protocol::V2;
+    use crate::utils::test_utils::{message_frame, spawn_remote};
+
+    const GCS: MavLinkId = MavLinkId {
+        system: 255,
+        component: 190,
+    };
+    const VEHICLE: MavLinkId = MavLinkId {
+        system: 1,
+        component: 1,
+    };
+
+    fn value(name: &str, index: u16, count: u16, value: TypedValue) -> Frame<V2> {
+        let param = Param {
+            id: param_id(name).unwrap(),
+            index,
+            count,
+            value,
+        };
+        message_frame(VEHICLE, &param.to_value(ParamEncoding::Bytewise))
+    }
+
+    #[test]
+    fn sync_fills_gaps() {
+        let ms = Duration::from_millis;
+        let mut client = ParamClient::new(GCS, VEHICLE)
+            .with_timeout(ms(100))
+            .with_retries(1);
+        client.start_sync().unwrap();
+        assert!(matches!(
+            client.poll(ms(0)),
+            Ok(Some(ParamMessage::RequestList(_)))
+        ));
+
+        // Stream extends the deadline
+        let event = client.handle(&value("A", 0, 4, TypedValue::U8(1)), ms(50));
+        assert!(matches!(event, Some(ParamEvent::Received(_))));
+        client.handle(&value("C", 2, 4, TypedValue::F32(0.5)), ms(120));
+        assert_eq!(client.poll(ms(150)), Ok(None));
+        assert_eq!(
+            client.cache().missing().collect::<Vec<_>>(),
+            [(VEHICLE, 1), (VEHICLE, 3)]
+        );
+
+        let Ok(Some(ParamMessage::RequestRead(read))) = client.poll(ms(220)) else {
+            panic!("expected PARAM_REQUEST_READ");
+        };
+        assert_eq!(read.param_index, 1);
+
+        client.handle(&value("B", 1, 4, TypedValue::I32(-7)), ms(230));
+        let Ok(Some(ParamMessage::RequestRead(read))) = client.poll(ms(230)) else {
+            panic!("expected PARAM_REQUEST_READ");
+        };
+        assert_eq!(read.param_index, 3);
+
+        let event = client.handle(&value("D", 3, 4, TypedValue::U16(9)), ms(240));
+        assert_eq!(event, Some(ParamEvent::Synced(4)));
+        assert!(client.cache().is_complete());
+        assert_eq!(
+            client.cache().get(VEHICLE, "B").map(|p| p.value),
+            Some(TypedValue::I32(-7))
+        );
+        assert_eq!(
+            client
+                .cache()
+                .component(VEHICLE)
+                .unwrap()
+                .iter()
+                .map(Param::name)
+                .collect::<Vec<_>>(),
+            ["A", "B", "C", "D"]
+        );
+    }
+
+    #[test]
+    fn sync_retransmits_missing_while_other_values_arrive() {
+        let ms = Duration::from_millis;
+        let mut client = ParamClient::new(GCS, VEHICLE)
+            .with_timeout(ms(100))
+            .with_retries(1);
+        client.start_sync().unwrap();
+        client.poll(ms(0)).unwrap();
+        client.handle(&value("A", 0, 4, TypedValue::U8(1)), ms(50));
+
+        let Ok(Some(ParamMessage::RequestRead(read))) = client.poll(ms(150)) else {
+            panic!("expected PARAM_REQUEST_READ");
+        };
+        assert_eq!(read.param_index, 1);
+
+        // Unrelated value neither resends the request nor restores retries
+        client.handle(&value("D", 3, 4, TypedValue::U8(4)), ms(200));
+        assert_eq!(client.poll(ms(200)), Ok(None));
+        let Ok(Some(ParamMessage::RequestRead(read))) = client.poll(ms(250)) else {
+            panic!("expected PARAM_REQUEST_READ");
+        };
+        assert_eq!(read.param_index, 1);
+        client.handle(&value("C", 2, 4, TypedValue::U8(3)), ms(300));
+        assert_eq!(client.poll(ms(350)), Err(ServiceError::TimedOut));
+    }
+
+    #[test]
+    fn sync_keeps_components_apart() {
+        let ms = Duration::from_millis;
+        let (autopilot, camera) = (VEHICLE, MavLinkId::new(1, 100));
+        let mut client = ParamClient::new(GCS, MavLinkId::new(1, 0)).with_timeout(ms(100));
+        client.start_sync().unwrap();
+        client.poll(ms(0)).unwrap();
+
+        let value = |id: MavLinkId, name: &str, index: u16, count: u16| {
+            let param = Param {
+                id: param_id(name).unwrap(),
+                index,
+                count,
+                value: TypedValue::U8(1),
+            };
+            message_frame(id, &param.to_value(ParamEncoding::Bytewise))
+        };
+        client.handle(&value(autopilot, "A", 0, 2), ms(10));
+        client.handle(&value(camera, "X", 0, 1), ms(20));
+        assert!(client.cache().get(autopilot, "A").is_some());
+        assert!(client.cache().get(camera, "A").is_none());
+
+        // Missing parameters are requested from the corresponding component
+        let Ok(Some(ParamMessage::RequestRead(read))) = client.poll(ms(120)) else {
+            panic!("expected PARAM_REQUEST_READ");
+        };
+        assert_eq!((read.target_component, read.param_index), (1, 1));
+
+        let event = client.handle(&value(autopilot, "B", 1, 2), ms(130));
+        assert_eq!(event, Some(ParamEvent::Synced(3)));
+        assert_eq!(client.cache().len(), 3);
+    }
+
+    #[test]
+    fn sync_without_parameters() {
+        let ms = Duration::from_millis;
+        let mut client = ParamClient::new(GCS, VEHICLE);
+        client.start_sync().unwrap();
+        client.poll(ms(0)).unwrap();
+
+        let event = client.handle(&value("X", u16::MAX, 0, TypedValue::F32(0.0)), ms(10));
+        assert_eq!(event, Some(ParamEvent::Synced(0)));
+        assert!(client.cache().is_complete());
+        assert!(client.cache().is_empty());
+        assert!(!client.is_busy());
+    }
+
+    #[test]
+    fn sync_times_out() {
+        let ms = Duration::from_millis;
+        let mut client = ParamClient::new(GCS, VEHICLE)
+            .with_timeout(ms(100))
+            .with_retries(1);
+        client.start_sync().unwrap();
+        assert_eq!(client.start_read_index(0), Err(ServiceError::Busy));
+        client.poll(ms(0)).unwrap();
+        assert!(matches!(
+            client.poll(ms(100)),
+            Ok(Some(ParamMessage::RequestList(_)))
+        ));
+        assert_eq!(client.poll(ms(200)), Err(ServiceError::TimedOut));
+        assert!(!client.is_busy());
+    }
+
+    #[test]
+    fn read_and_set() {
+        let ms = Duration::from_millis;
+        let mut client = ParamClient::new(GCS, VEHICLE).with_encoding(ParamEncoding::CCast);
+        assert_eq!(
+            client.start_read("SEVENTEEN_CHARS_ID"),
+            Err(ServiceError::InvalidParamId)
+        );
+        assert_eq!(
+            client.start_read_index(i16::MAX as u16 + 1),
+            Err(ServiceError::InvalidParamIndex)
+        );
+
+        client.start_read_index(5).unwrap();
+        client.poll(ms(0)).unwrap();
+        let param = Param {
+            id: param_id("RATE").unwrap(),
+            index: 5,
+            count: 10,
+            value: TypedValue::U16(400),
+        };
+        let frame = message_frame(VEHICLE, &param.to_value(ParamEncoding::CCast));
+        assert_eq!(client.handle(&frame, ms(10)), Some(ParamEvent::Read(param)));
+
+        // Echo with unknown index is matched by name
+        client.start_set("RATE", 500u16).unwrap();
+        let Ok(Some(ParamMessage::Set(set))) = client.poll(ms(20)) else {
+            panic!("expected PARAM_SET");
+        };
+        assert_eq!(set.param_value, 500.0);
+        let echo = Param {
+            index: u16::MAX,
+            value: TypedValue::U16(450),
+            ..param
+        };
+        let frame = message_frame(VEHICLE, &echo.to_value(ParamEncoding::CCast));
+        let Some(ParamEvent::Rejected(rejected)) = client.handle(&frame, ms(30)) else {
+            panic!("expected parameter to be rejected");
+        };
+        assert_eq!(rejected.index, 5);
+        assert_eq!(
+            client.cache().get_index(VEHICLE, 5).map(|p| p.value),
+            Some(TypedValue::U16(450))
+        );
+    }
+
+    #[test]
+    fn blocking_set() {
+        let (mut connection, vehicle) = spawn_remote(GCS, VEHICLE, |mut vehicle| {
+            // The first request is lost
+            vehicle.recv().unwrap();
+            let frame = vehicle.recv().unwrap();
+            let set = decode::<ParamSet, V2>(&frame).unwrap();
+            vehicle
+                .send_message(&ParamValue {
+                    param_id: set.param_id,
+                    param_value: set.param_value,
+                    param_type: set.param_type,
+                    param_count: 1,
+                    param_index: 0,
+                })
+                .unwrap();
+        });
+
+        let mut client = ParamClient::new(GCS, VEHICLE).with_timeout(Duration::from_millis(20));
+        let param = client.set(&mut connection, "GAIN", 1.5f32).unwrap();
+        assert_eq!(param.value, TypedValue::F32(1.5));
+        assert_eq!(param.name(), "GAIN");
+        vehicle.join().unwrap();
+    }
+}
@@ -0,0 +1,372 @@
+//! # Parameter protocol
+//!
+//! Implements MAVLink [parameter protocol](https://mavlink.io/en/services/parameter.html).
+//!
+//! * [`ParamClient`] synchronizes, reads, and sets parameters of a remote component, see
+//!   [`client`]. Available only when `alloc` feature is enabled.
+//! * [`ParamServer`] serves parameters of a [`ParamTable`] on behalf of a component, see
+//!   [`server`] and [`table`].
+//!
+//! Parameter values are transmitted as `f32` regardless of their actual type. This module provides
+//! [`TypedValue`] that converts typed values to and from their wire representation according to
+//! [`ParamEncoding`] used by remote system.
+
+use crate::microservices::parameter::enums::{MavParamType, MavProtocolCapability};
+use crate::microservices::parameter::messages::{
+    ParamRequestList, ParamRequestRead, ParamSet, ParamValue,
+};
+use crate::protocol::Message;
+
+#[cfg(feature = "alloc")]
+pub mod client;
+pub(crate) mod queue;
+#[cfg(feature = "alloc")]
+pub(crate) mod request;
+pub mod server;
+pub mod table;
+
+#[cfg(feature = "alloc")]
+#[doc(inline)]
+pub use client::{
+    CachedParam, ComponentParams, ParamCache, ParamClient, ParamEvent, DEFAULT_PARAM_RETRIES,
+    DEFAULT_PARAM_TIMEOUT,
+};
+#[doc(inline)]
+pub use server::{
+    ParamServer, ParamServerEvent, DEFAULT_PARAM_QUEUE_CAPACITY, DEFAULT_PARAM_STREAM_INTERVAL,
+};
+#[cfg(feature = "std")]
+#[doc(inline)]
+pub use table::{DynamicParamTable, ParamValidator};
+#[doc(inline)]
+pub use table::{ParamEntry, ParamTable};
+
+/// Maximum length of a parameter name in bytes.
+pub const PARAM_ID_LEN: usize = 16;
+
+/// Encoding of parameter values.
+///
+/// See [parameter encoding](https://mavlink.io/en/services/parameter.html#parameter-encoding) in
+/// MAVLink documentation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ParamEncoding {
+    /// Bytes of the value are copied into `f32` field as is (used by PX4).
+    ///
+    /// Only types up to 32 bits can be encoded losslessly. For 64-bit types the lower 32 bits are
+    /// transmitted.
+    #[default]
+    Bytewise,
+    /// Value is cast to `f32` (used by ArduPilot).
+    ///
+    /// Integer values that can't be represented as `f32` exactly lose precision.
+    CCast,
+}
+
+impl ParamEncoding {
+    /// Encoding advertised by `AUTOPILOT_VERSION` capabilities.
+    ///
+    /// Returns [`None`] if none of the parameter encoding flags is set.
+    pub fn from_capabilities(capabilities: MavProtocolCapability) -> Option<Self> {
+        if capabilities.contains(MavProtocolCapability::PARAM_ENCODE_BYTEWISE) {
+            Some(ParamEncoding::Bytewise)
+        } else if capabilities.contains(MavProtocolCapability::PARAM_ENCODE_C_CAST) {
+            Some(ParamEncoding::CCast)
+        } else {
+            None
+        }
+    }
+}
+
+/// Typed value of a parameter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TypedValue {
+    /// `MAV_PARAM_TYPE_UINT8`.
+    U8(u8),
+    /// `MAV_PARAM_TYPE_INT8`.
+    I8(i8),
+    /// `MAV_PARAM_TYPE_UINT16`.
+    U16(u16),
+    /// `MAV_PARAM_TYPE_INT16`.
+    I16(i16),
+    /// `MAV_PARAM_TYPE_UINT32`.
+    U32(u32),
+    /// `MAV_PARAM_TYPE_INT32`.
+    I32(i32),
+    /// `MAV_PARAM_TYPE_UINT64`.
+    U64(u64),
+    /// `MAV_PARAM_TYPE_INT64`.
+    I64(i64),
+    /// `MAV_PARAM_TYPE_REAL32`.
+    F32(f32),
+    /// `MAV_PARAM_TYPE_REAL64`.
+    F64(f64),
+}
+
+impl TypedValue {
+    /// Type of the value.
+    pub fn param_type(&self) -> MavParamType {
+        match self {
+            TypedValue::U8(_) => MavParamType::Uint8,
+            TypedValue::I8(_) => MavParamType::Int8,
+            TypedValue::U16(_) => MavParamType::Uint16,
+            TypedValue::I16(_) => MavParamType::Int16,
+            TypedValue::U32(_) => MavParamType::Uint32,
+            TypedValue::I32(_) => MavParamType::Int32,
+            TypedValue::U64(_) => MavParamType::Uint64,
+            TypedValue::I64(_) => MavParamType::Int64,
+            TypedValue::F32(_) => MavParamType::Real32,
+            TypedValue::F64(_) => MavParamType::Real64,
+        }
+    }
+
+    /// Decodes a value of a given type from its wire representation.
+    pub fn decode(raw: f32, param_type: MavParamType, encoding: ParamEncoding) -> Self {
+        match encoding {
+            ParamEncoding::Bytewise => {
+                let bits = raw.to_bits();
+                match param_type {
+                    MavParamType::Uint8 => TypedValue::U8(bits as u8),
+                    MavParamType::Int8 => TypedValue::I8(bits as u8 as i8),
+                    MavParamType::Uint16 => TypedValue::U16(bits as u16),
+                    MavParamType::Int16 => TypedValue::I16(bits as u16 as i16),
+                    MavParamType::Uint32 => TypedValue::U32(bits),
+                    MavParamType::Int32 => TypedValue::I32(bits as i32),
+                    MavParamType::Uint64 => TypedValue::U64(bits as u64),
+                    MavParamType::Int64 => TypedValue::I64(bits as i32 as i64),
+                    MavParamType::Real32 => TypedValue::F32(raw),
+                    MavParamType::Real64 => TypedValue::F64(raw as f64),
+                }
+            }
+            ParamEncoding::CCast => match param_type {
+                MavParamType::Uint8 => TypedValue::U8(raw as u8),
+                MavParamType::Int8 => TypedValue::I8(raw as i8),
+                MavParamType::Uint16 => TypedValue::U16(raw as u16),
+                MavParamType::Int16 => TypedValue::I16(raw as i16),
+                MavParamType::Uint32 => TypedValue::U32(raw as u32),
+                MavParamType::Int32 => TypedValue::I32(raw as i32),
+                MavParamType::Uint64 => TypedValue::U64(raw as u64),
+                MavParamType::Int64 => TypedValue::I64(raw as i64),
+                MavParamType::Real32 => TypedValue::F32(raw),
+                MavParamType::Real64 => TypedValue::F64(raw as f64),
+            },
+        }
+    }
+
+    /// Encodes value into its wire representation.
+    pub fn encode(&self, encoding: ParamEncoding) -> f32 {
+        match encoding {
+            ParamEncoding::Bytewise => match *self {
+                TypedValue::U8(value) => f32::from_bits(value as u32),
+                TypedValue::I8(value) => f32::from_bits(value as u8 as u32),
+                TypedValue::U16(value) => f32::from_bits(value as u32),
+                TypedValue::I16(value) => f32::from_bits(value as u16 as u32),
+                TypedValue::U32(value) => f32::from_bits(value),
+                TypedValue::I32(value) => f32::from_bits(value as u32),
+                TypedValue::U64(value) => f32::from_bits(value as u32),
+                TypedValue::I64(value) => f32::from_bits(value as u32),
+                TypedValue::F32(value) => value,
+                TypedValue::F64(value) => value as f32,
+            },
+            ParamEncoding::CCast => self.as_f64() as f32,
+        }
+    }
+
+    /// Value converted to `f64`.
+    ///
+    /// Large 64-bit integers lose precision.
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            TypedValue::U8(value) => value as f64,
+            TypedValue::I8(value) => value as f64,
+            TypedValue::U16(value) => value as f64,
+            TypedValue::I16(value) => value as f64,
+            TypedValue::U32(value) => value as f64,
+            TypedValue::I32(value) => value as f64,
+            TypedValue::U64(value) => value as f64,
+            TypedValue::I64(value) => value as f64,
+            TypedValue::F32(value) => value as f64,
+            TypedValue::F64(value) => value,
+        }
+    }
+
+    /// Converts `value` into a value of a given type.
+    ///
+    /// Uses saturating casts for integer types.
+    pub fn from_f64(value: f64, param_type: MavParamType) -> Self {
+        match param_type {
+            MavParamType::Uint8 => TypedValue::U8(value as u8),
+            MavParamType::Int8 => TypedValue::I8(value as i8),
+            MavParamType::Uint16 => TypedValue::U16(value as u16),
+            MavParamType::Int16 => TypedValue::I16(value as i16),
+            MavParamType::Uint32 => TypedValue::U32(value as u32),
+            MavParamType::Int32 => TypedValue::I32(value as i32),
+            MavParamType::Uint64 => TypedValue::U64(value as u64),
+            MavParamType::Int64 => TypedValue::I64(value as i64),
+            MavParamType::Real32 => TypedValue::F32(value as f32),
+            MavParamType::Real64 => TypedValue::F64(value),
+        }
+    }
+}
+
+macro_rules! impl_from_for_typed_value {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$ty> for TypedValue {
+                fn from(value: $ty) -> Self {
+                    TypedValue::$variant(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_for_typed_value!(
+    u8 => U8, i8 => I8, u16 => U16, i16 => I16, u32 => U32,
+    i32 => I32, u64 => U64, i64 => I64, f32 => F32, f64 => F64,
+);
+
+/// Parameter of a component.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Param {
+    /// Parameter name as transmitted in `param_id` field, see [`Param::name`].
+    pub id: [u8; PARAM_ID_LEN],
+    /// Parameter index.
+    pub index: u16,
+    /// Total number of parameters of the component.
+    pub count: u16,
+    /// Parameter value.
+    pub value: TypedValue,
+}
+
+impl Param {
+    /// Decodes parameter from `PARAM_VALUE` using a specified encoding.
+    pub fn from_value(msg: &ParamValue, encoding: ParamEncoding) -> Self {
+        Self {
+            id: msg.param_id,
+            index: msg.param_index,
+            count: msg.param_count,
+            value: TypedValue::decode(msg.param_value, msg.param_type, encoding),
+        }
+    }
+
+    /// Parameter name.
+    pub fn name(&self) -> &str {
+        param_name(&self.id)
+    }
+
+    /// Encodes parameter into `PARAM_VALUE` using a specified encoding.
+    pub fn to_value(&self, encoding: ParamEncoding) -> ParamValue {
+        ParamValue {
+            param_id: self.id,
+            param_value: self.value.encode(encoding),
+            param_type: self.value.param_type(),
+            param_count: self.count,
+            param_index: self.index,
+        }
+    }
+}
+
+/// Message of a parameter protocol.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamMessage {
+    /// `PARAM_REQUEST_LIST`.
+    RequestList(ParamRequestList),
+    /// `PARAM_REQUEST_READ`.
+    RequestRead(ParamRequestRead),
+    /// `PARAM_SET`.
+    Set(ParamSet),
+}
+
+impl ParamMessage {
+    /// Parameter protocol message as a MAVLink message.
+    pub fn as_message(&self) -> &dyn Message {
+        match self {
+            ParamMessage::RequestList(msg) => msg,
+            ParamMessage::RequestRead(msg) => msg,
+            ParamMessage::Set(msg) => msg,
+        }
+    }
+}
+
+/// Encodes parameter name into `param_id` field.
+///
+/// Returns [`None`] if name is empty or longer than [`PARAM_ID_LEN`] bytes.
+pub const fn param_id(name: &str) -> Option<[u8; PARAM_ID_LEN]> {
+    let bytes = name.as_bytes();
+    if bytes.is_empty() || bytes.len() > PARAM_ID_LEN {
+        return None;
+    }
+    let mut id = [0u8; PARAM_ID_LEN];
+    let mut idx = 0;
+    while idx < bytes.len() {
+        id[idx] = bytes[idx];
+        idx += 1;
+    }
+    Some(id)
+}
+
+/// Decodes parameter name from `param_id` field.
+///
+/// Name is terminated by the first `NUL` byte or by the end of the field. Invalid UTF-8 sequences
+/// truncate the name.
+pub fn param_name(param_id: &[u8]) -> &str {
+    let len = param_id
+        .iter()
+        .position(|&b| b == 0)
+        .unwrap_or(param_id.len());
+    match core::str::from_utf8(&param_id[..len]) {
+        Ok(name) => name,
+        Err(err) => core::str::from_utf8(&param_id[..err.valid_up_to()]).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodings() {
+        let value = TypedValue::I16(-2);
+        let raw = value.encode(ParamEncoding::Bytewise);
+        assert_eq!(raw.to_bits(), 0xfffe);
+        assert_eq!(
+            TypedValue::decode(raw, MavParamType::Int16, ParamEncoding::Bytewise),
+            value
+        );
+
+        let raw = value.encode(ParamEncoding::CCast);
+        assert_eq!(raw, -2.0);
+        assert_eq!(
+            TypedValue::decode(raw, MavParamType::Int16, ParamEncoding::CCast),
+            value
+        );
+
+        let value = TypedValue::from(1_000_000i32);
+        for encoding in [ParamEncoding::Bytewise, ParamEncoding::CCast] {
+            let raw = value.encode(encoding);
+            assert_eq!(
+                TypedValue::decode(raw, MavParamType::Int32, encoding),
+                value
+            );
+        }
+        assert_eq!(
+            TypedValue::from_f64(300.0, MavParamType::Uint8),
+            TypedValue::U8(255)
+        );
+        assert_eq!(
+            ParamEncoding::from_capabilities(MavProtocolCapability::PARAM_ENCODE_C_CAST),
+            Some(ParamEncoding::CCast)
+        );
+    }
+
+    #[test]
+    fn param_names() {
+        let id = param_id("SYSID_THISMAV").unwrap();
+        assert_eq!(param_name(&id), "SYSID_THISMAV");
+        let id = param_id("SIXTEEN_CHARS_ID").unwrap();
+        assert_eq!(param_name(&id), "SIXTEEN_CHARS_ID");
+        assert!(param_id("SEVENTEEN_CHARS_ID").is_none());
+        assert!(param_id("").is_none());
+        assert_eq!(param_name(b"AB\xffCD"), "AB");
+    }
+}
@@ -0,0 +1,121 @@
+//! Queue of pending messages shared by parameter protocol servers.
+//!
+//! Both [`ParamServer`](super::ParamServer) and
+//! [`ParamExtServer`](crate::services::parameter_ext::ParamExtServer) keep responses and
+//! broadcasts in a bounded queue that takes precedence over parameters streamed in response to a
+//! request for the whole list.
+
+use core::time::Duration;
+
+/// Bounded queue of pending messages `T` followed by a throttled stream of all parameters.
+#[derive(Clone, Debug)]
+pub(crate) struct ParamQueue<T, const N: usize> {
+    items: [Option<T>; N],
+    queued: usize,
+    stream_next: Option<u16>,
+    stream_due: Duration,
+    restream: bool,
+}
+
+impl<T: Copy + PartialEq, const N: usize> ParamQueue<T, N> {
+    /// Creates an empty queue.
+    pub(crate) fn new() -> Self {
+        Self {
+            items: [None; N],
+            queued: 0,
+            stream_next: None,
+            stream_due: Duration::ZERO,
+            restream: false,
+        }
+    }
+
+    /// Returns `true` if there is no room for new messages.
+    pub(crate) fn is_full(&self) -> bool {
+        self.queued >= N
+    }
+
+    /// Returns `true` if parameters are being streamed.
+    pub(crate) fn is_streaming(&self) -> bool {
+        self.stream_next.is_some()
+    }
+
+    /// Adds a message unless the same message is already queued.
+    ///
+    /// Returns `false` if queue is full.
+    pub(crate) fn push(&mut self, item: T) -> bool {
+        if self.items[..self.queued].contains(&Some(item)) {
+            return true;
+        }
+        if self.is_full() {
+            return false;
+        }
+        self.items[self.queued] = Some(item);
+        self.queued += 1;
+        true
+    }
+
+    /// Handles a parameter with a specified index that didn't fit into the queue.
+    ///
+    /// The whole table is streamed instead. If parameter has been already streamed, the table is
+    /// streamed once again afterward.
+    pub(crate) fn overflow(&mut self, index: u16) {
+        match self.stream_next {
+            None => self.stream_next = Some(0),
+            Some(next) if index < next => self.restream = true,
+            Some(_) => {}
+        }
+    }
+
+    /// Removes the oldest message.
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        if self.queued == 0 {
+            return None;
+        }
+        let item = self.items[0].take();
+        self.items[..self.queued].rotate_left(1);
+        self.queued -= 1;
+        item
+    }
+
+    /// Starts streaming all parameters at `now`.
+    pub(crate) fn start_stream(&mut self, now: Duration) {
+        self.stream_next = Some(0);
+        self.stream_due = now;
+    }
+
+    /// Returns index of a parameter that should be streamed at `now`.
+    ///
+    /// Parameters are streamed one by one with a specified `interval` until `count` is reached.
+    pub(crate) fn stream(&mut self, now: Duration, count: u16, interval: Duration) -> Option<u16> {
+        let next = self.stream_next?;
+        if now < self.stream_due {
+            return None;
+        }
+        if next >= count {
+            self.finish_stream();
+            return None;
+        }
+        self.stream_next = next.checked_add(1).filter(|&next| next < count);
+        if self.stream_next.is_none() {
+            self.finish_stream();
+        }
+        self.stream_due = now + interval;
+        Some(next)
+    }
+
+    /// Time left until the next message is due.
+    ///
+    /// Returns [`None`] if there is nothing to send.
+    pub(crate) fn timeout(&self, now: Duration) -> Option<Duration> {
+        if self.queued > 0 {
+            return Some(Duration::ZERO);
+        }
+        self.stream_next?;
+        Some(self.stream_due.saturating_sub(now))
+    }
+
+    fn finish_stream(&mut self) {
+        self.stream_next = self.restream.then_some(0);
+        self.restream = false;
+    }
+}
@@ -0,0 +1,153 @@
+//! Retransmission and synchronization state shared by parameter protocol clients.
+//!
+//! Both [`ParamClient`](super::ParamClient) and
+//! [`ParamExtClient`](crate::services::parameter_ext::ParamExtClient) are built on top of these
+//! state machines, which are generic over the request message.
+
+use core::time::Duration;
+
+use crate::protocol::MavLinkId;
+use crate::services::parameter::{CachedParam, ParamCache};
+use crate::services::ServiceError;
+
+/// Request that is retransmitted until response is received.
+#[derive(Clone, Debug)]
+pub(crate) struct Request<M> {
+    last: M,
+    attempts: u8,
+    deadline: Option<Duration>,
+}
+
+impl<M: Clone> Request<M> {
+    /// Creates a request that should be sent on the next poll.
+    pub(crate) fn new(message: M) -> Self {
+        Self {
+            last: message,
+            attempts: 0,
+            deadline: None,
+        }
+    }
+
+    /// Returns `true` if request was sent and response has not been received in time.
+    pub(crate) fn is_expired(&self, now: Duration) -> bool {
+        self.deadline.is_some_and(|deadline| now >= deadline)
+    }
+
+    /// Postpones retransmission after a partial response, if request was already sent.
+    ///
+    /// Retries are restored.
+    pub(crate) fn extend(&mut self, now: Duration, timeout: Duration) {
+        if self.deadline.is_some() {
+            self.deadline = Some(now + timeout);
+            self.attempts = 0;
+        }
+    }
+
+    /// Waits for a response until `deadline` without retransmitting the request.
+    #[cfg_attr(not(feature = "msrv-parameter-ext"), allow(dead_code))]
+    pub(crate) fn wait(&mut self, deadline: Duration) {
+        self.deadline = Some(deadline);
+    }
+
+    /// Returns a message that should be sent at `now`.
+    ///
+    /// Returns [`ServiceError::TimedOut`] if all retries are exhausted.
+    pub(crate) fn poll(
+        &mut self,
+        now: Duration,
+        timeout: Duration,
+        retries: u8,
+    ) -> core::result::Result<Option<M>, ServiceError> {
+        match self.deadline {
+            Some(deadline) if now < deadline => return Ok(None),
+            Some(_) if self.attempts >= retries => return Err(ServiceError::TimedOut),
+            Some(_) => self.attempts += 1,
+            None => {}
+        }
+        self.deadline = Some(now + timeout);
+        Ok(Some(self.last.clone()))
+    }
+
+    /// Time left until request should be polled.
+    pub(crate) fn timeout(&self, now: Duration) -> Duration {
+        self.deadline
+            .map_or(Duration::ZERO, |deadline| deadline.saturating_sub(now))
+    }
+}
+
+/// Synchronization of all parameters.
+///
+/// Starts with a request for the whole list and, once stream of parameters stops, requests
+/// missing parameters one by one.
+#[derive(Clone, Debug)]
+pub(crate) struct Synchronization<M> {
+    request: Request<M>,
+    filling: Option<(MavLinkId, u16)>,
+    read_request: fn(MavLinkId, u16) -> M,
+}
+
+impl<M: Clone> Synchronization<M> {
+    /// Creates synchronization that sends `list_request` and then requests missing parameters by
+    /// messages produced by `read_request`.
+    pub(crate) fn new(list_request: M, read_request: fn(MavLinkId, u16) -> M) -> Self {
+        Self {
+            request: Request::new(list_request),
+            filling: None,
+            read_request,
+        }
+    }
+
+    /// Handles a parameter that has been just stored in the `cache` at `now`.
+    ///
+    /// Returns the total number of parameters once all of them are received.
+    pub(crate) fn received<P: CachedParam>(
+        &mut self,
+        cache: &ParamCache<P>,
+        now: Duration,
+        timeout: Duration,
+    ) -> Option<u16> {
+        if cache.is_complete() {
+            return Some(cache.count());
+        }
+        if self.filling.is_none() {
+            // Stream is still active
+            self.request.extend(now, timeout);
+            return None;
+        }
+        // Keep retransmission state unless the requested parameter has been received
+        let missing = cache.missing().next();
+        if missing != self.filling {
+            self.fill(missing);
+        }
+        None
+    }
+
+    /// Returns a message that should be sent at `now`.
+    ///
+    /// Returns [`ServiceError::TimedOut`] if all retries are exhausted.
+    pub(crate) fn poll<P: CachedParam>(
+        &mut self,
+        cache: &ParamCache<P>,
+        now: Duration,
+        timeout: Duration,
+        retries: u8,
+    ) -> core::result::Result<Option<M>, ServiceError> {
+        // Stream of parameters has stopped, request missing parameters one by one
+        if self.filling.is_none() && self.request.is_expired(now) {
+            self.fill(cache.missing().next());
+        }
+        self.request.poll(now, timeout, retries)
+    }
+
+    fn fill(&mut self, missing: Option<(MavLinkId, u16)>) {
+        if let Some((component, index)) = missing {
+            self.filling = missing;
+            self.request = Request::new((self.read_request)(component, index));
+        }
+    }
+
+    /// Time left until synchronization should be polled.
+    pub(crate) fn timeout(&self, now: Duration) -> Duration {
+        self.request.timeout(now)
+    }
+}
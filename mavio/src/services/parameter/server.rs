@@ -0,0 +1,486 @@
+//! # Parameter protocol server
+//!
+//! Implements component side of MAVLink
+//! [parameter protocol](https://mavlink.io/en/services/parameter.html).
+//!
+//! [`ParamServer`] is a sans-I/O state machine that serves parameters of a [`ParamTable`]:
+//!
+//! * Streams all parameters on `PARAM_REQUEST_LIST` at a throttled rate.
+//! * Answers `PARAM_REQUEST_READ` by name or by index.
+//! * Validates and applies `PARAM_SET` and responds with the resulting value.
+//! * Broadcasts `PARAM_VALUE` whenever parameter is changed.
+
+use core::time::Duration;
+
+use crate::microservices::parameter::messages::{
+    ParamRequestList, ParamRequestRead, ParamSet, ParamValue,
+};
+use crate::protocol::{Frame, MavLinkId, MaybeVersioned};
+use crate::services::parameter::queue::ParamQueue;
+use crate::services::parameter::{
+    param_id, Param, ParamEncoding, ParamEntry, ParamTable, TypedValue,
+};
+use crate::services::{decode, is_addressed_to};
+
+/// Default interval between `PARAM_VALUE` messages streamed in response to `PARAM_REQUEST_LIST`.
+pub const DEFAULT_PARAM_STREAM_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Default capacity of a [`ParamServer`] queue of pending `PARAM_VALUE` messages.
+pub const DEFAULT_PARAM_QUEUE_CAPACITY: usize = 8;
+
+/// Event produced by [`ParamServer::handle`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamServerEvent {
+    /// Parameter was changed by remote system.
+    Changed(Param),
+    /// Value requested by remote system was rejected, contains unchanged parameter.
+    Rejected(Param),
+}
+
+/// Sans-I/O parameter protocol server.
+///
+/// Serves parameters of a [`ParamTable`] `T` on behalf of a component with a specified `ID`. Pass
+/// incoming frames to [`ParamServer::handle`] and call [`ParamServer::poll`] to obtain messages
+/// that should be sent. Call [`ParamServer::poll`] after each [`ParamServer::handle`] and whenever
+/// [`ParamServer::timeout`] expires.
+///
+/// Responses to `PARAM_REQUEST_READ` and `PARAM_SET`, as well as broadcasts of changed values,
+/// are kept in a queue of `N` parameters and sent before parameters streamed in response to
+/// `PARAM_REQUEST_LIST`. If queue overflows, the whole table is streamed instead. Overflowed
+/// parameters that were already streamed cause the table to be streamed once again.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use mavio::microservices::parameter::messages::ParamRequestList;
+/// use mavio::services::parameter::{ParamEntry, ParamServer, TypedValue};
+/// use mavio::prelude::*;
+///
+/// const PARAMS: [ParamEntry; 2] = [
+///     ParamEntry::new("SYSID_THISMAV", TypedValue::U8(1)).read_only(),
+///     ParamEntry::new("RATE_HZ", TypedValue::U16(50)).with_range(1.0, 400.0),
+/// ];
+///
+/// let gcs = Endpoint::v2(MavLinkId::new(255, 190));
+/// let mut server: ParamServer<_> = ParamServer::new(MavLinkId::new(1, 1), PARAMS);
+///
+/// let request = gcs.next_frame(&ParamRequestList {
+///     target_system: 1,
+///     target_component: 1,
+/// }).unwrap();
+/// server.handle(&request, Duration::ZERO);
+///
+/// let value = server.poll(Duration::ZERO).unwrap();
+/// assert_eq!(value.param_index, 0);
+/// // The next parameter is throttled
+/// assert!(server.poll(Duration::ZERO).is_none());
+/// assert_eq!(server.poll(Duration::from_millis(20)).unwrap().param_index, 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ParamServer<T: ParamTable, const N: usize = DEFAULT_PARAM_QUEUE_CAPACITY> {
+    id: MavLinkId,
+    table: T,
+    encoding: ParamEncoding,
+    stream_interval: Duration,
+    queue: ParamQueue<u16, N>,
+}
+
+impl<T: ParamTable, const N: usize> ParamServer<T, N> {
+    /// Creates a server for a component with a specified `id` backed by `table`.
+    pub fn new(id: MavLinkId, table: T) -> Self {
+        Self {
+            id,
+            table,
+            encoding: ParamEncoding::default(),
+            stream_interval: DEFAULT_PARAM_STREAM_INTERVAL,
+            queue: ParamQueue::new(),
+        }
+    }
+
+    /// Sets encoding of parameter values.
+    pub fn with_encoding(self, encoding: ParamEncoding) -> Self {
+        Self { encoding, ..self }
+    }
+
+    /// Sets interval between `PARAM_VALUE` messages streamed in response to `PARAM_REQUEST_LIST`.
+    pub fn with_stream_interval(self, stream_interval: Duration) -> Self {
+        Self {
+            stream_interval,
+            ..self
+        }
+    }
+
+    /// `ID` of the component this server acts on behalf of.
+    #[inline(always)]
+    pub fn id(&self) -> MavLinkId {
+        self.id
+    }
+
+    /// Encoding of parameter values.
+    #[inline(always)]
+    pub fn encoding(&self) -> ParamEncoding {
+        self.encoding
+    }
+
+    /// Parameter table.
+    #[inline(always)]
+    pub fn table(&self) -> &T {
+        &self.table
+    }
+
+    /// Mutable reference to parameter table.
+    ///
+    /// Changes made directly to the table are not announced to remote systems, use
+    /// [`ParamServer::set`] or [`ParamServer::announce`] instead.
+    #[inline(always)]
+    pub fn table_mut(&mut self) -> &mut T {
+        &mut self.table
+    }
+
+    /// Returns `true` if parameters are being streamed in response to `PARAM_REQUEST_LIST`.
+    #[inline(always)]
+    pub fn is_streaming(&self) -> bool {
+        self.queue.is_streaming()
+    }
+
+    /// Returns a parameter with a specified name.
+    pub fn get(&self, name: &str) -> Option<Param> {
+        let index = self.table.index_of(&param_id(name)?)?;
+        self.param(index)
+    }
+
+    /// Changes a parameter on behalf of the component and broadcasts the new value.
+    ///
+    /// Range and read-only restrictions are not applied. Returns `false` if there is no such
+    /// parameter or value has a different type.
+    pub fn set(&mut self, name: &str, value: impl Into<TypedValue>) -> bool {
+        let Some(index) = param_id(name).and_then(|id| self.table.index_of(&id)) else {
+            return false;
+        };
+        let changed = self
+            .table
+            .entry_mut(index)
+            .is_some_and(|entry| entry.set_value(value.into()));
+        if changed {
+            self.enqueue(index);
+        }
+        changed
+    }
+
+    /// Broadcasts current value of a parameter with a specified index.
+    ///
+    /// Use this method to announce changes made through [`ParamServer::table_mut`].
+    pub fn announce(&mut self, index: u16) {
+        if index < self.table.count() {
+            self.enqueue(index);
+        }
+    }
+
+    /// Handles incoming frame received at `now`.
+    ///
+    /// Call [`ParamServer::poll`] afterward to obtain responses.
+    pub fn handle<V: MaybeVersioned>(
+        &mut self,
+        frame: &Frame<V>,
+        now: Duration,
+    ) -> Option<ParamServerEvent> {
+        if let Some(msg) = decode::<ParamRequestList, V>(frame) {
+            if is_addressed_to(msg.target_system, msg.target_component, self.id) {
+                self.queue.start_stream(now);
+            }
+        } else if let Some(msg) = decode::<ParamRequestRead, V>(frame) {
+            if is_addressed_to(msg.target_system, msg.target_component, self.id) {
+                // Unknown parameters are not answered according to the protocol
+                let index = match u16::try_from(msg.param_index) {
+                    Ok(index) if index < self.table.count() => Some(index),
+                    Ok(_) => None,
+                    Err(_) => self.table.index_of(&msg.param_id),
+                };
+                if let Some(index) = index {
+                    self.enqueue(index);
+                }
+            }
+        } else if let Some(msg) = decode::<ParamSet, V>(frame) {
+            if is_addressed_to(msg.target_system, msg.target_component, self.id) {
+                return self.handle_set(msg);
+            }
+        }
+        None
+    }
+
+    /// Returns a message that should be sent at `now`.
+    ///
+    /// Call this method until it returns [`None`].
+    pub fn poll(&mut self, now: Duration) -> Option<ParamValue> {
+        while let Some(index) = self.queue.pop() {
+            if let Some(param) = self.param(index) {
+                return Some(param.to_value(self.encoding));
+            }
+        }
+
+        let index = self
+            .queue
+            .stream(now, self.table.count(), self.stream_interval)?;
+        Some(self.param(index)?.to_value(self.encoding))
+    }
+
+    /// Time left until [`ParamServer::poll`] should be called.
+    ///
+    /// Returns [`None`] if there is nothing to send.
+    pub fn timeout(&self, now: Duration) -> Option<Duration> {
+        self.queue.timeout(now)
+    }
+
+    fn handle_set(&mut self, msg: ParamSet) -> Option<ParamServerEvent> {
+        let index = self.table.index_of(&msg.param_id)?;
+        let entry = self.table.entry(index)?;
+        let accepted = entry.value().param_type() == msg.param_type && {
+            let value = TypedValue::decode(msg.param_value, msg.param_type, self.encoding);
+            self.table.set(index, value)
+        };
+
+        // The current value is sent in both cases, acknowledging or rejecting the request
+        self.enqueue(index);
+        let param = self.param(index)?;
+        Some(if accepted {
+            ParamServerEvent::Changed(param)
+        } else {
+            ParamServerEvent::Rejected(param)
+        })
+    }
+
+    fn param(&self, index: u16) -> Option<Param> {
+        let entry: &ParamEntry = self.table.entry(index)?;
+        Some(Param {
+            id: *entry.id(),
+            index,
+            count: self.table.count(),
+            value: entry.value(),
+        })
+    }
+
+    fn enqueue(&mut self, index: u16) {
+        if !self.queue.push(index) {
+            self.queue.overflow(index);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::protocol::V2;
+    use crate::services::parameter::DynamicParamTable;
+    use crate::utils::test_utils::message_frame;
+
+    const GCS: MavLinkId = MavLinkId {
+        system: 255,
+        component: 190,
+    };
+    const SERVER: MavLinkId = MavLinkId {
+        system: 1,
+        component: 1,
+    };
+
+    fn table() -> [ParamEntry; 3] {
+        [
+            ParamEntry::new("SYSID_THISMAV", TypedValue::U8(1)).read_only(),
+            ParamEntry::new("RATE_HZ", TypedValue::U16(50)).with_range(1.0, 400.0),
+            ParamEntry::new("GAIN", TypedValue::F32(0.5)),
+        ]
+    }
+
+    fn set_frame(name: &str, value: TypedValue) -> Frame<V2> {
+        message_frame(
+            GCS,
+            &ParamSet {
+                target_system: 1,
+                target_component: 1,
+                param_id: param_id(name).unwrap(),
+                param_value: value.encode(ParamEncoding::Bytewise),
+                param_type: value.param_type(),
+            },
+        )
+    }
+
+    #[test]
+    fn stream_is_throttled() {
+        let mut server: ParamServer<_> = ParamServer::new(SERVER, table());
+        let request = message_frame(
+            GCS,
+            &ParamRequestList {
+                target_system: 1,
+                target_component: 0,
+            },
+        );
+        server.handle(&request, Duration::ZERO);
+
+        let mut now = Duration::ZERO;
+        let mut indices = Vec::new();
+        while server.is_streaming() {
+            match server.poll(now) {
+                Some(value) => {
+                    assert_eq!(value.param_count, 3);
+                    indices.push(value.param_index);
+                }
+                None => now += server.timeout(now).unwrap(),
+            }
+        }
+        assert_eq!(indices, vec![0, 1, 2]);
+        assert_eq!(now, DEFAULT_PARAM_STREAM_INTERVAL * 2);
+        assert!(server.timeout(now).is_none());
+    }
+
+    #[test]
+    fn read_by_name_and_index() {
+        let mut server: ParamServer<_> = ParamServer::new(SERVER, table());
+        for (name, index) in [("GAIN", -1), ("", 1), ("UNKNOWN", -1), ("", 7)] {
+            let request = message_frame(
+                GCS,
+                &ParamRequestRead {
+                    target_system: 1,
+                    target_component: 1,
+                    param_id: param_id(name).unwrap_or_default(),
+                    param_index: index,
+                },
+            );
+            server.handle(&request, Duration::ZERO);
+        }
+
+        let value = server.poll(Duration::ZERO).unwrap();
+        assert_eq!((value.param_index, value.param_value), (2, 0.5));
+        assert_eq!(server.poll(Duration::ZERO).unwrap().param_index, 1);
+        assert!(server.poll(Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn set_is_validated() {
+        let mut server: ParamServer<_> = ParamServer::new(SERVER, table());
+
+        let event = server.handle(&set_frame("RATE_HZ", TypedValue::U16(100)), Duration::ZERO);
+        assert!(
+            matches!(event, Some(ParamServerEvent::Changed(p)) if p.value == TypedValue::U16(100))
+        );
+        assert_eq!(server.poll(Duration::ZERO).unwrap().param_index, 1);
+
+        for (name, value) in [
+            ("RATE_HZ", TypedValue::U16(1000)),
+            ("RATE_HZ", TypedValue::U32(100)),
+            ("SYSID_THISMAV", TypedValue::U8(2)),
+        ] {
+            let event = server.handle(&set_frame(name, value), Duration::ZERO);
+            assert!(matches!(event, Some(ParamServerEvent::Rejected(_))));
+        }
+        assert!(server
+            .handle(&set_frame("UNKNOWN", TypedValue::U8(1)), Duration::ZERO)
+            .is_none());
+
+        // Responses for the same parameter are merged
+        let value = server.poll(Duration::ZERO).unwrap();
+        assert_eq!(value.param_index, 1);
+        assert_eq!(value.param_value.to_bits(), 100);
+        assert_eq!(server.poll(Duration::ZERO).unwrap().param_index, 0);
+        assert!(server.poll(Duration::ZERO).is_none());
+        assert_eq!(
+            server.get("SYSID_THISMAV").unwrap().value,
+            TypedValue::U8(1)
+        );
+    }
+
+    #[test]
+    fn local_changes_are_broadcast() {
+        let mut server: ParamServer<_, 1> = ParamServer::new(SERVER, table());
+        assert!(server.set("SYSID_THISMAV", 3u8));
+        assert!(!server.set("SYSID_THISMAV", 3u16));
+        assert_eq!(server.poll(Duration::ZERO).unwrap().param_index, 0);
+
+        // Overflow falls back to streaming the whole table
+        assert!(server.set("RATE_HZ", 10u16));
+        assert!(server.set("GAIN", 1.5f32));
+        assert!(server.is_streaming());
+    }
+
+    #[test]
+    fn overflow_during_stream_restreams_table() {
+        let mut server: ParamServer<_, 1> = ParamServer::new(SERVER, table());
+        let request = message_frame(
+            GCS,
+            &ParamRequestList {
+                target_system: 1,
+                target_component: 1,
+            },
+        );
+        server.handle(&request, Duration::ZERO);
+        assert_eq!(server.poll(Duration::ZERO).unwrap().param_index, 0);
+
+        // The first parameter has been already streamed and doesn't fit into queue
+        assert!(server.set("GAIN", 1.5f32));
+        assert!(server.set("SYSID_THISMAV", 3u8));
+
+        let mut now = Duration::ZERO;
+        let mut indices = Vec::new();
+        while server.timeout(now).is_some() {
+            match server.poll(now) {
+                Some(value) => indices.push(value.param_index),
+                None => now += server.timeout(now).unwrap(),
+            }
+        }
+        assert_eq!(indices, vec![2, 1, 2, 0, 1, 2]);
+    }
+
+    #[test]
+    fn dynamic_table_validator() {
+        let mut table = DynamicParamTable::new().with_validator(|_, value| value.as_f64() >= 0.0);
+        table.insert(ParamEntry::new("GAIN", TypedValue::F32(0.5)));
+        let mut server: ParamServer<_> =
+            ParamServer::new(SERVER, table).with_encoding(ParamEncoding::CCast);
+
+        let frame = message_frame(
+            GCS,
+            &ParamSet {
+                target_system: 1,
+                target_component: 1,
+                param_id: param_id("GAIN").unwrap(),
+                param_value: -1.0,
+                param_type: crate::microservices::parameter::enums::MavParamType::Real32,
+            },
+        );
+        let event = server.handle(&frame, Duration::ZERO);
+        assert!(matches!(event, Some(ParamServerEvent::Rejected(_))));
+        assert_eq!(server.poll(Duration::ZERO).unwrap().param_value, 0.5);
+    }
+
+    #[test]
+    fn client_synchronizes_with_server() {
+        use crate::services::parameter::{ParamClient, ParamEvent};
+
+        let mut server: ParamServer<_> = ParamServer::new(SERVER, table());
+        let mut client = ParamClient::new(GCS, SERVER);
+
+        client.start_sync().unwrap();
+        let mut now = Duration::ZERO;
+        let mut synced = None;
+        for _ in 0..100 {
+            while let Some(msg) = client.poll(now).unwrap() {
+                server.handle(&message_frame(GCS, msg.as_message()), now);
+            }
+            while let Some(value) = server.poll(now) {
+                let frame = message_frame(SERVER, &value);
+                if let Some(ParamEvent::Synced(count)) = client.handle(&frame, now) {
+                    synced = Some(count);
+                }
+            }
+            if synced.is_some() {
+                break;
+            }
+            now += Duration::from_millis(10);
+        }
+        assert_eq!(synced, Some(3));
+        assert_eq!(
+            client.cache().get(SERVER, "RATE_HZ").unwrap().value,
+            TypedValue::U16(50)
+        );
+    }
+}
@@ -0,0 +1,372 @@
+//! # Parameter tables
+//!
+//! [`ParamTable`] is a storage backend of a [`ParamServer`](super::ParamServer).
+//!
+//! This module provides two implementations:
+//!
+//! * Arrays and slices of [`ParamEntry`] form static tables suitable for `no_std` and `no_alloc`
+//!   targets.
+//! * [`DynamicParamTable`] allows to add and remove parameters at runtime and to validate new
+//!   values with a custom function. Available only when `std` feature is enabled.
+
+use crate::services::parameter::{param_id, param_name, TypedValue, PARAM_ID_LEN};
+
+/// Parameter definition stored in a [`ParamTable`].
+///
+/// New values are accepted only if they have the same type as the current value, are within the
+/// allowed range, and parameter is not read-only.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParamEntry {
+    id: [u8; PARAM_ID_LEN],
+    value: TypedValue,
+    min: f64,
+    max: f64,
+    read_only: bool,
+}
+
+impl ParamEntry {
+    /// Creates a writable parameter with a specified name and a default value.
+    ///
+    /// This is a `const` function, so parameter tables can be defined as constants.
+    ///
+    /// # Panics
+    ///
+    /// Panics if name is empty or longer than [`PARAM_ID_LEN`] bytes. Use [`ParamEntry::from_id`]
+    /// for names that are not known at compile time.
+    pub const fn new(name: &str, value: TypedValue) -> Self {
+        match param_id(name) {
+            Some(id) => Self::from_id(id, value),
+            None => panic!("parameter name should have from 1 to 16 bytes"),
+        }
+    }
+
+    /// Creates a writable parameter with a specified `param_id` and a default value.
+    pub const fn from_id(id: [u8; PARAM_ID_LEN], value: TypedValue) -> Self {
+        Self {
+            id,
+            value,
+            min: f64::NEG_INFINITY,
+            max: f64::INFINITY,
+            read_only: false,
+        }
+    }
+
+    /// Sets the inclusive range of allowed values.
+    pub const fn with_range(self, min: f64, max: f64) -> Self {
+        Self { min, max, ..self }
+    }
+
+    /// Makes parameter read-only for remote systems.
+    pub const fn read_only(self) -> Self {
+        Self {
+            read_only: true,
+            ..self
+        }
+    }
+
+    /// Parameter name as transmitted in `param_id` field.
+    #[inline(always)]
+    pub fn id(&self) -> &[u8; PARAM_ID_LEN] {
+        &self.id
+    }
+
+    /// Parameter name.
+    pub fn name(&self) -> &str {
+        param_name(&self.id)
+    }
+
+    /// Current value.
+    #[inline(always)]
+    pub fn value(&self) -> TypedValue {
+        self.value
+    }
+
+    /// Returns `true` if parameter can't be changed by remote systems.
+    #[inline(always)]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Assigns a new value of the same type bypassing range and read-only checks.
+    ///
+    /// Intended for changes made by the component itself. Returns `false` if types mismatch.
+    pub fn set_value(&mut self, value: TypedValue) -> bool {
+        if value.param_type() != self.value.param_type() {
+            return false;
+        }
+        self.value = value;
+        true
+    }
+
+    /// Returns `true` if `value` can be assigned to this parameter by a remote system.
+    pub fn accepts(&self, value: TypedValue) -> bool {
+        let number = value.as_f64();
+        !self.read_only
+            && value.param_type() == self.value.param_type()
+            && !number.is_nan()
+            && number >= self.min
+            && number <= self.max
+    }
+}
+
+/// Table of parameters served by a [`ParamServer`](super::ParamServer).
+///
+/// Parameters are identified by their index from `0` to [`ParamTable::count`] exclusive.
+pub trait ParamTable {
+    /// Number of parameters.
+    fn count(&self) -> u16;
+
+    /// Returns a parameter with a specified index.
+    fn entry(&self, index: u16) -> Option<&ParamEntry>;
+
+    /// Returns a mutable reference to a parameter with a specified index.
+    fn entry_mut(&mut self, index: u16) -> Option<&mut ParamEntry>;
+
+    /// Index of a parameter with a specified `param_id`.
+    ///
+    /// Default implementation performs a linear search.
+    fn index_of(&self, id: &[u8; PARAM_ID_LEN]) -> Option<u16> {
+        (0..self.count()).find(|&index| self.entry(index).is_some_and(|entry| entry.id() == id))
+    }
+
+    /// Assigns a value received from a remote system to a parameter with a specified index.
+    ///
+    /// Default implementation accepts values allowed by [`ParamEntry::accepts`]. Returns `false`
+    /// if value was rejected.
+    fn set(&mut self, index: u16, value: TypedValue) -> bool {
+        match self.entry_mut(index) {
+            Some(entry) if entry.accepts(value) => entry.set_value(value),
+            _ => false,
+        }
+    }
+}
+
+impl<T: ParamTable + ?Sized> ParamTable for &mut T {
+    fn count(&self) -> u16 {
+        (**self).count()
+    }
+
+    fn entry(&self, index: u16) -> Option<&ParamEntry> {
+        (**self).entry(index)
+    }
+
+    fn entry_mut(&mut self, index: u16) -> Option<&mut ParamEntry> {
+        (**self).entry_mut(index)
+    }
+
+    fn index_of(&self, id: &[u8; PARAM_ID_LEN]) -> Option<u16> {
+        (**self).index_of(id)
+    }
+
+    fn set(&mut self, index: u16, value: TypedValue) -> bool {
+        (**self).set(index, value)
+    }
+}
+
+impl ParamTable for [ParamEntry] {
+    fn count(&self) -> u16 {
+        self.len().min(u16::MAX as usize) as u16
+    }
+
+    fn entry(&self, index: u16) -> Option<&ParamEntry> {
+        self.get(index as usize)
+    }
+
+    fn entry_mut(&mut self, index: u16) -> Option<&mut ParamEntry> {
+        self.get_mut(index as usize)
+    }
+}
+
+impl<const N: usize> ParamTable for [ParamEntry; N] {
+    fn count(&self) -> u16 {
+        self.as_slice().count()
+    }
+
+    fn entry(&self, index: u16) -> Option<&ParamEntry> {
+        self.as_slice().entry(index)
+    }
+
+    fn entry_mut(&mut self, index: u16) -> Option<&mut ParamEntry> {
+        self.as_mut_slice().entry_mut(index)
+    }
+}
+
+#[cfg(feature = "std")]
+pub use dynamic::{DynamicParamTable, ParamValidator};
+
+#[cfg(feature = "std")]
+mod dynamic {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    /// <sup>`std`</sup>
+    /// Function that validates a new value of a parameter in [`DynamicParamTable`].
+    ///
+    /// Available only when `std` feature is enabled.
+    pub type ParamValidator = Box<dyn FnMut(&ParamEntry, TypedValue) -> bool + Send>;
+
+    /// <sup>`std`</sup>
+    /// Parameter table that can be changed at runtime.
+    ///
+    /// Parameters are indexed in order of their insertion. Removing a parameter shifts indices of
+    /// the following parameters.
+    ///
+    /// Available only when `std` feature is enabled.
+    #[derive(Default)]
+    pub struct DynamicParamTable {
+        entries: Vec<ParamEntry>,
+        indices: HashMap<[u8; PARAM_ID_LEN], u16>,
+        validator: Option<ParamValidator>,
+    }
+
+    impl core::fmt::Debug for DynamicParamTable {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_struct("DynamicParamTable")
+                .field("entries", &self.entries)
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl DynamicParamTable {
+        /// Creates an empty table.
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Sets a function that validates new values received from remote systems.
+        ///
+        /// Validator is called only for values accepted by [`ParamEntry::accepts`].
+        pub fn with_validator(
+            self,
+            validator: impl FnMut(&ParamEntry, TypedValue) -> bool + Send + 'static,
+        ) -> Self {
+            Self {
+                validator: Some(Box::new(validator)),
+                ..self
+            }
+        }
+
+        /// Parameters ordered by their indices.
+        #[inline(always)]
+        pub fn entries(&self) -> &[ParamEntry] {
+            &self.entries
+        }
+
+        /// Returns a parameter with a specified name.
+        pub fn get(&self, name: &str) -> Option<&ParamEntry> {
+            let index = *self.indices.get(&param_id(name)?)?;
+            self.entries.get(index as usize)
+        }
+
+        /// Adds a parameter or replaces a parameter with the same name.
+        ///
+        /// Returns replaced parameter. New parameters are ignored once table contains
+        /// [`u16::MAX`] parameters.
+        pub fn insert(&mut self, entry: ParamEntry) -> Option<ParamEntry> {
+            if let Some(&index) = self.indices.get(entry.id()) {
+                return Some(core::mem::replace(&mut self.entries[index as usize], entry));
+            }
+            if self.entries.len() >= u16::MAX as usize {
+                return None;
+            }
+            self.indices.insert(entry.id, self.entries.len() as u16);
+            self.entries.push(entry);
+            None
+        }
+
+        /// Removes a parameter with a specified name.
+        pub fn remove(&mut self, name: &str) -> Option<ParamEntry> {
+            let index = self.indices.remove(&param_id(name)?)?;
+            let entry = self.entries.remove(index as usize);
+            for shifted in self.indices.values_mut() {
+                if *shifted > index {
+                    *shifted -= 1;
+                }
+            }
+            Some(entry)
+        }
+    }
+
+    impl ParamTable for DynamicParamTable {
+        fn count(&self) -> u16 {
+            self.entries.len() as u16
+        }
+
+        fn entry(&self, index: u16) -> Option<&ParamEntry> {
+            self.entries.get(index as usize)
+        }
+
+        fn entry_mut(&mut self, index: u16) -> Option<&mut ParamEntry> {
+            self.entries.get_mut(index as usize)
+        }
+
+        fn index_of(&self, id: &[u8; PARAM_ID_LEN]) -> Option<u16> {
+            self.indices.get(id).copied()
+        }
+
+        fn set(&mut self, index: u16, value: TypedValue) -> bool {
+            let Some(entry) = self.entries.get_mut(index as usize) else {
+                return false;
+            };
+            if !entry.accepts(value) {
+                return false;
+            }
+            if let Some(validator) = self.validator.as_mut() {
+                if !validator(entry, value) {
+                    return false;
+                }
+            }
+            entry.value = value;
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn static_table() {
+        let mut table = [
+            ParamEntry::new("RATE", TypedValue::U16(50)).with_range(1.0, 400.0),
+            ParamEntry::new("SERIAL", TypedValue::U32(1234)).read_only(),
+        ];
+        assert_eq!(table.count(), 2);
+        assert_eq!(table.index_of(&param_id("SERIAL").unwrap()), Some(1));
+        assert!(table.set(0, TypedValue::U16(100)));
+        assert!(!table.set(0, TypedValue::U16(500)));
+        assert!(!table.set(0, TypedValue::U32(100)));
+        assert!(!table.set(1, TypedValue::U32(1)));
+        assert!(!table.set(2, TypedValue::U8(1)));
+        assert_eq!(table[0].value(), TypedValue::U16(100));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn dynamic_table() {
+        let mut table = DynamicParamTable::new()
+            .with_validator(|entry, value| entry.name() != "EVEN" || value.as_f64() % 2.0 == 0.0);
+        table.insert(ParamEntry::new("A", TypedValue::U8(1)));
+        table.insert(ParamEntry::new("EVEN", TypedValue::I32(2)));
+        table.insert(ParamEntry::new("B", TypedValue::F32(0.5)));
+        assert_eq!(
+            table
+                .insert(ParamEntry::new("A", TypedValue::U8(3)))
+                .map(|e| e.value()),
+            Some(TypedValue::U8(1))
+        );
+
+        assert!(!table.set(1, TypedValue::I32(3)));
+        assert!(table.set(1, TypedValue::I32(4)));
+
+        table.remove("A");
+        assert_eq!(table.count(), 2);
+        assert_eq!(table.index_of(&param_id("B").unwrap()), Some(1));
+        assert_eq!(
+            table.get("EVEN").map(|e| e.value()),
+            Some(TypedValue::I32(4))
+        );
+    }
+}
@@ -0,0 +1,817 @@
+//! # Extended parameter protocol client
+//!
+//! Implements GCS side of MAVLink
+//! [extended parameter protocol](https://mavlink.io/en/services/parameter_ext.html).
+//!
+//! [`ParamExtClient`] is a sans-I/O state machine that keeps a [`ParamExtCache`] of remote
+//! components and performs one of the following operations:
+//!
+//! * [Synchronization](https://mavlink.io/en/services/parameter_ext.html#read_all): sends
+//!   `PARAM_EXT_REQUEST_LIST` and collects streamed `PARAM_EXT_VALUE` messages. Once stream stops,
+//!   missing parameters are requested one by one by `PARAM_EXT_REQUEST_READ`.
+//! * [Read](https://mavlink.io/en/services/parameter_ext.html#read_single): sends
+//!   `PARAM_EXT_REQUEST_READ` by name or by index and waits for `PARAM_EXT_VALUE`.
+//! * [Write](https://mavlink.io/en/services/parameter_ext.html#write): sends `PARAM_EXT_SET` and
+//!   waits for `PARAM_EXT_ACK`. Once remote component reports `PARAM_ACK_IN_PROGRESS`, request is
+//!   no longer retransmitted and client waits for the final acknowledgement, see
+//!   [`ParamExtClient::with_progress_timeout`].
+//!
+//! Unsolicited `PARAM_EXT_VALUE` messages update the cache at any time.
+//!
+//! Blocking drivers [`ParamExtClient::sync`], [`ParamExtClient::read`], and
+//! [`ParamExtClient::set`] run client over [`Connection`]. With `tokio-rt` feature enabled, the
+//! corresponding `*_async` methods do the same over [`AsyncConnection`].
+//!
+//! Available only when `alloc` feature is enabled.
+//!
+//! [`Connection`]: crate::io::Connection
+//! [`AsyncConnection`]: crate::io::AsyncConnection
+
+use core::time::Duration;
+
+use crate::microservices::parameter_ext::enums::ParamAck;
+use crate::microservices::parameter_ext::messages::{
+    ParamExtAck, ParamExtRequestList, ParamExtRequestRead, ParamExtSet, ParamExtValue,
+};
+use crate::protocol::{Frame, MavLinkId, MaybeVersioned};
+use crate::services::parameter::request::{Request, Synchronization};
+use crate::services::parameter::{CachedParam, ParamCache};
+use crate::services::parameter_ext::{param_id, ExtParam, ExtValue, ParamExtMessage, PARAM_ID_LEN};
+use crate::services::{decode, ServiceError};
+
+#[cfg(feature = "tokio-rt")]
+use crate::io::{AsyncConnection, AsyncRead, AsyncWrite};
+#[cfg(feature = "std")]
+use crate::io::{Connection, Read, Write};
+#[cfg(feature = "std")]
+use crate::protocol::{Endpoint, Versioned};
+#[cfg(feature = "tokio-rt")]
+use crate::services::run_async;
+#[cfg(feature = "std")]
+use crate::services::{run, Driven};
+#[cfg(feature = "std")]
+use crate::Error;
+
+/// Default time to wait for a response before retransmitting a request.
+///
+/// During synchronization this is the time of silence after which the stream of parameters is
+/// considered finished.
+pub const DEFAULT_PARAM_EXT_TIMEOUT: Duration = Duration::from_secs(1);
+/// Default number of retransmissions of each request.
+pub const DEFAULT_PARAM_EXT_RETRIES: u8 = 3;
+/// Default time to wait for the final acknowledgement after `PARAM_ACK_IN_PROGRESS`.
+pub const DEFAULT_PARAM_EXT_PROGRESS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Event produced by [`ParamExtClient::handle`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamExtEvent {
+    /// Parameter was received and cached.
+    ///
+    /// Produced for streamed parameters during synchronization and for unsolicited
+    /// `PARAM_EXT_VALUE` messages.
+    Received(ExtParam),
+    /// All parameters were received. Contains the total number of parameters.
+    Synced(u16),
+    /// Requested parameter was read.
+    Read(ExtParam),
+    /// Remote component is applying the requested value.
+    InProgress(ParamExtAck),
+    /// Parameter was set to the requested value.
+    Set(ParamExtAck),
+    /// Remote component has rejected the requested value.
+    Rejected(ParamExtAck),
+}
+
+/// Cache of extended parameters of remote components.
+///
+/// Parameters are kept separately for each component, see
+/// [`ComponentParams`](crate::services::parameter::ComponentParams).
+pub type ParamExtCache = ParamCache<ExtParam>;
+
+impl CachedParam for ExtParam {
+    #[inline(always)]
+    fn id(&self) -> &[u8; PARAM_ID_LEN] {
+        &self.id
+    }
+
+    #[inline(always)]
+    fn index(&self) -> u16 {
+        self.index
+    }
+
+    #[inline(always)]
+    fn count(&self) -> u16 {
+        self.count
+    }
+
+    #[inline(always)]
+    fn with_index(self, index: u16) -> Self {
+        Self { index, ..self }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Operation {
+    Sync(Synchronization<ParamExtMessage>),
+    Read {
+        id: Option<[u8; PARAM_ID_LEN]>,
+        index: u16,
+        request: Request<ParamExtMessage>,
+    },
+    Set {
+        id: [u8; PARAM_ID_LEN],
+        in_progress: bool,
+        request: Request<ParamExtMessage>,
+    },
+}
+
+/// Sans-I/O extended parameter protocol client.
+///
+/// Client communicates with a single remote component or, if `target` component is `0`, with all
+/// components of the remote system. Parameters are cached separately for each component that
+/// responds, see [`ParamExtCache`].
+///
+/// Start operation by [`ParamExtClient::start_sync`], [`ParamExtClient::start_read`],
+/// [`ParamExtClient::start_read_index`], or [`ParamExtClient::start_set`], then pass incoming
+/// frames to [`ParamExtClient::handle`] and call [`ParamExtClient::poll`] to obtain messages that
+/// should be sent. Call [`ParamExtClient::poll`] after each [`ParamExtClient::handle`] and
+/// whenever [`ParamExtClient::timeout`] expires.
+///
+/// Available only when `alloc` feature is enabled.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use mavio::microservices::parameter_ext::enums::ParamAck;
+/// use mavio::microservices::parameter_ext::messages::ParamExtAck;
+/// use mavio::services::parameter_ext::{ExtValue, ParamExtClient, ParamExtEvent, ParamExtMessage};
+/// use mavio::prelude::*;
+///
+/// let camera = Endpoint::v2(MavLinkId::new(1, 100));
+/// let mut client = ParamExtClient::new(MavLinkId::new(255, 190), camera.id());
+///
+/// client.start_set("CAM_MODE", ExtValue::string("video").unwrap()).unwrap();
+/// let Ok(Some(ParamExtMessage::Set(set))) = client.poll(Duration::ZERO) else {
+///     panic!("expected PARAM_EXT_SET");
+/// };
+///
+/// // Camera needs some time to switch mode
+/// let mut ack = ParamExtAck {
+///     param_id: set.param_id,
+///     param_value: set.param_value,
+///     param_type: set.param_type,
+///     param_result: ParamAck::InProgress,
+/// };
+/// let frame = camera.next_frame(&ack).unwrap();
+/// let event = client.handle(&frame, Duration::from_millis(10));
+/// assert!(matches!(event, Some(ParamExtEvent::InProgress(_))));
+///
+/// ack.param_result = ParamAck::Accepted;
+/// let frame = camera.next_frame(&ack).unwrap();
+/// let event = client.handle(&frame, Duration::from_millis(1500));
+/// assert!(matches!(event, Some(ParamExtEvent::Set(_))));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ParamExtClient {
+    id: MavLinkId,
+    target: MavLinkId,
+    timeout: Duration,
+    progress_timeout: Duration,
+    retries: u8,
+    cache: ParamExtCache,
+    operation: Option<Operation>,
+}
+
+impl ParamExtClient {
+    /// Creates a client for a component with a specified `id` that communicates with `target`.
+    pub fn new(id: MavLinkId, target: MavLinkId) -> Self {
+        Self {
+            id,
+            target,
+            timeout: DEFAULT_PARAM_EXT_TIMEOUT,
+            progress_timeout: DEFAULT_PARAM_EXT_PROGRESS_TIMEOUT,
+            retries: DEFAULT_PARAM_EXT_RETRIES,
+            cache: ParamExtCache::new(),
+            operation: None,
+        }
+    }
+
+    /// Sets time to wait for a response before retransmitting a request.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Sets time to wait for the final acknowledgement after `PARAM_ACK_IN_PROGRESS`.
+    pub fn with_progress_timeout(self, progress_timeout: Duration) -> Self {
+        Self {
+            progress_timeout,
+            ..self
+        }
+    }
+
+    /// Sets the number of retransmissions of each request after which operation fails.
+    pub fn with_retries(self, retries: u8) -> Self {
+        Self { retries, ..self }
+    }
+
+    /// `ID` of the component this client acts on behalf of.
+    #[inline(always)]
+    pub fn id(&self) -> MavLinkId {
+        self.id
+    }
+
+    /// `ID` of the remote component.
+    #[inline(always)]
+    pub fn target(&self) -> MavLinkId {
+        self.target
+    }
+
+    /// Cached parameters of remote components.
+    #[inline(always)]
+    pub fn cache(&self) -> &ParamExtCache {
+        &self.cache
+    }
+
+    /// Returns `true` if operation is in progress.
+    #[inline(always)]
+    pub fn is_busy(&self) -> bool {
+        self.operation.is_some()
+    }
+
+    /// Starts synchronization of all parameters.
+    ///
+    /// Cache is cleared.
+    ///
+    /// Returns [`ServiceError::Busy`] if another operation is in progress.
+    pub fn start_sync(&mut self) -> core::result::Result<(), ServiceError> {
+        let message = ParamExtMessage::RequestList(ParamExtRequestList {
+            target_system: self.target.system,
+            target_component: self.target.component,
+        });
+        self.start(Operation::Sync(Synchronization::new(message, read_request)))?;
+        self.cache.clear();
+        Ok(())
+    }
+
+    /// Starts reading a parameter with a specified name.
+    ///
+    /// Returns [`ServiceError::InvalidParamId`] if name is too long, or [`ServiceError::Busy`] if
+    /// another operation is in progress.
+    pub fn start_read(&mut self, name: &str) -> core::result::Result<(), ServiceError> {
+        let id = param_id(name).ok_or(ServiceError::InvalidParamId)?;
+        let message = ParamExtMessage::RequestRead(ParamExtRequestRead {
+            target_system: self.target.system,
+            target_component: self.target.component,
+            param_id: id,
+            param_index: -1,
+        });
+        self.start(Operation::Read {
+            id: Some(id),
+            index: 0,
+            request: Request::new(message),
+        })
+    }
+
+    /// Starts reading a parameter with a specified index.
+    ///
+    /// Returns [`ServiceError::InvalidParamIndex`] if index exceeds `i16::MAX`, or
+    /// [`ServiceError::Busy`] if another operation is in progress.
+    pub fn start_read_index(&mut self, index: u16) -> core::result::Result<(), ServiceError> {
+        if index > i16::MAX as u16 {
+            return Err(ServiceError::InvalidParamIndex);
+        }
+        self.start(Operation::Read {
+            id: None,
+            index,
+            request: Request::new(read_request(self.target, index)),
+        })
+    }
+
+    /// Starts setting a parameter with a specified name.
+    ///
+    /// Type of the parameter is defined by the type of the `value`.
+    ///
+    /// Returns [`ServiceError::InvalidParamId`] if name is too long, or [`ServiceError::Busy`] if
+    /// another operation is in progress.
+    pub fn start_set(
+        &mut self,
+        name: &str,
+        value: impl Into<ExtValue>,
+    ) -> core::result::Result<(), ServiceError> {
+        let id = param_id(name).ok_or(ServiceError::InvalidParamId)?;
+        let value = value.into();
+        let message = ParamExtMessage::Set(ParamExtSet {
+            target_system: self.target.system,
+            target_component: self.target.component,
+            param_id: id,
+            param_value: value.encode(),
+            param_type: value.param_type(),
+        });
+        self.start(Operation::Set {
+            id,
+            in_progress: false,
+            request: Request::new(message),
+        })
+    }
+
+    /// Aborts current operation.
+    pub fn cancel(&mut self) {
+        self.operation = None;
+    }
+
+    /// Handles incoming frame received at `now`.
+    ///
+    /// Every `PARAM_EXT_VALUE` from remote component updates the cache. Call
+    /// [`ParamExtClient::poll`] afterward to obtain a response.
+    pub fn handle<V: MaybeVersioned>(
+        &mut self,
+        frame: &Frame<V>,
+        now: Duration,
+    ) -> Option<ParamExtEvent> {
+        if frame.system_id() != self.target.system
+            || (self.target.component != 0 && frame.component_id() != self.target.component)
+        {
+            return None;
+        }
+        let source = MavLinkId::new(frame.system_id(), frame.component_id());
+        if let Some(ack) = decode::<ParamExtAck, V>(frame) {
+            return self.handle_ack(source, ack, now);
+        }
+        let msg = decode::<ParamExtValue, V>(frame)?;
+        let param = ExtParam::from_value(&msg);
+        let param = self.cache.insert(source, param).unwrap_or(param);
+
+        let Some(operation) = self.operation.as_mut() else {
+            return Some(ParamExtEvent::Received(param));
+        };
+        match operation {
+            Operation::Sync(sync) => match sync.received(&self.cache, now, self.timeout) {
+                Some(count) => {
+                    self.operation = None;
+                    Some(ParamExtEvent::Synced(count))
+                }
+                None => Some(ParamExtEvent::Received(param)),
+            },
+            Operation::Read { id: Some(id), .. } if *id == param.id => {
+                self.operation = None;
+                Some(ParamExtEvent::Read(param))
+            }
+            Operation::Read {
+                id: None, index, ..
+            } if *index == param.index => {
+                self.operation = None;
+                Some(ParamExtEvent::Read(param))
+            }
+            _ => Some(ParamExtEvent::Received(param)),
+        }
+    }
+
+    /// Returns a message that should be sent at `now`.
+    ///
+    /// This is either a new request or a retransmission of the last request if response has not
+    /// been received in time.
+    ///
+    /// Returns [`ServiceError::TimedOut`] and aborts operation, if all retries are exhausted or
+    /// the final acknowledgement has not been received after `PARAM_ACK_IN_PROGRESS`.
+    pub fn poll(
+        &mut self,
+        now: Duration,
+    ) -> core::result::Result<Option<ParamExtMessage>, ServiceError> {
+        let result = match self.operation.as_mut() {
+            None => return Ok(None),
+            Some(Operation::Sync(sync)) => sync.poll(&self.cache, now, self.timeout, self.retries),
+            // Requests that are in progress are not retransmitted
+            Some(Operation::Set {
+                in_progress: true,
+                request,
+                ..
+            }) if request.is_expired(now) => Err(ServiceError::TimedOut),
+            Some(Operation::Read { request, .. } | Operation::Set { request, .. }) => {
+                request.poll(now, self.timeout, self.retries)
+            }
+        };
+        if result.is_err() {
+            self.operation = None;
+        }
+        result
+    }
+
+    /// Time left until [`ParamExtClient::poll`] should be called.
+    ///
+    /// Returns [`None`] if there is no operation in progress.
+    pub fn timeout(&self, now: Duration) -> Option<Duration> {
+        Some(match self.operation.as_ref()? {
+            Operation::Sync(sync) => sync.timeout(now),
+            Operation::Read { request, .. } | Operation::Set { request, .. } => {
+                request.timeout(now)
+            }
+        })
+    }
+
+    fn handle_ack(
+        &mut self,
+        source: MavLinkId,
+        ack: ParamExtAck,
+        now: Duration,
+    ) -> Option<ParamExtEvent> {
+        let Some(Operation::Set {
+            id,
+            in_progress,
+            request,
+        }) = self.operation.as_mut()
+        else {
+            return None;
+        };
+        if *id != ack.param_id {
+            return None;
+        }
+
+        match ack.param_result {
+            ParamAck::InProgress => {
+                *in_progress = true;
+                request.wait(now + self.progress_timeout);
+                Some(ParamExtEvent::InProgress(ack))
+            }
+            ParamAck::Accepted => {
+                self.operation = None;
+                if let Some(param) = self.cache.get_id_mut(source, &ack.param_id) {
+                    param.value = ExtValue::decode(&ack.param_value, ack.param_type);
+                }
+                Some(ParamExtEvent::Set(ack))
+            }
+            ParamAck::ValueUnsupported | ParamAck::Failed => {
+                self.operation = None;
+                Some(ParamExtEvent::Rejected(ack))
+            }
+        }
+    }
+
+    fn start(&mut self, operation: Operation) -> core::result::Result<(), ServiceError> {
+        if self.operation.is_some() {
+            return Err(ServiceError::Busy);
+        }
+        self.operation = Some(operation);
+        Ok(())
+    }
+}
+
+fn read_request(target: MavLinkId, index: u16) -> ParamExtMessage {
+    ParamExtMessage::RequestRead(ParamExtRequestRead {
+        target_system: target.system,
+        target_component: target.component,
+        param_id: [0; PARAM_ID_LEN],
+        param_index: index as i16,
+    })
+}
+
+#[cfg(feature = "std")]
+impl ParamExtClient {
+    /// <sup>`std`</sup>
+    /// Synchronizes all parameters over a blocking [`Connection`].
+    ///
+    /// Frames that are not related to the extended parameter protocol are discarded.
+    ///
+    /// Returns [`ServiceError::TimedOut`] wrapped into [`Error::Service`] if remote component has
+    /// stopped responding.
+    ///
+    /// Available only when `std` feature is enabled.
+    pub fn sync<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+    ) -> crate::Result<&ParamExtCache>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        self.start_sync()?;
+        self.drive(connection)?;
+        Ok(&self.cache)
+    }
+
+    /// <sup>`std`</sup>
+    /// Reads a parameter with a specified name over a blocking [`Connection`].
+    ///
+    /// See [`ParamExtClient::sync`] for details.
+    ///
+    /// Available only when `std` feature is enabled.
+    pub fn read<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+        name: &str,
+    ) -> crate::Result<ExtParam>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        self.start_read(name)?;
+        match self.drive(connection)? {
+            ParamExtEvent::Read(param) => Ok(param),
+            _ => unreachable!("read is completed only by `ParamExtEvent::Read`"),
+        }
+    }
+
+    /// <sup>`std`</sup>
+    /// Sets a parameter with a specified name over a blocking [`Connection`] and waits for the
+    /// final acknowledgement.
+    ///
+    /// Returns [`ServiceError::ParamExt`] wrapped into [`Error::Service`] if value was rejected.
+    /// See [`ParamExtClient::sync`] for other details.
+    ///
+    /// Available only when `std` feature is enabled.
+    pub fn set<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+        name: &str,
+        value: impl Into<ExtValue>,
+    ) -> crate::Result<ParamExtAck>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        self.start_set(name, value)?;
+        match self.drive(connection)? {
+            ParamExtEvent::Set(ack) => Ok(ack),
+            _ => unreachable!("set is completed only by `ParamExtEvent::Set`"),
+        }
+    }
+
+    fn drive<E, R, W, V>(
+        &mut self,
+        connection: &mut Connection<E, R, W, V>,
+    ) -> crate::Result<ParamExtEvent>
+    where
+        E: Into<Error>,
+        R: Read<E>,
+        W: Write<E>,
+        V: Versioned,
+    {
+        run(self, connection, |client, frame, now| {
+            Ok(completed(client.handle(frame, now))?)
+        })
+    }
+}
+
+#[cfg(feature = "tokio-rt")]
+impl ParamExtClient {
+    /// <sup>`tokio-rt`</sup>
+    /// Synchronizes all parameters over [`AsyncConnection`].
+    ///
+    /// Asynchronous counterpart of [`ParamExtClient::sync`]. Must be called within Tokio runtime.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    pub async fn sync_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+    ) -> crate::Result<&ParamExtCache>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        self.start_sync()?;
+        self.drive_async(connection).await?;
+        Ok(&self.cache)
+    }
+
+    /// <sup>`tokio-rt`</sup>
+    /// Reads a parameter with a specified name over [`AsyncConnection`].
+    ///
+    /// Asynchronous counterpart of [`ParamExtClient::read`]. Must be called within Tokio runtime.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    pub async fn read_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+        name: &str,
+    ) -> crate::Result<ExtParam>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        self.start_read(name)?;
+        match self.drive_async(connection).await? {
+            ParamExtEvent::Read(param) => Ok(param),
+            _ => unreachable!("read is completed only by `ParamExtEvent::Read`"),
+        }
+    }
+
+    /// <sup>`tokio-rt`</sup>
+    /// Sets a parameter with a specified name over [`AsyncConnection`] and waits for the final
+    /// acknowledgement.
+    ///
+    /// Asynchronous counterpart of [`ParamExtClient::set`]. Must be called within Tokio runtime.
+    ///
+    /// Available only when `tokio-rt` feature is enabled.
+    pub async fn set_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+        name: &str,
+        value: impl Into<ExtValue>,
+    ) -> crate::Result<ParamExtAck>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        self.start_set(name, value)?;
+        match self.drive_async(connection).await? {
+            ParamExtEvent::Set(ack) => Ok(ack),
+            _ => unreachable!("set is completed only by `ParamExtEvent::Set`"),
+        }
+    }
+
+    async fn drive_async<E, R, W, V>(
+        &mut self,
+        connection: &mut AsyncConnection<E, R, W, V>,
+    ) -> crate::Result<ParamExtEvent>
+    where
+        E: Into<Error>,
+        R: AsyncRead<E>,
+        W: AsyncWrite<E>,
+        V: Versioned,
+    {
+        run_async(self, connection, |client, frame, now| {
+            Ok(completed(client.handle(frame, now))?)
+        })
+        .await
+    }
+}
+
+#[cfg(feature = "std")]
+impl Driven for ParamExtClient {
+    fn poll_frame<V: Versioned>(
+        &mut self,
+        endpoint: &Endpoint<V>,
+        now: Duration,
+    ) -> crate::Result<Option<Frame<V>>> {
+        self.poll(now)?
+            .map(|message| endpoint.next_frame(message.as_message()))
+            .transpose()
+    }
+
+    fn poll_timeout(&self, now: Duration) -> Option<Duration> {
+        self.timeout(now)
+    }
+
+    fn abort(&mut self) {
+        self.operation = None;
+    }
+}
+
+/// Returns an event if it completes operation.
+#[cfg(feature = "std")]
+fn completed(
+    event: Option<ParamExtEvent>,
+) -> core::result::Result<Option<ParamExtEvent>, ServiceError> {
+    match event {
+        Some(ParamExtEvent::Rejected(ack)) => Err(ServiceError::ParamExt(ack.param_result)),
+        Some(ParamExtEvent::Received(_) | ParamExtEvent::InProgress(_)) | None => Ok(None),
+        event => Ok(event),
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::protocol::V2;
+    use crate::services::parameter_ext::{ExtParamEntry, ParamExtServer};
+    use crate::utils::test_utils::{message_frame, spawn_remote};
+
+    const GCS: MavLinkId = MavLinkId {
+        system: 255,
+        component: 190,
+    };
+    const CAMERA: MavLinkId = MavLinkId {
+        system: 1,
+        component: 100,
+    };
+
+    fn value(name: &str, index: u16, count: u16, value: ExtValue) -> Frame<V2> {
+        let param = ExtParam {
+            id: param_id(name).unwrap(),
+            index,
+            count,
+            value,
+        };
+        message_frame(CAMERA, &param.to_value())
+    }
+
+    fn ack(set: &ParamExtSet, result: ParamAck) -> Frame<V2> {
+        message_frame(
+            CAMERA,
+            &ParamExtAck {
+                param_id: set.param_id,
+                param_value: set.param_value,
+                param_type: set.param_type,
+                param_result: result,
+            },
+        )
+    }
+
+    #[test]
+    fn sync_fills_gaps() {
+        let ms = Duration::from_millis;
+        let mut client = ParamExtClient::new(GCS, CAMERA)
+            .with_timeout(ms(100))
+            .with_retries(1);
+        client.start_sync().unwrap();
+        assert!(matches!(
+            client.poll(ms(0)),
+            Ok(Some(ParamExtMessage::RequestList(_)))
+        ));
+
+        client.handle(&value("A", 0, 2, ExtValue::U8(1)), ms(50));
+        let Ok(Some(ParamExtMessage::RequestRead(read))) = client.poll(ms(150)) else {
+            panic!("expected PARAM_EXT_REQUEST_READ");
+        };
+        assert_eq!(read.param_index, 1);
+
+        let mode = ExtValue::string("photo").unwrap();
+        let event = client.handle(&value("B", 1, 2, mode), ms(160));
+        assert_eq!(event, Some(ParamExtEvent::Synced(2)));
+        assert_eq!(client.cache().get(CAMERA, "B").map(|p| p.value), Some(mode));
+    }
+
+    #[test]
+    fn set_in_progress() {
+        let ms = Duration::from_millis;
+        let mut client = ParamExtClient::new(GCS, CAMERA)
+            .with_timeout(ms(100))
+            .with_progress_timeout(ms(1000))
+            .with_retries(1);
+        assert_eq!(
+            client.start_set("SEVENTEEN_CHARS_ID", 1u8),
+            Err(ServiceError::InvalidParamId)
+        );
+        assert_eq!(
+            client.start_read_index(i16::MAX as u16 + 1),
+            Err(ServiceError::InvalidParamIndex)
+        );
+
+        client.start_set("CAM_EV", 0.5f32).unwrap();
+        let Ok(Some(ParamExtMessage::Set(set))) = client.poll(ms(0)) else {
+            panic!("expected PARAM_EXT_SET");
+        };
+        let event = client.handle(&ack(&set, ParamAck::InProgress), ms(50));
+        assert!(matches!(event, Some(ParamExtEvent::InProgress(_))));
+
+        // Request is not retransmitted while in progress
+        assert_eq!(client.poll(ms(500)), Ok(None));
+        assert_eq!(client.poll(ms(1050)), Err(ServiceError::TimedOut));
+
+        client.start_set("CAM_EV", 0.5f32).unwrap();
+        client.poll(ms(2000)).unwrap();
+        let event = client.handle(&ack(&set, ParamAck::Failed), ms(2010));
+        assert!(matches!(
+            event,
+            Some(ParamExtEvent::Rejected(ack)) if ack.param_result == ParamAck::Failed
+        ));
+        assert!(!client.is_busy());
+    }
+
+    #[test]
+    fn blocking_set_with_server() {
+        let (mut connection, camera) = spawn_remote(GCS, CAMERA, |mut camera| {
+            let table = [
+                ExtParamEntry::new("CAM_MODE", ExtValue::string("photo").unwrap()),
+                ExtParamEntry::new("CAM_EV", 0.0f32),
+            ];
+            // Parameters are streamed at once, so server is polled only after incoming frames
+            let mut server: ParamExtServer<_> =
+                ParamExtServer::new(CAMERA, table).with_stream_interval(Duration::ZERO);
+            while server.get("CAM_EV").unwrap().value != ExtValue::F32(0.5) {
+                server.handle(&camera.recv().unwrap(), Duration::ZERO);
+                while let Some(message) = server.poll(Duration::ZERO) {
+                    camera.send_message(message.as_message()).unwrap();
+                }
+            }
+        });
+
+        let mut client = ParamExtClient::new(GCS, CAMERA).with_timeout(Duration::from_millis(50));
+        let cache = client.sync(&mut connection).unwrap();
+        assert_eq!(
+            cache.get(CAMERA, "CAM_MODE").and_then(|p| p.value.as_str()),
+            Some("photo")
+        );
+
+        let ack = client.set(&mut connection, "CAM_EV", 0.5f32).unwrap();
+        assert_eq!(ack.param_result, ParamAck::Accepted);
+        assert_eq!(
+            client.cache().get(CAMERA, "CAM_EV").map(|p| p.value),
+            Some(ExtValue::F32(0.5))
+        );
+        camera.join().unwrap();
+    }
+}
@@ -0,0 +1,273 @@
+//! # Extended parameter protocol
+//!
+//! Implements MAVLink
+//! [extended parameter protocol](https://mavlink.io/en/services/parameter_ext.html) used by
+//! cameras, gimbals, and other MAVLink components with non-numeric parameters.
+//!
+//! * [`ParamExtClient`] synchronizes, reads, and sets extended parameters of a remote component,
+//!   see [`client`]. Available only when `alloc` feature is enabled.
+//! * [`ParamExtServer`] serves extended parameters of a [`ParamExtTable`] on behalf of a
+//!   component, see [`server`] and [`table`].
+//!
+//! Unlike the [parameter protocol](https://mavlink.io/en/services/parameter.html), values are
+//! transmitted in a 128-byte `param_value` field. Numeric values occupy the first bytes of the
+//! field in little-endian byte order, while [`MavParamExtType::Custom`] values (usually strings)
+//! may occupy the entire field. This module provides [`ExtValue`] that converts values to and from
+//! their wire representation.
+
+use crate::microservices::parameter_ext::enums::MavParamExtType;
+use crate::microservices::parameter_ext::messages::{
+    ParamExtAck, ParamExtRequestList, ParamExtRequestRead, ParamExtSet, ParamExtValue,
+};
+use crate::protocol::Message;
+
+#[cfg(feature = "alloc")]
+pub mod client;
+pub mod server;
+pub mod table;
+
+#[cfg(feature = "alloc")]
+#[doc(inline)]
+pub use client::{
+    ParamExtCache, ParamExtClient, ParamExtEvent, DEFAULT_PARAM_EXT_PROGRESS_TIMEOUT,
+    DEFAULT_PARAM_EXT_RETRIES, DEFAULT_PARAM_EXT_TIMEOUT,
+};
+#[doc(inline)]
+pub use server::{
+    ParamExtServer, ParamExtServerEvent, DEFAULT_PARAM_EXT_QUEUE_CAPACITY,
+    DEFAULT_PARAM_EXT_STREAM_INTERVAL,
+};
+#[doc(inline)]
+pub use table::{ExtParamEntry, ParamExtTable};
+
+pub use crate::services::parameter::{param_id, param_name, PARAM_ID_LEN};
+
+/// Length of `param_value` field in bytes.
+pub const PARAM_EXT_VALUE_LEN: usize = 128;
+
+/// Typed value of an extended parameter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExtValue {
+    /// `MAV_PARAM_EXT_TYPE_UINT8`.
+    U8(u8),
+    /// `MAV_PARAM_EXT_TYPE_INT8`.
+    I8(i8),
+    /// `MAV_PARAM_EXT_TYPE_UINT16`.
+    U16(u16),
+    /// `MAV_PARAM_EXT_TYPE_INT16`.
+    I16(i16),
+    /// `MAV_PARAM_EXT_TYPE_UINT32`.
+    U32(u32),
+    /// `MAV_PARAM_EXT_TYPE_INT32`.
+    I32(i32),
+    /// `MAV_PARAM_EXT_TYPE_UINT64`.
+    U64(u64),
+    /// `MAV_PARAM_EXT_TYPE_INT64`.
+    I64(i64),
+    /// `MAV_PARAM_EXT_TYPE_REAL32`.
+    F32(f32),
+    /// `MAV_PARAM_EXT_TYPE_REAL64`.
+    F64(f64),
+    /// `MAV_PARAM_EXT_TYPE_CUSTOM`, see [`ExtValue::string`] and [`ExtValue::as_str`].
+    Custom([u8; PARAM_EXT_VALUE_LEN]),
+}
+
+impl ExtValue {
+    /// Custom value containing a string.
+    ///
+    /// Returns [`None`] if string is longer than [`PARAM_EXT_VALUE_LEN`] bytes.
+    pub fn string(value: &str) -> Option<Self> {
+        if value.len() > PARAM_EXT_VALUE_LEN {
+            return None;
+        }
+        let mut bytes = [0u8; PARAM_EXT_VALUE_LEN];
+        bytes[..value.len()].copy_from_slice(value.as_bytes());
+        Some(ExtValue::Custom(bytes))
+    }
+
+    /// String contained in a custom value.
+    ///
+    /// String is terminated by the first `NUL` byte or by the end of the field. Returns [`None`]
+    /// for numeric values.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ExtValue::Custom(bytes) => Some(param_name(bytes)),
+            _ => None,
+        }
+    }
+
+    /// Type of the value.
+    pub fn param_type(&self) -> MavParamExtType {
+        match self {
+            ExtValue::U8(_) => MavParamExtType::Uint8,
+            ExtValue::I8(_) => MavParamExtType::Int8,
+            ExtValue::U16(_) => MavParamExtType::Uint16,
+            ExtValue::I16(_) => MavParamExtType::Int16,
+            ExtValue::U32(_) => MavParamExtType::Uint32,
+            ExtValue::I32(_) => MavParamExtType::Int32,
+            ExtValue::U64(_) => MavParamExtType::Uint64,
+            ExtValue::I64(_) => MavParamExtType::Int64,
+            ExtValue::F32(_) => MavParamExtType::Real32,
+            ExtValue::F64(_) => MavParamExtType::Real64,
+            ExtValue::Custom(_) => MavParamExtType::Custom,
+        }
+    }
+
+    /// Decodes a value of a given type from `param_value` field.
+    pub fn decode(raw: &[u8; PARAM_EXT_VALUE_LEN], param_type: MavParamExtType) -> Self {
+        fn le<const N: usize>(raw: &[u8; PARAM_EXT_VALUE_LEN]) -> [u8; N] {
+            let mut bytes = [0u8; N];
+            bytes.copy_from_slice(&raw[..N]);
+            bytes
+        }
+
+        match param_type {
+            MavParamExtType::Uint8 => ExtValue::U8(raw[0]),
+            MavParamExtType::Int8 => ExtValue::I8(raw[0] as i8),
+            MavParamExtType::Uint16 => ExtValue::U16(u16::from_le_bytes(le(raw))),
+            MavParamExtType::Int16 => ExtValue::I16(i16::from_le_bytes(le(raw))),
+            MavParamExtType::Uint32 => ExtValue::U32(u32::from_le_bytes(le(raw))),
+            MavParamExtType::Int32 => ExtValue::I32(i32::from_le_bytes(le(raw))),
+            MavParamExtType::Uint64 => ExtValue::U64(u64::from_le_bytes(le(raw))),
+            MavParamExtType::Int64 => ExtValue::I64(i64::from_le_bytes(le(raw))),
+            MavParamExtType::Real32 => ExtValue::F32(f32::from_le_bytes(le(raw))),
+            MavParamExtType::Real64 => ExtValue::F64(f64::from_le_bytes(le(raw))),
+            MavParamExtType::Custom => ExtValue::Custom(*raw),
+        }
+    }
+
+    /// Encodes value into `param_value` field.
+    pub fn encode(&self) -> [u8; PARAM_EXT_VALUE_LEN] {
+        let mut raw = [0u8; PARAM_EXT_VALUE_LEN];
+        let mut put = |bytes: &[u8]| raw[..bytes.len()].copy_from_slice(bytes);
+        match *self {
+            ExtValue::U8(value) => put(&value.to_le_bytes()),
+            ExtValue::I8(value) => put(&value.to_le_bytes()),
+            ExtValue::U16(value) => put(&value.to_le_bytes()),
+            ExtValue::I16(value) => put(&value.to_le_bytes()),
+            ExtValue::U32(value) => put(&value.to_le_bytes()),
+            ExtValue::I32(value) => put(&value.to_le_bytes()),
+            ExtValue::U64(value) => put(&value.to_le_bytes()),
+            ExtValue::I64(value) => put(&value.to_le_bytes()),
+            ExtValue::F32(value) => put(&value.to_le_bytes()),
+            ExtValue::F64(value) => put(&value.to_le_bytes()),
+            ExtValue::Custom(value) => put(&value),
+        }
+        raw
+    }
+}
+
+macro_rules! impl_from_for_ext_value {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$ty> for ExtValue {
+                fn from(value: $ty) -> Self {
+                    ExtValue::$variant(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_for_ext_value!(
+    u8 => U8, i8 => I8, u16 => U16, i16 => I16, u32 => U32, i32 => I32,
+    u64 => U64, i64 => I64, f32 => F32, f64 => F64, [u8; PARAM_EXT_VALUE_LEN] => Custom,
+);
+
+/// Extended parameter of a component.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExtParam {
+    /// Parameter name as transmitted in `param_id` field, see [`ExtParam::name`].
+    pub id: [u8; PARAM_ID_LEN],
+    /// Parameter index.
+    pub index: u16,
+    /// Total number of parameters of the component.
+    pub count: u16,
+    /// Parameter value.
+    pub value: ExtValue,
+}
+
+impl ExtParam {
+    /// Decodes parameter from `PARAM_EXT_VALUE`.
+    pub fn from_value(msg: &ParamExtValue) -> Self {
+        Self {
+            id: msg.param_id,
+            index: msg.param_index,
+            count: msg.param_count,
+            value: ExtValue::decode(&msg.param_value, msg.param_type),
+        }
+    }
+
+    /// Parameter name.
+    pub fn name(&self) -> &str {
+        param_name(&self.id)
+    }
+
+    /// Encodes parameter into `PARAM_EXT_VALUE`.
+    pub fn to_value(&self) -> ParamExtValue {
+        ParamExtValue {
+            param_id: self.id,
+            param_value: self.value.encode(),
+            param_type: self.value.param_type(),
+            param_count: self.count,
+            param_index: self.index,
+        }
+    }
+}
+
+/// Message of an extended parameter protocol.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamExtMessage {
+    /// `PARAM_EXT_REQUEST_LIST`.
+    RequestList(ParamExtRequestList),
+    /// `PARAM_EXT_REQUEST_READ`.
+    RequestRead(ParamExtRequestRead),
+    /// `PARAM_EXT_SET`.
+    Set(ParamExtSet),
+    /// `PARAM_EXT_VALUE`.
+    Value(ParamExtValue),
+    /// `PARAM_EXT_ACK`.
+    Ack(ParamExtAck),
+}
+
+impl ParamExtMessage {
+    /// Extended parameter protocol message as a MAVLink message.
+    pub fn as_message(&self) -> &dyn Message {
+        match self {
+            ParamExtMessage::RequestList(msg) => msg,
+            ParamExtMessage::RequestRead(msg) => msg,
+            ParamExtMessage::Set(msg) => msg,
+            ParamExtMessage::Value(msg) => msg,
+            ParamExtMessage::Ack(msg) => msg,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodings() {
+        let value = ExtValue::I16(-2);
+        let raw = value.encode();
+        assert_eq!(&raw[..3], &[0xfe, 0xff, 0]);
+        assert_eq!(ExtValue::decode(&raw, MavParamExtType::Int16), value);
+
+        for value in [
+            ExtValue::from(u64::MAX - 1),
+            ExtValue::from(-1.5f64),
+            ExtValue::from(0.25f32),
+        ] {
+            assert_eq!(ExtValue::decode(&value.encode(), value.param_type()), value);
+        }
+
+        let value = ExtValue::string("CAM_MODE=video").unwrap();
+        assert_eq!(value.param_type(), MavParamExtType::Custom);
+        let decoded = ExtValue::decode(&value.encode(), MavParamExtType::Custom);
+        assert_eq!(decoded.as_str(), Some("CAM_MODE=video"));
+        let too_long = [b'x'; PARAM_EXT_VALUE_LEN + 1];
+        assert!(ExtValue::string(core::str::from_utf8(&too_long).unwrap()).is_none());
+        assert!(ExtValue::U8(1).as_str().is_none());
+    }
+}
@@ -0,0 +1,499 @@
+//! # Extended parameter protocol server
+//!
+//! Implements component side of MAVLink
+//! [extended parameter protocol](https://mavlink.io/en/services/parameter_ext.html).
+//!
+//! [`ParamExtServer`] is a sans-I/O state machine that serves parameters of a [`ParamExtTable`]:
+//!
+//! * Streams all parameters on `PARAM_EXT_REQUEST_LIST` at a throttled rate.
+//! * Answers `PARAM_EXT_REQUEST_READ` by name or by index.
+//! * Applies `PARAM_EXT_SET` and responds with `PARAM_EXT_ACK`. Writes that can't be completed
+//!   immediately are acknowledged with `PARAM_ACK_IN_PROGRESS` followed by the final result.
+//! * Broadcasts `PARAM_EXT_VALUE` whenever parameter is changed by the component itself.
+
+use core::time::Duration;
+
+use crate::microservices::parameter_ext::enums::ParamAck;
+use crate::microservices::parameter_ext::messages::{
+    ParamExtAck, ParamExtRequestList, ParamExtRequestRead, ParamExtSet,
+};
+use crate::protocol::{Frame, MavLinkId, MaybeVersioned};
+use crate::services::parameter::queue::ParamQueue;
+use crate::services::parameter_ext::{
+    param_id, ExtParam, ExtValue, ParamExtMessage, ParamExtTable,
+};
+use crate::services::{decode, is_addressed_to};
+
+/// Default interval between `PARAM_EXT_VALUE` messages streamed in response to
+/// `PARAM_EXT_REQUEST_LIST`.
+pub const DEFAULT_PARAM_EXT_STREAM_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Default capacity of a [`ParamExtServer`] queue of pending messages.
+pub const DEFAULT_PARAM_EXT_QUEUE_CAPACITY: usize = 8;
+
+/// Event produced by [`ParamExtServer::handle`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamExtServerEvent {
+    /// Parameter was changed by remote system.
+    Changed(ExtParam),
+    /// Parameter table has started applying a value requested by remote system, contains
+    /// unchanged parameter.
+    ///
+    /// Call [`ParamExtServer::finish`] once value is applied.
+    InProgress(ExtParam),
+    /// Value requested by remote system was rejected, contains unchanged parameter.
+    Rejected(ExtParam),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Pending {
+    Value(u16),
+    Ack(u16, ParamAck),
+}
+
+/// Sans-I/O extended parameter protocol server.
+///
+/// Serves parameters of a [`ParamExtTable`] `T` on behalf of a component with a specified `ID`.
+/// Pass incoming frames to [`ParamExtServer::handle`] and call [`ParamExtServer::poll`] to obtain
+/// messages that should be sent. Call [`ParamExtServer::poll`] after each
+/// [`ParamExtServer::handle`] and whenever [`ParamExtServer::timeout`] expires.
+///
+/// Responses and broadcasts are kept in a queue of `N` messages and sent before parameters
+/// streamed in response to `PARAM_EXT_REQUEST_LIST`. If queue overflows, broadcasts fall back to
+/// streaming the whole table. `PARAM_EXT_SET` received while queue is full is ignored without
+/// applying the value, leaving the remote system to retransmit its request.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use mavio::microservices::parameter_ext::enums::ParamAck;
+/// use mavio::microservices::parameter_ext::messages::ParamExtSet;
+/// use mavio::services::parameter_ext::{
+///     param_id, ExtParamEntry, ExtValue, ParamExtMessage, ParamExtServer,
+/// };
+/// use mavio::prelude::*;
+///
+/// let gcs = Endpoint::v2(MavLinkId::new(255, 190));
+/// let table = [ExtParamEntry::new("CAM_MODE", ExtValue::string("photo").unwrap())];
+/// let mut server: ParamExtServer<_> = ParamExtServer::new(MavLinkId::new(1, 100), table);
+///
+/// let value = ExtValue::string("video").unwrap();
+/// let request = gcs.next_frame(&ParamExtSet {
+///     target_system: 1,
+///     target_component: 100,
+///     param_id: param_id("CAM_MODE").unwrap(),
+///     param_value: value.encode(),
+///     param_type: value.param_type(),
+/// }).unwrap();
+/// server.handle(&request, Duration::ZERO);
+///
+/// let Some(ParamExtMessage::Ack(ack)) = server.poll(Duration::ZERO) else {
+///     panic!("expected PARAM_EXT_ACK");
+/// };
+/// assert_eq!(ack.param_result, ParamAck::Accepted);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ParamExtServer<T: ParamExtTable, const N: usize = DEFAULT_PARAM_EXT_QUEUE_CAPACITY> {
+    id: MavLinkId,
+    table: T,
+    stream_interval: Duration,
+    queue: ParamQueue<Pending, N>,
+}
+
+impl<T: ParamExtTable, const N: usize> ParamExtServer<T, N> {
+    /// Creates a server for a component with a specified `id` backed by `table`.
+    pub fn new(id: MavLinkId, table: T) -> Self {
+        Self {
+            id,
+            table,
+            stream_interval: DEFAULT_PARAM_EXT_STREAM_INTERVAL,
+            queue: ParamQueue::new(),
+        }
+    }
+
+    /// Sets interval between `PARAM_EXT_VALUE` messages streamed in response to
+    /// `PARAM_EXT_REQUEST_LIST`.
+    pub fn with_stream_interval(self, stream_interval: Duration) -> Self {
+        Self {
+            stream_interval,
+            ..self
+        }
+    }
+
+    /// `ID` of the component this server acts on behalf of.
+    #[inline(always)]
+    pub fn id(&self) -> MavLinkId {
+        self.id
+    }
+
+    /// Parameter table.
+    #[inline(always)]
+    pub fn table(&self) -> &T {
+        &self.table
+    }
+
+    /// Mutable reference to parameter table.
+    ///
+    /// Changes made directly to the table are not announced to remote systems, use
+    /// [`ParamExtServer::set`] or [`ParamExtServer::announce`] instead.
+    #[inline(always)]
+    pub fn table_mut(&mut self) -> &mut T {
+        &mut self.table
+    }
+
+    /// Returns `true` if parameters are being streamed in response to `PARAM_EXT_REQUEST_LIST`.
+    #[inline(always)]
+    pub fn is_streaming(&self) -> bool {
+        self.queue.is_streaming()
+    }
+
+    /// Returns a parameter with a specified name.
+    pub fn get(&self, name: &str) -> Option<ExtParam> {
+        let index = self.table.index_of(&param_id(name)?)?;
+        self.param(index)
+    }
+
+    /// Changes a parameter on behalf of the component and broadcasts the new value.
+    ///
+    /// Read-only restriction is not applied. Returns `false` if there is no such parameter or
+    /// value has a different type.
+    pub fn set(&mut self, name: &str, value: impl Into<ExtValue>) -> bool {
+        let Some(index) = param_id(name).and_then(|id| self.table.index_of(&id)) else {
+            return false;
+        };
+        let changed = self
+            .table
+            .entry_mut(index)
+            .is_some_and(|entry| entry.set_value(value.into()));
+        if changed {
+            self.enqueue(index);
+        }
+        changed
+    }
+
+    /// Broadcasts current value of a parameter with a specified index.
+    ///
+    /// Use this method to announce changes made through [`ParamExtServer::table_mut`].
+    pub fn announce(&mut self, index: u16) {
+        if index < self.table.count() {
+            self.enqueue(index);
+        }
+    }
+
+    /// Reports the final `result` of a write that was previously acknowledged with
+    /// [`ParamAck::InProgress`].
+    ///
+    /// Acknowledgement contains the current value of the parameter.
+    ///
+    /// Returns `false` if there is no such parameter or queue is full. In the latter case call
+    /// this method again after [`ParamExtServer::poll`].
+    pub fn finish(&mut self, index: u16, result: ParamAck) -> bool {
+        index < self.table.count() && self.queue.push(Pending::Ack(index, result))
+    }
+
+    /// Handles incoming frame received at `now`.
+    ///
+    /// Call [`ParamExtServer::poll`] afterward to obtain responses.
+    pub fn handle<V: MaybeVersioned>(
+        &mut self,
+        frame: &Frame<V>,
+        now: Duration,
+    ) -> Option<ParamExtServerEvent> {
+        if let Some(msg) = decode::<ParamExtRequestList, V>(frame) {
+            if is_addressed_to(msg.target_system, msg.target_component, self.id) {
+                self.queue.start_stream(now);
+            }
+        } else if let Some(msg) = decode::<ParamExtRequestRead, V>(frame) {
+            if is_addressed_to(msg.target_system, msg.target_component, self.id) {
+                // Unknown parameters are not answered according to the protocol
+                let index = match u16::try_from(msg.param_index) {
+                    Ok(index) if index < self.table.count() => Some(index),
+                    Ok(_) => None,
+                    Err(_) => self.table.index_of(&msg.param_id),
+                };
+                if let Some(index) = index {
+                    self.enqueue(index);
+                }
+            }
+        } else if let Some(msg) = decode::<ParamExtSet, V>(frame) {
+            if is_addressed_to(msg.target_system, msg.target_component, self.id) {
+                return self.handle_set(msg);
+            }
+        }
+        None
+    }
+
+    /// Returns a message that should be sent at `now`.
+    ///
+    /// Call this method until it returns [`None`].
+    pub fn poll(&mut self, now: Duration) -> Option<ParamExtMessage> {
+        while let Some(pending) = self.queue.pop() {
+            let message = match pending {
+                Pending::Value(index) => self
+                    .param(index)
+                    .map(|param| ParamExtMessage::Value(param.to_value())),
+                Pending::Ack(index, result) => self.ack(index, result).map(ParamExtMessage::Ack),
+            };
+            if message.is_some() {
+                return message;
+            }
+        }
+
+        let index = self
+            .queue
+            .stream(now, self.table.count(), self.stream_interval)?;
+        Some(ParamExtMessage::Value(self.param(index)?.to_value()))
+    }
+
+    /// Time left until [`ParamExtServer::poll`] should be called.
+    ///
+    /// Returns [`None`] if there is nothing to send.
+    pub fn timeout(&self, now: Duration) -> Option<Duration> {
+        self.queue.timeout(now)
+    }
+
+    fn handle_set(&mut self, msg: ParamExtSet) -> Option<ParamExtServerEvent> {
+        let index = self.table.index_of(&msg.param_id)?;
+        // Acknowledgement can't be dropped once value is applied
+        if self.queue.is_full() {
+            return None;
+        }
+        let value = ExtValue::decode(&msg.param_value, msg.param_type);
+        let result = self.table.set(index, value);
+
+        self.queue.push(Pending::Ack(index, result));
+        let param = self.param(index)?;
+        Some(match result {
+            ParamAck::Accepted => ParamExtServerEvent::Changed(param),
+            ParamAck::InProgress => ParamExtServerEvent::InProgress(param),
+            ParamAck::ValueUnsupported | ParamAck::Failed => ParamExtServerEvent::Rejected(param),
+        })
+    }
+
+    fn param(&self, index: u16) -> Option<ExtParam> {
+        let entry = self.table.entry(index)?;
+        Some(ExtParam {
+            id: *entry.id(),
+            index,
+            count: self.table.count(),
+            value: entry.value(),
+        })
+    }
+
+    fn ack(&self, index: u16, result: ParamAck) -> Option<ParamExtAck> {
+        let entry = self.table.entry(index)?;
+        Some(ParamExtAck {
+            param_id: *entry.id(),
+            param_value: entry.value().encode(),
+            param_type: entry.value().param_type(),
+            param_result: result,
+        })
+    }
+
+    fn enqueue(&mut self, index: u16) {
+        if !self.queue.push(Pending::Value(index)) {
+            self.queue.overflow(index);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::protocol::V2;
+    use crate::services::parameter_ext::ExtParamEntry;
+    use crate::utils::test_utils::message_frame;
+
+    const GCS: MavLinkId = MavLinkId {
+        system: 255,
+        component: 190,
+    };
+    const CAMERA: MavLinkId = MavLinkId {
+        system: 1,
+        component: 100,
+    };
+
+    fn table() -> [ExtParamEntry; 3] {
+        [
+            ExtParamEntry::new("CAM_MODE", ExtValue::string("photo").unwrap()),
+            ExtParamEntry::new("CAM_EV", 0.0f32),
+            ExtParamEntry::new("CAM_SERIAL", 1234u32).read_only(),
+        ]
+    }
+
+    fn set_frame(name: &str, value: ExtValue) -> Frame<V2> {
+        message_frame(
+            GCS,
+            &ParamExtSet {
+                target_system: 1,
+                target_component: 100,
+                param_id: param_id(name).unwrap(),
+                param_value: value.encode(),
+                param_type: value.param_type(),
+            },
+        )
+    }
+
+    fn ack(message: Option<ParamExtMessage>) -> ParamExtAck {
+        match message {
+            Some(ParamExtMessage::Ack(ack)) => ack,
+            message => panic!("expected PARAM_EXT_ACK, got {message:?}"),
+        }
+    }
+
+    /// Table that applies `CAM_MODE` asynchronously.
+    struct SlowTable([ExtParamEntry; 3]);
+
+    impl ParamExtTable for SlowTable {
+        fn count(&self) -> u16 {
+            self.0.count()
+        }
+
+        fn entry(&self, index: u16) -> Option<&ExtParamEntry> {
+            self.0.entry(index)
+        }
+
+        fn entry_mut(&mut self, index: u16) -> Option<&mut ExtParamEntry> {
+            self.0.entry_mut(index)
+        }
+
+        fn set(&mut self, index: u16, value: ExtValue) -> ParamAck {
+            match index {
+                0 => ParamAck::InProgress,
+                _ => self.0.set(index, value),
+            }
+        }
+    }
+
+    #[test]
+    fn stream_and_read() {
+        let mut server: ParamExtServer<_> = ParamExtServer::new(CAMERA, table());
+        let request = message_frame(
+            GCS,
+            &ParamExtRequestList {
+                target_system: 1,
+                target_component: 100,
+            },
+        );
+        server.handle(&request, Duration::ZERO);
+        let read = message_frame(
+            GCS,
+            &ParamExtRequestRead {
+                target_system: 1,
+                target_component: 100,
+                param_id: param_id("CAM_SERIAL").unwrap(),
+                param_index: -1,
+            },
+        );
+        server.handle(&read, Duration::ZERO);
+
+        let mut now = Duration::ZERO;
+        let mut indices = Vec::new();
+        while server.timeout(now).is_some() {
+            match server.poll(now) {
+                Some(ParamExtMessage::Value(value)) => indices.push(value.param_index),
+                Some(message) => panic!("unexpected {message:?}"),
+                None => now += server.timeout(now).unwrap(),
+            }
+        }
+        assert_eq!(indices, vec![2, 0, 1, 2]);
+        assert_eq!(now, DEFAULT_PARAM_EXT_STREAM_INTERVAL * 2);
+    }
+
+    #[test]
+    fn set_is_acknowledged() {
+        let mut server: ParamExtServer<_> = ParamExtServer::new(CAMERA, table());
+
+        let video = ExtValue::string("video").unwrap();
+        let event = server.handle(&set_frame("CAM_MODE", video), Duration::ZERO);
+        assert!(matches!(event, Some(ParamExtServerEvent::Changed(p)) if p.value == video));
+        let accepted = ack(server.poll(Duration::ZERO));
+        assert_eq!(accepted.param_result, ParamAck::Accepted);
+        assert_eq!(
+            ExtValue::decode(&accepted.param_value, accepted.param_type),
+            video
+        );
+
+        server.handle(&set_frame("CAM_EV", ExtValue::U8(1)), Duration::ZERO);
+        server.handle(&set_frame("CAM_SERIAL", ExtValue::U32(1)), Duration::ZERO);
+        assert!(server
+            .handle(&set_frame("UNKNOWN", ExtValue::U8(1)), Duration::ZERO)
+            .is_none());
+        assert_eq!(
+            ack(server.poll(Duration::ZERO)).param_result,
+            ParamAck::ValueUnsupported
+        );
+        let failed = ack(server.poll(Duration::ZERO));
+        assert_eq!(failed.param_result, ParamAck::Failed);
+        assert_eq!(
+            ExtValue::decode(&failed.param_value, failed.param_type),
+            ExtValue::U32(1234)
+        );
+        assert!(server.poll(Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn set_in_progress() {
+        let mut server: ParamExtServer<_> = ParamExtServer::new(CAMERA, SlowTable(table()));
+        let video = ExtValue::string("video").unwrap();
+
+        // Retransmitted requests are answered by a single acknowledgement
+        for _ in 0..2 {
+            let event = server.handle(&set_frame("CAM_MODE", video), Duration::ZERO);
+            assert!(matches!(event, Some(ParamExtServerEvent::InProgress(_))));
+        }
+        assert_eq!(
+            ack(server.poll(Duration::ZERO)).param_result,
+            ParamAck::InProgress
+        );
+        assert!(server.poll(Duration::ZERO).is_none());
+
+        server.table_mut().0[0].set_value(video);
+        assert!(server.finish(0, ParamAck::Accepted));
+        let accepted = ack(server.poll(Duration::ZERO));
+        assert_eq!(accepted.param_result, ParamAck::Accepted);
+        assert_eq!(
+            ExtValue::decode(&accepted.param_value, accepted.param_type).as_str(),
+            Some("video")
+        );
+    }
+
+    #[test]
+    fn local_changes_are_broadcast() {
+        let mut server: ParamExtServer<_> = ParamExtServer::new(CAMERA, table());
+        assert!(server.set("CAM_SERIAL", 4321u32));
+        assert!(!server.set("CAM_SERIAL", 1.0f32));
+        let Some(ParamExtMessage::Value(value)) = server.poll(Duration::ZERO) else {
+            panic!("expected PARAM_EXT_VALUE");
+        };
+        assert_eq!(ExtParam::from_value(&value).value, ExtValue::U32(4321));
+        assert_eq!(server.get("CAM_SERIAL").unwrap().value, ExtValue::U32(4321));
+    }
+
+    #[test]
+    fn set_is_ignored_when_queue_is_full() {
+        let mut server: ParamExtServer<_, 1> = ParamExtServer::new(CAMERA, table());
+        assert!(server.set("CAM_SERIAL", 4321u32));
+
+        // Value is not applied since acknowledgement can't be queued
+        assert!(server
+            .handle(&set_frame("CAM_EV", ExtValue::F32(1.0)), Duration::ZERO)
+            .is_none());
+        assert_eq!(server.get("CAM_EV").unwrap().value, ExtValue::F32(0.0));
+        assert!(!server.finish(1, ParamAck::Accepted));
+        assert!(matches!(
+            server.poll(Duration::ZERO),
+            Some(ParamExtMessage::Value(_))
+        ));
+
+        // Retransmitted request is applied and acknowledged
+        let event = server.handle(&set_frame("CAM_EV", ExtValue::F32(1.0)), Duration::ZERO);
+        assert!(matches!(event, Some(ParamExtServerEvent::Changed(_))));
+        assert_eq!(
+            ack(server.poll(Duration::ZERO)).param_result,
+            ParamAck::Accepted
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//! # Extended parameter tables
+//!
+//! [`ParamExtTable`] is a storage backend of a [`ParamExtServer`](super::ParamExtServer).
+//!
+//! Arrays and slices of [`ExtParamEntry`] form static tables suitable for `no_std` and `no_alloc`
+//! targets. Implement [`ParamExtTable`] directly to validate values or to apply them
+//! asynchronously, see [`ParamExtTable::set`].
+
+use crate::microservices::parameter_ext::enums::ParamAck;
+use crate::services::parameter_ext::{param_id, param_name, ExtValue, PARAM_ID_LEN};
+
+/// Extended parameter definition stored in a [`ParamExtTable`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExtParamEntry {
+    id: [u8; PARAM_ID_LEN],
+    value: ExtValue,
+    read_only: bool,
+}
+
+impl ExtParamEntry {
+    /// Creates a writable parameter with a specified name and a default value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if name is empty or longer than [`PARAM_ID_LEN`] bytes. Use
+    /// [`ExtParamEntry::from_id`] for names that are not known at compile time.
+    pub fn new(name: &str, value: impl Into<ExtValue>) -> Self {
+        let id = param_id(name).expect("parameter name should have from 1 to 16 bytes");
+        Self::from_id(id, value)
+    }
+
+    /// Creates a writable parameter with a specified `param_id` and a default value.
+    pub fn from_id(id: [u8; PARAM_ID_LEN], value: impl Into<ExtValue>) -> Self {
+        Self {
+            id,
+            value: value.into(),
+            read_only: false,
+        }
+    }
+
+    /// Makes parameter read-only for remote systems.
+    pub fn read_only(self) -> Self {
+        Self {
+            read_only: true,
+            ..self
+        }
+    }
+
+    /// Parameter name as transmitted in `param_id` field.
+    #[inline(always)]
+    pub fn id(&self) -> &[u8; PARAM_ID_LEN] {
+        &self.id
+    }
+
+    /// Parameter name.
+    pub fn name(&self) -> &str {
+        param_name(&self.id)
+    }
+
+    /// Current value.
+    #[inline(always)]
+    pub fn value(&self) -> ExtValue {
+        self.value
+    }
+
+    /// Returns `true` if parameter can't be changed by remote systems.
+    #[inline(always)]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Assigns a new value of the same type bypassing read-only check.
+    ///
+    /// Intended for changes made by the component itself. Returns `false` if types mismatch.
+    pub fn set_value(&mut self, value: ExtValue) -> bool {
+        if value.param_type() != self.value.param_type() {
+            return false;
+        }
+        self.value = value;
+        true
+    }
+
+    /// Result of assigning `value` to this parameter by a remote system.
+    ///
+    /// Returns [`ParamAck::ValueUnsupported`] if types mismatch, [`ParamAck::Failed`] if
+    /// parameter is read-only, and [`ParamAck::Accepted`] otherwise.
+    pub fn check(&self, value: &ExtValue) -> ParamAck {
+        if value.param_type() != self.value.param_type() {
+            ParamAck::ValueUnsupported
+        } else if self.read_only {
+            ParamAck::Failed
+        } else {
+            ParamAck::Accepted
+        }
+    }
+}
+
+/// Table of extended parameters served by a [`ParamExtServer`](super::ParamExtServer).
+///
+/// Parameters are identified by their index from `0` to [`ParamExtTable::count`] exclusive.
+pub trait ParamExtTable {
+    /// Number of parameters.
+    fn count(&self) -> u16;
+
+    /// Returns a parameter with a specified index.
+    fn entry(&self, index: u16) -> Option<&ExtParamEntry>;
+
+    /// Returns a mutable reference to a parameter with a specified index.
+    fn entry_mut(&mut self, index: u16) -> Option<&mut ExtParamEntry>;
+
+    /// Index of a parameter with a specified `param_id`.
+    ///
+    /// Default implementation performs a linear search.
+    fn index_of(&self, id: &[u8; PARAM_ID_LEN]) -> Option<u16> {
+        (0..self.count()).find(|&index| self.entry(index).is_some_and(|entry| entry.id() == id))
+    }
+
+    /// Assigns a value received from a remote system to a parameter with a specified index.
+    ///
+    /// Default implementation assigns values allowed by [`ExtParamEntry::check`] immediately.
+    ///
+    /// Implementations that can't apply a value immediately may return [`ParamAck::InProgress`]
+    /// and report the final result later by
+    /// [`ParamExtServer::finish`](super::ParamExtServer::finish).
+    fn set(&mut self, index: u16, value: ExtValue) -> ParamAck {
+        let Some(entry) = self.entry_mut(index) else {
+            return ParamAck::Failed;
+        };
+        let result = entry.check(&value);
+        if result == ParamAck::Accepted {
+            entry.value = value;
+        }
+        result
+    }
+}
+
+impl<T: ParamExtTable + ?Sized> ParamExtTable for &mut T {
+    fn count(&self) -> u16 {
+        (**self).count()
+    }
+
+    fn entry(&self, index: u16) -> Option<&ExtParamEntry> {
+        (**self).entry(index)
+    }
+
+    fn entry_mut(&mut self, index: u16) -> Option<&mut ExtParamEntry> {
+        (**self).entry_mut(index)
+    }
+
+    fn index_of(&self, id: &[u8; PARAM_ID_LEN]) -> Option<u16> {
+        (**self).index_of(id)
+    }
+
+    fn set(&mut self, index: u16, value: ExtValue) -> ParamAck {
+        (**self).set(index, value)
+    }
+}
+
+impl ParamExtTable for [ExtParamEntry] {
+    fn count(&self) -> u16 {
+        self.len().min(u16::MAX as usize) as u16
+    }
+
+    fn entry(&self, index: u16) -> Option<&ExtParamEntry> {
+        self.get(index as usize)
+    }
+
+    fn entry_mut(&mut self, index: u16) -> Option<&mut ExtParamEntry> {
+        self.get_mut(index as usize)
+    }
+}
+
+impl<const N: usize> ParamExtTable for [ExtParamEntry; N] {
+    fn count(&self) -> u16 {
+        self.as_slice().count()
+    }
+
+    fn entry(&self, index: u16) -> Option<&ExtParamEntry> {
+        self.as_slice().entry(index)
+    }
+
+    fn entry_mut(&mut self, index: u16) -> Option<&mut ExtParamEntry> {
+        self.as_mut_slice().entry_mut(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn static_table() {
+        let mut table = [
+            ExtParamEntry::new("CAM_MODE", ExtValue::string("photo").unwrap()),
+            ExtParamEntry::new("CAM_SERIAL", 1234u32).read_only(),
+        ];
+        assert_eq!(table.count(), 2);
+        assert_eq!(table.index_of(&param_id("CAM_SERIAL").unwrap()), Some(1));
+
+        let video = ExtValue::string("video").unwrap();
+        assert_eq!(table.set(0, video), ParamAck::Accepted);
+        assert_eq!(table.set(0, ExtValue::U8(1)), ParamAck::ValueUnsupported);
+        assert_eq!(table.set(1, ExtValue::U32(1)), ParamAck::Failed);
+        assert_eq!(table.set(2, ExtValue::U32(1)), ParamAck::Failed);
+        assert_eq!(table[0].value().as_str(), Some("video"));
+        assert!(table[1].set_value(ExtValue::U32(1)));
+    }
+}
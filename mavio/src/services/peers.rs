@@ -0,0 +1,556 @@
+//! # Peer registry
+//!
+//! Discovers MAVLink systems and components from received frames.
+//!
+//! [`PeerRegistry`] is a fixed-capacity sans-I/O registry suitable for `no_std` targets. It
+//! collects information about each `(system_id, component_id)` pair from `HEARTBEAT` messages and
+//! other traffic and emits [`PeerEvent`]s when peers connect or disconnect.
+//!
+//! With `std` feature enabled, [`PeerTracker`] provides a thread-safe registry of unlimited
+//! capacity with its own clock and channel-based event subscriptions.
+//!
+//! # Connection rules
+//!
+//! * Peer is connected once the first frame (of any type) is received from it.
+//! * Peer is disconnected if no `HEARTBEAT` was received within a configured timeout. Peers that
+//!   never sent a `HEARTBEAT` are disconnected if no frames at all were received within this
+//!   timeout.
+//! * Disconnected peers are removed from the registry.
+//!
+//! Registries don't run timers on their own, so disconnections are detected and reported only
+//! when [`PeerRegistry::expire`] or [`PeerTracker::expire`] is called. Call them periodically,
+//! for example, when [`PeerRegistry::next_expiry`] is reached or after
+//! [`PeerTracker::time_to_next_expiry`] elapses.
+
+use core::time::Duration;
+
+use crate::microservices::heartbeat::enums::{MavAutopilot, MavState, MavType};
+use crate::microservices::heartbeat::messages::Heartbeat;
+use crate::protocol::{Frame, MavLinkId, MavLinkVersion, MaybeVersioned};
+use crate::services::decode;
+
+#[cfg(feature = "std")]
+pub use tracker::PeerTracker;
+
+/// Default capacity of a [`PeerRegistry`].
+pub const DEFAULT_PEERS_CAPACITY: usize = 32;
+
+/// Default timeout after which peer without heartbeats is considered disconnected.
+///
+/// MAVLink [heartbeat](https://mavlink.io/en/services/heartbeat.html) service suggests to consider
+/// a component disconnected after several missed heartbeats.
+pub const DEFAULT_PEER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Information about MAVLink peer discovered by [`PeerRegistry`].
+///
+/// All moments of time are measured on the same clock as `now` passed to the registry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Peer {
+    /// Peer `ID`.
+    pub id: MavLinkId,
+    /// The last received heartbeat or [`None`] if peer has not sent heartbeats yet.
+    pub heartbeat: Option<Heartbeat>,
+    /// MAVLink protocol version of the last received frame.
+    pub version: MavLinkVersion,
+    /// Whether the last received frame was signed.
+    pub signed: bool,
+    /// Number of received frames.
+    pub frames: u32,
+    /// When peer was discovered.
+    pub first_seen: Duration,
+    /// When the last frame was received from this peer.
+    pub last_seen: Duration,
+    /// When the last heartbeat was received from this peer.
+    pub last_heartbeat: Option<Duration>,
+}
+
+impl Peer {
+    /// MAVLink type of the peer as reported by the last heartbeat.
+    pub fn type_(&self) -> Option<MavType> {
+        self.heartbeat.as_ref().map(|heartbeat| heartbeat.type_)
+    }
+
+    /// Autopilot type of the peer as reported by the last heartbeat.
+    pub fn autopilot(&self) -> Option<MavAutopilot> {
+        self.heartbeat.as_ref().map(|heartbeat| heartbeat.autopilot)
+    }
+
+    /// System status of the peer as reported by the last heartbeat.
+    pub fn system_status(&self) -> Option<MavState> {
+        self.heartbeat
+            .as_ref()
+            .map(|heartbeat| heartbeat.system_status)
+    }
+
+    /// Moment when this peer will be considered disconnected unless new heartbeat is received.
+    pub fn expires_at(&self, timeout: Duration) -> Duration {
+        self.last_heartbeat.unwrap_or(self.last_seen) + timeout
+    }
+
+    fn new<V: MaybeVersioned>(frame: &Frame<V>, now: Duration) -> Self {
+        let mut peer = Self {
+            id: MavLinkId::new(frame.system_id(), frame.component_id()),
+            heartbeat: None,
+            version: frame.version(),
+            signed: false,
+            frames: 0,
+            first_seen: now,
+            last_seen: now,
+            last_heartbeat: None,
+        };
+        peer.observe(frame, now);
+        peer
+    }
+
+    fn observe<V: MaybeVersioned>(&mut self, frame: &Frame<V>, now: Duration) {
+        self.version = frame.version();
+        self.signed = frame.is_signed();
+        self.frames = self.frames.wrapping_add(1);
+        self.last_seen = now;
+
+        if let Some(heartbeat) = decode::<Heartbeat, V>(frame) {
+            self.heartbeat = Some(heartbeat);
+            self.last_heartbeat = Some(now);
+        }
+    }
+}
+
+/// Event emitted by [`PeerRegistry`] and [`PeerTracker`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum PeerEvent {
+    /// New peer was discovered.
+    Connected(Peer),
+    /// Peer has timed out and was removed from the registry.
+    Disconnected(Peer),
+}
+
+impl PeerEvent {
+    /// Peer this event relates to.
+    pub fn peer(&self) -> &Peer {
+        match self {
+            PeerEvent::Connected(peer) | PeerEvent::Disconnected(peer) => peer,
+        }
+    }
+}
+
+/// Fixed-capacity registry of MAVLink peers.
+///
+/// Registry has a fixed capacity `N` that defines the maximum number of tracked peers and
+/// therefore is suitable for `no_std` targets. When the table is full, frames from new peers are
+/// ignored.
+///
+/// Registry does not own a clock. Pass the current time as `now` to [`PeerRegistry::observe`] and
+/// call [`PeerRegistry::expire`] periodically (for example, at [`PeerRegistry::next_expiry`]) to
+/// detect disconnected peers.
+///
+/// See [module docs](self) for connection rules.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use mavio::microservices::heartbeat::messages::Heartbeat;
+/// use mavio::services::peers::{PeerEvent, PeerRegistry};
+/// use mavio::prelude::*;
+///
+/// let mut registry = PeerRegistry::<8>::new().with_timeout(Duration::from_secs(3));
+///
+/// let endpoint = Endpoint::v2(MavLinkId::new(1, 1));
+/// let frame = endpoint.next_frame(&Heartbeat::default()).unwrap();
+///
+/// let event = registry.observe(&frame, Duration::from_secs(0));
+/// assert!(matches!(event, Some(PeerEvent::Connected(_))));
+///
+/// assert!(registry.expire(Duration::from_secs(1)).is_none());
+/// let event = registry.expire(Duration::from_secs(3));
+/// assert!(matches!(event, Some(PeerEvent::Disconnected(_))));
+/// assert!(registry.is_empty());
+/// ```
+#[derive(Clone, Debug)]
+pub struct PeerRegistry<const N: usize = DEFAULT_PEERS_CAPACITY> {
+    timeout: Duration,
+    peers: [Option<Peer>; N],
+}
+
+impl<const N: usize> PeerRegistry<N> {
+    /// Creates an empty registry with [`DEFAULT_PEER_TIMEOUT`].
+    pub fn new() -> Self {
+        Self {
+            timeout: DEFAULT_PEER_TIMEOUT,
+            peers: core::array::from_fn(|_| None),
+        }
+    }
+
+    /// Sets timeout after which peers without heartbeats are considered disconnected.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Timeout after which peers without heartbeats are considered disconnected.
+    #[inline(always)]
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Updates registry based on an incoming frame received at `now`.
+    ///
+    /// Returns [`PeerEvent::Connected`] if frame was received from a new peer. Frames from new
+    /// peers are ignored if registry is full.
+    pub fn observe<V: MaybeVersioned>(
+        &mut self,
+        frame: &Frame<V>,
+        now: Duration,
+    ) -> Option<PeerEvent> {
+        let id = MavLinkId::new(frame.system_id(), frame.component_id());
+
+        if let Some(peer) = self.peers.iter_mut().flatten().find(|peer| peer.id == id) {
+            peer.observe(frame, now);
+            return None;
+        }
+
+        let slot = self.peers.iter_mut().find(|slot| slot.is_none())?;
+        let peer = Peer::new(frame, now);
+        *slot = Some(peer.clone());
+        Some(PeerEvent::Connected(peer))
+    }
+
+    /// Removes a peer that has timed out by `now`.
+    ///
+    /// Returns [`PeerEvent::Disconnected`] for the removed peer or [`None`] if all peers are alive.
+    /// Call this method repeatedly until it returns [`None`] to remove all expired peers.
+    pub fn expire(&mut self, now: Duration) -> Option<PeerEvent> {
+        let timeout = self.timeout;
+        let slot = self
+            .peers
+            .iter_mut()
+            .find(|slot| matches!(slot, Some(peer) if peer.expires_at(timeout) <= now))?;
+        slot.take().map(PeerEvent::Disconnected)
+    }
+
+    /// The earliest moment when one of the peers may time out.
+    ///
+    /// Returns [`None`] if registry is empty.
+    pub fn next_expiry(&self) -> Option<Duration> {
+        self.peers().map(|peer| peer.expires_at(self.timeout)).min()
+    }
+
+    /// Returns information about a peer.
+    pub fn peer(&self, id: MavLinkId) -> Option<&Peer> {
+        self.peers().find(|peer| peer.id == id)
+    }
+
+    /// Iterates over all known peers.
+    pub fn peers(&self) -> impl Iterator<Item = &Peer> {
+        self.peers.iter().flatten()
+    }
+
+    /// Number of known peers.
+    pub fn len(&self) -> usize {
+        self.peers().count()
+    }
+
+    /// Returns `true` if there are no known peers.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes peer from the registry without emitting events.
+    pub fn forget(&mut self, id: MavLinkId) -> Option<Peer> {
+        self.peers
+            .iter_mut()
+            .find(|slot| matches!(slot, Some(peer) if peer.id == id))?
+            .take()
+    }
+
+    /// Removes all peers without emitting events.
+    pub fn clear(&mut self) {
+        self.peers.iter_mut().for_each(|slot| *slot = None);
+    }
+}
+
+impl<const N: usize> Default for PeerRegistry<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+mod tracker {
+    use std::collections::HashMap;
+    use std::sync::mpsc;
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+
+    use super::*;
+
+    /// <sup>`std`</sup>
+    /// Thread-safe registry of MAVLink peers.
+    ///
+    /// Unlike [`PeerRegistry`], tracker has unlimited capacity and measures time on its own: all
+    /// moments in [`Peer`] are durations since the tracker was created. Tracker can be cloned and
+    /// shared between threads, clones refer to the same registry.
+    ///
+    /// Events are returned from [`PeerTracker::observe`] and [`PeerTracker::expire`] and are also
+    /// delivered to all subscribers created by [`PeerTracker::subscribe`]. Disconnections are
+    /// detected only by [`PeerTracker::expire`], subscribers won't receive
+    /// [`PeerEvent::Disconnected`] unless it is called periodically.
+    ///
+    /// See [module docs](super) for connection rules.
+    ///
+    /// Available only when `std` feature is enabled.
+    #[derive(Clone, Debug)]
+    pub struct PeerTracker {
+        inner: Arc<Mutex<Inner>>,
+    }
+
+    #[derive(Debug)]
+    struct Inner {
+        start: Instant,
+        timeout: Duration,
+        peers: HashMap<MavLinkId, Peer>,
+        subscribers: Vec<mpsc::Sender<PeerEvent>>,
+    }
+
+    impl Inner {
+        fn publish(&mut self, event: &PeerEvent) {
+            self.subscribers
+                .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        }
+    }
+
+    impl PeerTracker {
+        /// Creates an empty tracker with [`DEFAULT_PEER_TIMEOUT`].
+        pub fn new() -> Self {
+            Self::with_timeout(DEFAULT_PEER_TIMEOUT)
+        }
+
+        /// Creates an empty tracker with a specified peer timeout.
+        pub fn with_timeout(timeout: Duration) -> Self {
+            Self {
+                inner: Arc::new(Mutex::new(Inner {
+                    start: Instant::now(),
+                    timeout,
+                    peers: HashMap::new(),
+                    subscribers: Vec::new(),
+                })),
+            }
+        }
+
+        /// Timeout after which peers without heartbeats are considered disconnected.
+        pub fn timeout(&self) -> Duration {
+            self.lock().timeout
+        }
+
+        /// Time elapsed since the tracker was created.
+        ///
+        /// This is a clock used for all moments in [`Peer`].
+        pub fn now(&self) -> Duration {
+            self.lock().start.elapsed()
+        }
+
+        /// Subscribes to peer events.
+        pub fn subscribe(&self) -> mpsc::Receiver<PeerEvent> {
+            let (sender, receiver) = mpsc::channel();
+            self.lock().subscribers.push(sender);
+            receiver
+        }
+
+        /// Updates tracker based on an incoming frame.
+        ///
+        /// Returns [`PeerEvent::Connected`] if frame was received from a new peer.
+        pub fn observe<V: MaybeVersioned>(&self, frame: &Frame<V>) -> Option<PeerEvent> {
+            let mut inner = self.lock();
+            let now = inner.start.elapsed();
+            let id = MavLinkId::new(frame.system_id(), frame.component_id());
+
+            if let Some(peer) = inner.peers.get_mut(&id) {
+                peer.observe(frame, now);
+                return None;
+            }
+
+            let peer = Peer::new(frame, now);
+            inner.peers.insert(id, peer.clone());
+            let event = PeerEvent::Connected(peer);
+            inner.publish(&event);
+            Some(event)
+        }
+
+        /// Removes all peers that have timed out.
+        ///
+        /// Returns [`PeerEvent::Disconnected`] for each removed peer.
+        pub fn expire(&self) -> Vec<PeerEvent> {
+            let mut inner = self.lock();
+            let now = inner.start.elapsed();
+            let timeout = inner.timeout;
+
+            let expired: Vec<MavLinkId> = inner
+                .peers
+                .values()
+                .filter(|peer| peer.expires_at(timeout) <= now)
+                .map(|peer| peer.id)
+                .collect();
+
+            let mut events = Vec::with_capacity(expired.len());
+            for id in expired {
+                if let Some(peer) = inner.peers.remove(&id) {
+                    let event = PeerEvent::Disconnected(peer);
+                    inner.publish(&event);
+                    events.push(event);
+                }
+            }
+            events
+        }
+
+        /// Time left until one of the peers may time out.
+        ///
+        /// Unlike [`PeerRegistry::next_expiry`], returns a duration relative to the current moment.
+        /// Returns [`None`] if there are no known peers.
+        pub fn time_to_next_expiry(&self) -> Option<Duration> {
+            let inner = self.lock();
+            let now = inner.start.elapsed();
+            inner
+                .peers
+                .values()
+                .map(|peer| peer.expires_at(inner.timeout).saturating_sub(now))
+                .min()
+        }
+
+        /// Returns information about a peer.
+        pub fn peer(&self, id: MavLinkId) -> Option<Peer> {
+            self.lock().peers.get(&id).cloned()
+        }
+
+        /// Returns all known peers.
+        pub fn peers(&self) -> Vec<Peer> {
+            self.lock().peers.values().cloned().collect()
+        }
+
+        /// Number of known peers.
+        pub fn len(&self) -> usize {
+            self.lock().peers.len()
+        }
+
+        /// Returns `true` if there are no known peers.
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        /// Removes peer from the tracker without emitting events.
+        pub fn forget(&self, id: MavLinkId) -> Option<Peer> {
+            self.lock().peers.remove(&id)
+        }
+
+        fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+            self.inner.lock().unwrap()
+        }
+    }
+
+    impl Default for PeerTracker {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialects::minimal::messages::ProtocolVersion;
+    use crate::microservices::heartbeat::enums::MavType;
+    use crate::protocol::{Endpoint, V2};
+
+    fn heartbeat(endpoint: &Endpoint<V2>, type_: MavType) -> Frame<V2> {
+        endpoint
+            .next_frame(&Heartbeat {
+                type_,
+                ..Default::default()
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn registry_tracks_peers() {
+        let s = Duration::from_secs;
+        let mut registry = PeerRegistry::<2>::new().with_timeout(s(5));
+        let autopilot = Endpoint::v2(MavLinkId::new(1, 1));
+        let camera = Endpoint::v2(MavLinkId::new(1, 100));
+        let gcs = Endpoint::v2(MavLinkId::new(255, 190));
+
+        let event = registry.observe(&heartbeat(&autopilot, MavType::Quadrotor), s(0));
+        let Some(PeerEvent::Connected(peer)) = event else {
+            panic!("expected connected event, got: {event:?}");
+        };
+        assert_eq!(peer.type_(), Some(MavType::Quadrotor));
+        assert_eq!(peer.version, MavLinkVersion::V2);
+        assert!(!peer.signed);
+
+        // Non-heartbeat traffic discovers peers too
+        let frame = camera.next_frame(&ProtocolVersion::default()).unwrap();
+        assert!(registry.observe(&frame, s(1)).is_some());
+        assert_eq!(registry.peer(camera.id()).unwrap().type_(), None);
+
+        // Registry is full
+        assert!(registry
+            .observe(&heartbeat(&gcs, MavType::Gcs), s(1))
+            .is_none());
+        assert_eq!(registry.len(), 2);
+
+        // Known peers are updated silently
+        assert!(registry
+            .observe(&heartbeat(&autopilot, MavType::Quadrotor), s(4))
+            .is_none());
+        let peer = registry.peer(autopilot.id()).unwrap();
+        assert_eq!(peer.frames, 2);
+        assert_eq!(peer.last_heartbeat, Some(s(4)));
+        assert_eq!(registry.next_expiry(), Some(s(6)));
+
+        let event = registry.expire(s(6)).unwrap();
+        assert_eq!(
+            event,
+            PeerEvent::Disconnected(registry_peer(camera.id(), s(1)))
+        );
+        assert!(registry.expire(s(6)).is_none());
+        assert!(registry.expire(s(9)).is_some());
+        assert!(registry.is_empty());
+    }
+
+    fn registry_peer(id: MavLinkId, seen: Duration) -> Peer {
+        Peer {
+            id,
+            heartbeat: None,
+            version: MavLinkVersion::V2,
+            signed: false,
+            frames: 1,
+            first_seen: seen,
+            last_seen: seen,
+            last_heartbeat: None,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn tracker_publishes_events() {
+        let tracker = PeerTracker::with_timeout(Duration::from_millis(20));
+        let events = tracker.subscribe();
+        let autopilot = Endpoint::v2(MavLinkId::new(1, 1));
+
+        assert!(tracker
+            .observe(&heartbeat(&autopilot, MavType::FixedWing))
+            .is_some());
+        assert!(tracker
+            .observe(&heartbeat(&autopilot, MavType::FixedWing))
+            .is_none());
+        assert!(matches!(events.try_recv(), Ok(PeerEvent::Connected(_))));
+        assert_eq!(tracker.peers().len(), 1);
+        assert!(tracker.time_to_next_expiry().unwrap() <= Duration::from_millis(20));
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(tracker.expire().len(), 1);
+        let Ok(PeerEvent::Disconnected(peer)) = events.try_recv() else {
+            panic!("expected disconnected event");
+        };
+        assert_eq!(peer.type_(), Some(MavType::FixedWing));
+        assert!(tracker.is_empty());
+    }
+}
@@ -7,6 +7,8 @@ pub(crate) mod sealed;
 mod signer;
 #[cfg(feature = "extras")]
 mod slice_rw;
+#[cfg(test)]
+pub(crate) mod test_utils;
 #[cfg(feature = "unsafe")]
 mod update;
 
@@ -89,6 +89,11 @@ impl<'a> Read<std::io::Error> for SliceReader<'a> {
         self.read_internal(buf);
         Ok(())
     }
+
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let num_bytes = core::cmp::min(self.num_remaining_bytes(), buf.len());
+        Ok(self.read_internal(&mut buf[..num_bytes]))
+    }
 }
 
 /// <sup>`extras`</sup>
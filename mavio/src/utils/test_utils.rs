@@ -0,0 +1,115 @@
+//! Frame factories and connection scaffolds shared by unit tests.
+
+use crate::protocol::{CrcExtra, MessageId, Sequence};
+
+#[cfg(feature = "std")]
+use crate::io::{
+    link::{duplex, PipeReader, PipeWriter},
+    Connection,
+};
+#[cfg(feature = "tokio-rt")]
+use crate::io::{AsyncConnection, TokioReader, TokioWriter};
+
+use crate::prelude::*;
+
+/// Default sender of frames in I/O tests.
+pub(crate) const SENDER: MavLinkId = MavLinkId {
+    system: 1,
+    component: 1,
+};
+
+/// Builds a frame of a `HEARTBEAT` size with a dummy payload.
+pub(crate) fn frame<V: Versioned>(id: MavLinkId, sequence: Sequence) -> Frame<V> {
+    raw_frame(id, sequence, 0, 50)
+}
+
+/// Builds a frame with a dummy payload for arbitrary message ID and `CRC_EXTRA`.
+pub(crate) fn raw_frame<V: Versioned>(
+    id: MavLinkId,
+    sequence: Sequence,
+    message_id: MessageId,
+    crc_extra: CrcExtra,
+) -> Frame<V> {
+    Frame::builder()
+        .sequence(sequence)
+        .system_id(id.system)
+        .component_id(id.component)
+        .version(V::v())
+        .message_id(message_id)
+        .payload(&[1u8; 9])
+        .crc_extra(crc_extra)
+        .build()
+}
+
+/// Builds a `MAVLink 2` frame of a `message` sent by `id`.
+#[allow(dead_code)]
+pub(crate) fn message_frame(id: MavLinkId, message: &dyn Message) -> Frame<V2> {
+    Endpoint::v2(id).next_frame(message).unwrap()
+}
+
+/// Runs a future to completion on a current-thread Tokio runtime.
+#[cfg(feature = "tokio-rt")]
+#[allow(dead_code)]
+pub(crate) fn block_on<F: core::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+/// Blocking `MAVLink 2` connection over in-memory pipes.
+#[cfg(feature = "std")]
+#[allow(dead_code)]
+pub(crate) type PipeConnection = Connection<std::io::Error, PipeReader, PipeWriter, V2>;
+
+/// Connects `local` component to a `remote` one served by `serve` in a separate thread.
+///
+/// Reader of the `local` connection is non-blocking, so service drivers can handle timeouts.
+#[cfg(feature = "std")]
+#[allow(dead_code)]
+pub(crate) fn spawn_remote<T: Send + 'static>(
+    local: MavLinkId,
+    remote: MavLinkId,
+    serve: impl FnOnce(PipeConnection) -> T + Send + 'static,
+) -> (PipeConnection, std::thread::JoinHandle<T>) {
+    let ((mut local_reader, local_writer), (remote_reader, remote_writer)) = duplex();
+    local_reader.set_nonblocking(true);
+    let remote = Connection::new(remote_reader, remote_writer, Endpoint::v2(remote));
+    (
+        Connection::new(local_reader, local_writer, Endpoint::v2(local)),
+        std::thread::spawn(move || serve(remote)),
+    )
+}
+
+/// Asynchronous `MAVLink 2` connection over a Tokio in-memory duplex stream.
+#[cfg(feature = "tokio-rt")]
+#[allow(dead_code)]
+pub(crate) type DuplexConnection = AsyncConnection<
+    std::io::Error,
+    TokioReader<tokio::io::ReadHalf<tokio::io::DuplexStream>>,
+    TokioWriter<tokio::io::WriteHalf<tokio::io::DuplexStream>>,
+    V2,
+>;
+
+/// Connects `local` and `remote` components over a Tokio in-memory duplex stream.
+#[cfg(feature = "tokio-rt")]
+#[allow(dead_code)]
+pub(crate) fn async_connections(
+    local: MavLinkId,
+    remote: MavLinkId,
+) -> (DuplexConnection, DuplexConnection) {
+    let connection = |stream, id| {
+        let (reader, writer) = tokio::io::split(stream);
+        AsyncConnection::new(
+            TokioReader::new(reader),
+            TokioWriter::new(writer),
+            Endpoint::v2(id),
+        )
+    };
+    let (local_stream, remote_stream) = tokio::io::duplex(1024);
+    (
+        connection(local_stream, local),
+        connection(remote_stream, remote),
+    )
+}
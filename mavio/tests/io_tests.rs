@@ -4,6 +4,8 @@ mod needs_dialect {
     use dialect::messages::Heartbeat;
     use mavio::dialects::minimal as dialect;
     use mavio::dialects::minimal::enums::{MavAutopilot, MavModeFlag, MavState, MavType};
+    use mavio::error::{Error, FrameError};
+    use mavio::io::{MessagePolicy, Received};
     use mavio::protocol::{Dialect, Sequence, Versioned, V1, V2};
     use mavio::utils::{SliceReader, SliceWriter};
     use mavio::{Frame, Receiver, Sender};
@@ -154,4 +156,196 @@ mod needs_dialect {
             assert_eq!(frame.sequence(), i);
         }
     }
+
+    #[test]
+    fn test_message_receiver_policies() {
+        let raw_frame = |message_id, crc_extra| {
+            Frame::builder()
+                .version(V2)
+                .sequence(0)
+                .system_id(1)
+                .component_id(42)
+                .message_id(message_id)
+                .payload(&[0u8; 9])
+                .crc_extra(crc_extra)
+                .build()
+        };
+
+        let mut buf = [0u8; 255];
+        let mut sender = Sender::new(SliceWriter::new(buf.as_mut_slice()));
+        let mut len = sender.send(&default_heartbeat_frame(V2)).unwrap();
+        len += sender.send(&raw_frame(9999, 0)).unwrap();
+        len += sender.send(&raw_frame(Heartbeat::ID, 0)).unwrap();
+        len += sender.send(&default_heartbeat_frame(V2)).unwrap();
+        let buf = &buf[0..len];
+
+        let mut receiver =
+            Receiver::versionless(SliceReader::new(buf)).with_dialect::<dialect::Minimal>();
+        receiver.recv().unwrap();
+        receiver.recv().unwrap();
+        assert!(receiver.recv().is_err());
+        let stats = receiver.stats();
+        assert_eq!(stats.frames, 4);
+        assert_eq!(stats.unknown_messages, 1);
+        assert_eq!(stats.checksum_errors, 1);
+
+        let mut receiver =
+            Receiver::versionless(SliceReader::new(buf)).with_dialect::<dialect::Minimal>();
+        assert!(matches!(receiver.recv_any(), Ok(Received::Message(..))));
+        match receiver.recv_any() {
+            Ok(Received::Unknown(frame)) => assert_eq!(frame.message_id(), 9999),
+            other => panic!("unexpected result: {other:?}"),
+        }
+        assert!(matches!(receiver.recv_any(), Ok(Received::Message(..))));
+
+        let mut receiver = Receiver::versionless(SliceReader::new(buf))
+            .with_dialect::<dialect::Minimal>()
+            .with_policy(MessagePolicy::surface());
+        receiver.recv().unwrap();
+        assert!(matches!(
+            receiver.recv(),
+            Err(Error::Frame(FrameError::NotInDialect(9999)))
+        ));
+        assert!(matches!(
+            receiver.recv(),
+            Err(Error::Frame(FrameError::Checksum))
+        ));
+        receiver.recv().unwrap();
+        assert_eq!(receiver.stats().unknown_messages, 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_try_recv_preserves_partial_frames() {
+        use mavio::io::StdIoReader;
+        use std::time::Duration;
+
+        /// Returns at most 3 bytes per read and fails with `WouldBlock` on every other read.
+        struct NonBlocking<'a> {
+            bytes: &'a [u8],
+            ready: bool,
+        }
+
+        impl std::io::Read for NonBlocking<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                self.ready = !self.ready;
+                if !self.ready {
+                    return Err(std::io::ErrorKind::WouldBlock.into());
+                }
+                let n = buf.len().min(self.bytes.len()).min(3);
+                buf[..n].copy_from_slice(&self.bytes[..n]);
+                self.bytes = &self.bytes[n..];
+                Ok(n)
+            }
+        }
+
+        let (buffer, _) = v1_v2_frames_buffer();
+        let mut receiver = Receiver::versionless(StdIoReader::new(NonBlocking {
+            bytes: &buffer,
+            ready: false,
+        }));
+
+        let mut frames = Vec::new();
+        let mut would_block = 0;
+        while frames.len() < 2 {
+            match receiver.try_recv().unwrap() {
+                Some(frame) => frames.push(frame),
+                None => would_block += 1,
+            }
+        }
+        assert!(would_block > 2);
+        assert_eq!(frames[0].version(), V1::version());
+        assert_eq!(frames[1].version(), V2::version());
+        assert_default_frame(frames[0].clone().try_into_versioned::<V1>().unwrap());
+        assert_default_frame(frames[1].clone().try_into_versioned::<V2>().unwrap());
+
+        let mut receiver = Receiver::versionless(StdIoReader::new(NonBlocking {
+            bytes: &buffer,
+            ready: false,
+        }));
+        let frame = receiver
+            .recv_timeout(Duration::from_secs(5))
+            .unwrap()
+            .unwrap();
+        assert_eq!(frame.version(), V1::version());
+    }
+
+    #[test]
+    fn test_receiver_iterator() {
+        use mavio::io::FrameIteratorExt;
+
+        let frame_from = |system_id| {
+            Frame::builder()
+                .version(V2)
+                .sequence(system_id)
+                .system_id(system_id)
+                .component_id(42)
+                .message(&default_heartbeat_message())
+                .unwrap()
+                .build()
+        };
+
+        let mut buf = [0u8; 255];
+        let mut sender = Sender::new(SliceWriter::new(buf.as_mut_slice()));
+        let mut len = 0;
+        for system_id in [1, 2, 1, 3] {
+            len += sender.send(&frame_from(system_id)).unwrap();
+        }
+        // Truncated frame at the end of the stream
+        sender.send(&frame_from(4)).unwrap();
+        len += 5;
+        let buf = &buf[0..len];
+
+        let frames: Vec<_> = Receiver::versionless(SliceReader::new(buf))
+            .into_iter()
+            .map(|frame| frame.unwrap().system_id())
+            .collect();
+        assert_eq!(frames, [1, 2, 1, 3]);
+
+        let mut receiver = Receiver::versionless(SliceReader::new(buf));
+        let messages: Vec<_> = receiver
+            .iter()
+            .frames_from(1)
+            .messages::<dialect::Minimal>()
+            .map(|result| result.unwrap())
+            .collect();
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(messages[1].1, dialect::Minimal::Heartbeat(_)));
+        assert_eq!(messages[1].0.sequence(), 1);
+        assert!(receiver.iter().next().is_none());
+    }
+
+    #[test]
+    fn test_connection_send_message_and_split() {
+        use mavio::io::Connection;
+        use mavio::{Endpoint, MavLinkId};
+
+        let incoming = [0u8; 0];
+        let mut outgoing = [0u8; 255];
+        let mut conn = Connection::new(
+            SliceReader::new(&incoming),
+            SliceWriter::new(&mut outgoing),
+            Endpoint::v2(MavLinkId::new(1, 42)),
+        );
+        assert_eq!(conn.id(), MavLinkId::new(1, 42));
+
+        let mut len = conn.send_message(&default_heartbeat_message()).unwrap();
+        let (receiver, mut sender) = conn.split();
+        len += sender.send_message(&default_heartbeat_message()).unwrap();
+        let mut conn = sender.reunite(receiver);
+        assert!(conn.recv().is_err());
+        drop(conn);
+
+        let frames: Vec<_> = Receiver::versioned(SliceReader::new(&outgoing[..len]), V2)
+            .into_iter()
+            .map(|frame| frame.unwrap())
+            .collect();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].sequence(), 0);
+        assert_eq!(frames[1].sequence(), 1);
+        for frame in &frames {
+            assert_eq!(frame.system_id(), 1);
+            assert_eq!(frame.component_id(), 42);
+        }
+    }
 }